@@ -0,0 +1,280 @@
+//! Entries of zip and tar archives found in the tree, indexed as `archive.zip::path/inside`
+//! next to the regular files. This is the naming of the zip.vim and tar.vim plugins shipped
+//! with neovim, so entries open without extracting the archive.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Separates the archive path from the path inside of it
+pub const ENTRY_SEPARATOR: &str = "::";
+/// Archives with more entries are cut off, a huge vendored bundle would crowd out the project
+const MAX_ARCHIVE_ENTRIES: usize = 50_000;
+
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_DIRECTORY: u32 = 0x0605_4b50;
+/// The end of directory record is 22 bytes followed by a comment of at most 64k
+const ZIP_MAX_TAIL: u64 = 22 + u16::MAX as u64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size: u64,
+    /// Seconds since the unix epoch, 0 when unknown
+    pub modified: u64,
+}
+
+/// Path of an entry as indexed, e.g. `vendor/lib.zip::src/main.rs`
+pub fn entry_path(archive: &Path, entry: &str) -> PathBuf {
+    let mut path = archive.as_os_str().to_owned();
+    path.push(ENTRY_SEPARATOR);
+    path.push(entry);
+    PathBuf::from(path)
+}
+
+/// Archive and the path inside of it of an indexed entry, `None` for regular files
+pub fn split_entry_path(path: &Path) -> Option<(&Path, &str)> {
+    let (archive, entry) = path.to_str()?.split_once(ENTRY_SEPARATOR)?;
+    Some((Path::new(archive), entry))
+}
+
+/// Regular file entries of a zip or tar archive, the format is detected from the content so
+/// jars, wheels and other zip based formats work as well
+pub fn list_entries(path: &Path) -> io::Result<Vec<ArchiveEntry>> {
+    let mut file = File::open(path)?;
+    let mut magic = [0; 4];
+    let is_zip = file.read_exact(&mut magic).is_ok()
+        && matches!(
+            u32::from_le_bytes(magic),
+            ZIP_LOCAL_HEADER | ZIP_END_OF_DIRECTORY
+        );
+    file.seek(SeekFrom::Start(0))?;
+
+    if is_zip {
+        zip_entries(&mut file)
+    } else {
+        tar_entries(file)
+    }
+}
+
+fn tar_entries(file: File) -> io::Result<Vec<ArchiveEntry>> {
+    let mut archive = tar::Archive::new(file);
+    let mut entries = Vec::new();
+    for entry in archive.entries()?.take(MAX_ARCHIVE_ENTRIES) {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        entries.push(ArchiveEntry {
+            name: entry.path()?.to_string_lossy().into_owned(),
+            size: entry.size(),
+            modified: entry.header().mtime().unwrap_or_default(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Reads the central directory only, nothing is decompressed
+fn zip_entries(file: &mut File) -> io::Result<Vec<ArchiveEntry>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(ZIP_MAX_TAIL);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| read_u32(&tail, offset) == ZIP_END_OF_DIRECTORY)
+        .ok_or_else(|| invalid("missing zip end of central directory"))?;
+    let count = read_u16(&tail, end + 10) as usize;
+    let directory_size = read_u32(&tail, end + 12);
+    let directory_offset = read_u32(&tail, end + 16);
+    if count == u16::MAX as usize || directory_offset == u32::MAX {
+        return Err(invalid("zip64 archives are not supported"));
+    }
+
+    file.seek(SeekFrom::Start(directory_offset as u64))?;
+    let mut directory = vec![0; directory_size as usize];
+    file.read_exact(&mut directory)?;
+
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    for _ in 0..count.min(MAX_ARCHIVE_ENTRIES) {
+        if offset + 46 > directory.len() || read_u32(&directory, offset) != ZIP_CENTRAL_HEADER {
+            return Err(invalid("corrupted zip central directory"));
+        }
+
+        let time = read_u16(&directory, offset + 12);
+        let date = read_u16(&directory, offset + 14);
+        let size = read_u32(&directory, offset + 24);
+        let name_len = read_u16(&directory, offset + 28) as usize;
+        let extra_len = read_u16(&directory, offset + 30) as usize;
+        let comment_len = read_u16(&directory, offset + 32) as usize;
+        let name = directory
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(|| invalid("corrupted zip central directory"))?;
+        offset += 46 + name_len + extra_len + comment_len;
+
+        let name = String::from_utf8_lossy(name);
+        if name.ends_with('/') {
+            continue;
+        }
+
+        entries.push(ArchiveEntry {
+            name: name.into_owned(),
+            size: size as u64,
+            modified: dos_time_to_unix(date, time),
+        });
+    }
+
+    Ok(entries)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+/// Zip entries store the local time of the machine that made them, it is taken as utc
+fn dos_time_to_unix(date: u16, time: u16) -> u64 {
+    let year = 1980 + (date >> 9) as i64;
+    let month = ((date >> 5) & 0xf) as i64;
+    let day = (date & 0x1f) as i64;
+    if !(1..=12).contains(&month) || day == 0 {
+        return 0;
+    }
+
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds =
+        (time >> 11) as i64 * 3600 + ((time >> 5) & 0x3f) as i64 * 60 + (time & 0x1f) as i64 * 2;
+    (days * 86_400 + seconds).max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stored (uncompressed) zip with the given files, enough to exercise the directory parsing
+    fn zip_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut directory = Vec::new();
+        // 2024-03-15 12:30:00
+        let (time, date) = (12u16 << 11 | 30 << 5, (2024u16 - 1980) << 9 | 3 << 5 | 15);
+        for (name, content) in files {
+            let offset = bytes.len() as u32;
+            let size = content.len() as u32;
+            bytes.extend(ZIP_LOCAL_HEADER.to_le_bytes());
+            bytes.extend([20, 0, 0, 0, 0, 0]);
+            bytes.extend(time.to_le_bytes());
+            bytes.extend(date.to_le_bytes());
+            bytes.extend([0; 4]);
+            bytes.extend(size.to_le_bytes());
+            bytes.extend(size.to_le_bytes());
+            bytes.extend((name.len() as u16).to_le_bytes());
+            bytes.extend([0, 0]);
+            bytes.extend(name.as_bytes());
+            bytes.extend(*content);
+
+            directory.extend(ZIP_CENTRAL_HEADER.to_le_bytes());
+            directory.extend([20, 0, 20, 0, 0, 0, 0, 0]);
+            directory.extend(time.to_le_bytes());
+            directory.extend(date.to_le_bytes());
+            directory.extend([0; 4]);
+            directory.extend(size.to_le_bytes());
+            directory.extend(size.to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+
+        let directory_offset = bytes.len() as u32;
+        bytes.extend(&directory);
+        bytes.extend(ZIP_END_OF_DIRECTORY.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend((directory.len() as u32).to_le_bytes());
+        bytes.extend(directory_offset.to_le_bytes());
+        bytes.extend([0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_list_archive_entries() {
+        let dir = std::env::temp_dir().join("fff_test_archive");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let zip = dir.join("vendor.jar");
+        std::fs::write(
+            &zip,
+            zip_bytes(&[("src/", b""), ("src/main.rs", b"fn main() {}")]),
+        )
+        .unwrap();
+        assert_eq!(
+            list_entries(&zip).unwrap(),
+            vec![ArchiveEntry {
+                name: "src/main.rs".to_string(),
+                size: 12,
+                modified: 1_710_505_800,
+            }]
+        );
+
+        let tar_path = dir.join("vendor.tar");
+        let mut builder = tar::Builder::new(File::create(&tar_path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mtime(1_700_000_000);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "lib/util.lua", &b"x = 1"[..])
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+        assert_eq!(
+            list_entries(&tar_path).unwrap(),
+            vec![ArchiveEntry {
+                name: "lib/util.lua".to_string(),
+                size: 5,
+                modified: 1_700_000_000,
+            }]
+        );
+
+        let path = entry_path(&zip, "src/main.rs");
+        assert_eq!(
+            path,
+            PathBuf::from(format!("{}::src/main.rs", zip.display()))
+        );
+        assert_eq!(
+            split_entry_path(&path),
+            Some((zip.as_path(), "src/main.rs"))
+        );
+        assert_eq!(split_entry_path(&zip), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
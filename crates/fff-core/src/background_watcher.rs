@@ -0,0 +1,542 @@
+use crate::error::Error;
+use crate::events::{push_event, PickerEventKind};
+use crate::file_picker::FilePicker;
+#[cfg(feature = "git")]
+use crate::git::GitStatusCache;
+use crate::ignore_cache::IgnoreCache;
+use crate::path_utils::{is_git_file, normalize_path};
+use crate::types::{ScanOptions, WatcherBackend, WatcherOptions};
+use crate::{FILE_PICKER, FRECENCY};
+#[cfg(feature = "git")]
+use git2::Repository;
+use notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer_opt, DebounceEventResult, DebouncedEvent};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Debouncer of either backend, the watcher itself is only used through `dyn Watcher`
+enum Debouncer {
+    Native(notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>),
+    Poll(notify_debouncer_mini::Debouncer<notify::PollWatcher>),
+}
+
+impl Debouncer {
+    fn watcher(&mut self) -> &mut dyn notify::Watcher {
+        match self {
+            Debouncer::Native(debouncer) => debouncer.watcher(),
+            Debouncer::Poll(debouncer) => debouncer.watcher(),
+        }
+    }
+}
+
+pub struct BackgroundWatcher {
+    debouncer: Arc<Mutex<Option<Debouncer>>>,
+}
+
+const MAX_PATHS_THRESHOLD: usize = 50;
+const ROOT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Set while a thread waits for the removed root to come back
+static AWAITING_ROOT: AtomicBool = AtomicBool::new(false);
+
+impl BackgroundWatcher {
+    pub fn new(
+        base_path: PathBuf,
+        extra_paths: Vec<PathBuf>,
+        git_workdir: Option<PathBuf>,
+        scan_options: ScanOptions,
+        watcher_options: WatcherOptions,
+    ) -> Result<Self, Error> {
+        info!(
+            "Initializing background watcher for path: {}",
+            base_path.display()
+        );
+
+        let debouncer = Self::create_debouncer(
+            base_path,
+            extra_paths,
+            git_workdir,
+            scan_options,
+            watcher_options,
+        )?;
+        info!("Background file watcher initialized successfully");
+
+        Ok(Self {
+            debouncer: Arc::new(Mutex::new(Some(debouncer))),
+        })
+    }
+
+    fn create_debouncer(
+        base_path: PathBuf,
+        extra_paths: Vec<PathBuf>,
+        git_workdir: Option<PathBuf>,
+        scan_options: ScanOptions,
+        watcher_options: WatcherOptions,
+    ) -> Result<Debouncer, Error> {
+        let root_id = root_identity(&base_path);
+        // every attempt to start a backend consumes a handler
+        let event_handler = || {
+            let base_path = base_path.clone();
+            let git_workdir = git_workdir.clone();
+            let scan_options = scan_options.clone();
+            let mut ignore_cache = IgnoreCache::new(
+                &base_path,
+                git_workdir.as_deref(),
+                &scan_options.custom_ignore_filenames,
+                &scan_options.user_ignore_files(),
+            );
+
+            move |result: DebounceEventResult| match result {
+                Ok(events) => {
+                    // removing the root drops every watch, even if it is re-created right away
+                    if root_identity(&base_path) != root_id {
+                        return restart_when_root_returns(base_path.clone());
+                    }
+
+                    if !events.is_empty() {
+                        handle_debounced_events(
+                            events,
+                            &base_path,
+                            &git_workdir,
+                            &scan_options,
+                            &mut ignore_cache,
+                        );
+                    }
+                }
+                Err(errors) => {
+                    error!("File watcher errors: {:?}", errors);
+                }
+            }
+        };
+
+        let start = |backend| Self::start(&base_path, &watcher_options, backend, event_handler());
+        let mut debouncer = match watcher_options.backend {
+            WatcherBackend::Native => start(WatcherBackend::Native).or_else(|e| {
+                // containers without inotify and exhausted watch limits end up here
+                warn!("Native file watcher unavailable, polling instead: {:?}", e);
+                start(WatcherBackend::Poll)
+            })?,
+            WatcherBackend::Poll => start(WatcherBackend::Poll)?,
+        };
+        info!("File watcher initizlieed for path: {}", base_path.display());
+
+        for extra_path in &extra_paths {
+            if let Err(e) = debouncer
+                .watcher()
+                .watch(extra_path, RecursiveMode::Recursive)
+            {
+                warn!(
+                    "Failed to watch extra path {}: {:?}",
+                    extra_path.display(),
+                    e
+                );
+            }
+        }
+
+        Ok(debouncer)
+    }
+
+    fn start(
+        base_path: &Path,
+        watcher_options: &WatcherOptions,
+        backend: WatcherBackend,
+        event_handler: impl notify_debouncer_mini::DebounceEventHandler,
+    ) -> Result<Debouncer, Error> {
+        let notify_config = notify::Config::default()
+            .with_poll_interval(Duration::from_millis(watcher_options.poll_interval_ms));
+        let config = notify_debouncer_mini::Config::default()
+            .with_timeout(Duration::from_millis(watcher_options.debounce_ms))
+            .with_notify_config(notify_config);
+
+        let mut debouncer = match backend {
+            WatcherBackend::Native => Debouncer::Native(new_debouncer_opt(config, event_handler)?),
+            WatcherBackend::Poll => Debouncer::Poll(new_debouncer_opt(config, event_handler)?),
+        };
+        debouncer
+            .watcher()
+            .watch(base_path, RecursiveMode::Recursive)?;
+        Ok(debouncer)
+    }
+
+    pub fn stop(&self) {
+        if let Ok(Some(debouncer)) = self.debouncer.lock().map(|mut debouncer| debouncer.take()) {
+            drop(debouncer);
+            info!("Background file watcher stopped successfully");
+        } else {
+            error!("Failed to stop background watcher");
+        }
+    }
+}
+
+impl Drop for BackgroundWatcher {
+    fn drop(&mut self) {
+        if let Ok(mut debouncer_guard) = self.debouncer.lock() {
+            if let Some(debouncer) = debouncer_guard.take() {
+                drop(debouncer);
+            }
+        } else {
+            error!("Failed to acquire debouncer lock to drop");
+        }
+    }
+}
+
+fn handle_debounced_events(
+    events: Vec<DebouncedEvent>,
+    base_path: &Path,
+    #[cfg_attr(not(feature = "git"), allow(unused_variables))] git_workdir: &Option<PathBuf>,
+    scan_options: &ScanOptions,
+    ignore_cache: &mut IgnoreCache,
+) {
+    debug!("Processing {} debounced events", events.len());
+
+    let Ok(mut file_picker_guard) = FILE_PICKER.write() else {
+        error!("Failed to acquire file picker write lock");
+        return;
+    };
+
+    let Some(ref mut picker) = *file_picker_guard else {
+        error!("File picker not initialized");
+        return;
+    };
+
+    let mut need_full_git_rescan = false;
+
+    #[cfg(feature = "git")]
+    let repo = git_workdir.as_ref().and_then(|p| Repository::open(p).ok());
+    #[cfg(feature = "git")]
+    let (in_repository, git_dir) = (repo.is_some(), repo.as_ref().map(Repository::path));
+    // without libgit2 the `.git` directory isn't watched for status changes and every path the
+    // ignore files don't exclude is indexed, the repository can't be opened to tell
+    #[cfg(not(feature = "git"))]
+    let (in_repository, git_dir) = (true, None);
+    let mut files_to_update_git_status = Vec::with_capacity(events.len() * 2);
+    let mut affected_paths_count = 0usize;
+
+    // windows backends may report verbatim (`\\?\C:\...`) or drive relative paths
+    let paths: Vec<PathBuf> = events
+        .iter()
+        .map(|event| normalize_path(&event.path, base_path).into_owned())
+        .collect();
+
+    let mut moved_dirs = Vec::new();
+    let mut moved_files = HashSet::new();
+    for (from, to) in detect_directory_moves(&paths, picker, ignore_cache) {
+        let moves = picker.move_directory(&from, &to);
+        info!(
+            "Detected directory move {} -> {}, remapped {} files",
+            from.display(),
+            to.display(),
+            moves.len()
+        );
+
+        move_frecency(&moves);
+        if moves.len() > MAX_PATHS_THRESHOLD {
+            need_full_git_rescan = true;
+        }
+        for (_, new_path) in moves {
+            if let Ok(relative) = new_path.strip_prefix(base_path) {
+                files_to_update_git_status.push(relative.to_string_lossy().into_owned());
+            }
+            moved_files.insert(new_path);
+        }
+        moved_dirs.push(from);
+    }
+
+    for (event, path) in events.iter().zip(&paths) {
+        let path = path.as_path();
+        if moved_files.contains(path) || moved_dirs.iter().any(|dir| path.starts_with(dir)) {
+            continue;
+        }
+
+        if is_ignore_definition_path(path, &scan_options.custom_ignore_filenames) {
+            info!(
+                "Detected change in the ignore definition file: {}",
+                path.display()
+            );
+
+            ignore_cache.invalidate(path);
+            return trigger_full_rescan(picker);
+        }
+
+        if is_dotgit_change_affecting_status(path, git_dir) {
+            ignore_cache.invalidate(path);
+            need_full_git_rescan = true;
+        }
+
+        if !should_include_file(path, in_repository, ignore_cache) {
+            continue;
+        }
+
+        debug!("Handling fs event: {:?}", event);
+
+        affected_paths_count += 1;
+        if affected_paths_count > MAX_PATHS_THRESHOLD {
+            warn!(
+                "Too many affected paths ({}) in a single batch, triggering full rescan",
+                affected_paths_count
+            );
+
+            return trigger_full_rescan(picker);
+        }
+
+        if !path.exists() {
+            picker.remove_file_by_path(path);
+            continue;
+        }
+
+        let file = picker.on_create_or_modify(path);
+        if let Some(file) = file {
+            files_to_update_git_status.push(file.relative_path.clone());
+        }
+    }
+
+    if need_full_git_rescan {
+        drop(file_picker_guard); // it's going to be relocked after rescan
+        info!("Triggering full git rescan by the notification results");
+
+        if let Err(e) = FilePicker::refresh_git_status_global() {
+            error!("Failed to refresh git status: {:?}", e);
+        }
+    } else {
+        #[cfg(feature = "git")]
+        if let Some(repo) = repo.as_ref() {
+            let status = GitStatusCache::git_status_for_paths(repo, &files_to_update_git_status);
+            if let Err(e) = picker.update_git_statuses(status) {
+                error!("Failed to update git statuses: {:?}", e);
+            }
+        }
+    }
+}
+
+fn should_include_file(path: &Path, in_repository: bool, ignore_cache: &mut IgnoreCache) -> bool {
+    if !path.is_file() || is_git_file(path) {
+        return false;
+    }
+
+    in_repository && !ignore_cache.is_ignored(path, false)
+}
+
+/// A directory rename arrives as a batch of events for the old and the new location. An old
+/// path that is gone but still has indexed files under it is paired with a new directory that
+/// is not indexed yet and holds all of those files under the same relative paths.
+fn detect_directory_moves(
+    paths: &[PathBuf],
+    picker: &FilePicker,
+    ignore_cache: &mut IgnoreCache,
+) -> Vec<(PathBuf, PathBuf)> {
+    let has_indexed_files_under = |dir: &Path| !picker.get_files_in_dir(dir).is_empty();
+
+    let mut removed: Vec<&Path> = paths
+        .iter()
+        .map(PathBuf::as_path)
+        .filter(|path| !path.exists() && picker.get_file_by_path(path).is_none())
+        .filter(|path| has_indexed_files_under(path))
+        .collect();
+    if removed.is_empty() {
+        return Vec::new();
+    }
+
+    let mut created: Vec<&Path> = paths
+        .iter()
+        .map(PathBuf::as_path)
+        .filter(|path| path.is_dir() && !is_git_file(path))
+        .filter(|path| !has_indexed_files_under(path))
+        .collect();
+
+    // events of the files inside a moved directory are reported too, keep the topmost ones
+    removed.sort_unstable();
+    removed.dedup_by(|child, parent| child.starts_with(parent));
+    created.sort_unstable();
+    created.dedup_by(|child, parent| child.starts_with(parent));
+
+    let mut moves = Vec::new();
+    for from in removed {
+        let files: Vec<&Path> = picker
+            .get_files_in_dir(from)
+            .iter()
+            .filter_map(|file| file.path.strip_prefix(from).ok())
+            .collect();
+
+        let Some(position) = created.iter().position(|to| {
+            !ignore_cache.is_ignored(to, true) && files.iter().all(|file| to.join(file).is_file())
+        }) else {
+            continue;
+        };
+
+        moves.push((from.to_path_buf(), created.remove(position).to_path_buf()));
+    }
+
+    moves
+}
+
+fn move_frecency(moves: &[(PathBuf, PathBuf)]) {
+    let Ok(frecency) = FRECENCY.read() else {
+        error!("Failed to acquire frecency read lock");
+        return;
+    };
+
+    if let Some(tracker) = frecency.as_ref() {
+        if let Err(e) = tracker.move_accesses(moves) {
+            error!("Failed to move frecency of the moved files: {:?}", e);
+        }
+    }
+}
+
+/// Device and inode of the root, a re-created directory gets a new inode under the same path
+#[cfg(unix)]
+fn root_identity(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_dir())
+        .map(|metadata| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn root_identity(path: &Path) -> Option<(u64, u64)> {
+    path.is_dir().then_some((0, 0))
+}
+
+/// Waits in the background until the root directory exists again, then re-establishes the
+/// watcher with a full rescan. Gives up once the picker moves to another base path.
+fn restart_when_root_returns(base_path: PathBuf) {
+    if AWAITING_ROOT.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    warn!(
+        "Watched root {} was removed, waiting for it to come back",
+        base_path.display()
+    );
+    push_event(PickerEventKind::RootRemoved, base_path.clone());
+
+    std::thread::spawn(move || {
+        let is_current_root = |picker: &FilePicker| picker.base_path() == base_path;
+        loop {
+            let still_watched = FILE_PICKER
+                .read()
+                .ok()
+                .is_some_and(|guard| guard.as_ref().is_some_and(is_current_root));
+            if !still_watched {
+                debug!("Picker base path changed, not waiting for the removed root anymore");
+                break;
+            }
+
+            if base_path.is_dir() {
+                if let Ok(mut guard) = FILE_PICKER.write() {
+                    if let Some(picker) = guard.as_mut().filter(|picker| is_current_root(picker)) {
+                        info!("Watched root {} is back, restarting", base_path.display());
+                        picker.rescan_in_background();
+                        push_event(PickerEventKind::WatcherRestarted, base_path.clone());
+                    }
+                }
+                break;
+            }
+
+            std::thread::sleep(ROOT_POLL_INTERVAL);
+        }
+
+        AWAITING_ROOT.store(false, Ordering::Release);
+    });
+}
+
+fn trigger_full_rescan(picker: &mut FilePicker) {
+    if let Err(e) = picker.trigger_rescan() {
+        error!("Failed to trigger full rescan: {:?}", e);
+    }
+}
+
+pub fn is_dotgit_change_affecting_status(changed: &Path, git_dir: Option<&Path>) -> bool {
+    let Some(git_dir) = git_dir else {
+        return false;
+    };
+
+    if let Ok(rel) = changed.strip_prefix(git_dir) {
+        if rel.starts_with("objects") || rel.starts_with("logs") || rel.starts_with("hooks") {
+            return false;
+        }
+        if rel == Path::new("index") || rel == Path::new("index.lock") {
+            return true;
+        }
+        if rel == Path::new("HEAD") {
+            return true;
+        }
+        if rel.starts_with("refs") || rel == Path::new("packed-refs") {
+            return true;
+        }
+        if rel == Path::new("info/exclude") || rel == Path::new("info/sparse-checkout") {
+            return true;
+        }
+
+        if let Some(fname) = rel.file_name().and_then(|f| f.to_str()) {
+            if matches!(fname, "MERGE_HEAD" | "CHERRY_PICK_HEAD" | "REVERT_HEAD") {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+fn is_ignore_definition_path(path: &Path, custom_ignore_filenames: &[String]) -> bool {
+    match path.file_name().and_then(|f| f.to_str()) {
+        Some(".ignore") | Some(".gitignore") => true,
+        Some(file_name) => custom_ignore_filenames.iter().any(|name| name == file_name),
+        None => false,
+    }
+}
+
+#[cfg(all(test, not(feature = "git")))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_created_file_is_indexed_without_git() {
+        let _lock = crate::GLOBAL_PICKER_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join("fff_test_watcher_no_git");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("scanned.rs"), "fn scanned() {}\n").unwrap();
+        let base_path = dir.canonicalize().unwrap();
+        let picker = FilePicker::new(
+            base_path.to_string_lossy().into_owned(),
+            ScanOptions::default(),
+        )
+        .unwrap();
+        *FILE_PICKER.write().unwrap() = Some(picker);
+
+        let is_indexed = |path: &Path| {
+            let picker = FILE_PICKER.read().unwrap();
+            let picker = picker.as_ref().unwrap();
+            !picker.is_scan_active() && picker.get_file_by_path(path).is_some()
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !is_indexed(&base_path.join("scanned.rs")) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        // the watcher starts after the scan, the file is rewritten until an event reaches it
+        let created = base_path.join("created.rs");
+        let indexed = loop {
+            std::fs::write(&created, "fn created() {}\n").unwrap();
+            std::thread::sleep(Duration::from_millis(200));
+            if is_indexed(&created) || Instant::now() > deadline {
+                break is_indexed(&created);
+            }
+        };
+
+        if let Some(mut picker) = FILE_PICKER.write().unwrap().take() {
+            picker.stop_background_monitor();
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(indexed);
+    }
+}
@@ -0,0 +1,190 @@
+//! Scan-time classification of files into roles (test, config, docs, source) by glob rules
+//! and the `cat:` query filter narrowing results to them.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::sync::Arc;
+use tracing::warn;
+
+const CATEGORY_FILTER_PREFIX: &str = "cat:";
+
+/// Globs are matched against the path relative to the base path, the first matching rule wins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryRule {
+    pub name: String,
+    pub globs: Vec<String>,
+}
+
+impl CategoryRule {
+    fn new(name: &str, globs: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            globs: globs.iter().map(|glob| glob.to_string()).collect(),
+        }
+    }
+}
+
+pub fn default_category_rules() -> Vec<CategoryRule> {
+    vec![
+        CategoryRule::new(
+            "test",
+            &[
+                "**/test/**",
+                "**/tests/**",
+                "**/__tests__/**",
+                "**/spec/**",
+                "**/*_test.*",
+                "**/*.test.*",
+                "**/*.spec.*",
+                "**/*_spec.*",
+                "**/test_*.py",
+            ],
+        ),
+        CategoryRule::new(
+            "docs",
+            &["**/doc/**", "**/docs/**", "**/*.md", "**/*.rst", "**/*.adoc"],
+        ),
+        CategoryRule::new(
+            "config",
+            &[
+                "**/*.toml",
+                "**/*.yaml",
+                "**/*.yml",
+                "**/*.json",
+                "**/*.ini",
+                "**/*.conf",
+                "**/.*rc",
+                "**/.env*",
+                "**/Dockerfile",
+                "**/Makefile",
+            ],
+        ),
+        CategoryRule::new(
+            "source",
+            &[
+                "**/*.{rs,c,h,cc,cpp,hpp,go,py,rb,js,jsx,ts,tsx,mjs,cjs,vue,svelte,lua,java,kt,swift,cs,php,ex,exs,zig,sh}",
+            ],
+        ),
+    ]
+}
+
+/// Compiled category rules
+#[derive(Debug, Default)]
+pub struct Classifier {
+    rules: Vec<(Arc<str>, GlobSet)>,
+}
+
+impl Classifier {
+    /// Invalid globs are logged and skipped so a typo doesn't break the whole scan
+    pub fn new(rules: &[CategoryRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| {
+                let mut builder = GlobSetBuilder::new();
+                for glob in &rule.globs {
+                    match Glob::new(glob) {
+                        Ok(glob) => {
+                            builder.add(glob);
+                        }
+                        Err(e) => {
+                            warn!("Invalid glob {:?} for category {}: {}", glob, rule.name, e)
+                        }
+                    }
+                }
+
+                match builder.build() {
+                    Ok(set) => Some((Arc::from(rule.name.as_str()), set)),
+                    Err(e) => {
+                        warn!("Failed to build globs for category {}: {}", rule.name, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    pub fn classify(&self, relative_path: &str) -> Option<Arc<str>> {
+        self.rules
+            .iter()
+            .find(|(_, set)| set.is_match(relative_path))
+            .map(|(name, _)| Arc::clone(name))
+    }
+}
+
+/// Categories requested with `cat:name` and excluded with `cat:!name` in the query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl CategoryFilter {
+    /// Splits the `cat:` tokens out of the query, returning the remaining fuzzy query
+    pub fn parse(query: &str) -> (String, Option<Self>) {
+        if !query.contains(CATEGORY_FILTER_PREFIX) {
+            return (query.to_string(), None);
+        }
+
+        let mut filter = Self::default();
+        let mut rest = Vec::new();
+        for token in query.split_whitespace() {
+            match token.strip_prefix(CATEGORY_FILTER_PREFIX) {
+                Some(name) if name.starts_with('!') && name.len() > 1 => {
+                    filter.exclude.push(name[1..].to_string())
+                }
+                Some(name) if !name.is_empty() && !name.starts_with('!') => {
+                    filter.include.push(name.to_string())
+                }
+                _ => rest.push(token),
+            }
+        }
+
+        if filter.include.is_empty() && filter.exclude.is_empty() {
+            return (query.to_string(), None);
+        }
+
+        (rest.join(" "), Some(filter))
+    }
+
+    pub fn matches(&self, category: Option<&str>) -> bool {
+        let is_excluded =
+            category.is_some_and(|category| self.exclude.iter().any(|c| c == category));
+        let is_included = self.include.is_empty()
+            || category.is_some_and(|category| self.include.iter().any(|c| c == category));
+
+        is_included && !is_excluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_classification() {
+        let classifier = Classifier::new(&default_category_rules());
+        let classify = |path: &str| classifier.classify(path).map(|c| c.to_string());
+
+        assert_eq!(classify("src/user_test.go").as_deref(), Some("test"));
+        assert_eq!(classify("tests/api.rs").as_deref(), Some("test"));
+        assert_eq!(classify("docs/guide.md").as_deref(), Some("docs"));
+        assert_eq!(classify("Cargo.toml").as_deref(), Some("config"));
+        assert_eq!(classify("src/main.rs").as_deref(), Some("source"));
+        assert_eq!(classify("assets/logo.png"), None);
+    }
+
+    #[test]
+    fn test_category_filter_parse() {
+        let (query, filter) = CategoryFilter::parse("user cat:test cat:!docs");
+        assert_eq!(query, "user");
+        let filter = filter.unwrap();
+        assert!(filter.matches(Some("test")));
+        assert!(!filter.matches(Some("docs")));
+        assert!(!filter.matches(None));
+
+        let (query, filter) = CategoryFilter::parse("concat:x");
+        assert_eq!(query, "concat:x");
+        assert!(filter.is_none());
+    }
+}
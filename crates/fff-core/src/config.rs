@@ -0,0 +1,105 @@
+//! Runtime reconfiguration of the global picker and frecency tracker without dropping the index.
+
+use crate::error::Error;
+use crate::profile::{self, SearchProfile};
+use crate::redact::{self, RedactionOptions};
+use crate::types::{FrecencyOptions, MemoryOptions, ScanOptions, ScoringWeights, WatcherOptions};
+use crate::{FILE_PICKER, FRECENCY};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Sections to update, `None` leaves the section untouched
+#[derive(Debug, Clone, Default)]
+pub struct ConfigUpdate {
+    pub scan: Option<ScanOptions>,
+    pub scoring: Option<ScoringWeights>,
+    pub watcher: Option<WatcherOptions>,
+    pub frecency: Option<FrecencyOptions>,
+    pub memory: Option<MemoryOptions>,
+    /// Search profiles by name, replacing all the previous ones
+    pub profiles: Option<HashMap<String, SearchProfile>>,
+    /// Masking of credentials in previews, applied by `redact::read_redacted_lines`
+    pub redaction: Option<RedactionOptions>,
+}
+
+/// Applies the update while holding both global locks so searches never observe a half
+/// applied configuration. Returns the names of the sections that actually changed.
+pub fn configure(update: ConfigUpdate) -> Result<Vec<&'static str>, Error> {
+    let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
+    let mut frecency = FRECENCY.write().map_err(|_| Error::AcquireFrecencyLock)?;
+    let mut changed = Vec::new();
+
+    if let Some(profiles) = update.profiles {
+        if profile::set_profiles(profiles) {
+            info!("Search profiles changed");
+            changed.push("profiles");
+        }
+    }
+
+    if let Some(redaction) = update.redaction {
+        if redact::set_options(redaction) {
+            info!("Redaction options changed");
+            changed.push("redaction");
+        }
+    }
+
+    if let Some(frecency_options) = update.frecency {
+        match frecency.as_mut() {
+            Some(tracker) if tracker.options() != frecency_options => {
+                info!(old = ?tracker.options(), new = ?frecency_options, "Frecency options changed");
+                tracker.set_options(frecency_options);
+                if let Some(picker) = file_picker.as_mut() {
+                    picker.refresh_frecency_scores(tracker)?;
+                }
+                changed.push("frecency");
+            }
+            Some(_) => {}
+            None => warn!("Frecency options ignored, the database is not initialized"),
+        }
+    }
+
+    let Some(picker) = file_picker.as_mut() else {
+        if update.scan.is_some()
+            || update.scoring.is_some()
+            || update.watcher.is_some()
+            || update.memory.is_some()
+        {
+            warn!("Picker options ignored, the file picker is not initialized");
+        }
+        return Ok(changed);
+    };
+
+    if let Some(scoring_weights) = update.scoring {
+        if scoring_weights != picker.scoring_weights() {
+            info!(old = ?picker.scoring_weights(), new = ?scoring_weights, "Scoring weights changed");
+            picker.set_scoring_weights(scoring_weights);
+            changed.push("scoring");
+        }
+    }
+
+    if let Some(memory_options) = update.memory {
+        if memory_options != picker.memory_options() {
+            info!(old = ?picker.memory_options(), new = ?memory_options, "Memory options changed");
+            picker.set_memory_options(memory_options);
+            changed.push("memory");
+        }
+    }
+
+    let scan_changed = update
+        .scan
+        .as_ref()
+        .is_some_and(|scan| scan != picker.scan_options());
+    let watcher_changed = update
+        .watcher
+        .is_some_and(|watcher| watcher != picker.watcher_options());
+
+    picker.reconfigure(update.scan, update.watcher)?;
+    if scan_changed {
+        changed.push("scan");
+    }
+    if watcher_changed {
+        changed.push("watcher");
+    }
+
+    Ok(changed)
+}
@@ -0,0 +1,368 @@
+use crate::error::Error;
+use crate::git::format_git_status;
+use crate::replay;
+use crate::{FILE_PICKER, FRECENCY};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Only the tail of the log is included, it is truncated on every start anyway
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Writes a tar archive with everything useful for a bug report: the tail of the log file,
+/// platform information, index and database statistics plus any extra sections provided by
+/// the caller (health check output, config snapshot). While searches are recorded, their trace
+/// with the paths of the indexed files is added for `replay::replay`. Contents of indexed files
+/// are never included, and unless `include_paths` is set every path component and query is
+/// replaced by its hash, so the ranking of a redacted trace can't be replayed faithfully.
+pub fn generate_debug_bundle(
+    bundle_path: &Path,
+    extra_sections: &BTreeMap<String, String>,
+    include_paths: bool,
+) -> Result<(), Error> {
+    if let Some(parent) = bundle_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut builder = tar::Builder::new(File::create(bundle_path)?);
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    append_entry(
+        &mut builder,
+        "platform.txt",
+        platform_info().as_bytes(),
+        mtime,
+    )?;
+    append_entry(
+        &mut builder,
+        "index_stats.txt",
+        index_stats(include_paths)?.as_bytes(),
+        mtime,
+    )?;
+
+    let trace = match *FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)? {
+        Some(ref picker) => replay::write_trace(picker, include_paths)?,
+        None => None,
+    };
+    if let Some(trace) = trace {
+        append_entry(&mut builder, replay::TRACE_ENTRY, trace.as_bytes(), mtime)?;
+    }
+
+    for (name, content) in extra_sections {
+        let content = match include_paths {
+            true => content.clone(),
+            false => redact_text(content),
+        };
+        append_entry(
+            &mut builder,
+            &format!("{}.txt", sanitize_entry_name(name)),
+            content.as_bytes(),
+            mtime,
+        )?;
+    }
+
+    match crate::tracing::log_file_path() {
+        Some(log_path) => {
+            let mut log = read_log_tail(log_path)?;
+            if !include_paths {
+                log = redact_text(&String::from_utf8_lossy(&log)).into_bytes();
+            }
+            append_entry(&mut builder, "fff.log", &log, mtime)?;
+        }
+        None => {
+            append_entry(
+                &mut builder,
+                "fff.log",
+                b"tracing is not initialized\n",
+                mtime,
+            )?;
+        }
+    }
+
+    builder.into_inner()?.sync_all()?;
+    ::tracing::info!("Debug bundle written to {}", bundle_path.display());
+
+    Ok(())
+}
+
+/// Replaces every component of the path by a short hash of it, keeping the separators and short
+/// extensions so the shape of the tree and the languages stay visible
+pub fn redact_path(path: &str) -> String {
+    let mut redacted = String::with_capacity(path.len());
+    let mut start = 0;
+    for (offset, separator) in path.match_indices(['/', '\\']) {
+        redacted.push_str(&redact_component(&path[start..offset]));
+        redacted.push_str(separator);
+        start = offset + 1;
+    }
+    redacted.push_str(&redact_component(&path[start..]));
+    redacted
+}
+
+fn redact_component(component: &str) -> String {
+    if component.is_empty() || component == "." || component == ".." {
+        return component.to_string();
+    }
+
+    let hash = blake3::hash(component.as_bytes()).to_hex();
+    let extension = Path::new(component)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .filter(|extension| {
+            extension.len() <= 6 && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    match extension {
+        Some(extension) => format!("{}.{}", &hash[..12], extension),
+        None => hash[..12].to_string(),
+    }
+}
+
+/// Redacts every word of free text that looks like a path, i.e. contains a separator
+fn redact_text(text: &str) -> String {
+    let is_boundary = |c: char| {
+        c.is_whitespace()
+            || matches!(
+                c,
+                '"' | '\'' | '`' | ',' | '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>' | '='
+            )
+    };
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut word_start = None;
+    for (offset, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        if !is_boundary(c) {
+            word_start.get_or_insert(offset);
+            continue;
+        }
+
+        if let Some(start) = word_start.take() {
+            let word = &text[start..offset];
+            match word.contains(['/', '\\']) {
+                true => redacted.push_str(&redact_path(word)),
+                false => redacted.push_str(word),
+            }
+        }
+        if offset < text.len() {
+            redacted.push(c);
+        }
+    }
+    redacted
+}
+
+fn append_entry(
+    builder: &mut tar::Builder<File>,
+    name: &str,
+    content: &[u8],
+    mtime: u64,
+) -> Result<(), Error> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+
+    builder.append_data(&mut header, name, content)?;
+    Ok(())
+}
+
+fn sanitize_entry_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn read_log_tail(log_path: &Path) -> Result<Vec<u8>, Error> {
+    let mut file = File::open(log_path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(MAX_LOG_BYTES)))?;
+
+    let mut buffer = Vec::with_capacity(len.min(MAX_LOG_BYTES) as usize);
+    file.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn platform_info() -> String {
+    let mut info = String::new();
+    let _ = writeln!(info, "fff_nvim version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(info, "os: {}", std::env::consts::OS);
+    let _ = writeln!(info, "os family: {}", std::env::consts::FAMILY);
+    let _ = writeln!(info, "arch: {}", std::env::consts::ARCH);
+    let _ = writeln!(
+        info,
+        "available parallelism: {}",
+        std::thread::available_parallelism().map_or(0, |n| n.get())
+    );
+    info
+}
+
+fn index_stats(include_paths: bool) -> Result<String, Error> {
+    let mut stats = String::new();
+    let path = |path: &Path| match include_paths {
+        true => path.display().to_string(),
+        false => redact_path(&path.to_string_lossy()),
+    };
+
+    match *FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)? {
+        Some(ref picker) => {
+            let files = picker.get_files();
+            let _ = writeln!(stats, "base path: {}", path(picker.base_path()));
+            let _ = writeln!(stats, "git root: {:?}", picker.git_root().map(path));
+            let _ = writeln!(stats, "is scanning: {}", picker.is_scan_active());
+            let _ = writeln!(stats, "indexed files: {}", files.len());
+            let _ = writeln!(stats, "scan options: {:?}", picker.scan_options());
+            let _ = writeln!(stats, "languages: {:?}", picker.index_stats().languages);
+
+            let mut git_statuses = BTreeMap::new();
+            for file in files {
+                *git_statuses
+                    .entry(format_git_status(file.git_status))
+                    .or_insert(0usize) += 1;
+            }
+            let _ = writeln!(stats, "git statuses: {:?}", git_statuses);
+        }
+        None => {
+            let _ = writeln!(stats, "file picker: not initialized");
+        }
+    }
+
+    match *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? {
+        Some(ref frecency) => {
+            let _ = writeln!(stats, "frecency db: {:?}", frecency.size_stats()?);
+        }
+        None => {
+            let _ = writeln!(stats, "frecency db: not initialized");
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_picker::FilePicker;
+    use crate::replay::TracedSearch;
+    use crate::types::{ScanOptions, ScoringWeights};
+    use std::time::{Duration, Instant};
+
+    fn bundle_contents(bundle_path: &Path) -> String {
+        let mut contents = String::new();
+        let mut archive = tar::Archive::new(File::open(bundle_path).unwrap());
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            contents.push_str(&entry.path().unwrap().to_string_lossy());
+            entry.read_to_string(&mut contents).unwrap();
+        }
+        contents
+    }
+
+    #[test]
+    fn test_redact_path() {
+        let redacted = redact_path("/home/quokka/src/main.rs");
+        assert!(!redacted.contains("home") && !redacted.contains("quokka"));
+        assert!(redacted.starts_with('/') && redacted.ends_with(".rs"));
+        assert_eq!(redacted.matches('/').count(), 4);
+        assert_eq!(redact_path("src/main.rs"), redact_path("src/main.rs"));
+        assert_eq!(
+            redact_path("./a/../b"),
+            format!("./{}/../{}", redact_component("a"), redact_component("b"))
+        );
+
+        let text = redact_text("opened \"/home/quokka/notes.md\" (path=C:\\quokka\\a.txt) done");
+        assert!(!text.contains("quokka"));
+        assert!(text.starts_with("opened \"/") && text.ends_with(".txt) done"));
+    }
+
+    #[test]
+    fn test_bundle_hashes_paths_by_default() {
+        let _lock = crate::GLOBAL_PICKER_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join("fff_test_bundle_quokka");
+        let _ = std::fs::remove_dir_all(&dir);
+        for path in ["zebrafolder/quokkafile.rs", "marmotdir/walrusnotes.md"] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "contents\n").unwrap();
+        }
+
+        let picker =
+            FilePicker::new(dir.to_string_lossy().into_owned(), ScanOptions::default()).unwrap();
+        *FILE_PICKER.write().unwrap() = Some(picker);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let scanned_generation = || {
+            let picker = FILE_PICKER.read().unwrap();
+            let picker = picker.as_ref().unwrap();
+            (!picker.is_scan_active() && picker.get_files().len() == 2)
+                .then(|| picker.index_generation())
+        };
+        let generation = loop {
+            if let Some(generation) = scanned_generation() {
+                break generation;
+            }
+            assert!(Instant::now() < deadline, "scan didn't finish");
+            std::thread::sleep(Duration::from_millis(20));
+        };
+
+        replay::set_capacity(4);
+        replay::record_search(TracedSearch {
+            generation,
+            query: "quokka".to_string(),
+            current_file: Some("marmotdir/walrusnotes.md".to_string()),
+            current_package: None,
+            max_results: 10,
+            weights: ScoringWeights::default(),
+            globs: Some(vec!["zebrafolder/**".to_string()]),
+            results: vec!["zebrafolder/quokkafile.rs".to_string()],
+        });
+        let extra_sections = BTreeMap::from([(
+            "config".to_string(),
+            format!("{{ base_path = '{}' }}", dir.display()),
+        )]);
+
+        let bundle_path = std::env::temp_dir().join("fff_test_bundle.tar");
+        let redacted = generate_debug_bundle(&bundle_path, &extra_sections, false)
+            .map(|()| bundle_contents(&bundle_path));
+        let included = generate_debug_bundle(&bundle_path, &extra_sections, true)
+            .map(|()| bundle_contents(&bundle_path));
+
+        replay::set_capacity(0);
+        if let Some(mut picker) = FILE_PICKER.write().unwrap().take() {
+            picker.stop_background_monitor();
+        }
+        let _ = std::fs::remove_file(&bundle_path);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (redacted, included) = (redacted.unwrap(), included.unwrap());
+        assert!(redacted.contains(replay::TRACE_ENTRY));
+        for component in [
+            "fff_test_bundle_quokka",
+            "zebra",
+            "quokka",
+            "marmot",
+            "walrus",
+        ] {
+            assert!(
+                !redacted.contains(component),
+                "{} is in the bundle",
+                component
+            );
+        }
+        assert!(included.contains("zebrafolder/quokkafile.rs"));
+        assert!(included.contains("fff_test_bundle_quokka"));
+    }
+}
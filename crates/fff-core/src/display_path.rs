@@ -0,0 +1,186 @@
+//! Middle truncation of long paths for the result list. The first directory and the file name
+//! are always shown, directories containing matched characters are kept before the others so
+//! the highlights of the query survive the truncation, and every run of omitted directories
+//! becomes a single `…`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const ELLIPSIS: &str = "…";
+
+/// Columns a displayed path may take, 0 leaves paths alone
+static PATH_WIDTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Columns the frontend has for a path, 0 disables the elision
+pub fn set_width(columns: usize) {
+    PATH_WIDTH.store(columns, Ordering::Relaxed);
+}
+
+pub fn width() -> usize {
+    PATH_WIDTH.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayPath {
+    pub text: String,
+    /// Byte ranges of `text` matched by the query, the end is exclusive
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// Byte positions of `path` matched by the words of `query`, location suffixes like `:12` and
+/// words that don't match are ignored
+fn matched_bytes(path: &str, query: &str) -> Vec<bool> {
+    let mut matched = vec![false; path.len()];
+    let options = neo_frizbee::Options {
+        max_typos: None,
+        ..Default::default()
+    };
+
+    for word in query.split_whitespace() {
+        let word = word.split(':').next().unwrap_or_default();
+        if word.is_empty() {
+            continue;
+        }
+        let Some(indices) = neo_frizbee::match_indices(word, path, options) else {
+            continue;
+        };
+        for index in indices.indices {
+            if let Some(byte) = matched.get_mut(index) {
+                *byte = true;
+            }
+        }
+    }
+
+    matched
+}
+
+/// Shortens `path` to `max_width` columns where possible
+pub fn elide(path: &str, query: &str, max_width: usize) -> DisplayPath {
+    let matched = matched_bytes(path, query);
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for segment in path.split('/') {
+        segments.push(start..start + segment.len());
+        start += segment.len() + 1;
+    }
+
+    let columns = |range: &std::ops::Range<usize>| path[range.clone()].chars().count();
+    if max_width == 0 || path.chars().count() <= max_width || segments.len() < 3 {
+        let keep = vec![true; segments.len()];
+        return assemble(path, &segments, &keep, &matched);
+    }
+
+    let last = segments.len() - 1;
+    let mut keep = vec![false; segments.len()];
+    keep[0] = true;
+    keep[last] = true;
+
+    let displayed_width = |keep: &[bool]| {
+        let mut width = 0;
+        let mut parts: usize = 0;
+        let mut omitting = false;
+        for (segment, &kept) in segments.iter().zip(keep) {
+            if kept {
+                width += columns(segment);
+                parts += 1;
+                omitting = false;
+            } else if !omitting {
+                width += ELLIPSIS.chars().count();
+                parts += 1;
+                omitting = true;
+            }
+        }
+        width + parts.saturating_sub(1)
+    };
+
+    // directories with highlights first, then the ones closest to the file name
+    for index in (1..last).rev() {
+        if matched[segments[index].clone()].contains(&true) {
+            keep[index] = true;
+            if displayed_width(&keep) > max_width {
+                keep[index] = false;
+            }
+        }
+    }
+    for index in (1..last).rev() {
+        if keep[index] {
+            continue;
+        }
+        keep[index] = true;
+        if displayed_width(&keep) > max_width {
+            keep[index] = false;
+            break;
+        }
+    }
+
+    assemble(path, &segments, &keep, &matched)
+}
+
+fn assemble(
+    path: &str,
+    segments: &[std::ops::Range<usize>],
+    keep: &[bool],
+    matched: &[bool],
+) -> DisplayPath {
+    let mut text = String::with_capacity(path.len());
+    let mut highlights: Vec<(usize, usize)> = Vec::new();
+    let mut omitting = false;
+
+    for (segment, &kept) in segments.iter().zip(keep) {
+        if !kept && omitting {
+            continue;
+        }
+        if !text.is_empty() || segment.start > 0 {
+            text.push('/');
+        }
+        if !kept {
+            text.push_str(ELLIPSIS);
+            omitting = true;
+            continue;
+        }
+        omitting = false;
+
+        for (offset, ch) in path[segment.clone()].char_indices() {
+            let position = text.len();
+            let byte = segment.start + offset;
+            if matched[byte] {
+                let end = position + ch.len_utf8();
+                match highlights.last_mut() {
+                    Some(last) if last.1 == position => last.1 = end,
+                    _ => highlights.push((position, end)),
+                }
+            }
+            text.push(ch);
+        }
+    }
+
+    DisplayPath { text, highlights }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elide() {
+        let path = "src/components/deeply/nested/file.rs";
+
+        assert_eq!(elide(path, "", 80).text, path);
+        assert_eq!(elide(path, "", 28).text, "src/…/deeply/nested/file.rs");
+        assert_eq!(elide(path, "", 10).text, "src/…/file.rs");
+        assert_eq!(elide("a/b.rs", "", 3).text, "a/b.rs");
+
+        let elided = elide(path, "ponent", 28);
+        assert_eq!(elided.text, "src/components/…/file.rs");
+        assert_eq!(elided.highlights, vec![(7, 13)]);
+
+        let whole = elide(path, "file", 0);
+        assert_eq!(whole.text, path);
+        assert_eq!(whole.highlights, vec![(29, 33)]);
+
+        let elided = elide(path, "nested file.rs:12", 24);
+        assert_eq!(elided.text, "src/…/nested/file.rs");
+        // the ellipsis takes 3 bytes
+        assert_eq!(elided.highlights.last(), Some(&(15, 22)));
+    }
+}
@@ -31,15 +31,30 @@ pub enum Error {
     DbWrite(#[source] heed::Error),
     #[error("Failed to commit write transaction to frecency database: {0}")]
     DbCommit(#[source] heed::Error),
+    #[error("Failed to resize frecency database: {0}")]
+    DbResize(#[source] heed::Error),
+    #[error("Failed to compact frecency database: {0}")]
+    DbCompact(#[source] heed::Error),
+    #[error("Failed to access index snapshot database: {0}")]
+    SnapshotDb(#[source] heed::Error),
+    #[error("Corrupted index snapshot: {0}")]
+    CorruptedSnapshot(&'static str),
     #[error("Failed to start file system watcher: {0}")]
     FileSystemWatch(#[from] notify::Error),
-}
-
-impl From<Error> for mlua::Error {
-    fn from(value: Error) -> Self {
-        let string_value = value.to_string();
-
-        ::tracing::error!(string_value);
-        mlua::Error::RuntimeError(string_value)
-    }
+    #[error("Failed to list remote files: {0}")]
+    RemoteListing(String),
+    #[error("Invalid glob: {0}")]
+    InvalidGlob(String),
+    #[error("Failed to write {0}: {1}")]
+    WriteFile(std::path::PathBuf, #[source] std::io::Error),
+    #[error("No replacement prepared")]
+    ReplacePlanMissing,
+    #[error("Invalid search options: {0}")]
+    InvalidSearchOptions(String),
+    #[error("Invalid search trace: {0}")]
+    InvalidTrace(String),
+    #[error("Invalid evaluation corpus: {0}")]
+    InvalidCorpus(String),
+    #[error("Redaction is not enabled")]
+    RedactionDisabled,
 }
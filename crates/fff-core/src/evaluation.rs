@@ -0,0 +1,253 @@
+//! Offline evaluation of the ranking. A corpus of queries with the file that was picked for
+//! each of them, e.g. the selection history the plugin records with `debug.selection_history`,
+//! is searched again against an index and the ranks of the picked files are summed up into the
+//! mean reciprocal rank and the top-k hit rates.
+
+use crate::error::Error;
+use crate::file_picker::FilePicker;
+use crate::scorer::Scorer;
+use crate::types::ScoringWeights;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Cutoffs of the reported hit rates
+pub const HIT_RANKS: [usize; 4] = [1, 3, 5, 10];
+/// Misses listed by the text report
+const LISTED_MISSES: usize = 10;
+
+/// One line of a corpus, the expected path is relative to the base path of the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalCase {
+    pub query: String,
+    pub expected: String,
+    #[serde(default)]
+    pub current_file: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaseResult {
+    pub query: String,
+    pub expected: String,
+    /// 1-based rank of the expected file, `None` below the searched ranks
+    pub rank: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HitRate {
+    pub k: usize,
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalReport {
+    /// Cases of the corpus
+    pub cases: usize,
+    /// Cases whose expected file is not in the index, left out of the metrics
+    pub not_indexed: usize,
+    /// Ranks searched for the expected file
+    pub max_rank: usize,
+    /// Mean reciprocal rank of the evaluated cases, 0 for an expected file below `max_rank`
+    pub mrr: f64,
+    pub hit_rates: Vec<HitRate>,
+    pub results: Vec<CaseResult>,
+}
+
+/// Reads a corpus of json lines, blank lines are skipped
+pub fn read_corpus(path: &Path) -> Result<Vec<EvalCase>, Error> {
+    let mut cases = Vec::new();
+    for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let case = serde_json::from_str(&line)
+            .map_err(|e| Error::InvalidCorpus(format!("line {}: {}", number + 1, e)))?;
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+/// Searches every case against the index of the picker with the weights and the scorer, the
+/// default scorer when `None`
+pub fn evaluate(
+    picker: &FilePicker,
+    cases: &[EvalCase],
+    weights: ScoringWeights,
+    max_rank: usize,
+    scorer: Option<&dyn Scorer>,
+) -> EvalReport {
+    let files = picker.get_files();
+    let indexed: HashSet<&str> = files
+        .iter()
+        .map(|file| file.relative_path.as_str())
+        .collect();
+
+    let mut not_indexed = 0;
+    let mut results = Vec::with_capacity(cases.len());
+    for case in cases {
+        if !indexed.contains(case.expected.as_str()) {
+            not_indexed += 1;
+            continue;
+        }
+
+        let current_package = case
+            .current_file
+            .as_deref()
+            .and_then(|current_file| picker.package_of(current_file));
+        let result = FilePicker::fuzzy_search_weighted(
+            files,
+            &case.query,
+            max_rank,
+            1,
+            case.current_file.as_deref(),
+            weights,
+            current_package.as_deref(),
+            None,
+            None,
+            picker.index_stats().entry_points(),
+            Some(picker.prefix_candidates()),
+            None,
+            scorer,
+        );
+        let rank = result
+            .items
+            .iter()
+            .position(|file| file.relative_path == case.expected)
+            .map(|position| position + 1);
+
+        results.push(CaseResult {
+            query: case.query.clone(),
+            expected: case.expected.clone(),
+            rank,
+        });
+    }
+
+    EvalReport::new(cases.len(), not_indexed, max_rank, results)
+}
+
+impl EvalReport {
+    pub fn new(
+        cases: usize,
+        not_indexed: usize,
+        max_rank: usize,
+        results: Vec<CaseResult>,
+    ) -> Self {
+        let evaluated = results.len().max(1) as f64;
+        let mrr = results
+            .iter()
+            .filter_map(|result| result.rank)
+            .map(|rank| 1.0 / rank as f64)
+            .sum::<f64>()
+            / evaluated;
+        let hit_rates = HIT_RANKS
+            .iter()
+            .map(|&k| HitRate {
+                k,
+                rate: results
+                    .iter()
+                    .filter(|result| result.rank.is_some_and(|rank| rank <= k))
+                    .count() as f64
+                    / evaluated,
+            })
+            .collect();
+
+        Self {
+            cases,
+            not_indexed,
+            max_rank,
+            mrr,
+            hit_rates,
+            results,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::InvalidCorpus(e.to_string()))
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} cases, {} evaluated, {} expected files not indexed",
+            self.cases,
+            self.results.len(),
+            self.not_indexed
+        )?;
+        writeln!(f, "MRR {:.3}", self.mrr)?;
+        let hit_rates = self
+            .hit_rates
+            .iter()
+            .map(|hit| format!("top {} {:.1}%", hit.k, hit.rate * 100.0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "{}", hit_rates)?;
+
+        // the worst ranked cases first, they are what a scoring change should fix
+        let mut misses: Vec<&CaseResult> = self
+            .results
+            .iter()
+            .filter(|result| result.rank != Some(1))
+            .collect();
+        misses.sort_by_key(|result| std::cmp::Reverse(result.rank.unwrap_or(usize::MAX)));
+        for miss in misses.iter().take(LISTED_MISSES) {
+            let rank = miss
+                .rank
+                .map_or(format!(">{}", self.max_rank), |rank| rank.to_string());
+            writeln!(f, "  {:>5}  {:?} -> {}", rank, miss.query, miss.expected)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_metrics() {
+        let result = |rank| CaseResult {
+            query: "query".to_string(),
+            expected: "expected".to_string(),
+            rank,
+        };
+        let report = EvalReport::new(
+            5,
+            1,
+            100,
+            vec![
+                result(Some(1)),
+                result(Some(2)),
+                result(Some(4)),
+                result(None),
+            ],
+        );
+
+        assert_eq!(report.mrr, (1.0 + 0.5 + 0.25) / 4.0);
+        let rates: Vec<f64> = report.hit_rates.iter().map(|hit| hit.rate).collect();
+        assert_eq!(rates, [0.25, 0.5, 0.75, 0.75]);
+        assert!(report.to_string().contains("MRR 0.438"));
+    }
+
+    #[test]
+    fn test_read_corpus() {
+        let corpus_path = std::env::temp_dir().join("fff_test_corpus.jsonl");
+        std::fs::write(
+            &corpus_path,
+            "{\"query\":\"main\",\"expected\":\"src/main.rs\"}\n\n\
+             {\"query\":\"lib\",\"expected\":\"src/lib.rs\",\"current_file\":\"src/main.rs\"}\n",
+        )
+        .unwrap();
+
+        let cases = read_corpus(&corpus_path).unwrap();
+        std::fs::remove_file(&corpus_path).ok();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[1].current_file.as_deref(), Some("src/main.rs"));
+    }
+}
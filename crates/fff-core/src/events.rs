@@ -0,0 +1,80 @@
+//! Notable things that happened to the index in the background. The watcher runs in its own
+//! thread and can't call into the frontend, so events are queued here until polled.
+
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Oldest events are dropped once a frontend stops polling
+const MAX_QUEUED_EVENTS: usize = 100;
+
+static EVENTS: Lazy<Mutex<VecDeque<PickerEvent>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickerEventKind {
+    /// The watched root was removed, the index is kept until it comes back
+    RootRemoved,
+    /// The root was re-created and the watcher re-established with a fresh scan
+    WatcherRestarted,
+}
+
+impl PickerEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PickerEventKind::RootRemoved => "root_removed",
+            PickerEventKind::WatcherRestarted => "watcher_restarted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickerEvent {
+    pub kind: PickerEventKind,
+    pub path: PathBuf,
+    /// Unix timestamp of the event
+    pub timestamp: u64,
+}
+
+pub fn push_event(kind: PickerEventKind, path: PathBuf) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut events = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    if events.len() >= MAX_QUEUED_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(PickerEvent {
+        kind,
+        path,
+        timestamp,
+    });
+}
+
+/// Takes all the queued events, oldest first
+pub fn drain_events() -> Vec<PickerEvent> {
+    EVENTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain(..)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_drops_oldest_events() {
+        for i in 0..MAX_QUEUED_EVENTS + 5 {
+            push_event(PickerEventKind::RootRemoved, PathBuf::from(i.to_string()));
+        }
+
+        let events = drain_events();
+        assert_eq!(events.len(), MAX_QUEUED_EVENTS);
+        assert_eq!(events[0].path, PathBuf::from("5"));
+        assert!(drain_events().is_empty());
+    }
+}
@@ -0,0 +1,1889 @@
+use crate::archive::split_entry_path;
+use crate::background_watcher::BackgroundWatcher;
+use crate::category::{CategoryFilter, Classifier};
+use crate::error::Error;
+use crate::filter::FileFilter;
+use crate::frecency::{max_total_score, mtime_score, FrecencyReader, FrecencyTracker};
+use crate::git::{
+    co_changed_files, git_status_rank, is_modified_status, GitStatusCache, RecentCommitPaths,
+    Status,
+};
+use crate::glob_filter::GlobFilter;
+use crate::grep::{self, GrepOptions, GrepResult, GrepSession};
+use crate::index_diff::{IndexChangeKind, IndexChangeLog, IndexDiff};
+use crate::languages::IndexStats;
+use crate::memory::{file_item_bytes, file_meta_bytes, IndexMetrics};
+use crate::package::PackageRoots;
+use crate::path_utils::{cmp_paths, expand_path, normalize_path, resolve_path};
+use crate::prefix_cache::{PrefixCache, PrefixCandidates};
+use crate::profile::ProfileFilter;
+use crate::replace::{self, ReplaceOutcome, ReplacePlan};
+use crate::roots::RootLabels;
+use crate::scan_batches;
+use crate::score::normalize_scores;
+use crate::scorer::{DefaultScorer, Scorer};
+use crate::sensitive::{SensitiveGuard, SensitiveMode};
+use crate::source::{ArchiveEntries, DiskWalker, FileSource};
+use crate::speculate::{Speculation, SpeculationKey, MAX_SPECULATED_PREFIXES};
+use crate::supersede::SearchTicket;
+use crate::types::{
+    DiagnosticsCount, FileItem, FileListOptions, FileListPage, FileListSort, FileMeta, IndexDrift,
+    MemoryOptions, ScanOptions, ScoringContext, ScoringWeights, SearchResult, WatcherOptions,
+};
+use crate::vcs::{self, StatusProvider, VcsKind};
+use crate::word_search::{self, WordHit, MAX_COUNTED_LINES};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, MutexGuard,
+};
+use std::time::SystemTime;
+use tracing::{debug, error, info, warn};
+
+use crate::{FILE_PICKER, FRECENCY, SNAPSHOTS};
+
+/// Each older jump is worth this much of the next newer one
+const JUMP_DECAY: f64 = 0.9;
+/// Caps jump recency well below what repeated explicit opens reach, jumps only nudge the ranking
+const MAX_JUMP_SCORE: f64 = 3.0;
+/// Each older commit of the author is worth this much of the next newer one
+const COMMIT_DECAY: f64 = 0.8;
+/// Files of the latest commit get this bonus, after ~10 commits it rounds down to nothing
+const MAX_COMMIT_SCORE: f64 = 4.0;
+
+#[derive(Debug, Clone)]
+struct FileSync {
+    pub files: Vec<FileItem>,
+    pub git_workdir: Option<PathBuf>,
+    pub status_provider: Option<Arc<dyn StatusProvider>>,
+    pub packages: PackageRoots,
+    pub roots: RootLabels,
+}
+
+impl FileSync {
+    fn new() -> Self {
+        Self {
+            files: Vec::new(),
+            git_workdir: None,
+            status_provider: None,
+            packages: PackageRoots::default(),
+            roots: RootLabels::default(),
+        }
+    }
+
+    fn find_file_index(&self, path: &Path, case_insensitive: bool) -> Result<usize, usize> {
+        self.files
+            .binary_search_by(|file| cmp_paths(&file.path, path, case_insensitive))
+    }
+
+    /// Paths are ordered component by component so the files of a directory are contiguous
+    fn dir_range(&self, dir: &Path, case_insensitive: bool) -> std::ops::Range<usize> {
+        let start = match self.find_file_index(dir, case_insensitive) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        };
+        let len = self.files[start..]
+            .iter()
+            .take_while(|file| file.path.starts_with(dir))
+            .count();
+
+        start..start + len
+    }
+}
+
+impl FileItem {
+    pub fn new(path: PathBuf, base_path: &Path, git_status: Option<Status>) -> Self {
+        let (size, modified) = match std::fs::metadata(&path) {
+            Ok(metadata) => (metadata.len(), modified_secs(&metadata)),
+            Err(_) => (0, 0),
+        };
+
+        let mut item = Self::listed(path, base_path, size, modified);
+        item.git_status = git_status;
+        item
+    }
+
+    /// File known from a listing, its size and modification time are not read from the disk
+    pub fn listed(path: PathBuf, base_path: &Path, size: u64, modified: u64) -> Self {
+        let relative_path = pathdiff::diff_paths(&path, base_path)
+            .unwrap_or_else(|| path.clone())
+            .to_string_lossy()
+            .into_owned();
+
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        Self {
+            path,
+            relative_path,
+            file_name: name,
+            size,
+            modified,
+            access_frecency_score: 0,
+            modification_frecency_score: 0,
+            jump_frecency_score: 0,
+            commit_frecency_score: 0,
+            total_frecency_score: 0,
+            git_status: None,
+            diagnostics: DiagnosticsCount::default(),
+            category: None,
+            package: None,
+            root: None,
+            sensitivity: None,
+            meta: None,
+        }
+    }
+
+    pub fn update_frecency_scores(&mut self, tracker: &FrecencyTracker) -> Result<(), Error> {
+        self.access_frecency_score = tracker.get_access_score(&self.path);
+        self.modification_frecency_score = tracker
+            .get_modification_score(self.modified, self.git_status)
+            .max(tracker.get_edit_score(&self.path));
+        self.update_total_frecency_score();
+
+        Ok(())
+    }
+
+    /// Same as `update_frecency_scores` within the read transaction of the reader
+    pub fn update_frecency_scores_with(&mut self, reader: &FrecencyReader) {
+        self.access_frecency_score = reader.get_access_score(&self.path);
+        self.modification_frecency_score = reader
+            .get_modification_score(self.modified, self.git_status)
+            .max(reader.get_edit_score(&self.path));
+        self.update_total_frecency_score();
+    }
+
+    /// Raises the modification score to the one of the modification time alone
+    fn apply_mtime_score(&mut self) {
+        self.modification_frecency_score = self
+            .modification_frecency_score
+            .max(mtime_score(self.modified));
+        self.update_total_frecency_score();
+    }
+
+    fn update_total_frecency_score(&mut self) {
+        self.total_frecency_score = (self.access_frecency_score
+            + self.modification_frecency_score
+            + self.jump_frecency_score
+            + self.commit_frecency_score)
+            .min(max_total_score());
+    }
+
+    /// Locks the tracker and updates frecensy score for one file. If need multiple files updates
+    /// use `update_frecency_scores` instead.
+    pub fn update_frecency_scores_global(&mut self) -> Result<(), Error> {
+        let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+            return Ok(());
+        };
+
+        self.update_frecency_scores(frecency)
+    }
+}
+
+/// One level of the narrowing stack, the files matched by `query` within the level below
+#[derive(Debug)]
+struct Narrowing {
+    query: String,
+    paths: HashSet<PathBuf>,
+}
+
+pub struct FilePicker {
+    base_path: PathBuf,
+    /// Directories outside the base path feeding the same index, e.g. a symlinked shared folder
+    extra_paths: Vec<PathBuf>,
+    scan_options: ScanOptions,
+    scoring_weights: ScoringWeights,
+    watcher_options: WatcherOptions,
+    classifier: Classifier,
+    sensitive_guard: SensitiveGuard,
+    sync_data: FileSync,
+    is_scanning: Arc<AtomicBool>,
+    scanned_files_count: Arc<AtomicUsize>,
+    background_watcher: Option<BackgroundWatcher>,
+    last_index_drift: Option<IndexDrift>,
+    memory_options: MemoryOptions,
+    dropped_meta_count: usize,
+    /// Bumped whenever files are inserted or removed, which invalidates file indices
+    index_generation: u64,
+    /// Bumped every time a git status update is published
+    git_status_generation: u64,
+    narrowing: Vec<Narrowing>,
+    index_changes: IndexChangeLog,
+    index_stats: IndexStats,
+    source: Arc<dyn FileSource>,
+    speculation: Mutex<Speculation>,
+    prefix_cache: Mutex<PrefixCache>,
+    grep_session: Mutex<GrepSession>,
+    replace_plan: Mutex<Option<ReplacePlan>>,
+}
+
+/// Git statuses resolved against the file indices of one index generation. It is built under
+/// the read lock and applied in one go, so searches never see a partially applied refresh.
+#[derive(Debug, Default)]
+pub struct GitStatusUpdate {
+    index_generation: u64,
+    /// file index, new status and the modification score derived from it
+    assignments: Vec<(usize, Status, i64)>,
+}
+
+impl GitStatusUpdate {
+    pub fn len(&self) -> usize {
+        self.assignments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+}
+
+impl std::fmt::Debug for FilePicker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilePicker")
+            .field("base_path", &self.base_path)
+            .field("extra_paths", &self.extra_paths)
+            .field("scan_options", &self.scan_options)
+            .field("scoring_weights", &self.scoring_weights)
+            .field("watcher_options", &self.watcher_options)
+            .field("sync_data", &self.sync_data)
+            .field("source", &self.source)
+            .field("is_scanning", &self.is_scanning.load(Ordering::Relaxed))
+            .field(
+                "scanned_files_count",
+                &self.scanned_files_count.load(Ordering::Relaxed),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl FilePicker {
+    pub fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    pub fn extra_paths(&self) -> &[PathBuf] {
+        &self.extra_paths
+    }
+
+    pub fn git_root(&self) -> Option<&Path> {
+        self.sync_data.git_workdir.as_deref()
+    }
+
+    /// Modification times score the files, no version control system was found
+    fn mtime_fallback(&self) -> bool {
+        self.scan_options.mtime_fallback
+            && self.source.is_local()
+            && self.sync_data.status_provider.is_none()
+    }
+
+    /// Base path relative to the git root when results are displayed relative to the git root
+    /// and the base path is a subdirectory of the repository
+    pub fn display_prefix(&self) -> Option<String> {
+        if !self.scan_options.relative_to_git_root {
+            return None;
+        }
+
+        let prefix = self.base_path.strip_prefix(self.git_root()?).ok()?;
+        (!prefix.as_os_str().is_empty()).then(|| prefix.to_string_lossy().into_owned())
+    }
+
+    pub fn get_files(&self) -> &[FileItem] {
+        &self.sync_data.files
+    }
+
+    /// Indexed files under `scope`, e.g. the cwd of a terminal or a nested project. A scope
+    /// containing the base path keeps every file and one outside of the index keeps none.
+    pub fn scoped_files(&self, scope: Option<&Path>) -> &[FileItem] {
+        let Some(scope) = scope else {
+            return self.get_files();
+        };
+        let scope = normalize_path(scope, &self.base_path);
+        if self.base_path.starts_with(&scope) {
+            return self.get_files();
+        }
+
+        let range = self
+            .sync_data
+            .dir_range(&scope, self.scan_options.case_insensitive);
+        &self.sync_data.files[range]
+    }
+
+    pub fn scan_options(&self) -> &ScanOptions {
+        &self.scan_options
+    }
+
+    /// Bumped by every change of the indexed files
+    pub fn index_generation(&self) -> u64 {
+        self.index_generation
+    }
+
+    pub fn scoring_weights(&self) -> ScoringWeights {
+        self.scoring_weights
+    }
+
+    pub fn set_scoring_weights(&mut self, scoring_weights: ScoringWeights) {
+        self.scoring_weights = scoring_weights;
+    }
+
+    pub fn watcher_options(&self) -> WatcherOptions {
+        self.watcher_options
+    }
+
+    pub fn new(base_path: String, scan_options: ScanOptions) -> Result<Self, Error> {
+        Self::with_source(
+            base_path,
+            scan_options,
+            Arc::new(ArchiveEntries::new(DiskWalker)),
+        )
+    }
+
+    /// Indexes the files listed by the source instead of walking the disk. Sources that are not
+    /// local are neither watched nor looked up in git, and their base path doesn't have to exist.
+    pub fn with_source(
+        base_path: String,
+        scan_options: ScanOptions,
+        source: Arc<dyn FileSource>,
+    ) -> Result<Self, Error> {
+        info!("Initializing FilePicker with base_path: {}", base_path);
+        let path = if source.is_local() {
+            let Ok(path) = resolve_path(&base_path) else {
+                error!("Base path does not exist: {}", base_path);
+                return Err(Error::InvalidPath(expand_path(&base_path)));
+            };
+            path
+        } else {
+            expand_path(&base_path)
+        };
+
+        // marked right away so nobody observes an empty idle index before the scan thread starts
+        let scan_signal = Arc::new(AtomicBool::new(true));
+        let synced_files_count = Arc::new(AtomicUsize::new(0));
+
+        let picker = Self {
+            base_path: path.clone(),
+            extra_paths: Vec::new(),
+            scan_options: scan_options.clone(),
+            scoring_weights: ScoringWeights::default(),
+            watcher_options: WatcherOptions::default(),
+            classifier: Classifier::new(&scan_options.categories),
+            sensitive_guard: SensitiveGuard::new(&scan_options.sensitive),
+            sync_data: FileSync::new(),
+            is_scanning: Arc::clone(&scan_signal),
+            scanned_files_count: Arc::clone(&synced_files_count),
+            background_watcher: None,
+            last_index_drift: None,
+            memory_options: MemoryOptions::default(),
+            dropped_meta_count: 0,
+            index_generation: 0,
+            git_status_generation: 0,
+            narrowing: Vec::new(),
+            index_changes: IndexChangeLog::default(),
+            index_stats: IndexStats::default(),
+            source: Arc::clone(&source),
+            speculation: Mutex::default(),
+            prefix_cache: Mutex::default(),
+            grep_session: Mutex::default(),
+            replace_plan: Mutex::default(),
+        };
+
+        spawn_scan_and_watcher(
+            path.clone(),
+            Vec::new(),
+            scan_options,
+            source,
+            Arc::clone(&scan_signal),
+            Arc::clone(&synced_files_count),
+        );
+
+        Ok(picker)
+    }
+
+    pub fn fuzzy_search<'a>(
+        files: &'a [FileItem],
+        query: &'a str,
+        max_results: usize,
+        max_threads: usize,
+        current_file: Option<&'a str>,
+    ) -> SearchResult<'a> {
+        Self::fuzzy_search_weighted(
+            files,
+            query,
+            max_results,
+            max_threads,
+            current_file,
+            ScoringWeights::default(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Files the searches are restricted to, `None` when nothing was pushed with `push_filter`
+    pub fn narrowed_paths(&self) -> Option<&HashSet<PathBuf>> {
+        self.narrowing.last().map(|narrowing| &narrowing.paths)
+    }
+
+    /// Queries of the narrowing stack from the bottom up
+    pub fn filter_queries(&self) -> Vec<&str> {
+        self.narrowing
+            .iter()
+            .map(|narrowing| narrowing.query.as_str())
+            .collect()
+    }
+
+    /// Restricts the following searches to the files currently matching the query, on top of
+    /// the previous restrictions. Returns the number of files left.
+    pub fn push_filter(&mut self, query: &str, max_threads: usize) -> usize {
+        let result = Self::fuzzy_search_weighted(
+            &self.sync_data.files,
+            query,
+            usize::MAX,
+            max_threads,
+            None,
+            self.scoring_weights,
+            None,
+            None,
+            self.narrowed_paths(),
+            self.index_stats.entry_points(),
+            Some(self.prefix_candidates()),
+            None,
+            None,
+        );
+        let paths: HashSet<PathBuf> = result.items.iter().map(|file| file.path.clone()).collect();
+
+        let count = paths.len();
+        self.narrowing.push(Narrowing {
+            query: query.to_string(),
+            paths,
+        });
+        count
+    }
+
+    /// Drops the latest restriction, returns false if there was none
+    pub fn pop_filter(&mut self) -> bool {
+        self.narrowing.pop().is_some()
+    }
+
+    /// Package of a file given by its path relative to the base path
+    pub fn package_of(&self, relative_path: &str) -> Option<Arc<str>> {
+        self.sync_data.packages.package_of(relative_path)
+    }
+
+    /// Lines of the files under `scope` containing `pattern`, a pattern extending the previous
+    /// one only reads the files that matched it
+    pub fn grep(&self, scope: Option<&Path>, pattern: &str, options: GrepOptions) -> GrepResult {
+        let options = GrepOptions {
+            skip_sensitive: self.sensitive_guard.mode().hides_content(),
+            ..options
+        };
+        grep::grep(
+            self.scoped_files(scope),
+            pattern,
+            options,
+            &self.grep_session,
+            self.index_generation,
+        )
+    }
+
+    /// Files to jump to from `word`, ranked by their path, the lines containing the word and how
+    /// often they were edited together with `current_file`
+    pub fn search_word(
+        &self,
+        word: &str,
+        current_file: Option<&Path>,
+        max_results: usize,
+        max_threads: usize,
+    ) -> Vec<WordHit> {
+        let word = word.trim();
+        if word.is_empty() {
+            return Vec::new();
+        }
+
+        let files = self.get_files();
+        let current_file = current_file.map(|path| self.base_path.join(path));
+        let names = Self::fuzzy_search(files, word, max_results, max_threads, None);
+        // a one off search, the session of the live grep keeps narrowing its own pattern
+        let options = GrepOptions {
+            max_matches_per_file: MAX_COUNTED_LINES,
+            skip_sensitive: self.sensitive_guard.mode().hides_content(),
+            ..GrepOptions::default()
+        };
+        let content = grep::grep(
+            files,
+            word,
+            options,
+            &Mutex::default(),
+            self.index_generation,
+        );
+
+        let mut co_changed = HashSet::new();
+        if let Some(current_file) = current_file.as_deref() {
+            if let Some(committed) = co_changed_files(current_file) {
+                co_changed.extend(committed.iter().cloned());
+            }
+            if let Some(Ok(related)) = FRECENCY
+                .read()
+                .ok()
+                .as_ref()
+                .and_then(|frecency| frecency.as_ref().map(|f| f.get_co_changes(current_file)))
+            {
+                co_changed.extend(related.into_iter().map(|(path, _)| path));
+            }
+        }
+
+        word_search::rank(
+            &names.items,
+            content,
+            &co_changed,
+            current_file.as_deref(),
+            max_results,
+        )
+    }
+
+    /// Edits replacing `pattern` in the files under `scope`, kept for `apply_replace`
+    pub fn prepare_replace(
+        &self,
+        scope: Option<&Path>,
+        pattern: &str,
+        replacement: &str,
+        glob_filter: Option<&GlobFilter>,
+        ignore_case: bool,
+    ) -> ReplacePlan {
+        let plan = replace::prepare(
+            self.scoped_files(scope),
+            pattern,
+            replacement,
+            glob_filter,
+            GrepOptions::default().max_file_size,
+            ignore_case,
+        );
+        *self.replace_plan.lock().unwrap_or_else(|e| e.into_inner()) = Some(plan.clone());
+        plan
+    }
+
+    /// Writes the hunks of the prepared replacement with the given ids, all of them without
+    /// ids, and updates the index right away instead of waiting for the watcher
+    pub fn apply_replace(&mut self, selection: Option<&[usize]>) -> Result<ReplaceOutcome, Error> {
+        let plan = self
+            .replace_plan
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner());
+        let outcome = replace::apply(plan.as_ref().ok_or(Error::ReplacePlanMissing)?, selection)?;
+        if !outcome.written.is_empty() {
+            // the hunks of the written files are stale now
+            *plan = None;
+        }
+        for path in &outcome.written {
+            self.on_create_or_modify(path);
+        }
+        Ok(outcome)
+    }
+
+    /// Candidate cache of the searches, valid for the files of the current index generation
+    pub fn prefix_candidates(&self) -> PrefixCandidates<'_> {
+        PrefixCandidates {
+            cache: &self.prefix_cache,
+            generation: self.index_generation,
+        }
+    }
+
+    /// With a ticket the search returns early with `superseded` set once a newer search of the
+    /// same picker starts. `narrowed_to` restricts the search to the top of the narrowing stack.
+    /// `prefix_candidates` must belong to the picker `files` come from, `glob_filter` keeps the
+    /// files passing the rg style globs of the caller.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fuzzy_search_weighted<'a>(
+        files: &'a [FileItem],
+        query: &'a str,
+        max_results: usize,
+        max_threads: usize,
+        current_file: Option<&'a str>,
+        weights: ScoringWeights,
+        current_package: Option<&'a str>,
+        ticket: Option<&'a SearchTicket>,
+        narrowed_to: Option<&'a HashSet<PathBuf>>,
+        entry_points: Option<&'a [&'static str]>,
+        prefix_candidates: Option<PrefixCandidates<'a>>,
+        glob_filter: Option<&'a GlobFilter>,
+        scorer: Option<&'a dyn Scorer>,
+    ) -> SearchResult<'a> {
+        let max_threads = max_threads.max(1);
+        debug!(
+            "Fuzzy search: query='{}', max_results={}, max_threads={}, current_file={:?}",
+            query, max_results, max_threads, current_file
+        );
+
+        let total_files = narrowed_to.map_or(files.len(), HashSet::len);
+        // files edited together with the current one before are likely the next to be opened
+        let current_item = current_file
+            .and_then(|current_file| files.iter().find(|f| f.relative_path == current_file));
+        let co_changed = current_item
+            .filter(|file| file.git_status.is_some_and(is_modified_status))
+            .and_then(|file| co_changed_files(&file.path));
+        let related_by_history: Option<HashMap<PathBuf, u32>> = current_item.and_then(|file| {
+            let frecency = FRECENCY.read().ok()?;
+            let related = frecency.as_ref()?.get_co_changes(&file.path).ok()?;
+            Some(related.into_iter().collect())
+        });
+        let (query, profile) = ProfileFilter::parse(query);
+        let weights = profile
+            .as_ref()
+            .and_then(|profile| profile.scoring())
+            .unwrap_or(weights);
+        let session_files = (weights.session != 0)
+            .then(|| FRECENCY.read().ok()?.as_ref()?.session_files().ok())
+            .flatten();
+        let time_of_day_scores = (weights.time_of_day != 0)
+            .then(|| FRECENCY.read().ok()?.as_ref()?.time_of_day_scores().ok())
+            .flatten();
+        let (query, category_filter) = CategoryFilter::parse(&query);
+        let (query, mut file_filter) = FileFilter::parse(&query);
+        if let Some(file_filter) = file_filter.as_mut() {
+            file_filter.load_authors(files);
+        }
+        let query = query.as_str();
+
+        // small queries with a large number of results can match absolutely everything
+        let max_typos = (query.len() as u16 / 4).clamp(2, 6);
+        let candidates = prefix_candidates
+            .and_then(|prefix_candidates| prefix_candidates.lookup(files, query, max_typos));
+        let context = ScoringContext {
+            query,
+            max_typos,
+            max_threads,
+            current_file,
+            max_results,
+            weights,
+            category_filter: category_filter.as_ref(),
+            file_filter: file_filter.as_ref(),
+            profile: profile.as_deref(),
+            glob_filter,
+            current_package,
+            ticket,
+            narrowed_to,
+            co_changed: co_changed.as_deref(),
+            related_by_history: related_by_history.as_ref(),
+            session_files: session_files.as_deref(),
+            time_of_day_scores: time_of_day_scores.as_deref(),
+            entry_points,
+            candidates: candidates.as_deref(),
+        };
+
+        let time = std::time::Instant::now();
+        let (items, mut scores, total_matched, approximate) = scorer
+            .unwrap_or(&DefaultScorer)
+            .match_and_score(files, &context);
+        if weights.normalize_scores {
+            normalize_scores(&mut scores);
+        }
+        debug!(
+            "Fuzzy search completed in {:?}: found {} results for query '{}', top result {:?}",
+            time.elapsed(),
+            total_matched,
+            query,
+            items.first(),
+        );
+        SearchResult {
+            items,
+            scores,
+            total_matched,
+            total_files,
+            superseded: context.is_superseded(),
+            approximate,
+            groups: None,
+            display_prefix: None,
+        }
+    }
+
+    /// Inputs of a search besides the query, speculated results are reused only if they match
+    pub fn speculation_key(
+        &self,
+        scope: Option<&Path>,
+        current_file: Option<&str>,
+        max_results: usize,
+    ) -> SpeculationKey {
+        SpeculationKey {
+            generations: (
+                self.index_generation,
+                self.git_status_generation,
+                self.index_changes.generation(),
+            ),
+            scope: scope.map(Path::to_path_buf),
+            current_file: current_file.map(str::to_string),
+            max_results,
+            weights: self.scoring_weights,
+            narrowing_depth: self.narrowing.len(),
+        }
+    }
+
+    pub fn speculation(&self) -> MutexGuard<'_, Speculation> {
+        self.speculation.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Matches the most often typed prefixes ahead of time, meant to run while the picker waits
+    /// for input after the empty query. The ticket of the next real search stops it.
+    pub fn speculate(
+        &self,
+        scope: Option<&Path>,
+        current_file: Option<&str>,
+        max_results: usize,
+        max_threads: usize,
+        ticket: &SearchTicket,
+    ) {
+        let key = self.speculation_key(scope, current_file, max_results);
+        let prefixes = self.speculation().likely_prefixes(MAX_SPECULATED_PREFIXES);
+        let files = self.scoped_files(scope);
+        let current_package = current_file.and_then(|current_file| self.package_of(current_file));
+
+        for prefix in prefixes {
+            let result = Self::fuzzy_search_weighted(
+                files,
+                &prefix,
+                max_results,
+                max_threads,
+                current_file,
+                self.scoring_weights,
+                current_package.as_deref(),
+                Some(ticket),
+                self.narrowed_paths(),
+                self.index_stats.entry_points(),
+                Some(self.prefix_candidates()),
+                None,
+                None,
+            );
+            if result.superseded {
+                debug!("Speculative search superseded by a real one");
+                return;
+            }
+            self.speculation().store(&key, &prefix, files, &result);
+        }
+    }
+
+    pub fn get_scan_progress(&self) -> ScanProgress {
+        let scanned_count = self.scanned_files_count.load(Ordering::Relaxed);
+        let is_scanning = self.is_scanning.load(Ordering::Relaxed);
+        ScanProgress {
+            scanned_files_count: scanned_count,
+            is_scanning,
+        }
+    }
+
+    pub fn update_git_statuses(
+        &mut self,
+        status_cache: Option<GitStatusCache>,
+    ) -> Result<(), Error> {
+        let Some(status_cache) = status_cache else {
+            return Ok(());
+        };
+
+        debug!(
+            statuses_count = status_cache.statuses_len(),
+            "Updating git status",
+        );
+
+        let frecency = FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)?;
+        let update = self.prepare_git_status_update(&status_cache, frecency.as_ref());
+        self.publish_git_status_update(update);
+
+        Ok(())
+    }
+
+    /// Resolves the statuses to files of the current index without modifying anything
+    pub fn prepare_git_status_update(
+        &self,
+        status_cache: &GitStatusCache,
+        frecency: Option<&FrecencyTracker>,
+    ) -> GitStatusUpdate {
+        let assignments = status_cache
+            .iter()
+            .filter_map(|(path, status)| {
+                let index = self
+                    .sync_data
+                    .find_file_index(path, self.scan_options.case_insensitive)
+                    .ok()?;
+                let file = &self.sync_data.files[index];
+                let modification_score = match frecency {
+                    Some(frecency) => frecency
+                        .get_modification_score(file.modified, Some(status))
+                        .max(frecency.get_edit_score(&file.path)),
+                    None => file.modification_frecency_score,
+                };
+
+                Some((index, status, modification_score))
+            })
+            .collect();
+
+        GitStatusUpdate {
+            index_generation: self.index_generation,
+            assignments,
+        }
+    }
+
+    /// Applies a prepared update all at once. Returns false without applying anything if files
+    /// were added or removed since it was prepared, the update has to be prepared again then.
+    pub fn publish_git_status_update(&mut self, update: GitStatusUpdate) -> bool {
+        if update.index_generation != self.index_generation {
+            debug!(
+                prepared = update.index_generation,
+                current = self.index_generation,
+                "Discarding git status update prepared for an outdated index"
+            );
+            return false;
+        }
+
+        for (index, status, modification_score) in update.assignments {
+            let file = &mut self.sync_data.files[index];
+            file.git_status = Some(status);
+            file.modification_frecency_score = modification_score;
+            file.update_total_frecency_score();
+        }
+
+        self.git_status_generation += 1;
+        true
+    }
+
+    /// Incremented by every published git status update, lets frontends tell whether the
+    /// statuses they display are still current
+    pub fn git_status_generation(&self) -> u64 {
+        self.git_status_generation
+    }
+
+    /// Fetches all the git statuses first and updates the global FILE_PICKER
+    /// with the new statuses with the smallest possible lock time.
+    pub fn refresh_git_status_global() -> Result<usize, Error> {
+        let (git_status, update) = {
+            let Some(ref picker) = *FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)? else {
+                return Err(Error::FilePickerMissing)?;
+            };
+
+            let provider = picker.sync_data.status_provider.as_ref();
+            debug!(
+                "Refreshing statuses for picker: {:?}",
+                provider.map(|provider| provider.root())
+            );
+
+            // we keep here readonly lock but allowing querying the index while it scan lasts
+            let git_status = provider.and_then(|provider| {
+                provider.all_files(&mut picker.sync_data.files.iter().map(|f| f.path.as_path()))
+            });
+
+            let frecency = FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)?;
+            let update = git_status
+                .as_ref()
+                .map(|cache| picker.prepare_git_status_update(cache, frecency.as_ref()));
+            (git_status, update)
+        };
+
+        let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
+        let picker = file_picker
+            .as_mut()
+            .ok_or_else(|| Error::FilePickerMissing)?;
+
+        let statuses_count = git_status.as_ref().map_or(0, |cache| cache.statuses_len());
+        let published = update.is_some_and(|update| picker.publish_git_status_update(update));
+        if !published {
+            // the index changed while the statuses were resolved, resolve them again
+            picker.update_git_statuses(git_status)?;
+        }
+
+        Ok(statuses_count)
+    }
+
+    /// Runs `refresh_git_status_global` on a separate thread so timers and focus events never
+    /// block the caller. Returns false if a background refresh is already running.
+    pub fn refresh_git_status_in_background() -> bool {
+        static REFRESH_RUNNING: AtomicBool = AtomicBool::new(false);
+
+        if REFRESH_RUNNING.swap(true, Ordering::AcqRel) {
+            debug!("Background git status refresh already running");
+            return false;
+        }
+
+        std::thread::spawn(|| {
+            match FilePicker::refresh_git_status_global() {
+                Ok(count) => debug!(count, "Background git status refresh completed"),
+                Err(e) => error!("Background git status refresh failed: {:?}", e),
+            }
+            REFRESH_RUNNING.store(false, Ordering::Release);
+        });
+
+        true
+    }
+
+    pub fn update_single_file_frecency(
+        &mut self,
+        file_path: impl AsRef<Path>,
+        frecency_tracker: &FrecencyTracker,
+    ) -> Result<(), Error> {
+        if let Ok(index) = self
+            .sync_data
+            .find_file_index(file_path.as_ref(), self.scan_options.case_insensitive)
+        {
+            let mtime_fallback = self.mtime_fallback();
+            if let Some(file) = self.sync_data.files.get_mut(index) {
+                file.update_frecency_scores(frecency_tracker)?;
+                if mtime_fallback {
+                    file.apply_mtime_score();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_file_by_path(&self, path: impl AsRef<Path>) -> Option<&FileItem> {
+        self.sync_data
+            .find_file_index(path.as_ref(), self.scan_options.case_insensitive)
+            .ok()
+            .and_then(|index| self.sync_data.files.get(index))
+    }
+
+    /// One page of the indexed files matching the prefix in the requested order
+    pub fn list_files(&self, options: &FileListOptions) -> FileListPage<'_> {
+        let mut files: Vec<&FileItem> = self
+            .sync_data
+            .files
+            .iter()
+            .filter(|file| {
+                options
+                    .prefix
+                    .as_deref()
+                    .is_none_or(|prefix| file.relative_path.starts_with(prefix))
+            })
+            .collect();
+
+        // the index is already in path order and the sorts are stable, so ties keep it
+        match options.sort_by {
+            FileListSort::Path => {}
+            FileListSort::Frecency => {
+                files.sort_by_key(|file| std::cmp::Reverse(file.total_frecency_score))
+            }
+            FileListSort::Modified => files.sort_by_key(|file| std::cmp::Reverse(file.modified)),
+            FileListSort::Size => files.sort_by_key(|file| std::cmp::Reverse(file.size)),
+            FileListSort::GitStatus => files.sort_by_key(|file| git_status_rank(file.git_status)),
+        }
+
+        let total = files.len();
+        let items = files
+            .into_iter()
+            .skip(options.offset)
+            .take(options.limit.unwrap_or(usize::MAX))
+            .collect();
+
+        FileListPage { items, total }
+    }
+
+    /// Indexed files under the directory, found with a binary search
+    pub fn get_files_in_dir(&self, dir: impl AsRef<Path>) -> &[FileItem] {
+        let range = self
+            .sync_data
+            .dir_range(dir.as_ref(), self.scan_options.case_insensitive);
+        &self.sync_data.files[range]
+    }
+
+    pub fn get_mut_file_by_path(&mut self, path: impl AsRef<Path>) -> Option<&mut FileItem> {
+        self.sync_data
+            .find_file_index(path.as_ref(), self.scan_options.case_insensitive)
+            .ok()
+            .and_then(|index| self.sync_data.files.get_mut(index))
+    }
+
+    /// Add a file to the picker's files in sorted order (used by background watcher)
+    pub fn add_file_sorted(&mut self, file: FileItem) -> Option<&FileItem> {
+        match self
+            .sync_data
+            .find_file_index(&file.path, self.scan_options.case_insensitive)
+        {
+            Ok(position) => {
+                warn!(
+                    "Trying to insert a file that already exists: {}",
+                    file.relative_path
+                );
+
+                self.sync_data.files.get(position)
+            }
+            Err(position) => {
+                self.index_generation += 1;
+                self.index_changes
+                    .record(IndexChangeKind::Added, file.path.clone());
+                self.sync_data.files.insert(position, file);
+                self.sync_data.files.get(position)
+            }
+        }
+    }
+
+    pub fn on_create_or_modify(&mut self, path: impl AsRef<Path>) -> Option<&FileItem> {
+        let path = path.as_ref();
+        match self
+            .sync_data
+            .find_file_index(path, self.scan_options.case_insensitive)
+        {
+            Ok(pos) => {
+                let modified = match std::fs::metadata(path) {
+                    Ok(metadata) => metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok()),
+                    Err(e) => {
+                        error!("Failed to get metadata for {}: {}", path.display(), e);
+                        None
+                    }
+                };
+
+                let mtime_fallback = self.mtime_fallback();
+                // safe to read because we are in lock and binary search returned valid position
+                let file = &mut self.sync_data.files[pos];
+                if let Some(modified) = modified {
+                    let modified = modified.as_secs();
+                    if file.modified < modified {
+                        file.modified = modified;
+                        if mtime_fallback {
+                            file.apply_mtime_score();
+                        }
+                        self.index_changes
+                            .record(IndexChangeKind::Changed, file.path.clone());
+                    }
+                }
+
+                Some(file)
+            }
+            Err(pos) => {
+                let is_within_max_depth = std::iter::once(&self.base_path)
+                    .chain(&self.extra_paths)
+                    .find_map(|root| path.strip_prefix(root).ok())
+                    .is_some_and(|relative| self.scan_options.is_within_max_depth(relative));
+                if !is_within_max_depth {
+                    debug!("Skipping file beyond max scan depth: {}", path.display());
+                    return None;
+                }
+
+                let mut file_item = FileItem::new(path.to_path_buf(), &self.base_path, None);
+                file_item.category = self.classifier.classify(&file_item.relative_path);
+                file_item.package = self.sync_data.packages.package_of(&file_item.relative_path);
+                file_item.root = self.sync_data.roots.label_of(&file_item.path);
+                file_item.sensitivity = self
+                    .sensitive_guard
+                    .classify(&file_item.relative_path, &file_item.path);
+                if self.sensitive_guard.excludes(file_item.sensitivity) {
+                    debug!("Skipping sensitive file: {}", path.display());
+                    return None;
+                }
+                if self.mtime_fallback() {
+                    file_item.apply_mtime_score();
+                }
+                self.index_generation += 1;
+                self.index_changes
+                    .record(IndexChangeKind::Added, file_item.path.clone());
+                self.sync_data.files.insert(pos, file_item);
+
+                self.sync_data.files.get(pos)
+            }
+        }
+    }
+
+    pub fn remove_file_by_path(&mut self, path: impl AsRef<Path>) -> bool {
+        match self
+            .sync_data
+            .find_file_index(path.as_ref(), self.scan_options.case_insensitive)
+        {
+            Ok(index) => {
+                self.index_generation += 1;
+                let file = self.sync_data.files.remove(index);
+                self.index_changes
+                    .record(IndexChangeKind::Removed, file.path);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    // TODO make this O(n)
+    pub fn remove_all_files_in_dir(&mut self, dir: impl AsRef<Path>) -> usize {
+        let dir_path = dir.as_ref();
+        let initial_len = self.sync_data.files.len();
+
+        self.index_generation += 1;
+        let index_changes = &mut self.index_changes;
+        self.sync_data.files.retain(|file| {
+            let keep = !file.path.starts_with(dir_path);
+            if !keep {
+                index_changes.record(IndexChangeKind::Removed, file.path.clone());
+            }
+            keep
+        });
+
+        initial_len - self.sync_data.files.len()
+    }
+
+    /// Remaps the files of a moved directory in place keeping their scores, metadata and
+    /// diagnostics. Returns the old and new path of every moved file.
+    pub fn move_directory(&mut self, from: &Path, to: &Path) -> Vec<(PathBuf, PathBuf)> {
+        let range = self
+            .sync_data
+            .dir_range(from, self.scan_options.case_insensitive);
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        let moved: Vec<FileItem> = self.sync_data.files.drain(range).collect();
+
+        self.index_generation += 1;
+        let mut moves = Vec::with_capacity(moved.len());
+        for mut file in moved {
+            let Ok(suffix) = file.path.strip_prefix(from) else {
+                continue;
+            };
+
+            let path = to.join(suffix);
+            file.relative_path = pathdiff::diff_paths(&path, &self.base_path)
+                .unwrap_or_else(|| path.clone())
+                .to_string_lossy()
+                .into_owned();
+            file.category = self.classifier.classify(&file.relative_path);
+            file.package = self.sync_data.packages.package_of(&file.relative_path);
+            file.root = self.sync_data.roots.label_of(&path);
+            file.sensitivity = self.sensitive_guard.classify(&file.relative_path, &path);
+
+            let from_path = std::mem::replace(&mut file.path, path);
+            self.index_changes
+                .record(IndexChangeKind::Removed, from_path.clone());
+            self.index_changes
+                .record(IndexChangeKind::Added, file.path.clone());
+            moves.push((from_path, file.path.clone()));
+            self.sync_data.files.push(file);
+        }
+
+        let case_insensitive = self.scan_options.case_insensitive;
+        self.sync_data
+            .files
+            .par_sort_unstable_by(|a, b| cmp_paths(&a.path, &b.path, case_insensitive));
+
+        moves
+    }
+
+    /// Attaches metadata to an indexed file, `None` removes it. Relative paths are resolved
+    /// against the base path. Returns false if the file is not in the index.
+    pub fn set_file_meta(&mut self, path: impl AsRef<Path>, meta: Option<FileMeta>) -> bool {
+        let path = normalize_path(path.as_ref(), &self.base_path).into_owned();
+        let Some(file) = self.get_mut_file_by_path(&path) else {
+            return false;
+        };
+
+        file.meta = meta.filter(|meta| !meta.is_empty()).map(Box::new);
+        self.enforce_memory_budget();
+        true
+    }
+
+    pub fn memory_options(&self) -> MemoryOptions {
+        self.memory_options
+    }
+
+    pub fn set_memory_options(&mut self, memory_options: MemoryOptions) {
+        self.memory_options = memory_options;
+        self.enforce_memory_budget();
+    }
+
+    /// Estimated size of the index, walks every file so it is not meant for hot paths
+    pub fn metrics(&self) -> IndexMetrics {
+        let (index_bytes, meta_bytes) = self
+            .sync_data
+            .files
+            .par_iter()
+            .map(|file| {
+                let meta_bytes = file.meta.as_deref().map_or(0, file_meta_bytes);
+                (file_item_bytes(file), meta_bytes)
+            })
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+        IndexMetrics {
+            files: self.sync_data.files.len(),
+            index_bytes,
+            meta_bytes,
+            budget_bytes: self.memory_options.budget_bytes(),
+            dropped_meta: self.dropped_meta_count,
+        }
+    }
+
+    /// Drops file metadata until the index fits the memory budget, files which were never
+    /// opened lose it first. Paths themselves are never dropped so the index stays complete.
+    fn enforce_memory_budget(&mut self) {
+        let Some(budget) = self.memory_options.budget_bytes() else {
+            return;
+        };
+
+        let mut index_bytes = self.metrics().index_bytes;
+        if index_bytes <= budget {
+            return;
+        }
+
+        for keep_opened in [true, false] {
+            for file in self.sync_data.files.iter_mut() {
+                if index_bytes <= budget {
+                    break;
+                }
+                if keep_opened && file.access_frecency_score > 0 {
+                    continue;
+                }
+
+                if let Some(meta) = file.meta.take() {
+                    index_bytes -= file_meta_bytes(&meta);
+                    self.dropped_meta_count += 1;
+                }
+            }
+        }
+
+        if index_bytes > budget {
+            warn!(
+                index_bytes,
+                budget, "Index exceeds the memory budget with nothing left to drop"
+            );
+        } else {
+            info!(
+                index_bytes,
+                budget,
+                dropped_meta = self.dropped_meta_count,
+                "Dropped file metadata to fit the memory budget"
+            );
+        }
+    }
+
+    /// Replaces the diagnostics of the whole index, files missing from `counts` are reset.
+    /// Returns how many of the given paths were found in the index.
+    pub fn set_diagnostics_counts(
+        &mut self,
+        counts: impl IntoIterator<Item = (PathBuf, DiagnosticsCount)>,
+    ) -> usize {
+        self.sync_data
+            .files
+            .iter_mut()
+            .filter(|file| !file.diagnostics.is_empty())
+            .for_each(|file| file.diagnostics = DiagnosticsCount::default());
+
+        let mut found = 0;
+        for (path, count) in counts {
+            let path = normalize_path(&path, &self.base_path).into_owned();
+            if let Some(file) = self.get_mut_file_by_path(&path) {
+                file.diagnostics = count;
+                found += 1;
+            }
+        }
+
+        found
+    }
+
+    /// Replaces the jump recency of the whole index. `paths` are the jumplist and changelist
+    /// locations ordered from the oldest to the most recent, every occurrence adds a bit less
+    /// the further it is from the newest jump.
+    pub fn set_jump_positions(&mut self, paths: impl IntoIterator<Item = PathBuf>) -> usize {
+        let paths: Vec<PathBuf> = paths.into_iter().collect();
+        let mut jump_scores: HashMap<usize, f64> = HashMap::new();
+        for (rank, path) in paths.iter().rev().enumerate() {
+            let path = normalize_path(path, &self.base_path);
+            let Ok(index) = self
+                .sync_data
+                .find_file_index(&path, self.scan_options.case_insensitive)
+            else {
+                continue;
+            };
+
+            *jump_scores.entry(index).or_default() += JUMP_DECAY.powi(rank as i32);
+        }
+
+        for file in self.sync_data.files.iter_mut() {
+            if file.jump_frecency_score != 0 {
+                file.jump_frecency_score = 0;
+                file.update_total_frecency_score();
+            }
+        }
+
+        for (index, score) in &jump_scores {
+            let file = &mut self.sync_data.files[*index];
+            file.jump_frecency_score = score.min(MAX_JUMP_SCORE).round() as i64;
+            file.update_total_frecency_score();
+        }
+
+        jump_scores.len()
+    }
+
+    /// Swaps in a freshly scanned index keeping the metadata, diagnostics and jump recency
+    /// attached to the files that are still present.
+    fn replace_sync_data(&mut self, sync: FileSync) {
+        let previous = std::mem::replace(&mut self.sync_data, sync);
+        self.index_generation += 1;
+        self.index_changes.reset();
+        self.index_stats = IndexStats::collect(&self.sync_data.files);
+        for file in previous.files {
+            if file.meta.is_none() && file.diagnostics.is_empty() && file.jump_frecency_score == 0 {
+                continue;
+            }
+
+            if let Some(new_file) = self.get_mut_file_by_path(&file.path) {
+                new_file.meta = file.meta;
+                new_file.diagnostics = file.diagnostics;
+                new_file.jump_frecency_score = file.jump_frecency_score;
+                new_file.update_total_frecency_score();
+            }
+        }
+
+        self.enforce_memory_budget();
+    }
+
+    /// Applies new scan and watcher options to a running picker. The current index keeps
+    /// serving searches until the rescan replaces it, a watcher only change just restarts the
+    /// watcher.
+    pub fn reconfigure(
+        &mut self,
+        scan_options: Option<ScanOptions>,
+        watcher_options: Option<WatcherOptions>,
+    ) -> Result<(), Error> {
+        let scan_changed = scan_options.is_some_and(|scan_options| {
+            let changed = scan_options != self.scan_options;
+            if changed {
+                info!(old = ?self.scan_options, new = ?scan_options, "Scan options changed");
+                self.classifier = Classifier::new(&scan_options.categories);
+                self.sensitive_guard = SensitiveGuard::new(&scan_options.sensitive);
+                self.scan_options = scan_options;
+            }
+            changed
+        });
+
+        let watcher_changed = watcher_options.is_some_and(|watcher_options| {
+            let changed = watcher_options != self.watcher_options;
+            if changed {
+                info!(old = ?self.watcher_options, new = ?watcher_options, "Watcher options changed");
+                self.watcher_options = watcher_options;
+            }
+            changed
+        });
+
+        if scan_changed {
+            self.rescan_in_background();
+        } else if watcher_changed {
+            // without a running watcher the scan thread picks up the new options on its own
+            if let Some(watcher) = self.background_watcher.take() {
+                watcher.stop();
+                self.background_watcher = Some(BackgroundWatcher::new(
+                    self.base_path.clone(),
+                    self.extra_paths.clone(),
+                    self.sync_data.git_workdir.clone(),
+                    self.scan_options.clone(),
+                    self.watcher_options,
+                )?);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds the index on a separate thread while the current one keeps serving searches.
+    /// The watcher captured the old scan options so the scan thread starts a new one.
+    pub(crate) fn rescan_in_background(&mut self) {
+        self.stop_background_monitor();
+        self.is_scanning.store(true, Ordering::Relaxed);
+        self.scanned_files_count.store(0, Ordering::Relaxed);
+        spawn_scan_and_watcher(
+            self.base_path.clone(),
+            self.extra_paths.clone(),
+            self.scan_options.clone(),
+            Arc::clone(&self.source),
+            Arc::clone(&self.is_scanning),
+            Arc::clone(&self.scanned_files_count),
+        );
+    }
+
+    /// Indexes and watches a directory outside the base path along with it. Returns false if
+    /// the directory is already covered by the base path or another extra path, or the files
+    /// don't come from the local disk.
+    pub fn watch_extra_path(&mut self, path: impl AsRef<Path>) -> Result<bool, Error> {
+        if !self.source.is_local() {
+            return Ok(false);
+        }
+
+        let path = normalize_path(path.as_ref(), &self.base_path).into_owned();
+        if !path.is_dir() {
+            return Err(Error::InvalidPath(path));
+        }
+
+        let is_covered = std::iter::once(&self.base_path)
+            .chain(&self.extra_paths)
+            .any(|root| path.starts_with(root));
+        if is_covered {
+            return Ok(false);
+        }
+
+        info!("Watching extra path: {}", path.display());
+        self.extra_paths.retain(|extra| !extra.starts_with(&path));
+        self.extra_paths.push(path);
+        self.rescan_in_background();
+        Ok(true)
+    }
+
+    /// Stats a random sample of up to `sample_size` indexed files and starts a background
+    /// rescan when at least `threshold_percent` of them are missing or outdated. Long sessions
+    /// accumulate such ghosts when the watcher drops events.
+    pub fn check_index_drift(
+        &mut self,
+        sample_size: usize,
+        threshold_percent: usize,
+    ) -> IndexDrift {
+        // listed files are not on the disk, every one of them would look missing
+        if self.is_scan_active() || !self.source.is_local() {
+            return self.last_index_drift.unwrap_or_default();
+        }
+
+        let files = &self.sync_data.files;
+        let mut drift = IndexDrift::default();
+        for index in sample_indices(files.len(), sample_size) {
+            let file = &files[index];
+            drift.sampled += 1;
+            // archive entries are only read again by a rescan, their archive has to exist
+            if let Some((archive, _)) = split_entry_path(&file.path) {
+                if !archive.exists() {
+                    drift.missing += 1;
+                }
+                continue;
+            }
+            match std::fs::metadata(&file.path) {
+                Ok(metadata) if modified_secs(&metadata) != file.modified => drift.stale += 1,
+                Ok(_) => {}
+                Err(_) => drift.missing += 1,
+            }
+        }
+
+        if drift.missing + drift.stale > 0 && drift.percent() >= threshold_percent {
+            warn!(?drift, "Index drifted from the filesystem, rescanning");
+            drift.rescan_triggered = true;
+            self.rescan_in_background();
+        } else {
+            debug!(?drift, "Index drift check");
+        }
+
+        self.last_index_drift = Some(drift);
+        drift
+    }
+
+    /// Extension distribution and languages detected by the latest full scan
+    pub fn index_stats(&self) -> &IndexStats {
+        &self.index_stats
+    }
+
+    /// Result of the latest `check_index_drift`, `None` if it never ran
+    pub fn last_index_drift(&self) -> Option<IndexDrift> {
+        self.last_index_drift
+    }
+
+    /// Files added, removed and changed since the given generation of the change log, see
+    /// `IndexDiff::reset` for when the whole index has to be read again instead
+    pub fn diff_index(&self, since_generation: u64) -> IndexDiff {
+        self.index_changes.diff(since_generation)
+    }
+
+    /// Recomputes the cached frecency scores of every indexed file
+    pub fn refresh_frecency_scores(&mut self, tracker: &FrecencyTracker) -> Result<(), Error> {
+        let mtime_fallback = self.mtime_fallback();
+        self.sync_data.files.par_iter_mut().try_for_each_init(
+            || tracker.reader(),
+            |reader, file| {
+                match reader {
+                    Ok(reader) => file.update_frecency_scores_with(reader),
+                    Err(_) => file.update_frecency_scores(tracker)?,
+                }
+                if mtime_fallback {
+                    file.apply_mtime_score();
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Moves the access history of the indexed files between the prefixes, typically after
+    /// the project was relocated and indexed at its new path
+    pub fn migrate_frecency_prefix(
+        &mut self,
+        tracker: &FrecencyTracker,
+        old_prefix: &Path,
+        new_prefix: &Path,
+    ) -> Result<usize, Error> {
+        let known_paths = self.sync_data.files.iter().map(|file| file.path.as_path());
+        let migrated = tracker.migrate_prefix(old_prefix, new_prefix, known_paths)?;
+        if migrated > 0 {
+            self.refresh_frecency_scores(tracker)?;
+        }
+
+        Ok(migrated)
+    }
+
+    /// Stores the current index as the snapshot of the base path, unless a scan is about to
+    /// replace it or the files are not on the local disk
+    pub fn store_snapshot(&self) {
+        if self.source.is_local() && !self.is_scan_active() {
+            store_snapshot(&self.base_path, &self.scan_options, &self.sync_data.files);
+        }
+    }
+
+    pub fn stop_background_monitor(&mut self) {
+        if let Some(watcher) = self.background_watcher.take() {
+            watcher.stop();
+        }
+    }
+
+    pub fn trigger_rescan(&mut self) -> Result<(), Error> {
+        if self.is_scanning.load(Ordering::Relaxed) {
+            debug!("Scan already in progress, skipping trigger_rescan");
+            return Ok(());
+        }
+
+        self.is_scanning.store(true, Ordering::Relaxed);
+        self.scanned_files_count.store(0, Ordering::Relaxed);
+
+        if let Ok(sync) = scan_filesystem(
+            &self.base_path,
+            &self.extra_paths,
+            &self.scan_options,
+            self.source.as_ref(),
+            &self.scanned_files_count,
+        ) {
+            info!(
+                "Filesystem scan completed: found {} files",
+                sync.files.len()
+            );
+            if self.source.is_local() {
+                store_snapshot(&self.base_path, &self.scan_options, &sync.files);
+            }
+            self.replace_sync_data(sync);
+        } else {
+            warn!("Filesystem scan failed");
+        }
+
+        self.is_scanning.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn is_scan_active(&self) -> bool {
+        self.is_scanning.load(Ordering::Relaxed)
+    }
+}
+
+#[allow(unused)]
+#[derive(Debug, Clone)]
+pub struct ScanProgress {
+    pub scanned_files_count: usize,
+    pub is_scanning: bool,
+}
+
+fn modified_secs(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Distinct pseudo random indices below `len`, all of them if there are fewer than `count`.
+/// A xorshift seeded from the clock is enough to avoid checking the same files every time.
+fn sample_indices(len: usize, count: usize) -> Vec<usize> {
+    if len <= count {
+        return (0..len).collect();
+    }
+
+    let mut state = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+        | 1;
+    let mut indices = std::collections::BTreeSet::new();
+    while indices.len() < count {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        indices.insert((state % len as u64) as usize);
+    }
+
+    indices.into_iter().collect()
+}
+
+/// Persists the freshly scanned index for other instances if snapshots are enabled
+fn store_snapshot(base_path: &Path, scan_options: &ScanOptions, files: &[FileItem]) {
+    let Ok(snapshots) = SNAPSHOTS.read() else {
+        return;
+    };
+
+    if let Some(snapshots) = snapshots.as_ref() {
+        if let Err(e) = snapshots.store(base_path, scan_options.sensitive.mode, files) {
+            error!("Failed to store index snapshot: {:?}", e);
+        }
+    }
+}
+
+fn spawn_scan_and_watcher(
+    base_path: PathBuf,
+    extra_paths: Vec<PathBuf>,
+    scan_options: ScanOptions,
+    source: Arc<dyn FileSource>,
+    scan_signal: Arc<AtomicBool>,
+    synced_files_count: Arc<AtomicUsize>,
+) {
+    std::thread::spawn(move || {
+        scan_signal.store(true, Ordering::Relaxed);
+        info!("Starting initial file scan");
+
+        let mut git_workdir = None;
+        match scan_filesystem(
+            &base_path,
+            &extra_paths,
+            &scan_options,
+            source.as_ref(),
+            &synced_files_count,
+        ) {
+            Ok(sync) => {
+                info!(
+                    "Initial filesystem scan completed: found {} files",
+                    sync.files.len()
+                );
+
+                git_workdir = sync.git_workdir.clone();
+                if source.is_local() {
+                    store_snapshot(&base_path, &scan_options, &sync.files);
+                }
+                if let Ok(mut file_picker_guard) = crate::FILE_PICKER.write() {
+                    if let Some(ref mut picker) = *file_picker_guard {
+                        picker.replace_sync_data(sync);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Initial scan failed: {:?}", e);
+            }
+        }
+        scan_signal.store(false, Ordering::Relaxed);
+
+        if let Some(ref git_workdir) = git_workdir {
+            spawn_co_change_mining(git_workdir.clone(), scan_options.co_change_commits);
+        }
+
+        if !source.is_local() {
+            return;
+        }
+
+        // configure() may have changed the watcher options while the scan was running
+        let watcher_options = crate::FILE_PICKER
+            .read()
+            .ok()
+            .and_then(|picker| picker.as_ref().map(FilePicker::watcher_options))
+            .unwrap_or_default();
+
+        match BackgroundWatcher::new(
+            base_path,
+            extra_paths,
+            git_workdir,
+            scan_options,
+            watcher_options,
+        ) {
+            Ok(watcher) => {
+                info!("Background file watcher initialized successfully");
+
+                if let Ok(mut file_picker_guard) = crate::FILE_PICKER.write() {
+                    if let Some(ref mut picker) = *file_picker_guard {
+                        picker.background_watcher = Some(watcher);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to initialize background file watcher: {:?}", e);
+            }
+        }
+
+        // the debouncer keeps running in its own thread
+    });
+}
+
+/// Mines the co-change graph of the repository in its own thread, walking a long history takes
+/// seconds and the picker is usable without it. The graph is stored in the frecency database
+/// so it survives restarts until the next scan replaces it.
+fn spawn_co_change_mining(git_workdir: PathBuf, max_commits: usize) {
+    if max_commits == 0 {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let Some(graph) = crate::git::mine_co_changes(&git_workdir, max_commits) else {
+            return;
+        };
+
+        // only the read lock, a waiting writer would block the readers holding the file picker
+        let Ok(frecency) = FRECENCY.read() else {
+            return;
+        };
+        let Some(ref frecency) = *frecency else {
+            return;
+        };
+        if frecency.needs_map_growth() {
+            if let Err(e) = frecency.grow_map_size() {
+                error!("Failed to grow the frecency database: {:?}", e);
+            }
+        }
+        if let Err(e) = frecency.store_co_changes(&graph) {
+            error!("Failed to store the co-change graph: {:?}", e);
+        }
+    });
+}
+
+/// Pool limited to the throttle threads, `None` runs on the global pool
+fn throttled_pool(scan_options: &ScanOptions) -> Option<rayon::ThreadPool> {
+    let threads = scan_options.throttle?.threads;
+    if threads == 0 {
+        return None;
+    }
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .inspect_err(|e| warn!("Failed to build the throttled scan pool: {:?}", e))
+        .ok()
+}
+
+fn scan_filesystem(
+    base_path: &Path,
+    extra_paths: &[PathBuf],
+    scan_options: &ScanOptions,
+    source: &dyn FileSource,
+    synced_files_count: &AtomicUsize,
+) -> Result<FileSync, Error> {
+    use std::thread;
+
+    let scan_start = std::time::Instant::now();
+    info!("SCAN: Starting parallel filesystem scan and git status");
+
+    // run separate thread for git status because it effectively does another separate file
+    // traversal which could be pretty slow on large repos (in general 300-500ms)
+    thread::scope(|s| {
+        let git_handle = s.spawn(|| {
+            if !source.is_local() {
+                return (None, None, None, None);
+            }
+
+            let status_provider = vcs::detect(base_path);
+            if let Some(ref provider) = status_provider {
+                debug!(
+                    "{} repository found at: {}",
+                    provider.kind().as_str(),
+                    provider.root().display()
+                );
+            } else {
+                debug!("No repository found for path: {}", base_path.display());
+            }
+            let git_workdir = status_provider
+                .as_ref()
+                .filter(|provider| provider.kind() == VcsKind::Git)
+                .map(|provider| provider.root().to_path_buf());
+
+            let status_cache = status_provider
+                .as_ref()
+                .and_then(|provider| provider.changed_files())
+                .map(|cache| cache.with_case_insensitive(scan_options.case_insensitive));
+
+            let recent_commits = git_workdir.as_deref().and_then(|git_workdir| {
+                RecentCommitPaths::read(
+                    git_workdir,
+                    scan_options.recent_commits,
+                    scan_options.case_insensitive,
+                )
+            });
+            (git_workdir, status_provider, status_cache, recent_commits)
+        });
+
+        let walker_start = std::time::Instant::now();
+        info!(?source, "SCAN: Collecting files");
+        let mut files =
+            source.collect_files(base_path, extra_paths, scan_options, synced_files_count)?;
+        scan_batches::finish(files.len());
+        let walker_time = walker_start.elapsed();
+        info!("SCAN: Collecting files completed in {:?}", walker_time);
+
+        let (git_workdir, status_provider, git_cache, recent_commits) =
+            git_handle.join().map_err(|_| {
+                error!("Failed to join git status thread");
+                Error::ThreadPanic
+            })?;
+
+        let mtime_fallback =
+            scan_options.mtime_fallback && source.is_local() && status_provider.is_none();
+        let classifier = Classifier::new(&scan_options.categories);
+        let sensitive_guard = SensitiveGuard::new(&scan_options.sensitive);
+        let packages = PackageRoots::from_files(&files);
+        let roots = RootLabels::new(base_path, extra_paths);
+        let frecency = FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)?;
+        let mut update_files = || {
+            // one frecency reader per worker, scoring every file at the same instant
+            let reader = || frecency.as_ref().map(FrecencyTracker::reader);
+            files
+                .par_iter_mut()
+                .try_for_each_init(reader, |reader, file| -> Result<(), Error> {
+                    file.category = classifier.classify(&file.relative_path);
+                    file.sensitivity = sensitive_guard.classify(&file.relative_path, &file.path);
+                    file.package = packages.package_of(&file.relative_path);
+                    file.root = roots.label_of(&file.path);
+                    if let Some(git_cache) = &git_cache {
+                        file.git_status = git_cache.lookup_status(&file.path);
+                    }
+                    if let Some(age) = recent_commits
+                        .as_ref()
+                        .and_then(|recent| recent.commit_age(&file.path))
+                    {
+                        file.commit_frecency_score =
+                            (MAX_COMMIT_SCORE * COMMIT_DECAY.powi(age as i32)).round() as i64;
+                        file.update_total_frecency_score();
+                    }
+
+                    match (reader, frecency.as_ref()) {
+                        (Some(Ok(reader)), _) => file.update_frecency_scores_with(reader),
+                        (_, Some(frecency)) => file.update_frecency_scores(frecency)?,
+                        (_, None) => {}
+                    }
+                    if mtime_fallback {
+                        file.apply_mtime_score();
+                    }
+
+                    Ok(())
+                })
+        };
+        match throttled_pool(scan_options) {
+            Some(pool) => pool.install(update_files)?,
+            None => update_files()?,
+        }
+        if scan_options.sensitive.mode == SensitiveMode::Exclude {
+            files.retain(|file| file.sensitivity.is_none());
+        }
+
+        if let Some(frecency) = frecency.as_ref() {
+            if let Err(e) = frecency.backfill_paths(files.iter().map(|file| file.path.as_path())) {
+                warn!("Failed to backfill frecency paths: {:?}", e);
+            }
+        }
+
+        let total_time = scan_start.elapsed();
+        info!(
+            "SCAN: Total scan time {:?} for {} files",
+            total_time,
+            files.len()
+        );
+
+        files.par_sort_unstable_by(|a, b| {
+            cmp_paths(&a.path, &b.path, scan_options.case_insensitive)
+        });
+        Ok(FileSync {
+            files,
+            git_workdir,
+            status_provider,
+            packages,
+            roots,
+        })
+    })
+}
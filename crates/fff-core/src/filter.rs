@@ -0,0 +1,228 @@
+//! Query filters on the file metadata collected by the scan, applied before matching so they
+//! never need a rescan: `max_size:2M` drops larger files, `nobin` drops binaries and
+//! `mtime:<2d` / `mtime:>2d` keep files modified within / before the last two days.
+//! `author:alice` keeps the files mostly committed by a matching author, which is the one
+//! filter reading the git history.
+
+use crate::git::DominantAuthors;
+use crate::types::FileItem;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_SIZE_FILTER_PREFIX: &str = "max_size:";
+const NO_BINARY_FILTER: &str = "nobin";
+const MTIME_FILTER_PREFIX: &str = "mtime:";
+const AUTHOR_FILTER_PREFIX: &str = "author:";
+
+/// Extensions treated as binary, kept in sync with the previewer's list
+const BINARY_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "tiff", "tif", "webp", "ico", "pdf", "ps", "eps", "heic",
+    "avif", "zip", "rar", "7z", "tar", "gz", "bz2", "xz", "exe", "dll", "so", "dylib", "bin",
+    "mp3", "mp4", "avi", "mkv", "wav", "flac", "ogg", "db", "sqlite", "dat", "iso",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileFilter {
+    /// Largest file size in bytes to keep
+    pub max_size: Option<u64>,
+    pub exclude_binary: bool,
+    /// Unix timestamps bounding the modification time
+    pub modified_after: Option<u64>,
+    pub modified_before: Option<u64>,
+    /// Lowercase part of the dominant author's name
+    pub author: Option<String>,
+    /// Loaded with `load_authors` once the filter is known to need them
+    pub authors: Option<Arc<DominantAuthors>>,
+}
+
+impl FileFilter {
+    /// Splits the filter tokens out of the query, returning the remaining fuzzy query
+    pub fn parse(query: &str) -> (String, Option<Self>) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        let mut filter = Self::default();
+        let mut rest = Vec::new();
+        for token in query.split_whitespace() {
+            if token == NO_BINARY_FILTER {
+                filter.exclude_binary = true;
+                continue;
+            }
+
+            if let Some(max_size) = token
+                .strip_prefix(MAX_SIZE_FILTER_PREFIX)
+                .and_then(parse_size)
+            {
+                filter.max_size = Some(max_size);
+                continue;
+            }
+
+            if let Some(author) = token
+                .strip_prefix(AUTHOR_FILTER_PREFIX)
+                .filter(|author| !author.is_empty())
+            {
+                filter.author = Some(author.to_lowercase());
+                continue;
+            }
+
+            let mtime = token.strip_prefix(MTIME_FILTER_PREFIX);
+            if let Some(age) = mtime.and_then(|mtime| mtime.strip_prefix('<')) {
+                if let Some(age) = parse_age(age) {
+                    filter.modified_after = Some(now.saturating_sub(age));
+                    continue;
+                }
+            }
+            if let Some(age) = mtime.and_then(|mtime| mtime.strip_prefix('>')) {
+                if let Some(age) = parse_age(age) {
+                    filter.modified_before = Some(now.saturating_sub(age));
+                    continue;
+                }
+            }
+
+            rest.push(token);
+        }
+
+        if filter == Self::default() {
+            return (query.to_string(), None);
+        }
+
+        (rest.join(" "), Some(filter))
+    }
+
+    /// Reads the dominant authors of the repository holding the files if the filter needs them
+    pub fn load_authors(&mut self, files: &[FileItem]) {
+        if self.author.is_some() && self.authors.is_none() {
+            self.authors = files
+                .first()
+                .and_then(|file| DominantAuthors::cached(&file.path));
+        }
+    }
+
+    pub fn matches(&self, file: &FileItem) -> bool {
+        self.max_size.is_none_or(|max_size| file.size <= max_size)
+            && !(self.exclude_binary && is_binary_file_name(&file.file_name))
+            && self
+                .modified_after
+                .is_none_or(|after| file.modified >= after)
+            && self
+                .modified_before
+                .is_none_or(|before| file.modified < before)
+            && self.author.as_ref().is_none_or(|author| {
+                self.authors
+                    .as_ref()
+                    .and_then(|authors| authors.author_of(&file.path))
+                    .is_some_and(|name| name.to_lowercase().contains(author.as_str()))
+            })
+    }
+}
+
+/// Sizes like `512`, `100k`, `2M` or `1gb`, units are powers of 1024
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.to_ascii_lowercase();
+    let size = size.strip_suffix('b').unwrap_or(&size);
+    let (number, multiplier) = match size.char_indices().last()? {
+        (i, 'k') => (&size[..i], 1 << 10),
+        (i, 'm') => (&size[..i], 1 << 20),
+        (i, 'g') => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Ages like `30m`, `12h`, `2d` or `1w` in seconds
+fn parse_age(age: &str) -> Option<u64> {
+    let (i, unit) = age.char_indices().last()?;
+    let multiplier = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    age[..i].parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+pub fn is_binary_file_name(file_name: &str) -> bool {
+    file_name.rsplit_once('.').is_some_and(|(_, extension)| {
+        BINARY_EXTENSIONS
+            .iter()
+            .any(|binary| binary.eq_ignore_ascii_case(extension))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(file_name: &str, size: u64, modified: u64) -> FileItem {
+        let mut file = FileItem::new(file_name.into(), "".as_ref(), None);
+        file.size = size;
+        file.modified = modified;
+        file
+    }
+
+    #[test]
+    fn test_file_filter_parse() {
+        let (query, filter) = FileFilter::parse("logo max_size:100k nobin");
+        assert_eq!(query, "logo");
+        let filter = filter.unwrap();
+        assert_eq!(filter.max_size, Some(100 * 1024));
+        assert!(filter.matches(&file("logo.svg", 1024, 0)));
+        assert!(!filter.matches(&file("logo.svg", 200 * 1024, 0)));
+        assert!(!filter.matches(&file("logo.PNG", 1024, 0)));
+
+        let (query, filter) = FileFilter::parse("max_size:big nobinary");
+        assert_eq!(query, "max_size:big nobinary");
+        assert!(filter.is_none());
+    }
+
+    #[test]
+    fn test_mtime_filter() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let fresh = file("fresh.rs", 0, now - 60);
+        let old = file("old.rs", 0, now - 3 * 24 * 60 * 60);
+
+        let (query, recent) = FileFilter::parse("mtime:<2d src");
+        assert_eq!(query, "src");
+        let recent = recent.unwrap();
+        assert!(recent.matches(&fresh));
+        assert!(!recent.matches(&old));
+
+        let (_, stale) = FileFilter::parse("mtime:>2d");
+        let stale = stale.unwrap();
+        assert!(!stale.matches(&fresh));
+        assert!(stale.matches(&old));
+
+        let (query, filter) = FileFilter::parse("mtime:2d mtime:<2y");
+        assert_eq!(query, "mtime:2d mtime:<2y");
+        assert!(filter.is_none());
+    }
+
+    #[test]
+    fn test_author_filter() {
+        let (query, filter) = FileFilter::parse("main author:Alice");
+        assert_eq!(query, "main");
+        let filter = filter.unwrap();
+        assert_eq!(filter.author.as_deref(), Some("alice"));
+        assert!(!filter.matches(&file("main.rs", 0, 0)), "no authors loaded");
+
+        let (query, filter) = FileFilter::parse("author:");
+        assert_eq!(query, "author:");
+        assert!(filter.is_none());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("2M"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_size("1gb"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size("k"), None);
+    }
+}
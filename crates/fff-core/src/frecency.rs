@@ -0,0 +1,2366 @@
+use crate::{
+    error::Error,
+    git::{is_conflicted_status, is_modified_status, Status},
+    types::{
+        AccessCount, AccessRecord, FrecencyDetails, FrecencyOptions, DEFAULT_MAX_FRECENCY_SCORE,
+    },
+};
+use chrono::{Datelike, TimeZone, Timelike};
+use heed::{
+    types::{Bytes, SerdeBincode, Str},
+    CompactionOption, EnvFlags,
+};
+use heed::{Database, Env, EnvOpenOptions};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+// LMDB default map size is only 10MB which is easy to fill with MDB_MAP_FULL on large projects
+const INITIAL_MAP_SIZE: usize = 24 * 1024 * 1024;
+const MAX_MAP_SIZE: usize = 1024 * 1024 * 1024;
+// grow the map once this share of it is used, before writes start failing
+const MAP_GROWTH_THRESHOLD: f64 = 0.75;
+// compact on open only when the file is big enough for it to matter and mostly free pages
+const COMPACTION_MIN_SIZE: usize = 8 * 1024 * 1024;
+const COMPACTION_FRAGMENTATION_THRESHOLD: f64 = 0.5;
+const DATA_FILE_NAME: &str = "data.mdb";
+/// Named database mapping the key hashes back to the tracked paths
+const PATHS_DB_NAME: &str = "paths";
+/// Named database holding the co-change graph mined from the git history
+const CO_CHANGES_DB_NAME: &str = "co_changes";
+/// Named database of the project roots with the last time a picker was opened in them
+const PROJECTS_DB_NAME: &str = "projects";
+/// LMDB keeps the named databases as records of the unnamed one, so it holds this many
+/// entries more than there are files
+const NAMED_DBS: usize = 3;
+const COMPACTED_FILE_NAME: &str = "data.mdb.compacted";
+/// Every open tracker holds a shared lock on this file, compaction swaps the data file only
+/// while it can hold the lock alone
+const USERS_LOCK_FILE_NAME: &str = "users.lock";
+/// Held exclusively across every write transaction, see `WriterLock`
+const WRITER_LOCK_FILE_NAME: &str = "writer.lock";
+/// Every tracker appends the accesses it hasn't flushed yet to a journal of its own,
+/// `access.<pid>-<n>.journal`, one `<timestamp> <path>` line each
+const JOURNAL_PREFIX: &str = "access.";
+const JOURNAL_SUFFIX: &str = ".journal";
+/// Tracked accesses are written at least this often, a crash loses at most this much history
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+/// The flusher is woken up early once this many histories changed since the last flush
+const MAX_DIRTY_ENTRIES: usize = 256;
+/// Accesses further from the current time of day don't count for `time_of_day_scores`
+const SIMILAR_HOURS: f64 = 3.0;
+/// Edit times kept in memory, the oldest edits are forgotten first
+const MAX_EDIT_TIMES: usize = 1024;
+
+/// Ceiling of the total frecency score of every file, see `FrecencyOptions::max_score`. Global
+/// because file scores are also summed up where no tracker is at hand.
+static MAX_TOTAL_SCORE: AtomicI64 = AtomicI64::new(DEFAULT_MAX_FRECENCY_SCORE);
+/// Trackers opened by this process, tells their journals apart
+static OPENED_TRACKERS: AtomicU64 = AtomicU64::new(0);
+
+pub fn max_total_score() -> i64 {
+    MAX_TOTAL_SCORE.load(Ordering::Relaxed)
+}
+
+type KeyHash = [u8; 32];
+type AccessDb = Database<Bytes, SerdeBincode<VecDeque<u64>>>;
+type PathsDb = Database<Bytes, Str>;
+type ProjectsDb = Database<Str, SerdeBincode<u64>>;
+/// Files of the latest sessions with the access generation, minute and gap they were found at
+type CachedSessions = ((u64, u64, u64), Arc<HashSet<PathBuf>>);
+/// Time of day scores with the access generation and 10 minutes window they were computed in
+type CachedTimeOfDay = ((u64, u64), Arc<HashMap<PathBuf, i64>>);
+
+/// Every access history of the database kept in memory, loaded once on open. Accesses tracked
+/// by other processes show up after the database is reopened.
+#[derive(Debug, Default)]
+struct AccessCache {
+    histories: HashMap<KeyHash, VecDeque<u64>>,
+    /// Paths of the histories changed since the last flush
+    dirty: HashMap<KeyHash, PathBuf>,
+    /// Every access is appended here before it's in the database, so a crash of the process or
+    /// a half written transaction (likely without the LMDB lock and syncs) loses nothing. It is
+    /// emptied once a flush leaves nothing dirty and locked as long as the tracker is open, the
+    /// journals nobody holds the lock of are left by crashed trackers and replayed on open.
+    journal: Option<(PathBuf, File)>,
+    /// Bumped by every change of the histories and the options, invalidates the aggregates
+    generation: u64,
+}
+
+impl AccessCache {
+    /// Adds the access unless the history has it already, replaying a journal twice is harmless
+    fn insert_access(&mut self, key_hash: KeyHash, path: &Path, time: u64) -> bool {
+        let accesses = self.histories.entry(key_hash).or_default();
+        let Err(position) = accesses.binary_search(&time) else {
+            return false;
+        };
+
+        accesses.insert(position, time);
+        self.dirty.insert(key_hash, path.to_path_buf());
+        self.generation += 1;
+        true
+    }
+
+    fn journal_access(&mut self, path: &Path, time: u64) {
+        let Some((_, journal)) = self.journal.as_mut() else {
+            return;
+        };
+        let Some(path) = path.to_str().filter(|path| !path.contains('\n')) else {
+            return;
+        };
+
+        if let Err(e) = writeln!(journal, "{} {}", time, path) {
+            tracing::warn!(?e, "Failed to journal frecency access");
+        }
+    }
+
+    /// Lines cut short by a crash are skipped
+    fn replay_journal(&mut self, journal: &File) -> Result<usize, Error> {
+        let mut replayed = 0;
+        for line in BufReader::new(journal).lines() {
+            let Some((time, path)) = line?
+                .split_once(' ')
+                .filter(|(_, path)| !path.is_empty())
+                .and_then(|(time, path)| Some((time.parse::<u64>().ok()?, PathBuf::from(path))))
+            else {
+                continue;
+            };
+            let Ok(key_hash) = FrecencyTracker::path_to_hash_bytes(&path) else {
+                continue;
+            };
+
+            if self.insert_access(key_hash, &path, time) {
+                replayed += 1;
+            }
+        }
+
+        Ok(replayed)
+    }
+}
+
+/// Session files and time of day scores, recomputed by the flusher thread from all histories.
+/// Searches read the latest ones and ask for a refresh once they are stale, so they never walk
+/// the whole history themselves.
+#[derive(Debug)]
+struct Aggregates {
+    sessions: Mutex<Option<CachedSessions>>,
+    time_of_day: Mutex<Option<CachedTimeOfDay>>,
+    /// Set from the tracker's options before the generation is bumped for them
+    options: Mutex<FrecencyOptions>,
+    /// A refresh was requested and hasn't finished yet
+    refreshing: AtomicBool,
+}
+
+impl Aggregates {
+    fn new(options: FrecencyOptions) -> Self {
+        Self {
+            sessions: Mutex::new(None),
+            time_of_day: Mutex::new(None),
+            options: Mutex::new(options),
+            refreshing: AtomicBool::new(false),
+        }
+    }
+
+    fn refresh(&self, env: &Env, paths: PathsDb, write_behind: &WriteBehind) {
+        if let Err(e) = self.compute(env, paths, write_behind) {
+            tracing::error!(
+                ?e,
+                "Failed to refresh frecency sessions and time of day scores"
+            );
+        }
+        self.refreshing.store(false, Ordering::Relaxed);
+    }
+
+    fn compute(&self, env: &Env, paths: PathsDb, write_behind: &WriteBehind) -> Result<(), Error> {
+        let now = now_secs();
+        // taken before the cache, see `WriteBehind::transactions`
+        let _transaction = write_behind.transaction()?;
+        let cache = write_behind
+            .cache
+            .read()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        // read after the generation, so options changed for a newer one are seen
+        let options = *self
+            .options
+            .lock()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        let rtxn = env.read_txn().map_err(Error::DbStartReadTxn)?;
+        let history_path = |key_hash: &KeyHash| match cache.dirty.get(key_hash) {
+            // the unflushed histories are only known to the cache
+            Some(path) => Some(path.clone()),
+            None => Some(PathBuf::from(paths.get(&rtxn, key_hash).ok()??)),
+        };
+
+        let gap = (options.session_gap_minutes * 60.0) as u64;
+        let times = cache.histories.values().flatten().copied().collect();
+        let files = match previous_session_start(times, now, gap) {
+            None => HashSet::new(),
+            Some(start) => cache
+                .histories
+                .iter()
+                .filter(|(_, accesses)| accesses.back().is_some_and(|&last| last >= start))
+                .filter_map(|(key_hash, _)| history_path(key_hash))
+                .collect(),
+        };
+        let key = (cache.generation, now / 60, gap);
+        *self
+            .sessions
+            .lock()
+            .map_err(|_| Error::AcquireFrecencyLock)? = Some((key, Arc::new(files)));
+
+        let cutoff_time = cutoff_time(&options, now);
+        let now_time = LocalTime::of(now);
+        let scores: HashMap<PathBuf, i64> = cache
+            .histories
+            .iter()
+            .filter_map(|(key_hash, accesses)| {
+                let total: f64 = accesses
+                    .iter()
+                    .filter(|&&access_time| access_time >= cutoff_time)
+                    .map(|&access_time| {
+                        access_decay(&options, now, access_time)
+                            * now_time.similarity(&LocalTime::of(access_time))
+                    })
+                    .sum();
+                let score = options.normalize_access(total).round() as i64;
+                if score == 0 {
+                    return None;
+                }
+                Some((history_path(key_hash)?, score))
+            })
+            .collect();
+        let key = (cache.generation, now / 600);
+        *self
+            .time_of_day
+            .lock()
+            .map_err(|_| Error::AcquireFrecencyLock)? = Some((key, Arc::new(scores)));
+        Ok(())
+    }
+}
+
+/// The cache shared with the flusher thread, written to the database behind the accesses
+#[derive(Debug)]
+struct WriteBehind {
+    cache: RwLock<AccessCache>,
+    /// Held while writing, flushes have to land in order
+    flush_lock: Mutex<()>,
+    /// Held shared for the lifetime of every transaction of this process and exclusively to
+    /// resize the map, LMDB can't resize under a live transaction. Taken before the cache and
+    /// the writer lock, never while holding them.
+    transactions: RwLock<()>,
+    /// Accesses older than this are dropped when written, `FrecencyOptions::max_history_days`
+    max_history_secs: AtomicU64,
+    writer: WriterLock,
+}
+
+impl WriteBehind {
+    /// Loads the histories with the accesses of the journals left by crashed trackers. The
+    /// journals are returned still locked, they can go once the replayed accesses are flushed.
+    fn load(
+        env: &Env,
+        db: AccessDb,
+        db_path: &Path,
+        writer: WriterLock,
+    ) -> Result<(Self, Vec<OrphanJournal>), Error> {
+        let rtxn = env.read_txn().map_err(Error::DbStartReadTxn)?;
+        // the records of the named databases live in the same database and don't decode
+        let histories = db
+            .iter(&rtxn)
+            .map_err(Error::DbRead)?
+            .filter_map(Result::ok)
+            .filter_map(|(key, accesses)| Some((KeyHash::try_from(key).ok()?, accesses)))
+            .collect();
+
+        drop(rtxn);
+
+        let mut cache = AccessCache {
+            histories,
+            ..AccessCache::default()
+        };
+        let journal_path = db_path.join(format!(
+            "{}{}-{}{}",
+            JOURNAL_PREFIX,
+            std::process::id(),
+            OPENED_TRACKERS.fetch_add(1, Ordering::Relaxed),
+            JOURNAL_SUFFIX
+        ));
+        let journal = loop {
+            let journal = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&journal_path)?;
+            // taken before looking for orphans, so no other tracker takes this journal for one
+            journal.lock()?;
+            // another tracker may have locked and removed it as an orphan right after creation
+            if journal_path.exists() {
+                break journal;
+            }
+        };
+
+        let orphans = orphan_journals(db_path, &journal_path)?;
+        let mut replayed = 0;
+        for orphan in &orphans {
+            replayed += cache.replay_journal(&orphan.file)?;
+        }
+        if replayed > 0 {
+            tracing::info!(replayed, "Replayed frecency accesses from crashed sessions");
+        }
+        cache.journal = Some((journal_path, journal));
+
+        let write_behind = Self {
+            cache: RwLock::new(cache),
+            flush_lock: Mutex::new(()),
+            transactions: RwLock::new(()),
+            max_history_secs: AtomicU64::new(
+                (FrecencyOptions::default().max_history_days * SECONDS_PER_DAY) as u64,
+            ),
+            writer,
+        };
+        Ok((write_behind, orphans))
+    }
+
+    fn transaction(&self) -> Result<RwLockReadGuard<'_, ()>, Error> {
+        self.transactions
+            .read()
+            .map_err(|_| Error::AcquireFrecencyLock)
+    }
+
+    fn accesses(&self, key_hash: &KeyHash) -> Result<VecDeque<u64>, Error> {
+        let cache = self.cache.read().map_err(|_| Error::AcquireFrecencyLock)?;
+        Ok(cache.histories.get(key_hash).cloned().unwrap_or_default())
+    }
+
+    /// Writes the histories changed since the last flush in one transaction, returns how many.
+    /// Other processes write the same database, so every history is merged with the one on disk
+    /// instead of overwriting the accesses they recorded since this cache was loaded.
+    fn flush(&self, env: &Env, db: AccessDb, paths: PathsDb) -> Result<usize, Error> {
+        let _writing = self
+            .flush_lock
+            .lock()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        let pending: Vec<(KeyHash, PathBuf, VecDeque<u64>)> = {
+            let mut cache = self.cache.write().map_err(|_| Error::AcquireFrecencyLock)?;
+            let AccessCache {
+                histories, dirty, ..
+            } = &mut *cache;
+            dirty
+                .drain()
+                .filter_map(|(key_hash, path)| {
+                    Some((key_hash, path, histories.get(&key_hash)?.clone()))
+                })
+                .collect()
+        };
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        let cutoff = now.saturating_sub(self.max_history_secs.load(Ordering::Relaxed));
+        let write = || -> Result<Vec<(KeyHash, VecDeque<u64>)>, Error> {
+            let _transaction = self.transaction()?;
+            let _writer = self.writer.lock()?;
+            let mut wtxn = env.write_txn().map_err(Error::DbStartWriteTxn)?;
+            let mut written = Vec::with_capacity(pending.len());
+            for (key_hash, path, accesses) in &pending {
+                let stored = db.get(&wtxn, key_hash).map_err(Error::DbRead)?;
+                let merged = merge_accesses(&stored.unwrap_or_default(), accesses, cutoff);
+                db.put(&mut wtxn, key_hash, &merged)
+                    .map_err(Error::DbWrite)?;
+                put_path(paths, &mut wtxn, key_hash, path)?;
+                written.push((*key_hash, merged));
+            }
+            wtxn.commit().map_err(Error::DbCommit)?;
+            // the journal is only emptied once the accesses are on disk, even without syncs
+            env.force_sync().map_err(Error::DbCommit)?;
+            Ok(written)
+        };
+        let written = match write() {
+            Ok(written) => written,
+            Err(e) => {
+                // retried on the next flush, the histories in the cache are still the latest
+                let mut cache = self.cache.write().map_err(|_| Error::AcquireFrecencyLock)?;
+                for (key_hash, path, _) in pending {
+                    cache.dirty.entry(key_hash).or_insert(path);
+                }
+                return Err(e);
+            }
+        };
+
+        let mut cache = self.cache.write().map_err(|_| Error::AcquireFrecencyLock)?;
+        // the accesses of the other processes show up right away, histories forgotten while
+        // writing stay forgotten
+        for (key_hash, merged) in written {
+            if let Some(accesses) = cache.histories.get_mut(&key_hash) {
+                *accesses = merge_accesses(accesses, &merged, cutoff);
+            }
+        }
+        if cache.dirty.is_empty() {
+            if let Some((_, journal)) = cache.journal.as_mut() {
+                journal.set_len(0)?;
+            }
+        }
+
+        tracing::debug!(flushed = pending.len(), "Flushed frecency accesses");
+        Ok(pending.len())
+    }
+}
+
+impl Drop for WriteBehind {
+    /// Runs after the last flush of the flusher, an empty journal isn't needed anymore
+    fn drop(&mut self) {
+        let Ok(cache) = self.cache.get_mut() else {
+            return;
+        };
+        if let Some((journal_path, _)) = cache.journal.take().filter(|_| cache.dirty.is_empty()) {
+            let _ = fs::remove_file(journal_path);
+        }
+    }
+}
+
+/// Serializes the write transactions on a database across threads and processes. LMDB only
+/// does it through its lock file, which isn't used by the trackers opened without locking.
+#[derive(Debug)]
+struct WriterLock(Mutex<File>);
+
+/// Unlocks the file when dropped, it has to outlive the transaction
+struct WriterGuard<'a>(MutexGuard<'a, File>);
+
+impl WriterLock {
+    fn open(db_path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(db_path.join(WRITER_LOCK_FILE_NAME))?;
+        Ok(Self(Mutex::new(file)))
+    }
+
+    fn lock(&self) -> Result<WriterGuard<'_>, Error> {
+        let file = self.0.lock().map_err(|_| Error::AcquireFrecencyLock)?;
+        file.lock()?;
+        Ok(WriterGuard(file))
+    }
+}
+
+impl Drop for WriterGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+/// Journal of a tracker that is gone, locked by the tracker replaying it
+#[derive(Debug)]
+struct OrphanJournal {
+    path: PathBuf,
+    file: File,
+}
+
+impl OrphanJournal {
+    fn remove(self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            tracing::warn!(?e, path = ?self.path, "Failed to remove a replayed frecency journal");
+        }
+    }
+}
+
+/// The journals in the database directory no open tracker holds the lock of
+fn orphan_journals(db_path: &Path, own_journal: &Path) -> Result<Vec<OrphanJournal>, Error> {
+    let mut orphans = Vec::new();
+    for entry in fs::read_dir(db_path)? {
+        let path = entry?.path();
+        let is_journal = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                name.len() > JOURNAL_PREFIX.len() + JOURNAL_SUFFIX.len()
+                    && name.starts_with(JOURNAL_PREFIX)
+                    && name.ends_with(JOURNAL_SUFFIX)
+            });
+        if !is_journal || path == own_journal {
+            continue;
+        }
+
+        let file = match OpenOptions::new().read(true).write(true).open(&path) {
+            Ok(file) => file,
+            // removed by a tracker that replayed it first
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        match file.try_lock() {
+            Ok(()) => orphans.push(OrphanJournal { path, file }),
+            Err(fs::TryLockError::WouldBlock) => continue,
+            Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+        }
+    }
+    Ok(orphans)
+}
+
+/// Thread flushing the tracked accesses every `FLUSH_INTERVAL`, flushes a last time when dropped
+#[derive(Debug)]
+struct Flusher {
+    wake: Option<Sender<Wake>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+enum Wake {
+    Flush,
+    /// Flush and recompute the aggregates
+    Refresh,
+}
+
+impl Flusher {
+    fn spawn(
+        env: Env,
+        db: AccessDb,
+        paths: PathsDb,
+        write_behind: Arc<WriteBehind>,
+        aggregates: Arc<Aggregates>,
+    ) -> Self {
+        let (wake, woken) = mpsc::channel();
+        let thread = std::thread::spawn(move || loop {
+            // requests sent before stopping are still received before the disconnection
+            let woken = woken.recv_timeout(FLUSH_INTERVAL);
+            if let Err(e) = write_behind.flush(&env, db, paths) {
+                tracing::error!(?e, "Failed to flush frecency accesses");
+            }
+            match woken {
+                Ok(Wake::Refresh) => aggregates.refresh(&env, paths, &write_behind),
+                Err(RecvTimeoutError::Disconnected) => break,
+                Ok(Wake::Flush) | Err(RecvTimeoutError::Timeout) => {}
+            }
+        });
+
+        Self {
+            wake: Some(wake),
+            thread: Some(thread),
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// Flushes ahead of the interval
+    fn wake(&self) {
+        if let Some(wake) = &self.wake {
+            let _ = wake.send(Wake::Flush);
+        }
+    }
+
+    /// Whether the thread is there to recompute the aggregates
+    fn refresh(&self) -> bool {
+        self.is_running()
+            && self
+                .wake
+                .as_ref()
+                .is_some_and(|wake| wake.send(Wake::Refresh).is_ok())
+    }
+}
+
+impl Flusher {
+    /// Stops the thread after a last flush
+    fn stop(&mut self) {
+        self.wake.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Flusher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Sorted union of two sorted histories where a time both have is kept as many times as the
+/// history with more copies has it, drops the accesses older than the cutoff
+fn merge_accesses(stored: &VecDeque<u64>, cached: &VecDeque<u64>, cutoff: u64) -> VecDeque<u64> {
+    let mut merged = VecDeque::with_capacity(stored.len().max(cached.len()));
+    let (mut stored, mut cached) = (stored.iter().peekable(), cached.iter().peekable());
+    loop {
+        let next = match (stored.peek(), cached.peek()) {
+            (Some(a), Some(b)) if a == b => {
+                cached.next();
+                stored.next()
+            }
+            (Some(a), Some(b)) if a < b => stored.next(),
+            (_, Some(_)) => cached.next(),
+            (stored_next, None) => stored_next.is_some().then(|| stored.next()).flatten(),
+        };
+        let Some(&time) = next else {
+            return merged;
+        };
+        if time >= cutoff {
+            merged.push_back(time);
+        }
+    }
+}
+
+fn put_path(
+    paths: PathsDb,
+    wtxn: &mut heed::RwTxn,
+    key_hash: &[u8],
+    path: &Path,
+) -> Result<(), Error> {
+    let Some(path) = path.to_str() else {
+        return Err(Error::InvalidPath(path.to_path_buf()));
+    };
+
+    paths.put(wtxn, key_hash, path).map_err(Error::DbWrite)
+}
+
+/// Scores many files with the same notion of now, e.g. all the files of a scan. The scores
+/// are read from memory, so a reader per thread costs nothing.
+pub struct FrecencyReader<'a> {
+    tracker: &'a FrecencyTracker,
+    now: u64,
+}
+
+impl FrecencyReader<'_> {
+    pub fn get_access_score(&self, file_path: &Path) -> i64 {
+        let Ok(key_hash) = FrecencyTracker::path_to_hash_bytes(file_path) else {
+            return 0;
+        };
+        let accesses = self
+            .tracker
+            .write_behind
+            .accesses(&key_hash)
+            .unwrap_or_default();
+
+        self.tracker.access_score_of(&accesses, self.now)
+    }
+
+    pub fn get_modification_score(&self, modified_time: u64, git_status: Option<Status>) -> i64 {
+        self.tracker
+            .get_modification_score(modified_time, git_status)
+    }
+
+    pub fn get_edit_score(&self, file_path: &Path) -> i64 {
+        self.tracker.edit_score_at(file_path, self.now)
+    }
+}
+
+#[derive(Debug)]
+pub struct FrecencyTracker {
+    env: Env,
+    db: AccessDb,
+    paths: PathsDb,
+    co_changes: Database<Bytes, SerdeBincode<Vec<(PathBuf, u32)>>>,
+    projects: ProjectsDb,
+    write_behind: Arc<WriteBehind>,
+    flusher: Flusher,
+    /// Shared lock on `USERS_LOCK_FILE_NAME` as long as the database is open
+    users_lock: File,
+    use_unsafe_no_lock: bool,
+    options: FrecencyOptions,
+    aggregates: Arc<Aggregates>,
+    /// Latest buffer edit per file reported by the editor, a modification signal that doesn't
+    /// need git. Only kept in memory, the modification time takes over once the file is saved.
+    edit_times: RwLock<HashMap<PathBuf, u64>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DbSizeStats {
+    pub page_size: usize,
+    pub map_size: usize,
+    /// Size up to the last page ever written, this is how much of the map is taken
+    pub allocated_size: usize,
+    /// Size of the pages actually holding data, the rest are free pages left by updates
+    pub used_size: usize,
+    pub entries: usize,
+}
+
+impl DbSizeStats {
+    pub fn fragmentation(&self) -> f64 {
+        if self.allocated_size == 0 {
+            return 0.0;
+        }
+
+        1.0 - (self.used_size as f64 / self.allocated_size as f64).min(1.0)
+    }
+}
+
+/// A compaction that failed with the tracker to carry on with, see `FrecencyTracker::compact`
+#[derive(Debug)]
+pub struct FailedCompaction {
+    pub error: Error,
+    pub tracker: Option<FrecencyTracker>,
+}
+
+impl FailedCompaction {
+    fn new(error: Error, tracker: Option<FrecencyTracker>) -> Box<Self> {
+        Box::new(Self { error, tracker })
+    }
+}
+
+/// Twice the score of a file modified right now, unmerged files are what a merge is about
+const CONFLICTED_SCORE: i64 = 32;
+
+const MODIFICATION_THRESHOLDS: [(i64, u64); 5] = [
+    (16, 60 * 2),          // 2 minutes
+    (8, 60 * 15),          // 15 minutes
+    (4, 60 * 60),          // 1 hour
+    (2, 60 * 60 * 24),     // 1 day
+    (1, 60 * 60 * 24 * 7), // 1 week
+];
+
+impl FrecencyTracker {
+    /// Opens the database and compacts it first if it is mostly made of free pages. A failed
+    /// compaction is logged, the database is used as it is.
+    pub fn new(db_path: &str, use_unsafe_no_lock: bool) -> Result<Self, Error> {
+        let tracker = Self::open(db_path, use_unsafe_no_lock)?;
+
+        let stats = tracker.size_stats()?;
+        if stats.allocated_size >= COMPACTION_MIN_SIZE
+            && stats.fragmentation() >= COMPACTION_FRAGMENTATION_THRESHOLD
+        {
+            tracing::info!(?stats, "Frecency database is fragmented, compacting");
+            return match tracker.compact() {
+                Ok(tracker) => Ok(tracker),
+                Err(failed) => {
+                    let FailedCompaction { error, tracker } = *failed;
+                    tracing::warn!(?error, "Failed to compact frecency database");
+                    tracker.ok_or(error)
+                }
+            };
+        }
+
+        Ok(tracker)
+    }
+
+    fn open(db_path: &str, use_unsafe_no_lock: bool) -> Result<Self, Error> {
+        fs::create_dir_all(db_path).map_err(Error::CreateDir)?;
+        let users_lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Path::new(db_path).join(USERS_LOCK_FILE_NAME))?;
+        // waits for a compaction of another process to finish swapping the data file
+        users_lock.lock_shared()?;
+        Self::open_locked(db_path, use_unsafe_no_lock, users_lock)
+    }
+
+    fn open_locked(
+        db_path: &str,
+        use_unsafe_no_lock: bool,
+        users_lock: File,
+    ) -> Result<Self, Error> {
+        let env = unsafe {
+            let mut opts = EnvOpenOptions::new();
+            opts.map_size(INITIAL_MAP_SIZE).max_dbs(NAMED_DBS as u32);
+            if use_unsafe_no_lock {
+                opts.flags(EnvFlags::NO_LOCK | EnvFlags::NO_SYNC | EnvFlags::NO_META_SYNC);
+            }
+            opts.open(db_path).map_err(Error::EnvOpen)?
+        };
+        env.clear_stale_readers()
+            .map_err(Error::DbClearStaleReaders)?;
+
+        // we will open the default unnamed database
+        let writer = WriterLock::open(Path::new(db_path))?;
+        let writing = writer.lock()?;
+        let mut wtxn = env.write_txn().map_err(Error::DbStartWriteTxn)?;
+        let db = env
+            .create_database(&mut wtxn, None)
+            .map_err(Error::DbCreate)?;
+        let paths = env
+            .create_database(&mut wtxn, Some(PATHS_DB_NAME))
+            .map_err(Error::DbCreate)?;
+        let co_changes = env
+            .create_database(&mut wtxn, Some(CO_CHANGES_DB_NAME))
+            .map_err(Error::DbCreate)?;
+        let projects = env
+            .create_database(&mut wtxn, Some(PROJECTS_DB_NAME))
+            .map_err(Error::DbCreate)?;
+        wtxn.commit().map_err(Error::DbCommit)?;
+        drop(writing);
+
+        let (write_behind, orphans) = WriteBehind::load(&env, db, Path::new(db_path), writer)?;
+        // accesses replayed from the journals of crashed sessions are written right away
+        write_behind.flush(&env, db, paths)?;
+        orphans.into_iter().for_each(OrphanJournal::remove);
+        let write_behind = Arc::new(write_behind);
+        let aggregates = Arc::new(Aggregates::new(FrecencyOptions::default()));
+        let flusher = Flusher::spawn(
+            env.clone(),
+            db,
+            paths,
+            Arc::clone(&write_behind),
+            Arc::clone(&aggregates),
+        );
+
+        Ok(FrecencyTracker {
+            db,
+            paths,
+            co_changes,
+            projects,
+            write_behind,
+            flusher,
+            users_lock,
+            env: env.clone(),
+            use_unsafe_no_lock,
+            options: FrecencyOptions::default(),
+            aggregates,
+            edit_times: RwLock::default(),
+        })
+    }
+
+    pub fn size_stats(&self) -> Result<DbSizeStats, Error> {
+        let info = self.env.info();
+        let _transaction = self.write_behind.transaction()?;
+        let rtxn = self.env.read_txn().map_err(Error::DbStartReadTxn)?;
+        let stat = self.db.stat(&rtxn).map_err(Error::DbRead)?;
+        let paths_stat = self.paths.stat(&rtxn).map_err(Error::DbRead)?;
+        let co_changes_stat = self.co_changes.stat(&rtxn).map_err(Error::DbRead)?;
+        let projects_stat = self.projects.stat(&rtxn).map_err(Error::DbRead)?;
+
+        let page_size = stat.page_size as usize;
+        // two meta pages are always at the start of the file
+        let used_pages = stat.branch_pages
+            + stat.leaf_pages
+            + stat.overflow_pages
+            + paths_stat.branch_pages
+            + paths_stat.leaf_pages
+            + paths_stat.overflow_pages
+            + co_changes_stat.branch_pages
+            + co_changes_stat.leaf_pages
+            + co_changes_stat.overflow_pages
+            + projects_stat.branch_pages
+            + projects_stat.leaf_pages
+            + projects_stat.overflow_pages
+            + 2;
+
+        Ok(DbSizeStats {
+            page_size,
+            map_size: info.map_size,
+            allocated_size: (info.last_page_number + 1) * page_size,
+            used_size: used_pages * page_size,
+            entries: stat.entries.saturating_sub(NAMED_DBS),
+        })
+    }
+
+    /// Checks if the memory map is close enough to being full that it should be grown before
+    /// the next write.
+    pub fn needs_map_growth(&self) -> bool {
+        self.size_stats().is_ok_and(|stats| {
+            stats.map_size < MAX_MAP_SIZE
+                && stats.allocated_size as f64 >= stats.map_size as f64 * MAP_GROWTH_THRESHOLD
+        })
+    }
+
+    /// Doubles the memory map size up to `MAX_MAP_SIZE`. Waits for the transactions of this
+    /// process to end because LMDB doesn't allow resizing while any of them is alive.
+    pub fn grow_map_size(&self) -> Result<usize, Error> {
+        let DbSizeStats {
+            map_size: current_size,
+            page_size,
+            ..
+        } = self.size_stats()?;
+        let new_size = (current_size.saturating_mul(2).min(MAX_MAP_SIZE) / page_size) * page_size;
+
+        if new_size <= current_size {
+            return Ok(current_size);
+        }
+
+        let _resizing = self
+            .write_behind
+            .transactions
+            .write()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        // SAFETY: every transaction of this process, the ones of the flusher included, holds
+        // the shared side of the lock, so none is alive
+        unsafe { self.env.resize(new_size) }.map_err(Error::DbResize)?;
+        tracing::info!(current_size, new_size, "Grew frecency database map size");
+
+        Ok(new_size)
+    }
+
+    /// Rewrites the database into a new file omitting all the free pages, then reopens it.
+    /// Consumes the tracker because the environment has to be fully closed for the swap.
+    ///
+    /// Skipped while another tracker has the database open, it would keep using the old file.
+    /// A failed compaction returns the tracker to carry on with, `None` only when the database
+    /// was closed already and couldn't be opened again.
+    pub fn compact(mut self) -> Result<Self, Box<FailedCompaction>> {
+        let Some(db_path) = self.env.path().to_str().map(str::to_string) else {
+            let db_path = self.env.path().to_path_buf();
+            return Err(FailedCompaction::new(
+                Error::InvalidPath(db_path),
+                Some(self),
+            ));
+        };
+        let compacted_path = Path::new(&db_path).join(COMPACTED_FILE_NAME);
+
+        if let Err(e) = self.flush() {
+            return Err(FailedCompaction::new(e, Some(self)));
+        }
+        // upgrading the shared lock may drop it before failing, so it is taken again
+        match self.users_lock.try_lock() {
+            Ok(()) => {}
+            Err(fs::TryLockError::WouldBlock) => {
+                tracing::info!("Frecency database is open in another process, not compacting");
+                return match self.users_lock.lock_shared() {
+                    Ok(()) => Ok(self),
+                    Err(e) => Err(FailedCompaction::new(e.into(), Some(self))),
+                };
+            }
+            Err(fs::TryLockError::Error(e)) => {
+                let _ = self.users_lock.lock_shared();
+                return Err(FailedCompaction::new(e.into(), Some(self)));
+            }
+        }
+
+        // the flusher holds the environment open as well, it has to stop before closing
+        self.flusher.stop();
+        let _ = fs::remove_file(&compacted_path);
+        if let Err(e) = self
+            .env
+            .copy_to_path(&compacted_path, CompactionOption::Enabled)
+        {
+            let _ = fs::remove_file(&compacted_path);
+            let _ = self.users_lock.lock_shared();
+            self.flusher = Flusher::spawn(
+                self.env.clone(),
+                self.db,
+                self.paths,
+                Arc::clone(&self.write_behind),
+                Arc::clone(&self.aggregates),
+            );
+            return Err(FailedCompaction::new(Error::DbCompact(e), Some(self)));
+        }
+
+        let FrecencyTracker {
+            env,
+            write_behind,
+            users_lock,
+            use_unsafe_no_lock,
+            options,
+            ..
+        } = self;
+        drop(write_behind);
+        env.prepare_for_closing().wait();
+
+        // the exclusive lock is kept until the tracker is open again, so nobody opens the
+        // database in between
+        let renamed = fs::rename(&compacted_path, Path::new(&db_path).join(DATA_FILE_NAME));
+        let reopened =
+            Self::open_locked(&db_path, use_unsafe_no_lock, users_lock).map(|mut tracker| {
+                let _ = tracker.users_lock.lock_shared();
+                tracker.set_options(options);
+                tracker
+            });
+        match (renamed, reopened) {
+            (Ok(()), Ok(tracker)) => {
+                tracing::info!(stats = ?tracker.size_stats(), "Compacted frecency database");
+                Ok(tracker)
+            }
+            (Err(e), reopened) => {
+                let _ = fs::remove_file(&compacted_path);
+                Err(FailedCompaction::new(e.into(), reopened.ok()))
+            }
+            (Ok(()), Err(e)) => Err(FailedCompaction::new(e, None)),
+        }
+    }
+
+    fn get_accesses(&self, path: &Path) -> Result<VecDeque<u64>, Error> {
+        let key_hash = Self::path_to_hash_bytes(path)?;
+        self.write_behind.accesses(&key_hash)
+    }
+
+    /// Writes the accesses tracked since the last flush, returns how many histories changed.
+    /// Happens in the background on its own, this is for when the process is about to exit.
+    pub fn flush(&self) -> Result<usize, Error> {
+        self.write_behind.flush(&self.env, self.db, self.paths)
+    }
+
+    fn get_now(&self) -> u64 {
+        now_secs()
+    }
+
+    fn path_to_hash_bytes(path: &Path) -> Result<[u8; 32], Error> {
+        let Some(key) = path.to_str() else {
+            return Err(Error::InvalidPath(path.to_path_buf()));
+        };
+
+        Ok(*blake3::hash(key.as_bytes()).as_bytes())
+    }
+
+    /// Records the access in memory, the database is written behind by the flusher thread
+    pub fn track_access(&self, path: &Path) -> Result<(), Error> {
+        let key_hash = Self::path_to_hash_bytes(path)?;
+        let now = self.get_now();
+        let cutoff_time = self.cutoff_time(now);
+        let dirty_entries = {
+            let mut cache = self
+                .write_behind
+                .cache
+                .write()
+                .map_err(|_| Error::AcquireFrecencyLock)?;
+            let accesses = cache.histories.entry(key_hash).or_default();
+            while let Some(&front_time) = accesses.front() {
+                if front_time < cutoff_time {
+                    accesses.pop_front();
+                } else {
+                    break;
+                }
+            }
+
+            accesses.push_back(now);
+            tracing::debug!(?path, accesses = accesses.len(), "Tracking access");
+            cache.generation += 1;
+            cache.dirty.insert(key_hash, path.to_path_buf());
+            cache.journal_access(path, now);
+            cache.dirty.len()
+        };
+
+        // without the flusher thread nothing would ever write the access
+        if !self.flusher.is_running() {
+            self.flush()?;
+        } else if dirty_entries >= MAX_DIRTY_ENTRIES {
+            self.flusher.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Files opened in the current session and the one before it, sessions being split by
+    /// gaps without any access longer than `FrecencyOptions::session_gap_minutes`. Right after
+    /// a break the current session is still empty, so these are the files of the last one.
+    /// Computed in the background, stale ones (empty at first) are returned while refreshing.
+    pub fn session_files(&self) -> Result<Arc<HashSet<PathBuf>>, Error> {
+        let now = self.get_now();
+        let gap = (self.options.session_gap_minutes * 60.0) as u64;
+        let key = (self.aggregates_generation()?, now / 60, gap);
+        let cached = self
+            .aggregates
+            .sessions
+            .lock()
+            .map_err(|_| Error::AcquireFrecencyLock)?
+            .clone();
+
+        match cached {
+            Some((cached_key, files)) if cached_key == key => Ok(files),
+            cached => {
+                self.refresh_aggregates();
+                Ok(cached.map(|(_, files)| files).unwrap_or_default())
+            }
+        }
+    }
+
+    /// Access scores counting only the accesses made at a similar time of the day and kind of
+    /// day as now, the files of the evening project stand out in the evening. Accesses within
+    /// `SIMILAR_HOURS` of the current local time count, the closer the more, and accesses of a
+    /// weekday count half on weekends and the other way around. Computed in the background like
+    /// `session_files`.
+    pub fn time_of_day_scores(&self) -> Result<Arc<HashMap<PathBuf, i64>>, Error> {
+        // the similarity moves slowly, recomputing every 10 minutes is plenty
+        let key = (self.aggregates_generation()?, self.get_now() / 600);
+        let cached = self
+            .aggregates
+            .time_of_day
+            .lock()
+            .map_err(|_| Error::AcquireFrecencyLock)?
+            .clone();
+
+        match cached {
+            Some((cached_key, scores)) if cached_key == key => Ok(scores),
+            cached => {
+                self.refresh_aggregates();
+                Ok(cached.map(|(_, scores)| scores).unwrap_or_default())
+            }
+        }
+    }
+
+    fn aggregates_generation(&self) -> Result<u64, Error> {
+        let cache = self
+            .write_behind
+            .cache
+            .read()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        Ok(cache.generation)
+    }
+
+    /// Asks the flusher to recompute the aggregates unless it's on it already, without the
+    /// flusher thread they are recomputed right away
+    fn refresh_aggregates(&self) {
+        if self.aggregates.refreshing.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        if !self.flusher.refresh() {
+            self.aggregates
+                .refresh(&self.env, self.paths, &self.write_behind);
+        }
+    }
+
+    /// Every path with access history whose path string is known. Databases written before the
+    /// paths were stored only list the files accessed or indexed since the upgrade.
+    pub fn tracked_paths(&self) -> Result<Vec<PathBuf>, Error> {
+        self.flush()?;
+        let _transaction = self.write_behind.transaction()?;
+        let rtxn = self.env.read_txn().map_err(Error::DbStartReadTxn)?;
+        let iter = self.paths.iter(&rtxn).map_err(Error::DbRead)?;
+
+        iter.map(|entry| {
+            let (_, path) = entry.map_err(Error::DbRead)?;
+            Ok(PathBuf::from(path))
+        })
+        .collect()
+    }
+
+    /// Lazy upgrade of databases written before the paths were stored: records the path of
+    /// every known file that has history but no path yet. Returns the recorded paths count.
+    pub fn backfill_paths<'a>(
+        &self,
+        known_paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Result<usize, Error> {
+        self.flush()?;
+        let mut missing = {
+            let _transaction = self.write_behind.transaction()?;
+            let rtxn = self.env.read_txn().map_err(Error::DbStartReadTxn)?;
+            let histories = self
+                .db
+                .len(&rtxn)
+                .map_err(Error::DbRead)?
+                .saturating_sub(NAMED_DBS as u64);
+            let paths = self.paths.len(&rtxn).map_err(Error::DbRead)?;
+            histories.saturating_sub(paths)
+        };
+        if missing == 0 {
+            return Ok(0);
+        }
+
+        let _transaction = self.write_behind.transaction()?;
+        let _writer = self.write_behind.writer.lock()?;
+        let mut wtxn = self.env.write_txn().map_err(Error::DbStartWriteTxn)?;
+        let mut recorded = 0;
+        for path in known_paths {
+            let Ok(key_hash) = Self::path_to_hash_bytes(path) else {
+                continue;
+            };
+            let has_history = self.db.remap_data_type::<Bytes>().get(&wtxn, &key_hash);
+            let has_path = self.paths.get(&wtxn, &key_hash);
+            if has_history.map_err(Error::DbRead)?.is_none()
+                || has_path.map_err(Error::DbRead)?.is_some()
+            {
+                continue;
+            }
+
+            put_path(self.paths, &mut wtxn, &key_hash, path)?;
+            recorded += 1;
+            missing -= 1;
+            if missing == 0 {
+                break;
+            }
+        }
+
+        wtxn.commit().map_err(Error::DbCommit)?;
+        tracing::info!(recorded, missing, "Backfilled frecency paths");
+        Ok(recorded)
+    }
+
+    /// Replaces the whole co-change graph with a freshly mined one, keyed by the full paths
+    pub fn store_co_changes(
+        &self,
+        graph: &HashMap<PathBuf, Vec<(PathBuf, u32)>>,
+    ) -> Result<(), Error> {
+        let _transaction = self.write_behind.transaction()?;
+        let _writer = self.write_behind.writer.lock()?;
+        let mut wtxn = self.env.write_txn().map_err(Error::DbStartWriteTxn)?;
+        self.co_changes.clear(&mut wtxn).map_err(Error::DbWrite)?;
+        for (path, related) in graph {
+            let key_hash = Self::path_to_hash_bytes(path)?;
+            self.co_changes
+                .put(&mut wtxn, &key_hash, related)
+                .map_err(Error::DbWrite)?;
+        }
+
+        wtxn.commit().map_err(Error::DbCommit)?;
+        Ok(())
+    }
+
+    /// Files most frequently committed together with the path and how many times they were,
+    /// empty until the graph is mined
+    pub fn get_co_changes(&self, path: &Path) -> Result<Vec<(PathBuf, u32)>, Error> {
+        let _transaction = self.write_behind.transaction()?;
+        let rtxn = self.env.read_txn().map_err(Error::DbStartReadTxn)?;
+
+        let key_hash = Self::path_to_hash_bytes(path)?;
+        let related = self
+            .co_changes
+            .get(&rtxn, &key_hash)
+            .map_err(Error::DbRead)?;
+        Ok(related.unwrap_or_default())
+    }
+
+    /// Moves the access history of renamed files to their new paths in one transaction,
+    /// merging it with any history the new path already has. Returns the moved entries count.
+    pub fn move_accesses(&self, moves: &[(PathBuf, PathBuf)]) -> Result<usize, Error> {
+        self.flush()?;
+        let _transaction = self.write_behind.transaction()?;
+        let _writer = self.write_behind.writer.lock()?;
+        let mut wtxn = self.env.write_txn().map_err(Error::DbStartWriteTxn)?;
+
+        let mut moved = Vec::new();
+        for (from, to) in moves {
+            let from_hash = Self::path_to_hash_bytes(from)?;
+            let Some(mut accesses) = self.db.get(&wtxn, &from_hash).map_err(Error::DbRead)? else {
+                continue;
+            };
+
+            let to_hash = Self::path_to_hash_bytes(to)?;
+            if let Some(existing) = self.db.get(&wtxn, &to_hash).map_err(Error::DbRead)? {
+                accesses.extend(existing);
+                accesses.make_contiguous().sort_unstable();
+            }
+
+            self.db
+                .delete(&mut wtxn, &from_hash)
+                .map_err(Error::DbWrite)?;
+            self.paths
+                .delete(&mut wtxn, &from_hash)
+                .map_err(Error::DbWrite)?;
+            self.db
+                .put(&mut wtxn, &to_hash, &accesses)
+                .map_err(Error::DbWrite)?;
+            put_path(self.paths, &mut wtxn, &to_hash, to)?;
+            moved.push((from_hash, to_hash, accesses));
+        }
+
+        wtxn.commit().map_err(Error::DbCommit)?;
+
+        let mut cache = self
+            .write_behind
+            .cache
+            .write()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        for (from_hash, to_hash, accesses) in &moved {
+            cache.histories.remove(from_hash);
+            cache.histories.insert(*to_hash, accesses.clone());
+        }
+
+        tracing::debug!(moved = moved.len(), "Moved access history");
+        Ok(moved.len())
+    }
+
+    /// Rewrites the history of every tracked path under the old prefix to live under the new
+    /// one. The known paths cover history written before the paths were stored in the database.
+    pub fn migrate_prefix<'a>(
+        &self,
+        old_prefix: &Path,
+        new_prefix: &Path,
+        known_paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Result<usize, Error> {
+        let rewrite = |path: &Path| {
+            if let Ok(suffix) = path.strip_prefix(new_prefix) {
+                Some((old_prefix.join(suffix), path.to_path_buf()))
+            } else if let Ok(suffix) = path.strip_prefix(old_prefix) {
+                Some((path.to_path_buf(), new_prefix.join(suffix)))
+            } else {
+                None
+            }
+        };
+
+        let tracked_paths = self.tracked_paths()?;
+        let moves: Vec<(PathBuf, PathBuf)> = known_paths
+            .into_iter()
+            .filter_map(rewrite)
+            .chain(tracked_paths.iter().filter_map(|path| rewrite(path)))
+            .collect();
+
+        let migrated = self.move_accesses(&moves)?;
+        tracing::info!(
+            ?old_prefix,
+            ?new_prefix,
+            migrated,
+            "Migrated frecency prefix"
+        );
+        Ok(migrated)
+    }
+
+    /// Records that a picker was opened in the project now, only recorded projects age out
+    pub fn touch_project(&self, root: &Path) -> Result<(), Error> {
+        let Some(root_str) = root.to_str() else {
+            return Err(Error::InvalidPath(root.to_path_buf()));
+        };
+
+        let _transaction = self.write_behind.transaction()?;
+        let _writer = self.write_behind.writer.lock()?;
+        let mut wtxn = self.env.write_txn().map_err(Error::DbStartWriteTxn)?;
+        self.projects
+            .put(&mut wtxn, root_str, &self.get_now())
+            .map_err(Error::DbWrite)?;
+        wtxn.commit().map_err(Error::DbCommit)?;
+        Ok(())
+    }
+
+    /// Drops the access history and the co-changes of every tracked path under the root
+    /// together with the record of the project. Returns the forgotten files count.
+    pub fn forget_project(&self, root: &Path) -> Result<usize, Error> {
+        let forgotten: Vec<(KeyHash, PathBuf)> = self
+            .tracked_paths()?
+            .into_iter()
+            .filter(|path| path.starts_with(root))
+            .filter_map(|path| Some((Self::path_to_hash_bytes(&path).ok()?, path)))
+            .collect();
+
+        let _transaction = self.write_behind.transaction()?;
+        let _writer = self.write_behind.writer.lock()?;
+        let mut wtxn = self.env.write_txn().map_err(Error::DbStartWriteTxn)?;
+        for (key_hash, _) in &forgotten {
+            self.db
+                .delete(&mut wtxn, key_hash)
+                .map_err(Error::DbWrite)?;
+            self.paths
+                .delete(&mut wtxn, key_hash)
+                .map_err(Error::DbWrite)?;
+            self.co_changes
+                .delete(&mut wtxn, key_hash)
+                .map_err(Error::DbWrite)?;
+        }
+        if let Some(root_str) = root.to_str() {
+            self.projects
+                .delete(&mut wtxn, root_str)
+                .map_err(Error::DbWrite)?;
+        }
+        wtxn.commit().map_err(Error::DbCommit)?;
+
+        {
+            let mut cache = self
+                .write_behind
+                .cache
+                .write()
+                .map_err(|_| Error::AcquireFrecencyLock)?;
+            for (key_hash, _) in &forgotten {
+                cache.histories.remove(key_hash);
+                cache.dirty.remove(key_hash);
+            }
+            // the cached sessions and time of day scores may list the forgotten files
+            cache.generation += 1;
+        }
+        if let Ok(mut edit_times) = self.edit_times.write() {
+            edit_times.retain(|path, _| !path.starts_with(root));
+        }
+
+        tracing::info!(
+            ?root,
+            forgotten = forgotten.len(),
+            "Forgot frecency project"
+        );
+        Ok(forgotten.len())
+    }
+
+    /// Forgets the recorded projects that were neither opened nor had a file accessed within
+    /// `forget_projects_after_days`, returns their roots. Does nothing when that is 0.
+    pub fn age_out_projects(&self) -> Result<Vec<PathBuf>, Error> {
+        if self.options.forget_projects_after_days <= 0.0 {
+            return Ok(Vec::new());
+        }
+
+        let cutoff = self
+            .get_now()
+            .saturating_sub((self.options.forget_projects_after_days * SECONDS_PER_DAY) as u64);
+        let mut last_used: Vec<(PathBuf, u64)> = {
+            let _transaction = self.write_behind.transaction()?;
+            let rtxn = self.env.read_txn().map_err(Error::DbStartReadTxn)?;
+            let iter = self.projects.iter(&rtxn).map_err(Error::DbRead)?;
+            iter.map(|entry| {
+                let (root, opened) = entry.map_err(Error::DbRead)?;
+                Ok((PathBuf::from(root), opened))
+            })
+            .collect::<Result<_, Error>>()?
+        };
+        if last_used.iter().all(|(_, opened)| *opened >= cutoff) {
+            return Ok(Vec::new());
+        }
+
+        let tracked_paths = self.tracked_paths()?;
+        {
+            let cache = self
+                .write_behind
+                .cache
+                .read()
+                .map_err(|_| Error::AcquireFrecencyLock)?;
+            for path in &tracked_paths {
+                let Some(&accessed) = Self::path_to_hash_bytes(path)
+                    .ok()
+                    .and_then(|key_hash| cache.histories.get(&key_hash)?.back())
+                else {
+                    continue;
+                };
+                for (root, last) in last_used.iter_mut() {
+                    if path.starts_with(root.as_path()) {
+                        *last = (*last).max(accessed);
+                    }
+                }
+            }
+        }
+
+        let mut forgotten = Vec::new();
+        for (root, last) in last_used {
+            if last < cutoff {
+                self.forget_project(&root)?;
+                forgotten.push(root);
+            }
+        }
+        Ok(forgotten)
+    }
+
+    pub fn get_access_score(&self, file_path: &Path) -> i64 {
+        tracing::debug!(?file_path, "Calculating access score");
+        let accesses = self.get_accesses(file_path).unwrap_or_default();
+
+        self.access_score_of(&accesses, self.get_now())
+    }
+
+    /// Reader scoring many files at once, see `FrecencyReader`
+    pub fn reader(&self) -> Result<FrecencyReader<'_>, Error> {
+        Ok(FrecencyReader {
+            tracker: self,
+            now: self.get_now(),
+        })
+    }
+
+    fn access_score_of(&self, accesses: &VecDeque<u64>, now: u64) -> i64 {
+        if accesses.is_empty() {
+            return 0;
+        }
+
+        let mut total_frecency = 0.0;
+
+        let cutoff_time = self.cutoff_time(now);
+
+        for &access_time in accesses.iter().rev() {
+            if access_time < cutoff_time {
+                break; // All remaining entries are older, stop processing
+            }
+
+            total_frecency += self.access_decay(now, access_time);
+        }
+
+        self.options.normalize_access(total_frecency).round() as i64
+    }
+
+    fn access_decay(&self, now: u64, access_time: u64) -> f64 {
+        access_decay(&self.options, now, access_time)
+    }
+
+    fn cutoff_time(&self, now: u64) -> u64 {
+        cutoff_time(&self.options, now)
+    }
+
+    pub fn options(&self) -> FrecencyOptions {
+        self.options
+    }
+
+    /// Only affects scores computed from now on, callers have to refresh cached file scores
+    pub fn set_options(&mut self, options: FrecencyOptions) {
+        self.options = options;
+        self.write_behind.max_history_secs.store(
+            (options.max_history_days * SECONDS_PER_DAY) as u64,
+            Ordering::Relaxed,
+        );
+        MAX_TOTAL_SCORE.store(options.max_score.max(0), Ordering::Relaxed);
+        // the aggregates are computed with the options, the time of day scores decay with the
+        // half life and the sessions are split by the gap
+        if let Ok(mut aggregates_options) = self.aggregates.options.lock() {
+            *aggregates_options = options;
+        }
+        if let Ok(mut cache) = self.write_behind.cache.write() {
+            cache.generation += 1;
+        }
+    }
+
+    /// Returns every stored access with its own decay contribution together with the inputs
+    /// of the modification score, this is what `get_access_score` and
+    /// `get_modification_score` compute under the hood.
+    pub fn get_frecency_details(
+        &self,
+        path: &Path,
+        modified_time: Option<u64>,
+        git_status: Option<Status>,
+    ) -> Result<FrecencyDetails, Error> {
+        let now = self.get_now();
+        let cutoff_time = self.cutoff_time(now);
+
+        let accesses: Vec<AccessRecord> = self
+            .get_accesses(path)?
+            .into_iter()
+            .map(|timestamp| AccessRecord {
+                timestamp,
+                days_ago: (now.saturating_sub(timestamp) as f64) / SECONDS_PER_DAY,
+                decay: if timestamp < cutoff_time {
+                    0.0
+                } else {
+                    self.access_decay(now, timestamp)
+                },
+            })
+            .collect();
+
+        let raw_access_score: f64 = accesses.iter().map(|access| access.decay).sum();
+
+        Ok(FrecencyDetails {
+            raw_access_score,
+            access_score: self.options.normalize_access(raw_access_score).round() as i64,
+            accesses,
+            modified: modified_time,
+            seconds_since_modification: modified_time.map(|time| now.saturating_sub(time)),
+            is_modified_git_status: git_status.is_some_and(is_modified_status),
+            last_edit: self.last_edit(path),
+            modification_score: modified_time
+                .map_or(0, |time| self.get_modification_score(time, git_status))
+                .max(self.edit_score_at(path, now)),
+        })
+    }
+
+    /// Recent access counts of many files, in the order of `paths`
+    pub fn get_access_counts(&self, paths: &[PathBuf]) -> Result<Vec<AccessCount>, Error> {
+        let cache = self
+            .write_behind
+            .cache
+            .read()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        let cutoff_time = self.cutoff_time(self.get_now());
+
+        paths
+            .iter()
+            .map(|path| {
+                let key_hash = Self::path_to_hash_bytes(path)?;
+                let accesses = cache.histories.get(&key_hash);
+                Ok(
+                    accesses.map_or_else(AccessCount::default, |accesses| AccessCount {
+                        count: accesses.iter().filter(|&&time| time >= cutoff_time).count(),
+                        last_access: accesses.back().copied(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    /// Calculating modification score but only if the file is modified in the current git dir
+    pub fn get_modification_score(&self, modified_time: u64, git_status: Option<Status>) -> i64 {
+        let is_modified_git_status = git_status.is_some_and(is_modified_status);
+        if !is_modified_git_status {
+            return 0;
+        }
+
+        // conflicts have to be resolved no matter how long ago the file was touched
+        if git_status.is_some_and(is_conflicted_status) {
+            return CONFLICTED_SCORE;
+        }
+
+        modification_points(self.get_now().saturating_sub(modified_time))
+    }
+
+    /// Records an edit of the file at `time`, now by default. The file then gets the same
+    /// modification score as a file with that git status and modification time.
+    pub fn track_edit(&self, path: &Path, time: Option<u64>) -> Result<(), Error> {
+        let time = time.unwrap_or_else(|| self.get_now());
+        let mut edit_times = self
+            .edit_times
+            .write()
+            .map_err(|_| Error::AcquireFrecencyLock)?;
+        if edit_times.len() >= MAX_EDIT_TIMES && !edit_times.contains_key(path) {
+            let oldest = edit_times
+                .iter()
+                .min_by_key(|(_, &time)| time)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                edit_times.remove(&oldest);
+            }
+        }
+
+        let edited = edit_times.entry(path.to_path_buf()).or_default();
+        *edited = (*edited).max(time);
+        Ok(())
+    }
+
+    pub fn last_edit(&self, path: &Path) -> Option<u64> {
+        let edit_times = self.edit_times.read().ok()?;
+        edit_times.get(path).copied()
+    }
+
+    /// Modification score of the latest edit reported with `track_edit`
+    pub fn get_edit_score(&self, path: &Path) -> i64 {
+        self.edit_score_at(path, self.get_now())
+    }
+
+    fn edit_score_at(&self, path: &Path, now: u64) -> i64 {
+        self.last_edit(path)
+            .map_or(0, |edited| modification_points(now.saturating_sub(edited)))
+    }
+}
+
+/// Modification score of a file outside of any repository, where its modification time is
+/// the only hint that it is being worked on
+pub fn mtime_score(modified_time: u64) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    modification_points(now.saturating_sub(modified_time))
+}
+
+/// Points of a file modified `duration_since` seconds ago, interpolated between the thresholds
+fn modification_points(duration_since: u64) -> i64 {
+    for i in 0..MODIFICATION_THRESHOLDS.len() {
+        let (current_points, current_threshold) = MODIFICATION_THRESHOLDS[i];
+
+        if duration_since <= current_threshold {
+            if i == 0 || duration_since == current_threshold {
+                return current_points;
+            }
+
+            let (prev_points, prev_threshold) = MODIFICATION_THRESHOLDS[i - 1];
+
+            let time_range = current_threshold - prev_threshold;
+            let time_offset = duration_since - prev_threshold;
+            let points_diff = prev_points - current_points;
+
+            let interpolated_score =
+                prev_points - (points_diff * time_offset as i64) / time_range as i64;
+
+            return interpolated_score;
+        }
+    }
+
+    0
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn access_decay(options: &FrecencyOptions, now: u64, access_time: u64) -> f64 {
+    let days_ago = (now.saturating_sub(access_time) as f64) / SECONDS_PER_DAY;
+    (-options.decay_constant() * days_ago).exp()
+}
+
+fn cutoff_time(options: &FrecencyOptions, now: u64) -> u64 {
+    now.saturating_sub((options.max_history_days * SECONDS_PER_DAY) as u64)
+}
+
+/// Local time of an access, as far as `time_of_day_scores` cares
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LocalTime {
+    /// Fractional hour of the day
+    hour: f64,
+    weekend: bool,
+}
+
+impl LocalTime {
+    fn of(timestamp: u64) -> Self {
+        let Some(time) = chrono::Local.timestamp_opt(timestamp as i64, 0).single() else {
+            return Self {
+                hour: 0.0,
+                weekend: false,
+            };
+        };
+
+        Self {
+            hour: time.hour() as f64 + time.minute() as f64 / 60.0,
+            weekend: time.weekday().number_from_monday() >= 6,
+        }
+    }
+
+    /// 1 for the same time of a day of the same kind, falling to 0 at `SIMILAR_HOURS` apart
+    fn similarity(&self, other: &Self) -> f64 {
+        let apart = (self.hour - other.hour).abs();
+        // 23:30 and 00:30 are an hour apart
+        let apart = apart.min(24.0 - apart);
+        let of_day = (1.0 - apart / SIMILAR_HOURS).max(0.0);
+        if self.weekend == other.weekend {
+            of_day
+        } else {
+            of_day / 2.0
+        }
+    }
+}
+
+/// Earliest access of the session before the current one, the current session being the
+/// accesses since the last gap longer than `gap` seconds. `None` without any access.
+fn previous_session_start(mut times: Vec<u64>, now: u64, gap: u64) -> Option<u64> {
+    times.sort_unstable_by(|a, b| b.cmp(a));
+    let mut gaps = 0;
+    let mut later = now;
+    for &time in &times {
+        if later.saturating_sub(time) > gap {
+            gaps += 1;
+            if gaps == 2 {
+                return Some(later);
+            }
+        }
+        later = time;
+    }
+
+    times.last().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calculate_test_frecency_score(access_timestamps: &[u64], current_time: u64) -> i64 {
+        let mut total_frecency = 0.0;
+
+        for &access_time in access_timestamps {
+            let days_ago = (current_time.saturating_sub(access_time) as f64) / SECONDS_PER_DAY;
+            let decay_factor = (-FrecencyOptions::default().decay_constant() * days_ago).exp();
+            total_frecency += decay_factor;
+        }
+
+        FrecencyOptions::default()
+            .normalize_access(total_frecency)
+            .round() as i64
+    }
+
+    #[test]
+    fn test_frecency_calculation() {
+        let current_time = 1000000000; // Base timestamp
+
+        let score = calculate_test_frecency_score(&[], current_time);
+        assert_eq!(score, 0);
+
+        let accesses = [current_time]; // Accessed right now
+        let score = calculate_test_frecency_score(&accesses, current_time);
+        assert_eq!(score, 1); // 1.0 decay factor = 1
+
+        let ten_days_seconds = 10 * 86400; // 10 days in seconds
+        let accesses = [current_time - ten_days_seconds];
+        let score = calculate_test_frecency_score(&accesses, current_time);
+        assert_eq!(score, 1); // ~0.5 decay factor rounds to 1
+
+        let accesses = [
+            current_time,          // Today
+            current_time - 86400,  // 1 day ago
+            current_time - 172800, // 2 days ago
+        ];
+        let score = calculate_test_frecency_score(&accesses, current_time);
+        assert!(score > 2 && score < 4, "Score: {}", score); // About 3 accesses with decay
+
+        let thirty_days = 30 * 86400;
+        let accesses = [current_time - thirty_days]; // 30 days ago
+        let score = calculate_test_frecency_score(&accesses, current_time);
+        assert!(
+            score < 2,
+            "Old access should have minimal score, got: {}",
+            score
+        );
+
+        let recent_frequent = [current_time, current_time - 86400, current_time - 172800];
+        let old_single = [current_time - ten_days_seconds];
+
+        let recent_score = calculate_test_frecency_score(&recent_frequent, current_time);
+        let old_score = calculate_test_frecency_score(&old_single, current_time);
+
+        assert!(
+            recent_score > old_score,
+            "Recent frequent access ({}) should score higher than old single access ({})",
+            recent_score,
+            old_score
+        );
+    }
+
+    #[test]
+    fn test_access_curve() {
+        let options = FrecencyOptions::default();
+        assert_eq!(options.normalize_access(4.0), 4.0);
+        assert_eq!(options.normalize_access(26.0), 14.0);
+
+        let linear = FrecencyOptions {
+            access_curve: 1.0,
+            ..options
+        };
+        assert_eq!(linear.normalize_access(26.0), 26.0);
+
+        let flat = FrecencyOptions {
+            access_soft_cap: 5.0,
+            access_curve: 0.0,
+            ..options
+        };
+        assert_eq!(flat.normalize_access(100.0), 6.0);
+    }
+
+    #[test]
+    fn test_modification_score_interpolation() {
+        let temp_dir = std::env::temp_dir().join("fff_test_interpolation");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let current_time = tracker.get_now();
+        let git_status = Some(Status::WT_MODIFIED);
+
+        // At 5 minutes: should interpolate between 16 and 8 points
+        let five_minutes_ago = current_time - (5 * 60);
+        let score = tracker.get_modification_score(five_minutes_ago, git_status);
+
+        // Expected: 16 - (8 * 3 / 13) = 16 - 1 = 15 points
+        // (time_offset = 5-2 = 3, time_range = 15-2 = 13, points_diff = 16-8 = 8)
+        assert_eq!(score, 15, "5 minutes should interpolate to 15 points");
+
+        let two_minutes_ago = current_time - (2 * 60);
+        let score = tracker.get_modification_score(two_minutes_ago, git_status);
+        assert_eq!(score, 16, "2 minutes should be exactly 16 points");
+
+        let fifteen_minutes_ago = current_time - (15 * 60);
+        let score = tracker.get_modification_score(fifteen_minutes_ago, git_status);
+        assert_eq!(score, 8, "15 minutes should be exactly 8 points");
+
+        // At 12 hours: should interpolate between 4 and 2 points
+        let twelve_hours_ago = current_time - (12 * 60 * 60);
+        let score = tracker.get_modification_score(twelve_hours_ago, git_status);
+        // Expected: 4 - (2 * 11 / 23) = 4 - 0 = 4 points (integer division)
+        // (time_offset = 12-1 = 11 hours, time_range = 24-1 = 23 hours, points_diff = 4-2 = 2)
+        assert_eq!(score, 4, "12 hours should interpolate to 4 points");
+
+        // at 18 hours for more significant interpolation
+        let eighteen_hours_ago = current_time - (18 * 60 * 60);
+        let score = tracker.get_modification_score(eighteen_hours_ago, git_status);
+        // Expected: 4 - (2 * 17 / 23) = 4 - 1 = 3 points
+        assert_eq!(score, 3, "18 hours should interpolate to 3 points");
+
+        let score = tracker.get_modification_score(five_minutes_ago, None);
+        assert_eq!(score, 0, "No git status should return 0");
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_conflicted_modification_score() {
+        let temp_dir = std::env::temp_dir().join("fff_test_conflicted");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let week_ago = tracker.get_now() - (8 * 24 * 60 * 60);
+        let score = tracker.get_modification_score(week_ago, Some(Status::CONFLICTED));
+        assert_eq!(score, CONFLICTED_SCORE);
+
+        let score = tracker.get_modification_score(week_ago, Some(Status::INDEX_RENAMED));
+        assert_eq!(score, 0, "old renames decay like any other modification");
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_grow_and_compact_keep_accesses() {
+        let temp_dir = std::env::temp_dir().join("fff_test_compaction");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let file_path = Path::new("/tmp/fff/compaction.rs");
+        tracker.track_access(file_path).unwrap();
+
+        let map_size = tracker.size_stats().unwrap().map_size;
+        // grown while other threads read and write the database
+        let grown_size = std::thread::scope(|scope| {
+            let growing = scope.spawn(|| tracker.grow_map_size().unwrap());
+            tracker.track_access(file_path).unwrap();
+            tracker.flush().unwrap();
+            tracker.session_files().unwrap();
+            growing.join().unwrap()
+        });
+        assert_eq!(grown_size, map_size * 2);
+        assert_eq!(tracker.get_access_score(file_path), 2);
+
+        let tracker = tracker.compact().unwrap();
+        let stats = tracker.size_stats().unwrap();
+        assert_eq!(stats.entries, 1);
+        assert!(stats.fragmentation() < COMPACTION_FRAGMENTATION_THRESHOLD);
+        assert_eq!(tracker.get_access_score(file_path), 2);
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_failed_or_shared_compaction_keeps_tracker() {
+        let temp_dir = std::env::temp_dir().join("fff_test_failed_compaction");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+        let file_path = Path::new("/tmp/fff/failed_compaction.rs");
+        tracker.track_access(file_path).unwrap();
+
+        // a user of its own, like a tracker in another process
+        let other_user = File::open(temp_dir.join(USERS_LOCK_FILE_NAME)).unwrap();
+        other_user.lock_shared().unwrap();
+        let tracker = tracker.compact().unwrap();
+        assert!(!temp_dir.join(COMPACTED_FILE_NAME).exists());
+        drop(other_user);
+
+        // the copy can't be written over a directory
+        std::fs::create_dir(temp_dir.join(COMPACTED_FILE_NAME)).unwrap();
+        let Some(tracker) = tracker.compact().unwrap_err().tracker else {
+            panic!("a failed copy should keep the tracker");
+        };
+        tracker.track_access(file_path).unwrap();
+        tracker.flush().unwrap();
+        assert_eq!(tracker.get_access_score(file_path), 2);
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_writes_wait_for_other_writers() {
+        let temp_dir = std::env::temp_dir().join("fff_test_writer_lock");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        // a writer of its own, like a tracker in another process
+        let other_writer = File::open(temp_dir.join(WRITER_LOCK_FILE_NAME)).unwrap();
+        other_writer.lock().unwrap();
+        std::thread::scope(|scope| {
+            let touch = scope.spawn(|| tracker.touch_project(Path::new("/tmp/fff")));
+            std::thread::sleep(Duration::from_millis(200));
+            assert!(!touch.is_finished());
+
+            other_writer.unlock().unwrap();
+            touch.join().unwrap().unwrap();
+        });
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_move_accesses() {
+        let temp_dir = std::env::temp_dir().join("fff_test_move_accesses");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let from = PathBuf::from("/tmp/fff/old/lib.rs");
+        let to = PathBuf::from("/tmp/fff/new/lib.rs");
+        tracker.track_access(&from).unwrap();
+        tracker.track_access(&from).unwrap();
+        tracker.track_access(&to).unwrap();
+
+        let counts = tracker
+            .get_access_counts(&[from.clone(), to.clone()])
+            .unwrap();
+        assert_eq!(counts[0].count, 2);
+        assert_eq!(counts[1].count, 1);
+        assert!(counts[0].last_access.is_some());
+
+        let moved = tracker
+            .move_accesses(&[(from.clone(), to.clone())])
+            .unwrap();
+        assert_eq!(moved, 1);
+        assert_eq!(tracker.get_access_score(&from), 0);
+        assert_eq!(tracker.get_access_score(&to), 3);
+        let counts = tracker.get_access_counts(&[from, to]).unwrap();
+        assert_eq!(counts[0], AccessCount::default());
+        assert_eq!(counts[1].count, 3);
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_reader_matches_tracker() {
+        let temp_dir = std::env::temp_dir().join("fff_test_reader");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let opened = PathBuf::from("/tmp/fff/reader/opened.rs");
+        let untouched = PathBuf::from("/tmp/fff/reader/untouched.rs");
+        tracker.track_access(&opened).unwrap();
+        tracker.track_access(&opened).unwrap();
+
+        let reader = tracker.reader().unwrap();
+        assert_eq!(
+            reader.get_access_score(&opened),
+            tracker.get_access_score(&opened)
+        );
+        assert_eq!(reader.get_access_score(&untouched), 0);
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_edit_score_without_git() {
+        let temp_dir = std::env::temp_dir().join("fff_test_edits");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let edited = PathBuf::from("/tmp/fff/edits/notes.md");
+        let now = tracker.get_now();
+        assert_eq!(tracker.get_edit_score(&edited), 0);
+
+        tracker.track_edit(&edited, Some(now - 15 * 60)).unwrap();
+        assert_eq!(tracker.get_edit_score(&edited), 8);
+        // an older report doesn't move the latest edit back
+        tracker.track_edit(&edited, Some(now - 60 * 60)).unwrap();
+        assert_eq!(tracker.last_edit(&edited), Some(now - 15 * 60));
+        tracker.track_edit(&edited, None).unwrap();
+        assert_eq!(tracker.reader().unwrap().get_edit_score(&edited), 16);
+        // a plain directory falls back to the modification time with the same points
+        assert_eq!(mtime_score(now - 15 * 60), 8);
+        assert_eq!(mtime_score(now - 30 * 24 * 60 * 60), 0);
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_accesses_written_behind() {
+        let temp_dir = std::env::temp_dir().join("fff_test_write_behind");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let file_path = Path::new("/tmp/fff/write_behind.rs");
+        tracker.track_access(file_path).unwrap();
+        tracker.track_access(file_path).unwrap();
+        assert_eq!(tracker.get_access_score(file_path), 2);
+        tracker.flush().unwrap();
+        assert_eq!(tracker.flush().unwrap(), 0);
+
+        tracker.track_access(file_path).unwrap();
+        // dropping the tracker stops the flusher with a last flush
+        drop(tracker);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+        assert_eq!(tracker.get_access_score(file_path), 3);
+        assert_eq!(
+            tracker.tracked_paths().unwrap(),
+            vec![file_path.to_path_buf()]
+        );
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    const SECOND_TRACKER_DB: &str = "FFF_TEST_SECOND_TRACKER_DB";
+    const SHARED_PATH: &str = "/tmp/fff/shared.rs";
+
+    /// The other process of `test_flush_keeps_accesses_of_other_processes`, heed opens an
+    /// environment only once per process
+    #[test]
+    #[ignore]
+    fn second_tracker_process() {
+        let Ok(db_path) = std::env::var(SECOND_TRACKER_DB) else {
+            return;
+        };
+        let tracker = FrecencyTracker::new(&db_path, false).unwrap();
+        tracker.track_access(Path::new(SHARED_PATH)).unwrap();
+        tracker.flush().unwrap();
+    }
+
+    #[test]
+    fn test_flush_keeps_accesses_of_other_processes() {
+        let temp_dir = std::env::temp_dir().join("fff_test_two_trackers");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), false).unwrap();
+        let file_path = Path::new(SHARED_PATH);
+        tracker.track_access(file_path).unwrap();
+        // accesses in the same second are one entry of a history
+        std::thread::sleep(Duration::from_millis(1100));
+
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "frecency::tests::second_tracker_process",
+                "--ignored",
+                "--quiet",
+            ])
+            .env(SECOND_TRACKER_DB, &temp_dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        tracker.flush().unwrap();
+        assert_eq!(tracker.get_access_score(file_path), 2);
+        drop(tracker);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), false).unwrap();
+        assert_eq!(tracker.get_access_score(file_path), 2);
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_replays_journal_after_crash() {
+        let temp_dir = std::env::temp_dir().join("fff_test_journal");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let crashed_journal = temp_dir.join("access.1-0.journal");
+        // the last line was cut short by the crash
+        std::fs::write(
+            &crashed_journal,
+            format!(
+                "{0} /tmp/fff/journal.rs\n{1} /tmp/fff/journal.rs\n{1} /tmp/fff/journal.rs\n{1}",
+                now - 60,
+                now
+            ),
+        )
+        .unwrap();
+
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+        let file_path = Path::new("/tmp/fff/journal.rs");
+        assert_eq!(tracker.get_access_score(file_path), 2);
+        assert!(!crashed_journal.exists());
+
+        let journals = journals_in(&temp_dir);
+        assert_eq!(journals.len(), 1);
+        tracker.track_access(file_path).unwrap();
+        assert!(std::fs::read_to_string(&journals[0])
+            .unwrap()
+            .ends_with(" /tmp/fff/journal.rs\n"));
+        tracker.flush().unwrap();
+        assert_eq!(std::fs::metadata(&journals[0]).unwrap().len(), 0);
+
+        drop(tracker);
+        assert!(journals_in(&temp_dir).is_empty());
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    fn journals_in(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "journal")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_skips_journals_of_open_trackers() {
+        let temp_dir = std::env::temp_dir().join("fff_test_live_journal");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let live_journal = temp_dir.join("access.1-0.journal");
+        std::fs::write(&live_journal, format!("{} /tmp/fff/live.rs\n", now)).unwrap();
+        // a lock of its own, like the one of a tracker in another process
+        let owner = File::open(&live_journal).unwrap();
+        owner.lock().unwrap();
+
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+        assert_eq!(tracker.get_access_score(Path::new("/tmp/fff/live.rs")), 0);
+        assert!(live_journal.exists());
+        drop(tracker);
+
+        drop(owner);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+        assert_eq!(tracker.get_access_score(Path::new("/tmp/fff/live.rs")), 1);
+        assert!(!live_journal.exists());
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_store_co_changes() {
+        let temp_dir = std::env::temp_dir().join("fff_test_co_changes");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let lib = PathBuf::from("/tmp/fff/src/lib.rs");
+        let related = vec![(PathBuf::from("/tmp/fff/src/main.rs"), 4)];
+        let graph = HashMap::from([(lib.clone(), related.clone())]);
+        tracker.store_co_changes(&graph).unwrap();
+        assert_eq!(tracker.get_co_changes(&lib).unwrap(), related);
+        assert_eq!(tracker.size_stats().unwrap().entries, 0);
+
+        tracker.store_co_changes(&HashMap::new()).unwrap();
+        assert!(tracker.get_co_changes(&lib).unwrap().is_empty());
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_tracked_paths_and_migration() {
+        let temp_dir = std::env::temp_dir().join("fff_test_tracked_paths");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let tracked = PathBuf::from("/tmp/fff/old/src/main.rs");
+        tracker.track_access(&tracked).unwrap();
+        assert_eq!(tracker.tracked_paths().unwrap(), [tracked]);
+        assert_eq!(tracker.size_stats().unwrap().entries, 1);
+
+        // history written before the paths were stored
+        let legacy = PathBuf::from("/tmp/fff/old/README.md");
+        let mut wtxn = tracker.env.write_txn().unwrap();
+        let key_hash = FrecencyTracker::path_to_hash_bytes(&legacy).unwrap();
+        tracker
+            .db
+            .put(&mut wtxn, &key_hash, &VecDeque::from([tracker.get_now()]))
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        assert_eq!(tracker.backfill_paths([legacy.as_path()]).unwrap(), 1);
+        assert_eq!(tracker.backfill_paths([legacy.as_path()]).unwrap(), 0);
+
+        let migrated = tracker
+            .migrate_prefix(Path::new("/tmp/fff/old"), Path::new("/tmp/fff/new"), [])
+            .unwrap();
+        assert_eq!(migrated, 2);
+        assert_eq!(
+            tracker.get_access_score(Path::new("/tmp/fff/new/src/main.rs")),
+            1
+        );
+        assert_eq!(
+            tracker.get_access_score(Path::new("/tmp/fff/new/README.md")),
+            1
+        );
+
+        let mut paths = tracker.tracked_paths().unwrap();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/tmp/fff/new/README.md"),
+                PathBuf::from("/tmp/fff/new/src/main.rs")
+            ]
+        );
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_forget_and_age_out_projects() {
+        let temp_dir = std::env::temp_dir().join("fff_test_forget_projects");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let mut tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+        let year_ago = tracker.get_now() - 365 * 24 * 60 * 60;
+
+        let active = PathBuf::from("/tmp/fff/active/src/main.rs");
+        let stale = PathBuf::from("/tmp/fff/stale/src/main.rs");
+        let deleted = PathBuf::from("/tmp/fff/deleted/lib.rs");
+        tracker.track_access(&active).unwrap();
+        for path in [&stale, &deleted] {
+            let key_hash = FrecencyTracker::path_to_hash_bytes(path).unwrap();
+            let mut cache = tracker.write_behind.cache.write().unwrap();
+            cache.insert_access(key_hash, path, year_ago);
+        }
+
+        tracker.touch_project(Path::new("/tmp/fff/active")).unwrap();
+        let mut wtxn = tracker.env.write_txn().unwrap();
+        tracker
+            .projects
+            .put(&mut wtxn, "/tmp/fff/stale", &year_ago)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        assert_eq!(
+            tracker
+                .forget_project(Path::new("/tmp/fff/deleted"))
+                .unwrap(),
+            1
+        );
+        assert_eq!(tracker.tracked_paths().unwrap().len(), 2);
+
+        tracker.set_options(FrecencyOptions {
+            forget_projects_after_days: 0.0,
+            ..FrecencyOptions::default()
+        });
+        assert!(tracker.age_out_projects().unwrap().is_empty());
+
+        tracker.set_options(FrecencyOptions::default());
+        assert_eq!(
+            tracker.age_out_projects().unwrap(),
+            [PathBuf::from("/tmp/fff/stale")]
+        );
+        assert_eq!(tracker.tracked_paths().unwrap(), [active.as_path()]);
+        assert_eq!(tracker.get_access_score(&stale), 0);
+        assert_eq!(tracker.get_access_score(&active), 1);
+        assert!(tracker.age_out_projects().unwrap().is_empty());
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_aggregates_refresh_in_background() {
+        let temp_dir = std::env::temp_dir().join("fff_test_aggregates");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let tracker = FrecencyTracker::new(temp_dir.to_str().unwrap(), true).unwrap();
+
+        let file_path = Path::new("/tmp/fff/aggregated.rs");
+        tracker.track_access(file_path).unwrap();
+        // the first searches get whatever was computed before, the flusher catches up
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        let refreshed = loop {
+            let sessions = tracker.session_files().unwrap();
+            let scores = tracker.time_of_day_scores().unwrap();
+            if sessions.contains(file_path) && scores.contains_key(file_path) {
+                break true;
+            }
+            if std::time::Instant::now() > deadline {
+                break false;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        assert!(refreshed);
+
+        drop(tracker);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_previous_session_start() {
+        let gap = 30 * 60;
+        let hour = 3600;
+        // an early look at 7:00, then a file every 20 minutes from 9:00 to 11:40
+        let mut times: Vec<u64> = (0..9).map(|i| 9 * hour + i * 20 * 60).collect();
+        times.push(7 * hour);
+
+        // right after lunch the current session is empty, the morning is the previous one
+        assert_eq!(
+            previous_session_start(times.clone(), 13 * hour, gap),
+            Some(9 * hour)
+        );
+        times.push(13 * hour);
+        assert_eq!(
+            previous_session_start(times.clone(), 13 * hour + 60, gap),
+            Some(9 * hour)
+        );
+        // during the morning the session before it is the early look
+        times.retain(|&time| time < 12 * hour);
+        assert_eq!(
+            previous_session_start(times, 11 * hour + 50 * 60, gap),
+            Some(7 * hour)
+        );
+        assert_eq!(previous_session_start(Vec::new(), 13 * hour, gap), None);
+    }
+
+    #[test]
+    fn test_time_of_day_similarity() {
+        let at = |hour: f64, weekend: bool| LocalTime { hour, weekend };
+
+        assert_eq!(at(9.0, false).similarity(&at(9.0, false)), 1.0);
+        assert_eq!(at(9.0, false).similarity(&at(10.5, false)), 0.5);
+        assert_eq!(at(9.0, false).similarity(&at(20.0, false)), 0.0);
+        assert!((at(23.5, false).similarity(&at(0.5, false)) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(at(9.0, true).similarity(&at(9.0, false)), 0.5);
+    }
+}
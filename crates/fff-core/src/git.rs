@@ -0,0 +1,621 @@
+//! Git statuses and history. Builds without the `git` feature leave libgit2 out: the status
+//! flags are kept for the other version control systems and every history query answers
+//! `None`, the same as outside of a repository.
+
+use crate::path_utils::cmp_paths;
+#[cfg(feature = "git")]
+use git2::{Oid, Repository, Sort, StatusOptions};
+#[cfg(feature = "git")]
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+#[cfg(feature = "git")]
+use std::{fmt::Debug, sync::Mutex};
+#[cfg(feature = "git")]
+use tracing::{debug, error, info};
+
+#[cfg(feature = "git")]
+pub use git2::Status;
+
+#[cfg(not(feature = "git"))]
+bitflags::bitflags! {
+    /// The flags of `git2::Status` with the same bits
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+    pub struct Status: u32 {
+        const CURRENT = 0;
+        const INDEX_NEW = 1 << 0;
+        const INDEX_MODIFIED = 1 << 1;
+        const INDEX_DELETED = 1 << 2;
+        const INDEX_RENAMED = 1 << 3;
+        const INDEX_TYPECHANGE = 1 << 4;
+        const WT_NEW = 1 << 7;
+        const WT_MODIFIED = 1 << 8;
+        const WT_DELETED = 1 << 9;
+        const WT_TYPECHANGE = 1 << 10;
+        const WT_RENAMED = 1 << 11;
+        const WT_UNREADABLE = 1 << 12;
+        const IGNORED = 1 << 14;
+        const CONFLICTED = 1 << 15;
+    }
+}
+
+/// Represents a cache of a single git status query, if there is no
+/// status aka file is clear but it was specifically requested to updated
+/// the status is `None` otherwise contains only actual file statuses.
+#[derive(Debug, Clone)]
+pub struct GitStatusCache {
+    entries: Vec<(PathBuf, Status)>,
+    case_insensitive: bool,
+}
+
+impl IntoIterator for GitStatusCache {
+    type Item = (PathBuf, Status);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl GitStatusCache {
+    pub fn statuses_len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, Status)> {
+        self.entries
+            .iter()
+            .map(|(path, status)| (path.as_path(), *status))
+    }
+
+    pub fn lookup_status(&self, full_path: &Path) -> Option<Status> {
+        self.entries
+            .binary_search_by(|(path, _)| cmp_paths(path, full_path, self.case_insensitive))
+            .ok()
+            .and_then(|idx| self.entries.get(idx).map(|(_, status)| *status))
+    }
+
+    /// Switches lookups to ignore case, statuses are resorted to keep binary search valid
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        if case_insensitive {
+            self.entries
+                .sort_by(|(a, _), (b, _)| cmp_paths(a, b, case_insensitive));
+        }
+
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Statuses reported by something else than libgit2, in any order. The first status of a
+    /// path listed twice is kept.
+    pub(crate) fn from_entries(mut entries: Vec<(PathBuf, Status)>) -> Self {
+        entries.sort_by(|(a, _), (b, _)| cmp_paths(a, b, false));
+        entries.dedup_by(|(a, _), (b, _)| a == b);
+        Self {
+            entries,
+            case_insensitive: false,
+        }
+    }
+
+    /// Marks every path of `paths` without a status as unmodified, the way a status read with
+    /// `include_unmodified` reports them
+    pub(crate) fn with_unmodified<'a>(self, paths: impl Iterator<Item = &'a Path>) -> Self {
+        let case_insensitive = self.case_insensitive;
+        let missing: Vec<(PathBuf, Status)> = paths
+            .filter(|path| self.lookup_status(path).is_none())
+            .map(|path| (path.to_path_buf(), Status::CURRENT))
+            .collect();
+
+        let mut entries = self.entries;
+        entries.extend(missing);
+        Self::from_entries(entries).with_case_insensitive(case_insensitive)
+    }
+
+    #[cfg(feature = "git")]
+    fn read_status_impl(repo: &Repository, status_options: &mut StatusOptions) -> Option<Self> {
+        let status_start = std::time::Instant::now();
+        info!("GIT: Reading git status");
+        let statuses = repo
+            .statuses(Some(status_options))
+            .map_err(|e| {
+                error!("Failed to get git statuses: {}", e);
+                e
+            })
+            .ok()?;
+        let status_time = status_start.elapsed();
+        let repo_path = repo.path().parent()?;
+        info!("GIT: Status query completed in {:?}", status_time);
+
+        let mut entries = Vec::with_capacity(statuses.len());
+        for entry in &statuses {
+            if let Some(entry_path) = entry.path() {
+                let full_path = repo_path.join(entry_path);
+                entries.push((full_path, entry.status()));
+            }
+        }
+
+        Some(Self {
+            entries,
+            case_insensitive: false,
+        })
+    }
+
+    #[cfg(feature = "git")]
+    pub fn read_git_status(
+        git_workdir: Option<&Path>,
+        status_options: &mut StatusOptions,
+    ) -> Option<Self> {
+        let git_workdir = git_workdir.as_ref()?;
+        let repository = Repository::open(git_workdir).ok()?;
+
+        Self::read_status_impl(&repository, status_options)
+    }
+
+    #[cfg(feature = "git")]
+    pub fn git_status_for_paths<TPath: AsRef<Path> + Debug>(
+        repo: &Repository,
+        paths: &[TPath],
+    ) -> Option<Self> {
+        if paths.is_empty() {
+            return None;
+        }
+
+        debug!(?paths, "Git partial git status for paths");
+        let mut status_options = StatusOptions::new();
+
+        status_options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            // when reading partial status it's important to include all files requested
+            .include_unmodified(true);
+
+        for path in paths {
+            status_options.pathspec(path.as_ref());
+        }
+
+        let statuses = Self::read_status_impl(repo, &mut status_options)?;
+        debug!(
+            "Git partial status for paths {:?} returned {} entries",
+            statuses,
+            statuses.statuses_len()
+        );
+
+        Some(statuses)
+    }
+}
+
+/// Upper bound of commits inspected while looking for the author's ones, keeps the scan fast
+/// on huge histories where the author barely committed
+#[cfg(feature = "git")]
+const MAX_REVWALK_COMMITS: usize = 2000;
+
+/// Files touched by the last commits of the configured git author, each mapped to the age of
+/// the newest of those commits touching it (0 is the latest commit)
+#[derive(Debug, Clone, Default)]
+pub struct RecentCommitPaths {
+    entries: Vec<(PathBuf, usize)>,
+    case_insensitive: bool,
+}
+
+impl RecentCommitPaths {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn commit_age(&self, full_path: &Path) -> Option<usize> {
+        self.entries
+            .binary_search_by(|(path, _)| cmp_paths(path, full_path, self.case_insensitive))
+            .ok()
+            .map(|idx| self.entries[idx].1)
+    }
+
+    /// Walks the history from HEAD collecting paths of the last `max_commits` non merge
+    /// commits whose author email matches `user.email` of the repository config
+    #[cfg(feature = "git")]
+    pub fn read(git_workdir: &Path, max_commits: usize, case_insensitive: bool) -> Option<Self> {
+        if max_commits == 0 {
+            return None;
+        }
+
+        let walk_start = std::time::Instant::now();
+        let repo = Repository::open(git_workdir).ok()?;
+        let email = repo.signature().ok()?.email()?.to_string();
+
+        let mut revwalk = repo.revwalk().ok()?;
+        revwalk.set_sorting(Sort::TIME).ok()?;
+        revwalk.push_head().ok()?;
+
+        let mut ages: HashMap<PathBuf, usize> = HashMap::new();
+        let mut age = 0;
+        for oid in revwalk.take(MAX_REVWALK_COMMITS) {
+            let Some(commit) = oid.ok().and_then(|oid| repo.find_commit(oid).ok()) else {
+                continue;
+            };
+            if commit.parent_count() > 1 || commit.author().email() != Some(email.as_str()) {
+                continue;
+            }
+
+            let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+            let Some(diff) = commit.tree().ok().and_then(|tree| {
+                repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+                    .ok()
+            }) else {
+                continue;
+            };
+
+            for delta in diff.deltas() {
+                if let Some(path) = delta.new_file().path() {
+                    ages.entry(git_workdir.join(path)).or_insert(age);
+                }
+            }
+
+            age += 1;
+            if age == max_commits {
+                break;
+            }
+        }
+
+        let mut entries: Vec<_> = ages.into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| cmp_paths(a, b, case_insensitive));
+        info!(
+            "GIT: Collected {} paths from {} recent commits of {} in {:?}",
+            entries.len(),
+            age,
+            email,
+            walk_start.elapsed()
+        );
+
+        Some(Self {
+            entries,
+            case_insensitive,
+        })
+    }
+
+    #[cfg(not(feature = "git"))]
+    pub fn read(_: &Path, _: usize, _: bool) -> Option<Self> {
+        None
+    }
+}
+
+/// Dominant authors of the latest read repository and the HEAD they were read at
+#[cfg(feature = "git")]
+type CachedAuthors = (PathBuf, Oid, Arc<DominantAuthors>);
+#[cfg(feature = "git")]
+static DOMINANT_AUTHORS: Lazy<Mutex<Option<CachedAuthors>>> = Lazy::new(|| Mutex::new(None));
+
+/// Author of the most commits touching each file within the last `MAX_REVWALK_COMMITS`
+/// commits, used by the `author:` query filter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DominantAuthors {
+    authors: HashMap<PathBuf, Arc<str>>,
+}
+
+impl DominantAuthors {
+    pub fn author_of(&self, full_path: &Path) -> Option<&str> {
+        self.authors.get(full_path).map(AsRef::as_ref)
+    }
+
+    /// Walking the history is too slow for every query, so the authors are read once per
+    /// repository and HEAD commit. `path` is any path inside the repository.
+    #[cfg(feature = "git")]
+    pub fn cached(path: &Path) -> Option<Arc<Self>> {
+        let repo = Repository::discover(path).ok()?;
+        let git_workdir = repo.workdir()?.to_path_buf();
+        let head = repo.head().ok()?.target()?;
+
+        let mut cache = DOMINANT_AUTHORS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((workdir, oid, authors)) = cache.as_ref() {
+            if *workdir == git_workdir && *oid == head {
+                return Some(Arc::clone(authors));
+            }
+        }
+
+        let authors = Arc::new(Self::read(&repo, &git_workdir)?);
+        *cache = Some((git_workdir, head, Arc::clone(&authors)));
+        Some(authors)
+    }
+
+    #[cfg(not(feature = "git"))]
+    pub fn cached(_: &Path) -> Option<Arc<Self>> {
+        None
+    }
+
+    #[cfg(feature = "git")]
+    fn read(repo: &Repository, git_workdir: &Path) -> Option<Self> {
+        let walk_start = std::time::Instant::now();
+        let mut revwalk = repo.revwalk().ok()?;
+        revwalk.set_sorting(Sort::TIME).ok()?;
+        revwalk.push_head().ok()?;
+
+        let mut commit_counts: HashMap<PathBuf, HashMap<Arc<str>, usize>> = HashMap::new();
+        let mut names: HashMap<String, Arc<str>> = HashMap::new();
+        for oid in revwalk.take(MAX_REVWALK_COMMITS) {
+            let Some(commit) = oid.ok().and_then(|oid| repo.find_commit(oid).ok()) else {
+                continue;
+            };
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let Some(name) = commit.author().name().map(str::to_string) else {
+                continue;
+            };
+
+            let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+            let Some(diff) = commit.tree().ok().and_then(|tree| {
+                repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+                    .ok()
+            }) else {
+                continue;
+            };
+
+            let name = names
+                .entry(name)
+                .or_insert_with_key(|name| Arc::from(name.as_str()));
+            for delta in diff.deltas() {
+                if let Some(path) = delta.new_file().path() {
+                    *commit_counts
+                        .entry(git_workdir.join(path))
+                        .or_default()
+                        .entry(Arc::clone(name))
+                        .or_default() += 1;
+                }
+            }
+        }
+
+        // ties go to the alphabetically first author so the result doesn't depend on hashing
+        let authors: HashMap<PathBuf, Arc<str>> = commit_counts
+            .into_iter()
+            .filter_map(|(path, counts)| {
+                let author = counts
+                    .into_iter()
+                    .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))?
+                    .0;
+                Some((path, author))
+            })
+            .collect();
+        info!(
+            "GIT: Collected dominant authors of {} paths in {:?}",
+            authors.len(),
+            walk_start.elapsed()
+        );
+
+        Some(Self { authors })
+    }
+}
+
+/// Upper bound of commits searched for the ones touching the current file
+#[cfg(feature = "git")]
+const MAX_CO_CHANGE_COMMITS: usize = 500;
+
+/// Co-changed files of the latest requested file and the HEAD they were read at
+#[cfg(feature = "git")]
+type CachedCoChanges = (PathBuf, Oid, Arc<HashSet<PathBuf>>);
+#[cfg(feature = "git")]
+static CO_CHANGED_FILES: Lazy<Mutex<Option<CachedCoChanges>>> = Lazy::new(|| Mutex::new(None));
+
+/// Files committed together with `full_path` in the last `MAX_CO_CHANGE_COMMITS` commits.
+/// The search asks for the same current file over and over, so the latest one is cached until
+/// HEAD moves.
+#[cfg(feature = "git")]
+pub fn co_changed_files(full_path: &Path) -> Option<Arc<HashSet<PathBuf>>> {
+    let repo = Repository::discover(full_path).ok()?;
+    let git_workdir = repo.workdir()?.to_path_buf();
+    let head = repo.head().ok()?.target()?;
+
+    let mut cache = CO_CHANGED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((path, oid, files)) = cache.as_ref() {
+        if path == full_path && *oid == head {
+            return Some(Arc::clone(files));
+        }
+    }
+
+    let relative_path = full_path.strip_prefix(&git_workdir).ok()?;
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.set_sorting(Sort::TIME).ok()?;
+    revwalk.push_head().ok()?;
+
+    let mut files = HashSet::new();
+    for oid in revwalk.take(MAX_CO_CHANGE_COMMITS) {
+        let Some(commit) = oid.ok().and_then(|oid| repo.find_commit(oid).ok()) else {
+            continue;
+        };
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+        let Some(diff) = commit.tree().ok().and_then(|tree| {
+            repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+                .ok()
+        }) else {
+            continue;
+        };
+
+        let paths: Vec<&Path> = diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path())
+            .collect();
+        if paths.contains(&relative_path) {
+            files.extend(
+                paths
+                    .into_iter()
+                    .filter(|path| *path != relative_path)
+                    .map(|path| git_workdir.join(path)),
+            );
+        }
+    }
+
+    debug!(
+        "GIT: {} files were committed together with {}",
+        files.len(),
+        full_path.display()
+    );
+    let files = Arc::new(files);
+    *cache = Some((full_path.to_path_buf(), head, Arc::clone(&files)));
+    Some(files)
+}
+
+#[cfg(not(feature = "git"))]
+pub fn co_changed_files(_: &Path) -> Option<Arc<HashSet<PathBuf>>> {
+    None
+}
+
+/// Commits touching more files than this are mass renames or reformats, not related changes
+#[cfg(feature = "git")]
+const MAX_CO_CHANGE_COMMIT_FILES: usize = 50;
+/// Files have to be committed together at least this many times to count as related
+#[cfg(feature = "git")]
+const MIN_CO_CHANGE_COUNT: u32 = 2;
+/// Most frequently co-changed files kept for every file
+#[cfg(feature = "git")]
+const MAX_RELATED_FILES: usize = 20;
+
+/// Mines the last `max_commits` non merge commits into a co-change graph mapping every file
+/// to the files it was most frequently committed together with and how many times
+#[cfg(feature = "git")]
+pub fn mine_co_changes(
+    git_workdir: &Path,
+    max_commits: usize,
+) -> Option<HashMap<PathBuf, Vec<(PathBuf, u32)>>> {
+    let mine_start = std::time::Instant::now();
+    let repo = Repository::open(git_workdir).ok()?;
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.set_sorting(Sort::TIME).ok()?;
+    revwalk.push_head().ok()?;
+
+    let mut counts: HashMap<PathBuf, HashMap<PathBuf, u32>> = HashMap::new();
+    let mut mined = 0;
+    for oid in revwalk.take(max_commits) {
+        let Some(commit) = oid.ok().and_then(|oid| repo.find_commit(oid).ok()) else {
+            continue;
+        };
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+        let Some(diff) = commit.tree().ok().and_then(|tree| {
+            repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+                .ok()
+        }) else {
+            continue;
+        };
+
+        let paths: Vec<&Path> = diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path())
+            .collect();
+        if paths.len() < 2 || paths.len() > MAX_CO_CHANGE_COMMIT_FILES {
+            continue;
+        }
+
+        mined += 1;
+        for path in &paths {
+            let related = counts.entry(git_workdir.join(path)).or_default();
+            for other in paths.iter().filter(|other| *other != path) {
+                *related.entry(git_workdir.join(other)).or_default() += 1;
+            }
+        }
+    }
+
+    let graph: HashMap<_, _> = counts
+        .into_iter()
+        .filter_map(|(path, related)| {
+            let mut related: Vec<_> = related
+                .into_iter()
+                .filter(|(_, count)| *count >= MIN_CO_CHANGE_COUNT)
+                .collect();
+            if related.is_empty() {
+                return None;
+            }
+
+            related.sort_by(|(a_path, a), (b_path, b)| b.cmp(a).then_with(|| a_path.cmp(b_path)));
+            related.truncate(MAX_RELATED_FILES);
+            Some((path, related))
+        })
+        .collect();
+
+    info!(
+        "GIT: Mined co-changes of {} files from {} commits in {:?}",
+        graph.len(),
+        mined,
+        mine_start.elapsed()
+    );
+    Some(graph)
+}
+
+#[cfg(not(feature = "git"))]
+pub fn mine_co_changes(_: &Path, _: usize) -> Option<HashMap<PathBuf, Vec<(PathBuf, u32)>>> {
+    None
+}
+
+#[inline]
+pub fn is_modified_status(status: Status) -> bool {
+    status.intersects(
+        Status::WT_MODIFIED
+            | Status::INDEX_MODIFIED
+            | Status::WT_NEW
+            | Status::INDEX_NEW
+            | Status::WT_RENAMED
+            | Status::INDEX_RENAMED
+            | Status::CONFLICTED,
+    )
+}
+
+/// Unmerged file during a merge, rebase or cherry-pick. libgit2 reports it on both the index
+/// and the worktree side as a single flag.
+#[inline]
+pub fn is_conflicted_status(status: Status) -> bool {
+    status.contains(Status::CONFLICTED)
+}
+
+/// Orders statuses by how much attention the file needs: conflicted, modified, anything else
+/// that differs from HEAD, then clean and ignored files
+pub fn git_status_rank(status: Option<Status>) -> u8 {
+    match status {
+        Some(status) if is_conflicted_status(status) => 0,
+        Some(status) if is_modified_status(status) => 1,
+        Some(status) if !status.is_empty() && !status.intersects(Status::IGNORED) => 2,
+        _ => 3,
+    }
+}
+
+pub fn format_git_status(status: Option<Status>) -> &'static str {
+    match status {
+        None => "clear",
+        Some(status) => {
+            if is_conflicted_status(status) {
+                "conflicted"
+            } else if status.contains(Status::WT_NEW) {
+                "untracked"
+            } else if status.contains(Status::WT_MODIFIED) {
+                "modified"
+            } else if status.contains(Status::WT_DELETED) {
+                "deleted"
+            } else if status.contains(Status::WT_RENAMED) {
+                "renamed"
+            } else if status.contains(Status::INDEX_NEW) {
+                "staged_new"
+            } else if status.contains(Status::INDEX_MODIFIED) {
+                "staged_modified"
+            } else if status.contains(Status::INDEX_DELETED) {
+                "staged_deleted"
+            } else if status.contains(Status::INDEX_RENAMED) {
+                "staged_renamed"
+            } else if status.contains(Status::IGNORED) {
+                "ignored"
+            } else if status.contains(Status::CURRENT) || status.is_empty() {
+                "clean"
+            } else {
+                "unknown"
+            }
+        }
+    }
+}
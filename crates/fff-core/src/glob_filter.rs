@@ -0,0 +1,94 @@
+//! ripgrep style `-g` globs passed along a search, for callers building queries
+//! programmatically instead of embedding filters in the query. `src/**` keeps the matching
+//! files only, `!*.test.ts` hides them, the last matching glob wins and globs without a slash
+//! match the file name in any directory, just like `rg -g`.
+
+use crate::error::Error;
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::Match;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Compiled glob lists kept around, callers tend to send the same few lists on every keystroke
+const MAX_CACHED_FILTERS: usize = 32;
+
+static FILTERS: Lazy<Mutex<HashMap<Vec<String>, Arc<GlobFilter>>>> = Lazy::new(Default::default);
+
+#[derive(Debug)]
+pub struct GlobFilter {
+    overrides: Override,
+}
+
+impl GlobFilter {
+    pub fn new(globs: &[String]) -> Result<Self, Error> {
+        let mut builder = OverrideBuilder::new("");
+        for glob in globs {
+            builder
+                .add(glob)
+                .map_err(|e| Error::InvalidGlob(e.to_string()))?;
+        }
+        let overrides = builder
+            .build()
+            .map_err(|e| Error::InvalidGlob(e.to_string()))?;
+
+        Ok(Self { overrides })
+    }
+
+    /// The filter of the globs compiled by a previous search, compiled now otherwise
+    pub fn cached(globs: &[String]) -> Result<Arc<Self>, Error> {
+        let mut filters = FILTERS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(filter) = filters.get(globs) {
+            return Ok(Arc::clone(filter));
+        }
+
+        let filter = Arc::new(Self::new(globs)?);
+        if filters.len() >= MAX_CACHED_FILTERS {
+            filters.clear();
+        }
+        filters.insert(globs.to_vec(), Arc::clone(&filter));
+        Ok(filter)
+    }
+
+    pub fn matches(&self, relative_path: &str) -> bool {
+        let path = Path::new(relative_path);
+        // an excluded directory hides everything below it, rg doesn't even walk into it
+        let in_excluded_dir = path
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .any(|dir| matches!(self.overrides.matched(dir, true), Match::Ignore(_)));
+
+        !in_excluded_dir && !matches!(self.overrides.matched(path, false), Match::Ignore(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(globs: &[&str]) -> Arc<GlobFilter> {
+        let globs: Vec<String> = globs.iter().map(|glob| glob.to_string()).collect();
+        GlobFilter::cached(&globs).unwrap()
+    }
+
+    #[test]
+    fn test_rg_glob_semantics() {
+        let excluded = filter(&["!*.test.ts"]);
+        assert!(excluded.matches("src/app.ts"));
+        assert!(!excluded.matches("src/deep/app.test.ts"));
+
+        let included = filter(&["src/**", "!src/generated"]);
+        assert!(included.matches("src/main.rs"));
+        assert!(!included.matches("docs/guide.md"));
+        assert!(!included.matches("src/generated/schema.rs"));
+
+        let last_wins = filter(&["!*.rs", "main.rs"]);
+        assert!(last_wins.matches("src/main.rs"));
+        assert!(!last_wins.matches("src/lib.rs"));
+
+        assert!(Arc::ptr_eq(&excluded, &filter(&["!*.test.ts"])));
+        assert!(GlobFilter::new(&["src/[".to_string()]).is_err());
+    }
+}
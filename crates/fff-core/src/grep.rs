@@ -0,0 +1,413 @@
+//! Live grep of the indexed files for a literal pattern, smart case like rg: a pattern without
+//! uppercase letters ignores ascii case. Typing extends the pattern one character at a time, and
+//! every file containing the longer pattern also contains the shorter one, so each search only
+//! reads the files the previous one matched plus the ones modified since.
+//!
+//! Files with a NUL byte near the start are binary and skipped, UTF-16 files are recognized by
+//! their byte order mark and files that aren't valid UTF-8 are read as latin-1. Lines and
+//! columns refer to the text decoded to UTF-8, every match tells the encoding it was read with.
+
+use crate::types::FileItem;
+use memchr::memmem::Finder;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrepOptions {
+    /// Lines returned before and after every matching line
+    pub before_context: usize,
+    pub after_context: usize,
+    /// Matching lines kept per file, a minified bundle shouldn't crowd out every other file
+    pub max_matches_per_file: usize,
+    /// Matching lines returned over all files
+    pub max_matches: usize,
+    /// Larger files are skipped without being read
+    pub max_file_size: u64,
+    /// Files flagged as sensitive are not read, set by the picker from `ScanOptions::sensitive`
+    pub skip_sensitive: bool,
+}
+
+impl Default for GrepOptions {
+    fn default() -> Self {
+        Self {
+            before_context: 0,
+            after_context: 0,
+            max_matches_per_file: 20,
+            max_matches: 1_000,
+            max_file_size: 10 * 1024 * 1024,
+            skip_sensitive: false,
+        }
+    }
+}
+
+/// Bytes sniffed for a NUL byte, like git and rg decide a file is binary
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+/// Encoding a file was decoded from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl TextEncoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Latin1 => "latin-1",
+        }
+    }
+}
+
+/// One matching line, positions are 1-based like the quickfix list and columns count bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub lnum: usize,
+    pub col: usize,
+    /// Column right after the first occurrence in the line
+    pub end_col: usize,
+    pub text: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    pub encoding: TextEncoding,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrepResult {
+    /// In the order of the index
+    pub matches: Vec<GrepMatch>,
+    /// Files containing the pattern, counting the ones whose matches were cut off
+    pub matched_files: usize,
+    /// Files read to answer the search, all of them unless the previous pattern narrowed them
+    pub searched_files: usize,
+    /// Some matches were dropped by `max_matches` or `max_matches_per_file`
+    pub truncated: bool,
+    /// Binary files and files over `max_file_size` that were not searched
+    pub skipped_files: usize,
+}
+
+/// Files matched by the previous pattern, the candidates of any pattern extending it
+#[derive(Debug, Default)]
+pub struct GrepSession {
+    /// Index generation, start and length of the files `matched` indexes into
+    key: Option<(u64, usize, usize)>,
+    /// Case folded previous pattern
+    pattern: Vec<u8>,
+    case_sensitive: bool,
+    matched: Vec<u32>,
+    /// Unix time of the previous search, files modified since are read again
+    searched_at: u64,
+}
+
+impl GrepSession {
+    /// Indices of the files worth reading for `pattern`, `None` reads all of them
+    fn candidates(
+        &self,
+        key: (u64, usize, usize),
+        files: &[FileItem],
+        pattern: &[u8],
+        case_sensitive: bool,
+    ) -> Option<Vec<u32>> {
+        // a case sensitive previous search may have missed files a case insensitive one matches
+        let extends = self.key == Some(key)
+            && (case_sensitive || !self.case_sensitive)
+            && !self.pattern.is_empty()
+            && Finder::new(&self.pattern)
+                .find(&fold_case(pattern, self.case_sensitive))
+                .is_some();
+        if !extends {
+            return None;
+        }
+
+        let mut candidates = self.matched.clone();
+        candidates.extend(
+            files
+                .iter()
+                .enumerate()
+                .filter(|(_, file)| file.modified >= self.searched_at)
+                .map(|(index, _)| index as u32),
+        );
+        candidates.sort_unstable();
+        candidates.dedup();
+        Some(candidates)
+    }
+}
+
+fn lock_session(session: &Mutex<GrepSession>) -> MutexGuard<'_, GrepSession> {
+    session.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Searches `files` for `pattern`, `session` must belong to the picker the files come from and
+/// `generation` be its index generation
+pub fn grep(
+    files: &[FileItem],
+    pattern: &str,
+    options: GrepOptions,
+    session: &Mutex<GrepSession>,
+    generation: u64,
+) -> GrepResult {
+    if pattern.is_empty() {
+        return GrepResult::default();
+    }
+
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let pattern = fold_case(pattern.as_bytes(), case_sensitive);
+    let key = (generation, files.as_ptr() as usize, files.len());
+    let searched_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let candidates = lock_session(session).candidates(key, files, &pattern, case_sensitive);
+    let candidates = candidates.unwrap_or_else(|| (0..files.len() as u32).collect::<Vec<_>>());
+    let finder = Finder::new(&pattern);
+    let skipped_files = AtomicUsize::new(0);
+    let file_matches: Vec<(u32, Vec<GrepMatch>, bool)> = candidates
+        .par_iter()
+        .filter_map(|&index| {
+            let file = files.get(index as usize)?;
+            if file.size > options.max_file_size {
+                skipped_files.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            if options.skip_sensitive && file.sensitivity.is_some() {
+                return None;
+            }
+            let content = std::fs::read(&file.path).ok()?;
+            let Some((text, encoding)) = decode(content) else {
+                skipped_files.fetch_add(1, Ordering::Relaxed);
+                return None;
+            };
+            let (matches, truncated) =
+                grep_content(text.as_bytes(), &finder, case_sensitive, &options)?;
+            let matches = matches
+                .into_iter()
+                .map(|m| GrepMatch {
+                    path: file.path.clone(),
+                    relative_path: file.relative_path.clone(),
+                    encoding,
+                    ..m
+                })
+                .collect();
+            Some((index, matches, truncated))
+        })
+        .collect();
+
+    *lock_session(session) = GrepSession {
+        key: Some(key),
+        pattern: pattern.to_vec(),
+        case_sensitive,
+        matched: file_matches.iter().map(|(index, _, _)| *index).collect(),
+        searched_at,
+    };
+
+    let mut result = GrepResult {
+        matched_files: file_matches.len(),
+        searched_files: candidates.len(),
+        skipped_files: skipped_files.into_inner(),
+        ..GrepResult::default()
+    };
+    for (_, matches, truncated) in file_matches {
+        result.truncated |= truncated;
+        let room = options.max_matches.saturating_sub(result.matches.len());
+        result.truncated |= matches.len() > room;
+        result.matches.extend(matches.into_iter().take(room));
+    }
+    result
+}
+
+/// Text of a file, `None` for binary files
+pub(crate) fn decode(content: Vec<u8>) -> Option<(String, TextEncoding)> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = bytes
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect::<String>()
+    };
+    if let Some(bytes) = content.strip_prefix(b"\xFF\xFE") {
+        return Some((utf16(bytes, u16::from_le_bytes), TextEncoding::Utf16Le));
+    }
+    if let Some(bytes) = content.strip_prefix(b"\xFE\xFF") {
+        return Some((utf16(bytes, u16::from_be_bytes), TextEncoding::Utf16Be));
+    }
+
+    let content = match content.strip_prefix(b"\xEF\xBB\xBF") {
+        Some(bytes) => bytes.to_vec(),
+        None => content,
+    };
+    if memchr::memchr(0, &content[..content.len().min(BINARY_SNIFF_LEN)]).is_some() {
+        return None;
+    }
+
+    match String::from_utf8(content) {
+        Ok(text) => Some((text, TextEncoding::Utf8)),
+        // every byte is a latin-1 character, the code points match
+        Err(e) => Some((
+            e.into_bytes().into_iter().map(char::from).collect(),
+            TextEncoding::Latin1,
+        )),
+    }
+}
+
+/// `text` back in the encoding it was decoded from, `None` if latin-1 can't represent it
+pub(crate) fn encode(text: &str, encoding: TextEncoding, utf8_bom: bool) -> Option<Vec<u8>> {
+    let utf16 = |bom: [u8; 2], to_bytes: fn(u16) -> [u8; 2]| {
+        let units = text.encode_utf16().flat_map(to_bytes);
+        bom.into_iter().chain(units).collect()
+    };
+    match encoding {
+        TextEncoding::Utf8 if utf8_bom => Some([b"\xEF\xBB\xBF", text.as_bytes()].concat()),
+        TextEncoding::Utf8 => Some(text.as_bytes().to_vec()),
+        TextEncoding::Utf16Le => Some(utf16([0xFF, 0xFE], u16::to_le_bytes)),
+        TextEncoding::Utf16Be => Some(utf16([0xFE, 0xFF], u16::to_be_bytes)),
+        TextEncoding::Latin1 => text.chars().map(|c| u8::try_from(c).ok()).collect(),
+    }
+}
+
+pub(crate) fn fold_case(bytes: &[u8], case_sensitive: bool) -> Cow<'_, [u8]> {
+    if case_sensitive {
+        Cow::Borrowed(bytes)
+    } else {
+        Cow::Owned(bytes.to_ascii_lowercase())
+    }
+}
+
+/// Matching lines of one file without their path, `None` if the pattern doesn't occur at all.
+/// The bool tells whether lines were dropped by `max_matches_per_file`.
+fn grep_content(
+    content: &[u8],
+    finder: &Finder,
+    case_sensitive: bool,
+    options: &GrepOptions,
+) -> Option<(Vec<GrepMatch>, bool)> {
+    let folded = fold_case(content, case_sensitive);
+    finder.find(&folded)?;
+
+    let lines: Vec<&[u8]> = content.split(|&byte| byte == b'\n').collect();
+    let folded_lines = folded.split(|&byte| byte == b'\n');
+    let mut matches = Vec::new();
+    for (index, folded_line) in folded_lines.enumerate() {
+        let Some(col) = finder.find(folded_line) else {
+            continue;
+        };
+        if matches.len() == options.max_matches_per_file {
+            return Some((matches, true));
+        }
+
+        let context = |range: std::ops::Range<usize>| {
+            lines[range].iter().map(|line| line_text(line)).collect()
+        };
+        matches.push(GrepMatch {
+            lnum: index + 1,
+            col: col + 1,
+            end_col: col + finder.needle().len() + 1,
+            text: line_text(lines[index]),
+            before: context(index.saturating_sub(options.before_context)..index),
+            after: context(index + 1..(index + 1 + options.after_context).min(lines.len())),
+            ..GrepMatch::default()
+        });
+    }
+
+    Some((matches, false))
+}
+
+fn line_text(line: &[u8]) -> String {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_grep_narrows_and_keeps_context() {
+        let dir = std::env::temp_dir().join("fff_test_grep");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.rs"),
+            "fn main() {\n    let picker = Picker::new();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("b.rs"), "pick\npick\npick\r\n").unwrap();
+        std::fs::write(dir.join("c.rs"), "nothing here\n").unwrap();
+        // written long before the searches, so only the matched files are read again
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            let file = std::fs::File::options()
+                .write(true)
+                .open(dir.join(name))
+                .unwrap();
+            file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(1_000))
+                .unwrap();
+        }
+        let files: Vec<FileItem> = ["a.rs", "b.rs", "c.rs"]
+            .iter()
+            .map(|name| FileItem::new(dir.join(name), Path::new(&dir), None))
+            .collect();
+
+        let session = Mutex::default();
+        let options = GrepOptions {
+            before_context: 1,
+            after_context: 1,
+            max_matches_per_file: 2,
+            ..GrepOptions::default()
+        };
+        let result = grep(&files, "pick", options, &session, 1);
+        assert_eq!(result.searched_files, 3);
+        assert_eq!(result.matched_files, 2);
+        assert!(result.truncated);
+        let first = &result.matches[0];
+        assert_eq!((first.lnum, first.col, first.end_col), (2, 9, 13));
+        assert_eq!(first.before, ["fn main() {"]);
+        assert_eq!(first.after, ["}"]);
+        assert_eq!(result.matches.len(), 3);
+
+        // only the files matching `pick` are read again, the uppercase pattern is case sensitive
+        let result = grep(&files, "Picker", options, &session, 1);
+        assert_eq!(result.searched_files, 2);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].col, 18);
+
+        // a new index generation starts over
+        let result = grep(&files, "Picker::", options, &session, 2);
+        assert_eq!(result.searched_files, 3);
+        assert_eq!(result.matched_files, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            decode(b"\xEF\xBB\xBFplain".to_vec()),
+            Some(("plain".to_string(), TextEncoding::Utf8))
+        );
+        assert_eq!(
+            decode(b"caf\xE9".to_vec()),
+            Some(("café".to_string(), TextEncoding::Latin1))
+        );
+        assert_eq!(
+            decode(b"\xFF\xFEh\0i\0".to_vec()),
+            Some(("hi".to_string(), TextEncoding::Utf16Le))
+        );
+        assert_eq!(
+            decode(b"\xFE\xFF\0h\0i".to_vec()),
+            Some(("hi".to_string(), TextEncoding::Utf16Be))
+        );
+        assert_eq!(decode(b"\x89PNG\r\n\x1a\n\0\0".to_vec()), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Compiled ignore matchers for the background watcher. Asking libgit2 whether a path is
+//! ignored reads and parses every ignore file on the way for each event, here every directory
+//! is compiled once and dropped only when one of its ignore files changes.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+pub struct IgnoreCache {
+    /// Topmost directory whose ignore files apply, the git workdir when indexing inside a repo
+    root: PathBuf,
+    ignore_filenames: Vec<String>,
+    /// The global excludes file and `.git/info/exclude`, only used inside a git repository
+    global_excludes: Option<Gitignore>,
+    info_exclude: Option<(PathBuf, Gitignore)>,
+    /// The global fd and `~/.ignore` files, relative to the base path like in the walker
+    user_ignores: Option<(PathBuf, Gitignore)>,
+    matchers: HashMap<PathBuf, Gitignore>,
+}
+
+impl IgnoreCache {
+    /// Mirrors the walker of the scan: `.gitignore` files only count inside a git repository,
+    /// `.ignore` and the custom ignore files always do
+    pub fn new(
+        base_path: &Path,
+        git_workdir: Option<&Path>,
+        custom_ignore: &[String],
+        user_ignore_files: &[PathBuf],
+    ) -> Self {
+        let mut ignore_filenames = vec![".ignore".to_string()];
+        ignore_filenames.extend(custom_ignore.iter().cloned());
+
+        let mut global_excludes = None;
+        let mut info_exclude = None;
+        let root = match git_workdir.filter(|workdir| base_path.starts_with(workdir)) {
+            Some(workdir) => {
+                ignore_filenames.insert(0, ".gitignore".to_string());
+
+                let (global, error) = Gitignore::global();
+                if let Some(error) = error {
+                    warn!(?error, "Failed to read the global git excludes");
+                }
+                global_excludes = Some(global);
+
+                let exclude = workdir.join(".git").join("info").join("exclude");
+                info_exclude = Some((exclude.clone(), build_matcher(workdir, &[exclude])));
+
+                workdir.to_path_buf()
+            }
+            None => base_path.to_path_buf(),
+        };
+
+        let user_ignores = (!user_ignore_files.is_empty()).then(|| {
+            (
+                base_path.to_path_buf(),
+                build_matcher(base_path, user_ignore_files),
+            )
+        });
+
+        Self {
+            root,
+            ignore_filenames,
+            global_excludes,
+            info_exclude,
+            user_ignores,
+            matchers: HashMap::new(),
+        }
+    }
+
+    /// Drops the compiled matcher of the directory holding a changed ignore file
+    pub fn invalidate(&mut self, ignore_file: &Path) {
+        if let Some((exclude, matcher)) = self.info_exclude.as_mut() {
+            if exclude == ignore_file {
+                *matcher = build_matcher(&self.root, std::slice::from_ref(exclude));
+                return;
+            }
+        }
+
+        if let Some(dir) = ignore_file.parent() {
+            if self.matchers.remove(dir).is_some() {
+                debug!("Invalidated ignore matcher of {}", dir.display());
+            }
+        }
+    }
+
+    /// Deeper ignore files take precedence, so directories are checked from the path upwards
+    /// and the first explicit ignore or whitelist decides
+    pub fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let dirs: Vec<PathBuf> = relative
+            .parent()
+            .into_iter()
+            .flat_map(Path::ancestors)
+            .map(|dir| self.root.join(dir))
+            .collect();
+
+        for dir in dirs {
+            let matcher = self.matcher(&dir);
+            match matcher.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        // git checks the exclude file before the global one, the user ignore files come last
+        let user_ignores = self
+            .user_ignores
+            .iter()
+            .filter(|(base_path, _)| path.starts_with(base_path))
+            .map(|(_, matcher)| matcher);
+        self.info_exclude
+            .iter()
+            .map(|(_, matcher)| matcher)
+            .chain(self.global_excludes.iter())
+            .chain(user_ignores)
+            .map(|matcher| matcher.matched_path_or_any_parents(path, is_dir))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matched.is_ignore())
+    }
+
+    fn matcher(&mut self, dir: &Path) -> &Gitignore {
+        let ignore_filenames = &self.ignore_filenames;
+        self.matchers.entry(dir.to_path_buf()).or_insert_with(|| {
+            let files: Vec<PathBuf> = ignore_filenames
+                .iter()
+                .map(|file_name| dir.join(file_name))
+                .collect();
+            build_matcher(dir, &files)
+        })
+    }
+}
+
+/// Global ignore files of fd and ripgrep that exist: `$XDG_CONFIG_HOME/fd/ignore` (by default
+/// `~/.config/fd/ignore`) and `~/.ignore`
+pub fn user_ignore_files() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+
+    config_dir
+        .map(|config_dir| config_dir.join("fd").join("ignore"))
+        .into_iter()
+        .chain(home.map(|home| home.join(".ignore")))
+        .filter(|file| file.is_file())
+        .collect()
+}
+
+fn build_matcher(root: &Path, files: &[PathBuf]) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for file in files.iter().filter(|file| file.is_file()) {
+        if let Some(error) = builder.add(file) {
+            warn!(?error, "Failed to parse {}", file.display());
+        }
+    }
+
+    builder.build().unwrap_or_else(|error| {
+        warn!(
+            ?error,
+            "Failed to compile the ignore rules of {}",
+            root.display()
+        );
+        Gitignore::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let root = std::env::temp_dir().join("fff_test_ignore_cache");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("sub/target")).unwrap();
+        std::fs::write(root.join(".ignore"), "target/\n*.log\n").unwrap();
+        std::fs::write(root.join("sub/.ignore"), "!keep.log\n").unwrap();
+
+        let mut cache = IgnoreCache::new(&root, None, &[], &[]);
+        assert!(cache.is_ignored(&root.join("sub/target/out.rs"), false));
+        assert!(cache.is_ignored(&root.join("debug.log"), false));
+        assert!(cache.is_ignored(&root.join("sub/debug.log"), false));
+        assert!(!cache.is_ignored(&root.join("sub/keep.log"), false));
+        assert!(!cache.is_ignored(&root.join("sub/main.rs"), false));
+
+        std::fs::write(root.join(".ignore"), "").unwrap();
+        assert!(cache.is_ignored(&root.join("debug.log"), false), "cached");
+        cache.invalidate(&root.join(".ignore"));
+        assert!(!cache.is_ignored(&root.join("debug.log"), false));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_user_ignore_files() {
+        let root = std::env::temp_dir().join("fff_test_user_ignore");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("project")).unwrap();
+        std::fs::write(root.join("fd_ignore"), "*.bak\n").unwrap();
+        std::fs::write(root.join("project/.ignore"), "!keep.bak\n").unwrap();
+
+        let base_path = root.join("project");
+        let mut cache = IgnoreCache::new(&base_path, None, &[], &[root.join("fd_ignore")]);
+        assert!(cache.is_ignored(&base_path.join("notes.bak"), false));
+        assert!(!cache.is_ignored(&base_path.join("keep.bak"), false));
+        assert!(!cache.is_ignored(&base_path.join("main.rs"), false));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,136 @@
+//! Log of the recent index changes, letting sidebars and tree views catch up with the index
+//! incrementally instead of copying all of it over again after every filesystem event.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+
+/// Oldest changes are dropped past this, consumers that far behind have to start over
+const MAX_LOGGED_CHANGES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Files added, removed and changed between two generations of the index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexDiff {
+    /// Generation to pass to the next diff
+    pub generation: u64,
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub changed: Vec<PathBuf>,
+    /// The changes since the requested generation are no longer known, e.g. after a full
+    /// rescan, so the whole index has to be read again
+    pub reset: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct IndexChangeLog {
+    generation: u64,
+    /// Generation of the latest reset, diffs from before it can't be answered
+    reset_generation: u64,
+    changes: VecDeque<(u64, IndexChangeKind, PathBuf)>,
+}
+
+impl IndexChangeLog {
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn record(&mut self, kind: IndexChangeKind, path: PathBuf) {
+        self.generation += 1;
+        if self.changes.len() >= MAX_LOGGED_CHANGES {
+            if let Some((generation, _, _)) = self.changes.pop_front() {
+                self.reset_generation = self.reset_generation.max(generation);
+            }
+        }
+        self.changes.push_back((self.generation, kind, path));
+    }
+
+    /// Forgets every logged change, used when the whole index is replaced at once
+    pub fn reset(&mut self) {
+        self.generation += 1;
+        self.reset_generation = self.generation;
+        self.changes.clear();
+    }
+
+    /// Net changes since `since`, a file added and removed again in between is not reported
+    /// and one removed and added back is reported as changed
+    pub fn diff(&self, since: u64) -> IndexDiff {
+        if since < self.reset_generation || since > self.generation {
+            return IndexDiff {
+                generation: self.generation,
+                reset: true,
+                ..Default::default()
+            };
+        }
+
+        let mut net: HashMap<&PathBuf, (IndexChangeKind, IndexChangeKind)> = HashMap::new();
+        let mut order = Vec::new();
+        for (_, kind, path) in self.changes.iter().filter(|(gen, _, _)| *gen > since) {
+            net.entry(path)
+                .and_modify(|(_, last)| *last = *kind)
+                .or_insert_with(|| {
+                    order.push(path);
+                    (*kind, *kind)
+                });
+        }
+
+        let mut diff = IndexDiff {
+            generation: self.generation,
+            ..Default::default()
+        };
+        for path in order {
+            let target = match net[path] {
+                (IndexChangeKind::Added, IndexChangeKind::Removed) => continue,
+                (IndexChangeKind::Added, _) => &mut diff.added,
+                (_, IndexChangeKind::Removed) => &mut diff.removed,
+                _ => &mut diff.changed,
+            };
+            target.push(path.clone());
+        }
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_coalesces_changes() {
+        let mut log = IndexChangeLog::default();
+        log.reset();
+        let since = log.generation();
+
+        log.record(IndexChangeKind::Added, "new.rs".into());
+        log.record(IndexChangeKind::Changed, "new.rs".into());
+        log.record(IndexChangeKind::Added, "tmp.rs".into());
+        log.record(IndexChangeKind::Removed, "tmp.rs".into());
+        log.record(IndexChangeKind::Removed, "old.rs".into());
+        log.record(IndexChangeKind::Removed, "lib.rs".into());
+        log.record(IndexChangeKind::Added, "lib.rs".into());
+
+        let diff = log.diff(since);
+        assert!(!diff.reset);
+        assert_eq!(diff.generation, log.generation());
+        assert_eq!(diff.added, vec![PathBuf::from("new.rs")]);
+        assert_eq!(diff.removed, vec![PathBuf::from("old.rs")]);
+        assert_eq!(diff.changed, vec![PathBuf::from("lib.rs")]);
+        assert_eq!(
+            log.diff(log.generation()),
+            IndexDiff {
+                generation: log.generation(),
+                ..Default::default()
+            }
+        );
+
+        log.reset();
+        assert!(log.diff(since).reset);
+        assert!(log.diff(log.generation() + 1).reset);
+    }
+}
@@ -0,0 +1,206 @@
+//! Languages of the project, detected from the extensions of the scanned files. They tune the
+//! defaults that only make sense for some languages, e.g. `mod.rs` being an entry point is
+//! noise in a javascript project that happens to vendor a rust crate.
+
+use crate::types::FileItem;
+use std::collections::HashMap;
+
+struct Language {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    /// File names exporting a module or starting a program, they get the entry point bonus
+    entry_points: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "rust",
+        extensions: &["rs"],
+        entry_points: &["mod.rs", "lib.rs", "main.rs"],
+    },
+    Language {
+        name: "javascript",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        entry_points: &["index.js", "index.jsx", "index.mjs", "index.cjs"],
+    },
+    Language {
+        name: "typescript",
+        extensions: &["ts", "tsx", "mts", "cts"],
+        entry_points: &["index.ts", "index.tsx"],
+    },
+    Language {
+        name: "vue",
+        extensions: &["vue"],
+        entry_points: &["index.vue"],
+    },
+    Language {
+        name: "python",
+        extensions: &["py", "pyi"],
+        entry_points: &["__init__.py", "__main__.py"],
+    },
+    Language {
+        name: "go",
+        extensions: &["go"],
+        entry_points: &["main.go"],
+    },
+    Language {
+        name: "c",
+        extensions: &["c", "h"],
+        entry_points: &["main.c"],
+    },
+    Language {
+        name: "cpp",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh"],
+        entry_points: &["main.cpp"],
+    },
+    Language {
+        name: "php",
+        extensions: &["php"],
+        entry_points: &["index.php"],
+    },
+    Language {
+        name: "ruby",
+        extensions: &["rb"],
+        entry_points: &["main.rb", "index.rb"],
+    },
+    Language {
+        name: "lua",
+        extensions: &["lua"],
+        entry_points: &["init.lua"],
+    },
+    Language {
+        name: "java",
+        extensions: &["java"],
+        entry_points: &[],
+    },
+    Language {
+        name: "kotlin",
+        extensions: &["kt", "kts"],
+        entry_points: &[],
+    },
+    Language {
+        name: "swift",
+        extensions: &["swift"],
+        entry_points: &["main.swift"],
+    },
+];
+
+/// Languages with a smaller share of the source files are incidental, like build scripts
+const MIN_LANGUAGE_SHARE: f64 = 0.1;
+/// Most common extensions kept in the stats
+const MAX_LISTED_EXTENSIONS: usize = 20;
+
+/// Extension distribution of the index computed once per scan
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    pub files: usize,
+    /// Most common lowercase extensions first, files without one are not counted
+    pub extensions: Vec<(String, usize)>,
+    /// Detected project languages, the most used first
+    pub languages: Vec<&'static str>,
+    entry_points: Vec<&'static str>,
+}
+
+impl IndexStats {
+    pub fn collect(files: &[FileItem]) -> Self {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for file in files {
+            if let Some((_, extension)) = file.file_name.rsplit_once('.') {
+                *counts.entry(extension.to_ascii_lowercase()).or_default() += 1;
+            }
+        }
+
+        let mut language_counts: Vec<(&Language, usize)> = LANGUAGES
+            .iter()
+            .map(|language| {
+                let count = language
+                    .extensions
+                    .iter()
+                    .filter_map(|extension| counts.get(*extension))
+                    .sum();
+                (language, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        let source_files: usize = language_counts.iter().map(|(_, count)| count).sum();
+        language_counts
+            .retain(|(_, count)| *count as f64 >= source_files as f64 * MIN_LANGUAGE_SHARE);
+        language_counts.sort_by(|(a, a_count), (b, b_count)| {
+            b_count.cmp(a_count).then_with(|| a.name.cmp(b.name))
+        });
+
+        let mut extensions: Vec<_> = counts.into_iter().collect();
+        extensions
+            .sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+        extensions.truncate(MAX_LISTED_EXTENSIONS);
+
+        Self {
+            files: files.len(),
+            extensions,
+            languages: language_counts
+                .iter()
+                .map(|(language, _)| language.name)
+                .collect(),
+            entry_points: language_counts
+                .iter()
+                .flat_map(|(language, _)| language.entry_points.iter().copied())
+                .collect(),
+        }
+    }
+
+    /// Entry point file names of the detected languages, `None` until a language is detected
+    pub fn entry_points(&self) -> Option<&[&'static str]> {
+        (!self.languages.is_empty()).then_some(self.entry_points.as_slice())
+    }
+}
+
+/// Language of a lowercase extension, `None` for the ones no language claims
+pub fn language_of(extension: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|language| language.extensions.contains(&extension))
+        .map(|language| language.name)
+}
+
+/// Checks the file name against the entry points of the given languages or of all of them
+pub fn is_entry_point_file(file_name: &str, entry_points: Option<&[&'static str]>) -> bool {
+    match entry_points {
+        Some(entry_points) => entry_points.contains(&file_name),
+        None => LANGUAGES
+            .iter()
+            .any(|language| language.entry_points.contains(&file_name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(names: &[&str]) -> Vec<FileItem> {
+        names
+            .iter()
+            .map(|name| FileItem::new(name.into(), "".as_ref(), None))
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_project_languages() {
+        let mut names = vec!["build.rs", "README.md", "Makefile"];
+        names.extend(["a.ts", "b.ts", "c.tsx", "d.ts", "e.ts", "f.js", "g.js"]);
+        names.extend(["h.ts", "i.ts", "j.ts", "k.ts", "l.ts"]);
+        let stats = IndexStats::collect(&files(&names));
+
+        assert_eq!(stats.files, 15);
+        assert_eq!(stats.extensions[0], ("ts".to_string(), 9));
+        assert_eq!(stats.languages, vec!["typescript", "javascript"]);
+        let entry_points = stats.entry_points();
+        assert!(is_entry_point_file("index.ts", entry_points));
+        assert!(is_entry_point_file("index.js", entry_points));
+        assert!(
+            !is_entry_point_file("mod.rs", entry_points),
+            "incidental rust"
+        );
+        assert!(is_entry_point_file("mod.rs", None));
+        assert_eq!(IndexStats::collect(&[]).entry_points(), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! Columns of the search results computed once per search, so frontends render aligned rows
+//! from plain fields instead of splitting every path again on each redraw.
+
+use crate::git::format_git_status;
+use crate::languages::language_of;
+use crate::types::SearchResult;
+use std::path::Path;
+
+/// Score buckets counted from the best result, each one spans a quarter of its score
+const SCORE_BUCKETS: i32 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultLayout {
+    pub filename: String,
+    /// Displayed parent directory, `""` for files of the root
+    pub directory: String,
+    /// Kind of file for picking an icon or color, a language name like `rust` or a broader
+    /// class like `config`, `doc`, `image`, `lock` or `file`
+    pub icon_class: &'static str,
+    /// One letter git status in the style of `git status --short`, `""` for clean files
+    pub git_badge: &'static str,
+    /// 0 for results within a quarter of the best score, up to 3 for the weakest ones
+    pub score_bucket: u8,
+    /// 0 without frecency, then 1, 2 and 3 for low, moderate and high frecency
+    pub frecency_bucket: u8,
+}
+
+/// Widest cell of each column, in characters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutColumns {
+    pub filename_width: usize,
+    pub directory_width: usize,
+}
+
+pub fn icon_class(file_name: &str) -> &'static str {
+    let Some((_, extension)) = file_name.rsplit_once('.') else {
+        return "file";
+    };
+    let extension = extension.to_ascii_lowercase();
+    if let Some(language) = language_of(&extension) {
+        return language;
+    }
+
+    match extension.as_str() {
+        "toml" | "yaml" | "yml" | "json" | "jsonc" | "ini" | "cfg" | "conf" => "config",
+        "md" | "markdown" | "rst" | "txt" | "adoc" => "doc",
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "ico" | "bmp" => "image",
+        "lock" => "lock",
+        _ => "file",
+    }
+}
+
+pub fn git_badge(git_status: &str) -> &'static str {
+    match git_status {
+        "conflicted" => "U",
+        "untracked" | "unknown" => "?",
+        "modified" | "staged_modified" => "M",
+        "deleted" | "staged_deleted" => "D",
+        "renamed" | "staged_renamed" => "R",
+        "staged_new" => "A",
+        "ignored" => "!",
+        _ => "",
+    }
+}
+
+pub fn score_bucket(total: i32, best: i32) -> u8 {
+    if best <= 0 || total >= best {
+        return 0;
+    }
+
+    let below_best = (best - total.max(0)) as i64 * SCORE_BUCKETS as i64 / best as i64;
+    below_best.min(SCORE_BUCKETS as i64 - 1) as u8
+}
+
+/// Thresholds of the frecency indicators of the list
+pub fn frecency_bucket(total_frecency_score: i64) -> u8 {
+    match total_frecency_score {
+        score if score >= 8 => 3,
+        score if score >= 4 => 2,
+        score if score >= 1 => 1,
+        _ => 0,
+    }
+}
+
+impl SearchResult<'_> {
+    /// Layout of every item in order, with the widths of the filename and directory columns
+    pub fn layout(&self) -> (Vec<ResultLayout>, LayoutColumns) {
+        let best = self.scores.first().map_or(0, |score| score.total);
+        let mut columns = LayoutColumns::default();
+
+        let layouts = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let directory = Path::new(&file.relative_path)
+                    .parent()
+                    .and_then(Path::to_str)
+                    .unwrap_or_default();
+                let directory = self.display_path(directory).into_owned();
+
+                columns.filename_width = columns.filename_width.max(file.file_name.chars().count());
+                columns.directory_width = columns.directory_width.max(directory.chars().count());
+
+                ResultLayout {
+                    filename: file.file_name.clone(),
+                    directory,
+                    icon_class: icon_class(&file.file_name),
+                    git_badge: git_badge(format_git_status(file.git_status)),
+                    score_bucket: self
+                        .scores
+                        .get(index)
+                        .map_or(0, |score| score_bucket(score.total, best)),
+                    frecency_bucket: frecency_bucket(file.total_frecency_score),
+                }
+            })
+            .collect();
+
+        (layouts, columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_fields() {
+        assert_eq!(icon_class("main.rs"), "rust");
+        assert_eq!(icon_class("Cargo.TOML"), "config");
+        assert_eq!(icon_class("Cargo.lock"), "lock");
+        assert_eq!(icon_class("Makefile"), "file");
+
+        assert_eq!(git_badge("staged_new"), "A");
+        assert_eq!(git_badge("clean"), "");
+
+        assert_eq!(score_bucket(100, 100), 0);
+        assert_eq!(score_bucket(80, 100), 0);
+        assert_eq!(score_bucket(60, 100), 1);
+        assert_eq!(score_bucket(10, 100), 3);
+        assert_eq!(score_bucket(-5, 100), 3);
+        assert_eq!(score_bucket(10, 0), 0);
+
+        assert_eq!(frecency_bucket(0), 0);
+        assert_eq!(frecency_bucket(5), 2);
+    }
+}
@@ -0,0 +1,72 @@
+//! Matching, indexing and frecency engine behind fff.nvim.
+//!
+//! The engine keeps a single global [`FilePicker`](file_picker::FilePicker) and
+//! [`FrecencyTracker`](frecency::FrecencyTracker) so that the background watcher and every
+//! frontend (neovim bindings, CLI, benchmarks) share the same index.
+
+use crate::file_picker::FilePicker;
+use crate::frecency::FrecencyTracker;
+use crate::list::ListEntry;
+use crate::snapshot::IndexSnapshots;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+pub mod archive;
+mod background_watcher;
+pub mod category;
+pub mod config;
+pub mod debug_bundle;
+pub mod display_path;
+pub mod error;
+pub mod evaluation;
+pub mod events;
+pub mod file_picker;
+pub mod filter;
+pub mod frecency;
+pub mod git;
+pub mod glob_filter;
+pub mod grep;
+mod ignore_cache;
+pub mod index_diff;
+pub mod languages;
+pub mod layout;
+pub mod list;
+pub mod memory;
+pub mod package;
+pub mod path_utils;
+pub mod prefix_cache;
+pub mod profile;
+pub mod redact;
+pub mod replace;
+pub mod replay;
+pub mod result_count;
+pub mod result_diff;
+pub mod roots;
+pub mod scan_batches;
+pub mod score;
+pub mod scorer;
+pub mod sensitive;
+pub mod shutdown;
+pub mod snapshot;
+pub mod source;
+pub mod speculate;
+pub mod supersede;
+pub mod tracing;
+pub mod types;
+pub mod typo;
+pub mod vcs;
+pub mod word_search;
+
+#[cfg(feature = "lua")]
+mod lua;
+
+pub use error::Error;
+
+pub static FRECENCY: Lazy<RwLock<Option<FrecencyTracker>>> = Lazy::new(|| RwLock::new(None));
+pub static FILE_PICKER: Lazy<RwLock<Option<FilePicker>>> = Lazy::new(|| RwLock::new(None));
+pub static SNAPSHOTS: Lazy<RwLock<Option<IndexSnapshots>>> = Lazy::new(|| RwLock::new(None));
+pub static LIST_ENTRIES: Lazy<RwLock<Vec<ListEntry>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Held by the tests that install their own picker in `FILE_PICKER`
+#[cfg(test)]
+pub(crate) static GLOBAL_PICKER_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
@@ -0,0 +1,128 @@
+//! Fuzzy search over arbitrary location lists (quickfix, loclist) pushed by the frontend.
+
+use crate::frecency::FrecencyTracker;
+use crate::path_utils::normalize_path;
+use crate::types::{Score, ScoringContext};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A single location of the list, matched on `path:lnum: text`
+#[derive(Debug, Clone)]
+pub struct ListEntry {
+    pub path: String,
+    pub lnum: u32,
+    pub col: u32,
+    pub text: String,
+    pub access_frecency_score: i64,
+    haystack: String,
+}
+
+impl ListEntry {
+    pub fn new(path: String, lnum: u32, col: u32, text: String) -> Self {
+        let haystack = format!("{}:{}: {}", path, lnum, text.trim());
+        Self {
+            path,
+            lnum,
+            col,
+            text,
+            access_frecency_score: 0,
+            haystack,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListSearchResult<'a> {
+    pub items: Vec<&'a ListEntry>,
+    pub scores: Vec<Score>,
+    pub total_matched: usize,
+    pub total_entries: usize,
+}
+
+/// Fills the access frecency of every entry, each distinct path is looked up only once.
+/// Relative paths are resolved against `cwd` the same way the editor resolves them.
+pub fn update_list_frecency(entries: &mut [ListEntry], tracker: &FrecencyTracker, cwd: &Path) {
+    let mut scores: HashMap<String, i64> = HashMap::new();
+    for entry in entries.iter_mut() {
+        entry.access_frecency_score = *scores.entry(entry.path.clone()).or_insert_with(|| {
+            let path: PathBuf = normalize_path(Path::new(&entry.path), cwd).into_owned();
+            tracker.get_access_score(&path)
+        });
+    }
+}
+
+/// Matches the entries keeping the original list order for short queries and between
+/// entries with equal scores, since quickfix order is usually meaningful.
+pub fn match_and_score_list<'a>(
+    entries: &'a [ListEntry],
+    context: &ScoringContext,
+) -> ListSearchResult<'a> {
+    let total_entries = entries.len();
+    if context.query.len() < 2 {
+        let items: Vec<_> = entries.iter().take(context.max_results).collect();
+        let scores = items
+            .iter()
+            .map(|entry| Score {
+                total: entry.access_frecency_score as i32,
+                frecency_boost: entry.access_frecency_score as i32,
+                match_type: "list",
+                ..Default::default()
+            })
+            .collect();
+
+        return ListSearchResult {
+            items,
+            scores,
+            total_matched: total_entries,
+            total_entries,
+        };
+    }
+
+    let options = neo_frizbee::Options {
+        prefilter: true,
+        max_typos: Some(context.max_typos),
+        sort: false,
+    };
+
+    let haystack: Vec<&str> = entries.iter().map(|e| e.haystack.as_str()).collect();
+    let matches =
+        neo_frizbee::match_list_parallel(context.query, &haystack, options, context.max_threads);
+
+    let mut results: Vec<_> = matches
+        .into_par_iter()
+        .map(|m| {
+            let index = m.index_in_haystack as usize;
+            let entry = &entries[index];
+            let base_score = m.score as i32;
+            let frecency_boost =
+                base_score.saturating_mul(entry.access_frecency_score as i32) / 100;
+
+            let score = Score {
+                total: base_score.saturating_add(frecency_boost),
+                base_score,
+                frecency_boost,
+                match_type: "list",
+                ..Default::default()
+            };
+
+            (index, score)
+        })
+        .collect();
+
+    results.par_sort_unstable_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(&b.0)));
+
+    let total_matched = results.len();
+    results.truncate(context.max_results);
+    let (items, scores) = results
+        .into_iter()
+        .map(|(index, score)| (&entries[index], score))
+        .unzip();
+
+    ListSearchResult {
+        items,
+        scores,
+        total_matched,
+        total_entries,
+    }
+}
@@ -0,0 +1,992 @@
+//! Conversions of the core types to and from lua values for the neovim bindings.
+
+use crate::category::CategoryRule;
+use crate::config::ConfigUpdate;
+use crate::error::Error;
+use crate::events::PickerEvent;
+use crate::git::format_git_status;
+use crate::grep::{GrepMatch, GrepOptions, GrepResult};
+use crate::index_diff::IndexDiff;
+use crate::languages::IndexStats;
+use crate::layout::{LayoutColumns, ResultLayout};
+use crate::list::{ListEntry, ListSearchResult};
+use crate::memory::IndexMetrics;
+use crate::profile::SearchProfile;
+use crate::redact::RedactionOptions;
+use crate::replace::{FileReplacement, ReplaceHunk, ReplaceOutcome, ReplacePlan};
+use crate::result_diff::ResultDiff;
+use crate::scan_batches::ScanBatch;
+use crate::scorer::Divergence;
+use crate::sensitive::{SensitiveMode, SensitiveOptions};
+use crate::source::ListedFile;
+use crate::types::{
+    AccessCount, AccessRecord, DiagnosticsCount, FileItem, FileListOptions, FileListPage,
+    FileListSort, FrecencyDetails, FrecencyOptions, IndexDrift, MemoryOptions, MetaValue,
+    PackageScope, ResultGroup, ScanOptions, ScanThrottle, Score, ScoringWeights, SearchContext,
+    SearchOptions, SearchResult, TieBreak, TieBreaks, WatcherBackend, WatcherOptions,
+};
+use crate::word_search::WordHit;
+use mlua::prelude::*;
+
+impl From<Error> for mlua::Error {
+    fn from(value: Error) -> Self {
+        let string_value = value.to_string();
+
+        ::tracing::error!(string_value);
+        mlua::Error::RuntimeError(string_value)
+    }
+}
+
+impl IntoLua for &FileItem {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("path", self.path.to_string_lossy().to_string())?;
+        table.set("relative_path", self.relative_path.clone())?;
+        table.set("name", self.file_name.clone())?;
+        table.set("size", self.size)?;
+        table.set("modified", self.modified)?;
+        table.set("access_frecency_score", self.access_frecency_score)?;
+        table.set(
+            "modification_frecency_score",
+            self.modification_frecency_score,
+        )?;
+        table.set("jump_frecency_score", self.jump_frecency_score)?;
+        table.set("commit_frecency_score", self.commit_frecency_score)?;
+        table.set("total_frecency_score", self.total_frecency_score)?;
+        table.set("git_status", format_git_status(self.git_status))?;
+        table.set("category", self.category.as_deref())?;
+        table.set("package", self.package.as_deref())?;
+        table.set("root", self.root.as_deref())?;
+        table.set(
+            "sensitivity",
+            self.sensitivity.map(|sensitivity| sensitivity.as_str()),
+        )?;
+        table.set("error_count", self.diagnostics.errors)?;
+        table.set("warning_count", self.diagnostics.warnings)?;
+        if let Some(meta) = &self.meta {
+            table.set(
+                "meta",
+                lua.create_table_from(meta.iter().map(|(key, value)| (key.as_str(), value)))?,
+            )?;
+        }
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for FileListPage<'_> {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("items", self.items)?;
+        table.set("total", self.total)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for &MetaValue {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        match self {
+            MetaValue::Bool(value) => Ok(LuaValue::Boolean(*value)),
+            MetaValue::Integer(value) => Ok(LuaValue::Integer(*value)),
+            MetaValue::Number(value) => Ok(LuaValue::Number(*value)),
+            MetaValue::String(value) => value.as_str().into_lua(lua),
+            MetaValue::Array(values) => lua.create_sequence_from(values.iter())?.into_lua(lua),
+            MetaValue::Map(values) => lua
+                .create_table_from(values.iter().map(|(key, value)| (key.as_str(), value)))?
+                .into_lua(lua),
+        }
+    }
+}
+
+impl FromLua for MetaValue {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Boolean(value) => Ok(Self::Bool(value)),
+            LuaValue::Integer(value) => Ok(Self::Integer(value)),
+            LuaValue::Number(value) => Ok(Self::Number(value)),
+            LuaValue::String(value) => Ok(Self::String(value.to_str()?.to_string())),
+            // non empty sequences are arrays, everything else has to be keyed by strings
+            LuaValue::Table(table) if table.raw_len() > 0 => Ok(Self::Array(
+                table.sequence_values().collect::<LuaResult<_>>()?,
+            )),
+            LuaValue::Table(table) => {
+                Ok(Self::Map(FromLua::from_lua(LuaValue::Table(table), lua)?))
+            }
+            other => Err(LuaError::FromLuaConversionError {
+                from: other.type_name(),
+                to: "MetaValue".to_string(),
+                message: Some(
+                    "only booleans, numbers, strings and tables are supported".to_string(),
+                ),
+            }),
+        }
+    }
+}
+
+impl IntoLua for Score {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("total", self.total)?;
+        table.set("base_score", self.base_score)?;
+        table.set("filename_bonus", self.filename_bonus)?;
+        table.set("special_filename_bonus", self.special_filename_bonus)?;
+        table.set("frecency_boost", self.frecency_boost)?;
+        table.set("distance_penalty", self.distance_penalty)?;
+        table.set("depth_penalty", self.depth_penalty)?;
+        table.set("path_component_bonus", self.path_component_bonus)?;
+        table.set("diagnostics_bonus", self.diagnostics_bonus)?;
+        table.set("package_bonus", self.package_bonus)?;
+        table.set("word_order_bonus", self.word_order_bonus)?;
+        table.set("abbreviation_bonus", self.abbreviation_bonus)?;
+        table.set("typo_bonus", self.typo_bonus)?;
+        table.set("co_change_bonus", self.co_change_bonus)?;
+        table.set("session_bonus", self.session_bonus)?;
+        table.set("time_of_day_boost", self.time_of_day_boost)?;
+        table.set("normalized", self.normalized)?;
+        table.set("match_type", self.match_type)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for AccessRecord {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("timestamp", self.timestamp)?;
+        table.set("days_ago", self.days_ago)?;
+        table.set("decay", self.decay)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for AccessCount {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("count", self.count)?;
+        table.set("last_access", self.last_access)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for PickerEvent {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("kind", self.kind.as_str())?;
+        table.set("path", self.path.to_string_lossy().to_string())?;
+        table.set("timestamp", self.timestamp)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for ScanBatch {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("count", self.count)?;
+        table.set("total", self.total)?;
+        table.set("samples", self.samples)?;
+        table.set("done", self.done)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for IndexDiff {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let paths = |paths: Vec<std::path::PathBuf>| -> Vec<String> {
+            paths
+                .into_iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let table = lua.create_table()?;
+        table.set("generation", self.generation)?;
+        table.set("added", paths(self.added))?;
+        table.set("removed", paths(self.removed))?;
+        table.set("changed", paths(self.changed))?;
+        table.set("reset", self.reset)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for &IndexStats {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let extensions = lua.create_table_with_capacity(self.extensions.len(), 0)?;
+        for (extension, count) in &self.extensions {
+            let entry = lua.create_table()?;
+            entry.set("extension", extension.as_str())?;
+            entry.set("count", *count)?;
+            extensions.push(entry)?;
+        }
+
+        let table = lua.create_table()?;
+        table.set("files", self.files)?;
+        table.set("extensions", extensions)?;
+        table.set("languages", self.languages.clone())?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for FrecencyDetails {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("accesses", self.accesses)?;
+        table.set("raw_access_score", self.raw_access_score)?;
+        table.set("access_score", self.access_score)?;
+        table.set("modified", self.modified)?;
+        table.set(
+            "seconds_since_modification",
+            self.seconds_since_modification,
+        )?;
+        table.set("is_modified_git_status", self.is_modified_git_status)?;
+        table.set("last_edit", self.last_edit)?;
+        table.set("modification_score", self.modification_score)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for SearchResult<'_> {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let items = lua.create_sequence_from(self.items.iter().copied())?;
+        if self.display_prefix.is_some() {
+            for (item, file) in items.sequence_values::<LuaTable>().zip(&self.items) {
+                item?.set("relative_path", self.display_path(&file.relative_path))?;
+            }
+        }
+        let (layouts, columns) = self.layout();
+        for (item, layout) in items.sequence_values::<LuaTable>().zip(layouts) {
+            item?.set("layout", layout)?;
+        }
+
+        let table = lua.create_table()?;
+        table.set("items", items)?;
+        table.set("columns", columns)?;
+        table.set("scores", self.scores)?;
+        table.set("total_matched", self.total_matched)?;
+        table.set("total_files", self.total_files)?;
+        table.set("superseded", self.superseded)?;
+        table.set("approximate", self.approximate)?;
+        table.set("groups", self.groups)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for ResultLayout {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("filename", self.filename)?;
+        table.set("directory", self.directory)?;
+        table.set("icon_class", self.icon_class)?;
+        table.set("git_badge", self.git_badge)?;
+        table.set("score_bucket", self.score_bucket)?;
+        table.set("frecency_bucket", self.frecency_bucket)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for LayoutColumns {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("filename_width", self.filename_width)?;
+        table.set("directory_width", self.directory_width)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for Divergence {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("same_ranking", self.is_same_ranking())?;
+        table.set("shared", self.shared)?;
+        table.set("only_baseline", self.only_baseline)?;
+        table.set("only_candidate", self.only_candidate)?;
+        // lua tables are 1-based
+        table.set(
+            "first_difference",
+            self.first_difference.map(|rank| rank + 1),
+        )?;
+        table.set("mean_displacement", self.mean_displacement)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for ResultDiff {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("unchanged", self.is_unchanged())?;
+        // lua tables are 1-based
+        table.set(
+            "kept",
+            lua.create_sequence_from(
+                self.kept
+                    .into_iter()
+                    .map(|(previous, current)| vec![previous + 1, current + 1]),
+            )?,
+        )?;
+        table.set(
+            "inserted",
+            lua.create_sequence_from(self.inserted.into_iter().map(|index| index + 1))?,
+        )?;
+        table.set(
+            "removed",
+            lua.create_sequence_from(self.removed.into_iter().map(|index| index + 1))?,
+        )?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for ResultGroup {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("directory", self.directory)?;
+        // lua tables are 1-based
+        table.set(
+            "items",
+            lua.create_sequence_from(self.items.into_iter().map(|index| index + 1))?,
+        )?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+fn expect_table(value: LuaValue, to: &str) -> LuaResult<Option<LuaTable>> {
+    match value {
+        LuaValue::Nil => Ok(None),
+        LuaValue::Table(table) => Ok(Some(table)),
+        other => Err(LuaError::FromLuaConversionError {
+            from: other.type_name(),
+            to: to.to_string(),
+            message: Some("expected a table".to_string()),
+        }),
+    }
+}
+
+impl FromLua for CategoryRule {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let Some(table) = expect_table(value, "CategoryRule")? else {
+            return Err(LuaError::FromLuaConversionError {
+                from: "nil",
+                to: "CategoryRule".to_string(),
+                message: Some("expected a table".to_string()),
+            });
+        };
+
+        Ok(Self {
+            name: table.get("name")?,
+            globs: table.get("globs")?,
+        })
+    }
+}
+
+impl FromLua for ScanOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "ScanOptions")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            custom_ignore_filenames: table
+                .get::<Option<Vec<String>>>("custom_ignore_filenames")?
+                .unwrap_or(defaults.custom_ignore_filenames),
+            relative_to_git_root: table
+                .get::<Option<bool>>("relative_to_git_root")?
+                .unwrap_or(defaults.relative_to_git_root),
+            global_ignore_files: table
+                .get::<Option<bool>>("global_ignore_files")?
+                .unwrap_or(defaults.global_ignore_files),
+            max_depth: table.get("max_depth")?,
+            case_insensitive: table
+                .get::<Option<bool>>("case_insensitive")?
+                .unwrap_or(defaults.case_insensitive),
+            categories: table
+                .get::<Option<Vec<CategoryRule>>>("categories")?
+                .unwrap_or(defaults.categories),
+            recent_commits: table
+                .get::<Option<usize>>("recent_commits")?
+                .unwrap_or(defaults.recent_commits),
+            co_change_commits: table
+                .get::<Option<usize>>("co_change_commits")?
+                .unwrap_or(defaults.co_change_commits),
+            throttle: table.get("throttle")?,
+            archive_extensions: table
+                .get::<Option<Vec<String>>>("archive_extensions")?
+                .unwrap_or(defaults.archive_extensions),
+            mtime_fallback: table
+                .get::<Option<bool>>("mtime_fallback")?
+                .unwrap_or(defaults.mtime_fallback),
+            sensitive: table
+                .get::<Option<SensitiveOptions>>("sensitive")?
+                .unwrap_or(defaults.sensitive),
+        })
+    }
+}
+
+impl FromLua for SensitiveOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "SensitiveOptions")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            mode: table
+                .get::<Option<SensitiveMode>>("mode")?
+                .unwrap_or(defaults.mode),
+            patterns: table
+                .get::<Option<Vec<String>>>("patterns")?
+                .unwrap_or(defaults.patterns),
+            check_ownership: table
+                .get::<Option<bool>>("check_ownership")?
+                .unwrap_or(defaults.check_ownership),
+        })
+    }
+}
+
+impl FromLua for ScanThrottle {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "ScanThrottle")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            threads: table
+                .get::<Option<usize>>("threads")?
+                .unwrap_or(defaults.threads),
+            files_per_second: table
+                .get::<Option<usize>>("files_per_second")?
+                .unwrap_or(defaults.files_per_second),
+        })
+    }
+}
+
+impl FromLua for ScoringWeights {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "ScoringWeights")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            frecency: table
+                .get::<Option<i32>>("frecency")?
+                .unwrap_or(defaults.frecency),
+            distance: table
+                .get::<Option<i32>>("distance")?
+                .unwrap_or(defaults.distance),
+            depth: table.get::<Option<i32>>("depth")?.unwrap_or(defaults.depth),
+            filename: table
+                .get::<Option<i32>>("filename")?
+                .unwrap_or(defaults.filename),
+            path_component: table
+                .get::<Option<i32>>("path_component")?
+                .unwrap_or(defaults.path_component),
+            diagnostics: table
+                .get::<Option<i32>>("diagnostics")?
+                .unwrap_or(defaults.diagnostics),
+            package_scope: table
+                .get::<Option<PackageScope>>("package_scope")?
+                .unwrap_or(defaults.package_scope),
+            word_order: table
+                .get::<Option<i32>>("word_order")?
+                .unwrap_or(defaults.word_order),
+            session: table
+                .get::<Option<i32>>("session")?
+                .unwrap_or(defaults.session),
+            time_of_day: table
+                .get::<Option<i32>>("time_of_day")?
+                .unwrap_or(defaults.time_of_day),
+            normalize_scores: table
+                .get::<Option<bool>>("normalize_scores")?
+                .unwrap_or(defaults.normalize_scores),
+            tie_breaks: table
+                .get::<Option<Vec<TieBreak>>>("tie_breaks")?
+                .map_or(defaults.tie_breaks, TieBreaks::new),
+        })
+    }
+}
+
+impl FromLua for PackageScope {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let scope = match &value {
+            LuaValue::String(value) => value.to_str()?.to_string(),
+            _ => String::new(),
+        };
+
+        match scope.as_str() {
+            "off" => Ok(Self::Off),
+            "boost" => Ok(Self::Boost),
+            "restrict" => Ok(Self::Restrict),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "PackageScope".to_string(),
+                message: Some("expected one of 'off', 'boost' or 'restrict'".to_string()),
+            }),
+        }
+    }
+}
+
+impl FromLua for SensitiveMode {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let mode = match &value {
+            LuaValue::String(value) => value.to_str()?.to_string(),
+            _ => String::new(),
+        };
+
+        match mode.as_str() {
+            "off" => Ok(Self::Off),
+            "flag" => Ok(Self::Flag),
+            "hide_preview" => Ok(Self::HidePreview),
+            "exclude" => Ok(Self::Exclude),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "SensitiveMode".to_string(),
+                message: Some(
+                    "expected one of 'off', 'flag', 'hide_preview' or 'exclude'".to_string(),
+                ),
+            }),
+        }
+    }
+}
+
+impl FromLua for TieBreak {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let tie_break = match &value {
+            LuaValue::String(value) => value.to_str()?.to_string(),
+            _ => String::new(),
+        };
+
+        match tie_break.as_str() {
+            "frecency" => Ok(Self::Frecency),
+            "modified" => Ok(Self::Modified),
+            "path_length" => Ok(Self::PathLength),
+            "alphabetical" => Ok(Self::Alphabetical),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "TieBreak".to_string(),
+                message: Some(
+                    "expected one of 'frecency', 'modified', 'path_length' or 'alphabetical'"
+                        .to_string(),
+                ),
+            }),
+        }
+    }
+}
+
+impl FromLua for SearchOptions {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let options: Self = match value {
+            LuaValue::Nil => Self::default(),
+            value => lua.from_value(value)?,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+impl FromLua for SearchContext {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            value => lua.from_value(value),
+        }
+    }
+}
+
+impl FromLua for FileListOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let Some(table) = expect_table(value, "FileListOptions")? else {
+            return Ok(Self::default());
+        };
+
+        let sort_by = match table.get::<Option<String>>("sort_by")? {
+            Some(sort_by) => {
+                FileListSort::parse(&sort_by).ok_or_else(|| LuaError::FromLuaConversionError {
+                    from: "string",
+                    to: "FileListSort".to_string(),
+                    message: Some(
+                        "expected one of 'path', 'frecency', 'modified', 'size' or 'git_status'"
+                            .to_string(),
+                    ),
+                })?
+            }
+            None => FileListSort::default(),
+        };
+
+        Ok(Self {
+            offset: table.get::<Option<usize>>("offset")?.unwrap_or_default(),
+            limit: table.get("limit")?,
+            sort_by,
+            prefix: table.get("prefix")?,
+        })
+    }
+}
+
+impl FromLua for GrepOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "GrepOptions")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            before_context: table
+                .get::<Option<usize>>("before_context")?
+                .unwrap_or(defaults.before_context),
+            after_context: table
+                .get::<Option<usize>>("after_context")?
+                .unwrap_or(defaults.after_context),
+            max_matches_per_file: table
+                .get::<Option<usize>>("max_matches_per_file")?
+                .unwrap_or(defaults.max_matches_per_file),
+            max_matches: table
+                .get::<Option<usize>>("max_matches")?
+                .unwrap_or(defaults.max_matches),
+            max_file_size: table
+                .get::<Option<u64>>("max_file_size")?
+                .unwrap_or(defaults.max_file_size),
+            skip_sensitive: defaults.skip_sensitive,
+        })
+    }
+}
+
+/// Matches are quickfix items, `vim.fn.setqflist(result.matches)` lists them as they are
+impl IntoLua for GrepMatch {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("filename", self.path.to_string_lossy())?;
+        table.set("relative_path", self.relative_path)?;
+        table.set("lnum", self.lnum)?;
+        table.set("col", self.col)?;
+        table.set("end_col", self.end_col)?;
+        table.set("text", self.text)?;
+        table.set("before", self.before)?;
+        table.set("after", self.after)?;
+        table.set("encoding", self.encoding.as_str())?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for GrepResult {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("matches", self.matches)?;
+        table.set("matched_files", self.matched_files)?;
+        table.set("searched_files", self.searched_files)?;
+        table.set("truncated", self.truncated)?;
+        table.set("skipped_files", self.skipped_files)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for ReplaceHunk {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("id", self.id)?;
+        table.set("lnum", self.lnum)?;
+        table.set("old_text", self.old_text)?;
+        table.set("new_text", self.new_text)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for FileReplacement {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("filename", self.path.to_string_lossy())?;
+        table.set("relative_path", self.relative_path)?;
+        table.set("encoding", self.encoding.as_str())?;
+        table.set("hunks", self.hunks)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for ReplacePlan {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("hunk_count", self.hunk_count())?;
+        table.set("pattern", self.pattern)?;
+        table.set("replacement", self.replacement)?;
+        table.set("files", self.files)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for ReplaceOutcome {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        let written: Vec<String> = self
+            .written
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        table.set("written", written)?;
+        table.set("applied_hunks", self.applied_hunks)?;
+        table.set("conflicts", self.conflicts)?;
+        table.set("failed", self.failed)?;
+        table.set("error", self.error)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for WordHit {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("filename", self.path.to_string_lossy())?;
+        table.set("relative_path", self.relative_path)?;
+        table.set("score", self.score)?;
+        table.set("name_match", self.name_match)?;
+        table.set("content_matches", self.content_matches)?;
+        // quickfix items need a line, files only matched by name open at the top
+        table.set("lnum", self.lnum.max(1))?;
+        table.set("col", self.col.max(1))?;
+        table.set("text", self.text)?;
+        table.set("co_changed", self.co_changed)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl FromLua for ListedFile {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        if let LuaValue::String(path) = value {
+            return Ok(Self {
+                path: path.to_str()?.to_string().into(),
+                ..Default::default()
+            });
+        }
+
+        let Some(table) = expect_table(value, "ListedFile")? else {
+            return Err(LuaError::FromLuaConversionError {
+                from: "nil",
+                to: "ListedFile".to_string(),
+                message: Some("expected a path or a table with a path".to_string()),
+            });
+        };
+
+        Ok(Self {
+            path: String::from_lua(table.get("path")?, lua)?.into(),
+            size: table.get::<Option<u64>>("size")?.unwrap_or_default(),
+            modified: table.get::<Option<u64>>("modified")?.unwrap_or_default(),
+        })
+    }
+}
+
+impl FromLua for DiagnosticsCount {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let Some(table) = expect_table(value, "DiagnosticsCount")? else {
+            return Ok(Self::default());
+        };
+
+        Ok(Self {
+            errors: table.get::<Option<u16>>("errors")?.unwrap_or_default(),
+            warnings: table.get::<Option<u16>>("warnings")?.unwrap_or_default(),
+        })
+    }
+}
+
+impl FromLua for WatcherOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "WatcherOptions")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            debounce_ms: table
+                .get::<Option<u64>>("debounce_ms")?
+                .unwrap_or(defaults.debounce_ms),
+            backend: table
+                .get::<Option<WatcherBackend>>("backend")?
+                .unwrap_or(defaults.backend),
+            poll_interval_ms: table
+                .get::<Option<u64>>("poll_interval_ms")?
+                .unwrap_or(defaults.poll_interval_ms),
+        })
+    }
+}
+
+impl FromLua for WatcherBackend {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let backend = match &value {
+            LuaValue::String(value) => value.to_str()?.to_string(),
+            _ => String::new(),
+        };
+
+        match backend.as_str() {
+            "native" => Ok(Self::Native),
+            "poll" => Ok(Self::Poll),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "WatcherBackend".to_string(),
+                message: Some("expected 'native' or 'poll'".to_string()),
+            }),
+        }
+    }
+}
+
+impl FromLua for MemoryOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let Some(table) = expect_table(value, "MemoryOptions")? else {
+            return Ok(Self::default());
+        };
+
+        Ok(Self {
+            budget_mb: table.get("budget_mb")?,
+        })
+    }
+}
+
+impl FromLua for FrecencyOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "FrecencyOptions")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            half_life_days: table
+                .get::<Option<f64>>("half_life_days")?
+                .unwrap_or(defaults.half_life_days),
+            max_history_days: table
+                .get::<Option<f64>>("max_history_days")?
+                .unwrap_or(defaults.max_history_days),
+            session_gap_minutes: table
+                .get::<Option<f64>>("session_gap_minutes")?
+                .unwrap_or(defaults.session_gap_minutes),
+            access_soft_cap: table
+                .get::<Option<f64>>("access_soft_cap")?
+                .unwrap_or(defaults.access_soft_cap),
+            access_curve: table
+                .get::<Option<f64>>("access_curve")?
+                .unwrap_or(defaults.access_curve),
+            max_score: table
+                .get::<Option<i64>>("max_score")?
+                .unwrap_or(defaults.max_score),
+            forget_projects_after_days: table
+                .get::<Option<f64>>("forget_projects_after_days")?
+                .unwrap_or(defaults.forget_projects_after_days),
+        })
+    }
+}
+
+impl FromLua for ConfigUpdate {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let Some(table) = expect_table(value, "ConfigUpdate")? else {
+            return Ok(Self::default());
+        };
+
+        Ok(Self {
+            scan: table.get("scan")?,
+            scoring: table.get("scoring")?,
+            watcher: table.get("watcher")?,
+            frecency: table.get("frecency")?,
+            memory: table.get("memory")?,
+            profiles: table.get("profiles")?,
+            redaction: table.get("redaction")?,
+        })
+    }
+}
+
+impl FromLua for RedactionOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let Some(table) = expect_table(value, "RedactionOptions")? else {
+            return Ok(defaults);
+        };
+
+        Ok(Self {
+            enabled: table
+                .get::<Option<bool>>("enabled")?
+                .unwrap_or(defaults.enabled),
+            patterns: table
+                .get::<Option<Vec<String>>>("patterns")?
+                .unwrap_or(defaults.patterns),
+            mask: table
+                .get::<Option<String>>("mask")?
+                .and_then(|mask| mask.chars().next())
+                .unwrap_or(defaults.mask),
+        })
+    }
+}
+
+impl FromLua for SearchProfile {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let Some(table) = expect_table(value, "SearchProfile")? else {
+            return Ok(Self::default());
+        };
+
+        Ok(Self {
+            extensions: table
+                .get::<Option<Vec<String>>>("extensions")?
+                .unwrap_or_default(),
+            ignore: table
+                .get::<Option<Vec<String>>>("ignore")?
+                .unwrap_or_default(),
+            scoring: table.get("scoring")?,
+        })
+    }
+}
+
+impl IntoLua for &ListEntry {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("path", self.path.as_str())?;
+        table.set("lnum", self.lnum)?;
+        table.set("col", self.col)?;
+        table.set("text", self.text.as_str())?;
+        table.set("access_frecency_score", self.access_frecency_score)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl FromLua for ListEntry {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let Some(table) = expect_table(value, "ListEntry")? else {
+            return Err(LuaError::FromLuaConversionError {
+                from: "nil",
+                to: "ListEntry".to_string(),
+                message: Some("expected a table".to_string()),
+            });
+        };
+
+        Ok(Self::new(
+            table.get("path")?,
+            table.get::<Option<u32>>("lnum")?.unwrap_or_default(),
+            table.get::<Option<u32>>("col")?.unwrap_or_default(),
+            table.get::<Option<String>>("text")?.unwrap_or_default(),
+        ))
+    }
+}
+
+impl IntoLua for ListSearchResult<'_> {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("items", self.items)?;
+        table.set("scores", self.scores)?;
+        table.set("total_matched", self.total_matched)?;
+        table.set("total_entries", self.total_entries)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for IndexMetrics {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("files", self.files)?;
+        table.set("index_bytes", self.index_bytes)?;
+        table.set("meta_bytes", self.meta_bytes)?;
+        table.set("budget_bytes", self.budget_bytes)?;
+        table.set("over_budget", self.is_over_budget())?;
+        table.set("dropped_meta", self.dropped_meta)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl IntoLua for IndexDrift {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("sampled", self.sampled)?;
+        table.set("missing", self.missing)?;
+        table.set("stale", self.stale)?;
+        table.set("percent", self.percent())?;
+        table.set("rescan_triggered", self.rescan_triggered)?;
+        Ok(LuaValue::Table(table))
+    }
+}
@@ -0,0 +1,61 @@
+//! Rough accounting of the index memory used to enforce the soft memory budget.
+//!
+//! The estimates count the heap allocations owned by every item, they are meant to be
+//! proportional to the real usage rather than exact.
+
+use crate::types::{FileItem, FileMeta, MetaValue};
+use std::mem::size_of;
+
+/// Per entry overhead of a `BTreeMap` node split between its entries
+const BTREE_ENTRY_OVERHEAD: usize = 2 * size_of::<usize>();
+
+/// Snapshot of the index size reported by `get_metrics`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexMetrics {
+    pub files: usize,
+    /// Estimated bytes of the whole index including the metadata
+    pub index_bytes: usize,
+    /// Estimated bytes of the metadata attached with `set_file_meta`
+    pub meta_bytes: usize,
+    pub budget_bytes: Option<usize>,
+    /// Files whose metadata was dropped to get back under the budget since the picker started
+    pub dropped_meta: usize,
+}
+
+impl IndexMetrics {
+    pub fn is_over_budget(&self) -> bool {
+        self.budget_bytes
+            .is_some_and(|budget| self.index_bytes > budget)
+    }
+}
+
+pub fn file_item_bytes(file: &FileItem) -> usize {
+    size_of::<FileItem>()
+        + file.path.as_os_str().len()
+        + file.relative_path.capacity()
+        + file.file_name.capacity()
+        + file.meta.as_deref().map_or(0, file_meta_bytes)
+}
+
+pub fn file_meta_bytes(meta: &FileMeta) -> usize {
+    size_of::<FileMeta>()
+        + meta
+            .iter()
+            .map(|(key, value)| {
+                size_of::<String>()
+                    + key.capacity()
+                    + BTREE_ENTRY_OVERHEAD
+                    + meta_value_bytes(value)
+            })
+            .sum::<usize>()
+}
+
+fn meta_value_bytes(value: &MetaValue) -> usize {
+    size_of::<MetaValue>()
+        + match value {
+            MetaValue::Bool(_) | MetaValue::Integer(_) | MetaValue::Number(_) => 0,
+            MetaValue::String(value) => value.capacity(),
+            MetaValue::Array(values) => values.iter().map(meta_value_bytes).sum(),
+            MetaValue::Map(map) => file_meta_bytes(map),
+        }
+}
@@ -0,0 +1,88 @@
+//! Monorepo package boundaries, a package is any directory holding one of the manifests.
+
+use crate::types::FileItem;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const PACKAGE_MANIFESTS: [&str; 3] = ["package.json", "Cargo.toml", "go.mod"];
+
+/// Package root directories relative to the base path, the base path itself is `""`
+#[derive(Debug, Clone, Default)]
+pub struct PackageRoots {
+    roots: HashMap<String, Arc<str>>,
+}
+
+impl PackageRoots {
+    pub fn from_files(files: &[FileItem]) -> Self {
+        let roots = files
+            .iter()
+            .filter(|file| PACKAGE_MANIFESTS.contains(&file.file_name.as_str()))
+            .filter_map(|file| Path::new(&file.relative_path).parent()?.to_str())
+            .map(|dir| (dir.to_string(), Arc::from(dir)))
+            .collect();
+
+        Self { roots }
+    }
+
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// The nearest package root containing the file
+    pub fn package_of(&self, relative_path: &str) -> Option<Arc<str>> {
+        if self.roots.is_empty() {
+            return None;
+        }
+
+        Path::new(relative_path)
+            .ancestors()
+            .skip(1)
+            .filter_map(Path::to_str)
+            .find_map(|dir| self.roots.get(dir).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn file(relative_path: &str) -> FileItem {
+        FileItem::new(PathBuf::from(relative_path), Path::new(""), None)
+    }
+
+    #[test]
+    fn test_nearest_package() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let files = [
+            file("Cargo.toml"),
+            file(&format!("crates{sep}core{sep}Cargo.toml")),
+            file(&format!("web{sep}package.json")),
+        ];
+        let roots = PackageRoots::from_files(&files);
+
+        let core_file = format!("crates{sep}core{sep}src{sep}lib.rs");
+        let core_package = format!("crates{sep}core");
+        assert_eq!(
+            roots.package_of(&core_file).as_deref(),
+            Some(core_package.as_str())
+        );
+        assert_eq!(
+            roots
+                .package_of(&format!("web{sep}src{sep}app.ts"))
+                .as_deref(),
+            Some("web")
+        );
+        assert_eq!(
+            roots
+                .package_of(&format!("scripts{sep}build.sh"))
+                .as_deref(),
+            Some("")
+        );
+    }
+}
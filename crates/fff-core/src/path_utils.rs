@@ -0,0 +1,491 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf, Prefix};
+
+/// Strips the verbatim `\\?\` prefix windows adds to extended length paths (returned by
+/// `canonicalize` and some `notify` backends) and resolves relative or drive relative paths
+/// against `base_path`, so every path compares equal to the ones produced by the walker.
+pub fn normalize_path<'a>(path: &'a Path, base_path: &Path) -> Cow<'a, Path> {
+    let mut components = path.components();
+    let normalized_prefix = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::VerbatimDisk(disk) => Some(PathBuf::from(format!("{}:", disk as char))),
+            Prefix::VerbatimUNC(server, share) => {
+                let mut unc = std::ffi::OsString::from(r"\\");
+                unc.push(server);
+                unc.push(r"\");
+                unc.push(share);
+                Some(PathBuf::from(unc))
+            }
+            Prefix::Verbatim(root) => Some(PathBuf::from(root)),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    if let Some(mut normalized) = normalized_prefix {
+        normalized.extend(components);
+        return Cow::Owned(normalized);
+    }
+
+    if path.is_absolute() {
+        Cow::Borrowed(path)
+    } else {
+        // covers both plain relative and drive relative (`C:foo`) event paths
+        let relative = path
+            .components()
+            .filter(|component| !matches!(component, Component::Prefix(_)));
+        Cow::Owned(base_path.join(relative.collect::<PathBuf>()))
+    }
+}
+
+/// `std::fs::canonicalize` that doesn't produce verbatim paths on windows
+pub fn canonicalize(path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+    let canonical = std::fs::canonicalize(path)?;
+    Ok(normalize_path(&canonical, Path::new("")).into_owned())
+}
+
+/// Expands a leading `~` and `$VAR` or `${VAR}` references, unset variables are kept as written
+pub fn expand_path(path: &str) -> PathBuf {
+    let home = || std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"));
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path;
+    if let Some(after_tilde) = path.strip_prefix('~') {
+        if after_tilde.is_empty() || after_tilde.starts_with(['/', '\\']) {
+            if let Ok(home) = home() {
+                expanded.push_str(&home);
+                rest = after_tilde;
+            }
+        }
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after_dollar = &rest[dollar + 1..];
+        let (name, reference_len) = match after_dollar.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after_dollar
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after_dollar.len());
+                (&after_dollar[..end], end)
+            }
+        };
+
+        match std::env::var(name).ok().filter(|_| !name.is_empty()) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[dollar..dollar + 1 + reference_len]),
+        }
+        rest = &after_dollar[reference_len..];
+    }
+    expanded.push_str(rest);
+
+    PathBuf::from(expanded)
+}
+
+/// Resolves a user given path to the canonical path used as the index and frecency key:
+/// expands `~` and variables, then resolves relative segments and symlinks
+pub fn resolve_path(path: &str) -> std::io::Result<PathBuf> {
+    canonicalize(expand_path(path))
+}
+
+/// Ordering used for the sorted file index and git status lookups. With `case_insensitive`
+/// the paths are compared component by component on their lowercased names.
+pub fn cmp_paths(a: &Path, b: &Path, case_insensitive: bool) -> Ordering {
+    if !case_insensitive {
+        return a.cmp(b);
+    }
+
+    let mut a_components = a.components();
+    let mut b_components = b.components();
+    loop {
+        match (a_components.next(), b_components.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match cmp_ignore_case(a.as_os_str(), b.as_os_str()) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
+        }
+    }
+}
+
+fn cmp_ignore_case(a: &OsStr, b: &OsStr) -> Ordering {
+    match (a.to_str(), b.to_str()) {
+        (Some(a), Some(b)) => a
+            .chars()
+            .flat_map(char::to_lowercase)
+            .cmp(b.chars().flat_map(char::to_lowercase)),
+        _ => a.cmp(b),
+    }
+}
+
+#[inline]
+pub fn is_git_file(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == ".git")
+}
+
+pub fn calculate_distance_penalty(current_file: Option<&str>, candidate_path: &str) -> i32 {
+    let Some(ref current_path) = current_file else {
+        return 0; // No penalty if no current file
+    };
+
+    let current_dir = if let Some(parent) = std::path::Path::new(current_path).parent() {
+        parent.to_string_lossy().to_string()
+    } else {
+        String::new()
+    };
+
+    let candidate_dir = if let Some(parent) = std::path::Path::new(candidate_path).parent() {
+        parent.to_string_lossy().to_string()
+    } else {
+        String::new()
+    };
+
+    if current_dir == candidate_dir {
+        return 0; // Same directory, no penalty
+    }
+
+    let current_parts: Vec<&str> = current_dir
+        .split(std::path::MAIN_SEPARATOR)
+        .filter(|s| !s.is_empty())
+        .collect();
+    let candidate_parts: Vec<&str> = candidate_dir
+        .split(std::path::MAIN_SEPARATOR)
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let common_len = current_parts
+        .iter()
+        .zip(candidate_parts.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let current_depth_from_common = current_parts.len() - common_len;
+    let candidate_depth_from_common = candidate_parts.len() - common_len;
+    let total_distance = current_depth_from_common + candidate_depth_from_common;
+
+    if total_distance == 0 {
+        return 0; // Same path
+    }
+
+    let penalty = -(total_distance as i32 * 2);
+
+    penalty.max(-20)
+}
+
+/// Small penalty for every directory a file is nested in, so between otherwise equal matches
+/// `src/app.ts` wins over `src/legacy/old/backup/app.ts`
+pub fn calculate_depth_penalty(relative_path: &str) -> i32 {
+    let depth = relative_path
+        .matches(std::path::MAIN_SEPARATOR)
+        .count()
+        .min(10) as i32;
+
+    -depth
+}
+
+/// Checks that every term occurs in the path after the previous one, ignoring case. Used to
+/// reward `dir file` style queries whose terms follow the path from left to right.
+pub fn contains_terms_in_order(path: &str, terms: &[&str]) -> bool {
+    let path = path.to_lowercase();
+    let mut position = 0;
+    for term in terms {
+        let term = term.to_lowercase();
+        match path[position..].find(&term) {
+            Some(index) => position += index + term.len(),
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// Splits a fish-style directory abbreviation like `s/f/p` into its segments. Any query made
+/// of at least two non empty segments qualifies, `src/main` is just a very long abbreviation.
+pub fn parse_dir_abbreviation(query: &str) -> Option<Vec<&str>> {
+    if query.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let segments: Vec<&str> = query.split(['/', std::path::MAIN_SEPARATOR]).collect();
+    if segments.len() < 2 || segments.iter().any(|segment| segment.is_empty()) {
+        return None;
+    }
+
+    Some(segments)
+}
+
+/// Checks that the segments are prefixes of consecutive components of the path, ignoring
+/// case. The last segment may also match the file name.
+pub fn matches_dir_abbreviation(path: &str, segments: &[&str]) -> bool {
+    let components: Vec<&str> = path.split(['/', std::path::MAIN_SEPARATOR]).collect();
+    components.windows(segments.len()).any(|window| {
+        window.iter().zip(segments).all(|(component, segment)| {
+            component
+                .get(..segment.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(segment))
+        })
+    })
+}
+
+/// Returns how much of the match landed in the last two path components on a `0..=100` scale.
+/// Matched characters in the file name count twice as much as the ones in its parent directory,
+/// while matches in any other ancestor directory do not count at all.
+pub fn calculate_tail_match_ratio(path: &str, matched_indices: &[usize]) -> i32 {
+    if matched_indices.is_empty() {
+        return 0;
+    }
+
+    let file_name_start = path
+        .rfind(std::path::MAIN_SEPARATOR)
+        .map_or(0, |index| index + 1);
+    let parent_dir_start = path[..file_name_start.saturating_sub(1)]
+        .rfind(std::path::MAIN_SEPARATOR)
+        .map_or(0, |index| index + 1);
+
+    let weight: usize = matched_indices
+        .iter()
+        .map(|&index| {
+            if index >= file_name_start {
+                2
+            } else if index >= parent_dir_start {
+                1
+            } else {
+                0
+            }
+        })
+        .sum();
+
+    (weight * 100 / (matched_indices.len() * 2)) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_path() {
+        std::env::set_var("FFF_TEST_EXPAND", "/tmp/fff");
+        let home = std::env::var("HOME").unwrap();
+
+        assert_eq!(expand_path("~"), PathBuf::from(&home));
+        assert_eq!(expand_path("~/src"), PathBuf::from(format!("{home}/src")));
+        assert_eq!(expand_path("~user/src"), PathBuf::from("~user/src"));
+        assert_eq!(
+            expand_path("$FFF_TEST_EXPAND/a/${FFF_TEST_EXPAND}"),
+            PathBuf::from("/tmp/fff/a//tmp/fff")
+        );
+        assert_eq!(
+            expand_path("$FFF_TEST_UNSET/a$/${b"),
+            PathBuf::from("$FFF_TEST_UNSET/a$/${b")
+        );
+    }
+
+    #[test]
+    fn test_calculate_distance_penalty() {
+        {
+            let other_path = Path::new("path").join("to").join("file.txt");
+            assert_eq!(
+                calculate_distance_penalty(None, other_path.to_str().unwrap()),
+                0
+            );
+        }
+        {
+            let base_path = Path::new("path").join("to").join("current");
+            let current_path = base_path.join("file.txt");
+            let other_path = base_path.join("other.txt");
+            assert_eq!(
+                calculate_distance_penalty(
+                    Some(current_path.to_str().unwrap()),
+                    other_path.to_str().unwrap()
+                ),
+                0
+            );
+        }
+        {
+            let base_path = Path::new("path").join("to");
+            let current_path = base_path.join("current").join("file.txt");
+            let other_path = base_path.join("file.txt");
+            assert_eq!(
+                calculate_distance_penalty(
+                    Some(current_path.to_str().unwrap()),
+                    other_path.to_str().unwrap()
+                ),
+                -2
+            );
+        }
+        {
+            let base_path = Path::new("path").join("to");
+            let current_path = base_path.join("current").join("file.txt");
+            let other_path = base_path.join("other").join("file.txt");
+            assert_eq!(
+                calculate_distance_penalty(
+                    Some(current_path.to_str().unwrap()),
+                    other_path.to_str().unwrap()
+                ),
+                -4
+            );
+        }
+        {
+            let base_path = Path::new("path").join("to");
+            let current_path = base_path.join("current").join("file.txt");
+            let other_path = base_path.join("another").join("dir").join("file.txt");
+            assert_eq!(
+                calculate_distance_penalty(
+                    Some(current_path.to_str().unwrap()),
+                    other_path.to_str().unwrap()
+                ),
+                -6
+            );
+        }
+        {
+            let current_path = Path::new("a")
+                .join("b")
+                .join("c")
+                .join("d")
+                .join("file.txt");
+            let other_path = Path::new("x")
+                .join("y")
+                .join("z")
+                .join("w")
+                .join("file.txt");
+            assert_eq!(
+                calculate_distance_penalty(
+                    Some(current_path.to_str().unwrap()),
+                    other_path.to_str().unwrap()
+                ),
+                -16
+            );
+        }
+        {
+            let current_path = Path::new("file1.txt").to_str().unwrap();
+            let other_path = Path::new("file2.txt").to_str().unwrap();
+            assert_eq!(
+                calculate_distance_penalty(Some(current_path), other_path),
+                0
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        let base_path = std::env::temp_dir();
+        let absolute = base_path.join("src").join("main.rs");
+        assert!(matches!(
+            normalize_path(&absolute, &base_path),
+            Cow::Borrowed(path) if path == absolute
+        ));
+
+        let relative = Path::new("src").join("main.rs");
+        assert_eq!(normalize_path(&relative, &base_path), absolute);
+
+        assert!(is_git_file(&base_path.join(".git").join("HEAD")));
+        assert!(!is_git_file(&base_path.join(".github").join("ci.yml")));
+    }
+
+    #[test]
+    fn test_cmp_paths_case_insensitive() {
+        let walked = Path::new("src").join("foo.txt");
+        let reported = Path::new("src").join("Foo.txt");
+        assert_ne!(cmp_paths(&walked, &reported, false), Ordering::Equal);
+        assert_eq!(cmp_paths(&walked, &reported, true), Ordering::Equal);
+
+        // sorting must stay consistent with the binary search comparator
+        let mut paths = [
+            Path::new("b").join("a.rs"),
+            Path::new("A").join("z.rs"),
+            Path::new("a").join("b.rs"),
+        ];
+        paths.sort_by(|a, b| cmp_paths(a, b, true));
+        let needle = Path::new("A").join("B.rs");
+        assert_eq!(
+            paths.binary_search_by(|p| cmp_paths(p, &needle, true)),
+            Ok(0)
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_normalize_verbatim_path() {
+        let base_path = Path::new(r"C:\project");
+        assert_eq!(
+            normalize_path(Path::new(r"\\?\C:\project\src\main.rs"), base_path),
+            Path::new(r"C:\project\src\main.rs")
+        );
+        assert_eq!(
+            normalize_path(Path::new(r"\\?\UNC\server\share\main.rs"), base_path),
+            Path::new(r"\\server\share\main.rs")
+        );
+    }
+
+    #[test]
+    fn test_calculate_tail_match_ratio() {
+        let path = Path::new("models").join("user.rs");
+        let path = path.to_str().unwrap();
+        assert_eq!(calculate_tail_match_ratio(path, &[7, 8, 9, 10]), 100);
+        assert_eq!(calculate_tail_match_ratio(path, &[0, 1]), 50);
+        assert_eq!(calculate_tail_match_ratio(path, &[]), 0);
+
+        // "user" matched in the top level directory and ".rs" in the file name
+        let path = Path::new("user").join("legacy").join("old.rs");
+        let path = path.to_str().unwrap();
+        assert_eq!(
+            calculate_tail_match_ratio(path, &[0, 1, 2, 3, 15, 16, 17]),
+            42
+        );
+
+        assert_eq!(calculate_tail_match_ratio("user.rs", &[0, 1, 2, 3]), 100);
+    }
+
+    #[test]
+    fn test_calculate_depth_penalty() {
+        assert_eq!(calculate_depth_penalty("app.ts"), 0);
+
+        let shallow = Path::new("src").join("app.ts");
+        let deep = Path::new("src")
+            .join("legacy")
+            .join("old")
+            .join("backup")
+            .join("app.ts");
+        assert_eq!(calculate_depth_penalty(shallow.to_str().unwrap()), -1);
+        assert_eq!(calculate_depth_penalty(deep.to_str().unwrap()), -4);
+
+        let very_deep = "a/".repeat(20).replace('/', std::path::MAIN_SEPARATOR_STR) + "file.rs";
+        assert_eq!(calculate_depth_penalty(&very_deep), -10);
+    }
+
+    #[test]
+    fn test_contains_terms_in_order() {
+        let path = Path::new("src").join("Components").join("button.tsx");
+        let path = path.to_str().unwrap();
+        assert!(contains_terms_in_order(path, &["comp", "butt"]));
+        assert!(!contains_terms_in_order(path, &["butt", "comp"]));
+        assert!(!contains_terms_in_order(path, &["comp", "missing"]));
+        assert!(contains_terms_in_order(path, &[]));
+    }
+
+    #[test]
+    fn test_dir_abbreviation() {
+        assert_eq!(parse_dir_abbreviation("s/f/p"), Some(vec!["s", "f", "p"]));
+        assert_eq!(parse_dir_abbreviation("main"), None);
+        assert_eq!(parse_dir_abbreviation("s//p"), None);
+        assert_eq!(parse_dir_abbreviation("s/f p"), None);
+
+        let path = Path::new("lua")
+            .join("fff")
+            .join("file_picker")
+            .join("init.lua");
+        let path = path.to_str().unwrap();
+        assert!(matches_dir_abbreviation(path, &["l", "f", "f"]));
+        assert!(matches_dir_abbreviation(path, &["fff", "File", "in"]));
+        assert!(!matches_dir_abbreviation(path, &["l", "f", "x"]));
+        assert!(!matches_dir_abbreviation(path, &["l", "file_picker"]));
+    }
+}
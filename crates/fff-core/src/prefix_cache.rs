@@ -0,0 +1,130 @@
+//! Files that can still match a query extending a short prefix. A query character missing
+//! from a path always costs a typo, so a path missing more characters of the prefix than the
+//! typo budget allows can't match any query starting with it. The candidates of the prefix are
+//! computed on the keystroke that types it and every following keystroke only matches them.
+
+use crate::types::FileItem;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Length of the cached prefixes in bytes. Shorter prefixes don't rule anything out, every
+/// search allows at least two typos.
+const PREFIX_LEN: usize = 3;
+/// Typos allowed to queries shorter than 12 characters, longer queries skip the cache
+const CANDIDATE_TYPOS: u16 = 2;
+/// The cache starts over once it holds this many prefixes
+const MAX_CACHED_PREFIXES: usize = 64;
+
+#[derive(Debug, Default)]
+pub struct PrefixCache {
+    /// Index generation, start and length of the searched files the candidates index into
+    key: Option<(u64, usize, usize)>,
+    /// Indices of the candidate files per lowercase prefix
+    candidates: HashMap<[u8; PREFIX_LEN], Arc<[u32]>>,
+}
+
+/// The cache of a picker together with the index generation of its files
+#[derive(Debug, Clone, Copy)]
+pub struct PrefixCandidates<'a> {
+    pub cache: &'a Mutex<PrefixCache>,
+    pub generation: u64,
+}
+
+impl PrefixCandidates<'_> {
+    fn cache(&self) -> MutexGuard<'_, PrefixCache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Indices of the files `query` can match with `max_typos`, computed the first time its
+    /// prefix is seen. `None` searches all of them.
+    pub fn lookup(&self, files: &[FileItem], query: &str, max_typos: u16) -> Option<Arc<[u32]>> {
+        if max_typos > CANDIDATE_TYPOS {
+            return None;
+        }
+        let prefix: [u8; PREFIX_LEN] = query.as_bytes().get(..PREFIX_LEN)?.try_into().ok()?;
+        let prefix = prefix.map(|byte| byte.to_ascii_lowercase());
+
+        let key = (self.generation, files.as_ptr() as usize, files.len());
+        let mut cache = self.cache();
+        if cache.key != Some(key) || cache.candidates.len() >= MAX_CACHED_PREFIXES {
+            cache.key = Some(key);
+            cache.candidates.clear();
+        }
+
+        let candidates = cache
+            .candidates
+            .entry(prefix)
+            .or_insert_with(|| candidates(files, &prefix));
+        Some(Arc::clone(candidates))
+    }
+}
+
+fn candidates(files: &[FileItem], prefix: &[u8; PREFIX_LEN]) -> Arc<[u32]> {
+    files
+        .iter()
+        .enumerate()
+        .filter(|(_, file)| missing_chars(prefix, file.relative_path.as_bytes()) <= CANDIDATE_TYPOS)
+        .map(|(index, _)| index as u32)
+        .collect()
+}
+
+/// Characters of the prefix that don't occur in the path at all, ignoring ascii case
+fn missing_chars(prefix: &[u8; PREFIX_LEN], path: &[u8]) -> u16 {
+    prefix
+        .iter()
+        .filter(|&&c| !path.iter().any(|byte| byte.to_ascii_lowercase() == c))
+        .count() as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(paths: &[&str]) -> Vec<FileItem> {
+        paths
+            .iter()
+            .map(|path| FileItem::new(path.into(), "".as_ref(), None))
+            .collect()
+    }
+
+    #[test]
+    fn test_candidates_cover_extended_queries() {
+        let files = files(&[
+            "src/main.rs",
+            "lua/fff/init.lua",
+            "README.md",
+            "xyz/qqq.txt",
+            "docs/Makefile",
+        ]);
+        let cache = Mutex::default();
+        let candidates = PrefixCandidates {
+            cache: &cache,
+            generation: 1,
+        };
+
+        for query in ["mai", "main.rs", "MAKE", "readme", "init"] {
+            let found = candidates.lookup(&files, query, 2).unwrap();
+            let haystack: Vec<&str> = files.iter().map(|f| f.relative_path.as_str()).collect();
+            let options = neo_frizbee::Options {
+                prefilter: true,
+                max_typos: Some(2),
+                sort: false,
+            };
+            for m in neo_frizbee::match_list(query, &haystack, options) {
+                assert!(found.contains(&m.index_in_haystack), "{}", query);
+            }
+        }
+        assert_eq!(&*candidates.lookup(&files, "mai", 2).unwrap(), [0, 1, 2, 4]);
+        assert_eq!(cache.lock().unwrap().candidates.len(), 4);
+
+        assert!(candidates.lookup(&files, "ma", 2).is_none());
+        assert!(candidates.lookup(&files, "main_window.rs", 3).is_none());
+
+        let rebuilt = PrefixCandidates {
+            cache: &cache,
+            generation: 2,
+        };
+        rebuilt.lookup(&files, "mai", 2);
+        assert_eq!(cache.lock().unwrap().candidates.len(), 1);
+    }
+}
@@ -0,0 +1,176 @@
+//! Named search profiles like `code` or `docs` bundling the extensions to keep, globs hidden on
+//! top of the ignore files and their own scoring weights. `profile:<name>` in the query selects
+//! one, so "find source" and "find doc" pickers only differ by the query they start with.
+//! Profiles filter the shared index, switching between them never rescans.
+
+use crate::types::{FileItem, ScoringWeights};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
+
+const PROFILE_FILTER_PREFIX: &str = "profile:";
+
+static PROFILES: Lazy<RwLock<HashMap<String, Arc<ProfileFilter>>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchProfile {
+    /// Extensions of the files kept without the dot, empty keeps every file
+    pub extensions: Vec<String>,
+    /// Globs matched against the relative path of the files hidden from the searches
+    pub ignore: Vec<String>,
+    /// Replace the weights of the picker for the searches of the profile
+    pub scoring: Option<ScoringWeights>,
+}
+
+/// Compiled profile
+#[derive(Debug)]
+pub struct ProfileFilter {
+    profile: SearchProfile,
+    extensions: Vec<String>,
+    ignore: GlobSet,
+}
+
+impl ProfileFilter {
+    /// Invalid globs are logged and skipped like the ones of the category rules
+    pub fn new(name: &str, profile: SearchProfile) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        for glob in &profile.ignore {
+            match Glob::new(glob) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => warn!("Invalid ignore glob {:?} of profile {}: {}", glob, name, e),
+            }
+        }
+        let ignore = builder.build().unwrap_or_else(|e| {
+            warn!(
+                "Failed to build the ignore globs of profile {}: {}",
+                name, e
+            );
+            GlobSet::empty()
+        });
+        let extensions = profile
+            .extensions
+            .iter()
+            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+            .collect();
+
+        Self {
+            profile,
+            extensions,
+            ignore,
+        }
+    }
+
+    pub fn scoring(&self) -> Option<ScoringWeights> {
+        self.profile.scoring
+    }
+
+    pub fn matches(&self, file: &FileItem) -> bool {
+        let has_extension = self.extensions.is_empty()
+            || file
+                .file_name
+                .rsplit_once('.')
+                .is_some_and(|(_, extension)| {
+                    self.extensions
+                        .iter()
+                        .any(|e| e.eq_ignore_ascii_case(extension))
+                });
+
+        has_extension && !self.ignore.is_match(&file.relative_path)
+    }
+
+    /// Splits the `profile:` token out of the query, returning the remaining fuzzy query. The
+    /// last token wins and unknown names are dropped without filtering anything.
+    pub fn parse(query: &str) -> (String, Option<Arc<Self>>) {
+        if !query.contains(PROFILE_FILTER_PREFIX) {
+            return (query.to_string(), None);
+        }
+
+        let profiles = PROFILES.read().unwrap_or_else(|e| e.into_inner());
+        let mut profile = None;
+        let mut rest = Vec::new();
+        for token in query.split_whitespace() {
+            match token.strip_prefix(PROFILE_FILTER_PREFIX) {
+                Some(name) if !name.is_empty() => {
+                    profile = profiles.get(name).cloned();
+                    if profile.is_none() {
+                        debug!("Unknown search profile {}", name);
+                    }
+                }
+                _ => rest.push(token),
+            }
+        }
+
+        (rest.join(" "), profile)
+    }
+}
+
+/// Replaces all the profiles, returns whether any of them changed
+pub fn set_profiles(profiles: HashMap<String, SearchProfile>) -> bool {
+    let mut current = PROFILES.write().unwrap_or_else(|e| e.into_inner());
+    let unchanged = current.len() == profiles.len()
+        && profiles.iter().all(|(name, profile)| {
+            current
+                .get(name)
+                .is_some_and(|filter| filter.profile == *profile)
+        });
+    if unchanged {
+        return false;
+    }
+
+    *current = profiles
+        .into_iter()
+        .map(|(name, profile)| {
+            let filter = Arc::new(ProfileFilter::new(&name, profile));
+            (name, filter)
+        })
+        .collect();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str) -> FileItem {
+        FileItem::new(path.into(), "".as_ref(), None)
+    }
+
+    #[test]
+    fn test_profile_filter() {
+        let filter = ProfileFilter::new(
+            "code",
+            SearchProfile {
+                extensions: vec![".rs".to_string(), "LUA".to_string()],
+                ignore: vec!["vendor/**".to_string(), "[".to_string()],
+                scoring: None,
+            },
+        );
+        assert!(filter.matches(&file("src/main.rs")));
+        assert!(filter.matches(&file("lua/fff/init.lua")));
+        assert!(!filter.matches(&file("README.md")));
+        assert!(!filter.matches(&file("Makefile")));
+        assert!(!filter.matches(&file("vendor/dep/lib.rs")));
+
+        let docs = SearchProfile {
+            extensions: vec!["md".to_string()],
+            ..Default::default()
+        };
+        assert!(set_profiles(HashMap::from([(
+            "docs".to_string(),
+            docs.clone()
+        )])));
+        assert!(!set_profiles(HashMap::from([("docs".to_string(), docs)])));
+
+        let (query, profile) = ProfileFilter::parse("profile:docs guide");
+        assert_eq!(query, "guide");
+        assert!(profile.unwrap().matches(&file("docs/guide.md")));
+
+        let (query, profile) = ProfileFilter::parse("guide profile:nope");
+        assert_eq!(query, "guide");
+        assert!(profile.is_none());
+    }
+}
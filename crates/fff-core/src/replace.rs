@@ -0,0 +1,361 @@
+//! Project wide search and replace in two steps. `prepare` computes the edited lines of every
+//! file without touching the disk, the caller shows them and picks the hunks to keep, `apply`
+//! then writes the picked hunks. A hunk whose line changed since it was prepared is a conflict
+//! and nothing is written at all, the new contents go to temporary files first and are renamed
+//! over the originals once every file was written. Only a failing rename leaves the files
+//! half replaced, the outcome tells which of them were.
+
+use crate::error::Error;
+use crate::glob_filter::GlobFilter;
+use crate::grep::{decode, encode, fold_case, TextEncoding};
+use crate::types::FileItem;
+use memchr::memmem::Finder;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the temporary files written next to the edited ones
+const TEMP_SUFFIX: &str = ".fff-replace";
+
+/// One edited line, positions are 1-based like the quickfix list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaceHunk {
+    /// Unique within the plan, selects the hunk when applying
+    pub id: usize,
+    pub lnum: usize,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileReplacement {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub encoding: TextEncoding,
+    pub hunks: Vec<ReplaceHunk>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplacePlan {
+    pub pattern: String,
+    pub replacement: String,
+    /// In the order of the index
+    pub files: Vec<FileReplacement>,
+}
+
+impl ReplacePlan {
+    pub fn hunk_count(&self) -> usize {
+        self.files.iter().map(|file| file.hunks.len()).sum()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaceOutcome {
+    /// Files written, empty when there were conflicts
+    pub written: Vec<PathBuf>,
+    /// Hunks of the written files
+    pub applied_hunks: usize,
+    /// Relative paths of the files whose hunks no longer match their content
+    pub conflicts: Vec<String>,
+    /// Relative path of the file whose rename failed, the files after it were left as they were
+    pub failed: Option<String>,
+    /// Why `failed` couldn't be replaced
+    pub error: Option<String>,
+}
+
+/// Replaces every occurrence of `pattern` in the text files passing `glob_filter`, ascii case
+/// is only ignored with `ignore_case` so differently cased identifiers are kept. Binary files
+/// and files over `max_file_size` are left alone.
+pub fn prepare(
+    files: &[FileItem],
+    pattern: &str,
+    replacement: &str,
+    glob_filter: Option<&GlobFilter>,
+    max_file_size: u64,
+    ignore_case: bool,
+) -> ReplacePlan {
+    let mut plan = ReplacePlan {
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        files: Vec::new(),
+    };
+    if pattern.is_empty() {
+        return plan;
+    }
+
+    let case_sensitive = !ignore_case;
+    let needle = fold_case(pattern.as_bytes(), case_sensitive);
+    let finder = Finder::new(&needle);
+    plan.files = files
+        .par_iter()
+        .filter(|file| file.size <= max_file_size)
+        .filter(|file| glob_filter.is_none_or(|filter| filter.matches(&file.relative_path)))
+        .filter_map(|file| {
+            let (text, encoding) = decode(std::fs::read(&file.path).ok()?)?;
+            let hunks: Vec<ReplaceHunk> = text
+                .split('\n')
+                .enumerate()
+                .filter_map(|(index, line)| {
+                    let line = line.strip_suffix('\r').unwrap_or(line);
+                    let new_text = replace_line(line, &finder, case_sensitive, replacement)?;
+                    Some(ReplaceHunk {
+                        id: 0,
+                        lnum: index + 1,
+                        old_text: line.to_string(),
+                        new_text,
+                    })
+                })
+                .collect();
+            if hunks.is_empty() {
+                return None;
+            }
+
+            Some(FileReplacement {
+                path: file.path.clone(),
+                relative_path: file.relative_path.clone(),
+                encoding,
+                hunks,
+            })
+        })
+        .collect();
+
+    let hunks = plan.files.iter_mut().flat_map(|file| &mut file.hunks);
+    for (id, hunk) in hunks.enumerate() {
+        hunk.id = id;
+    }
+    plan
+}
+
+/// `line` with every occurrence replaced, `None` if there is none
+fn replace_line(
+    line: &str,
+    finder: &Finder,
+    case_sensitive: bool,
+    replacement: &str,
+) -> Option<String> {
+    let folded = fold_case(line.as_bytes(), case_sensitive);
+    let mut occurrences = finder.find_iter(&folded).peekable();
+    occurrences.peek()?;
+
+    // ascii folding keeps the byte offsets, so they are valid char boundaries of `line`
+    let mut replaced = String::with_capacity(line.len());
+    let mut end = 0;
+    for start in occurrences {
+        // find_iter reports non overlapping occurrences
+        replaced.push_str(&line[end..start]);
+        replaced.push_str(replacement);
+        end = start + finder.needle().len();
+    }
+    replaced.push_str(&line[end..]);
+    Some(replaced)
+}
+
+/// Writes the hunks of `plan` listed in `selection`, all of them without one. Nothing is
+/// written when a hunk conflicts with the current content of its file or a temporary file
+/// can't be written, no temporary file is left behind either way.
+pub fn apply(plan: &ReplacePlan, selection: Option<&[usize]>) -> Result<ReplaceOutcome, Error> {
+    let selection: Option<HashSet<usize>> = selection.map(|ids| ids.iter().copied().collect());
+    let mut outcome = ReplaceOutcome::default();
+    let mut contents = Vec::new();
+    for file in &plan.files {
+        let hunks: Vec<&ReplaceHunk> = file
+            .hunks
+            .iter()
+            .filter(|hunk| selection.as_ref().is_none_or(|ids| ids.contains(&hunk.id)))
+            .collect();
+        if hunks.is_empty() {
+            continue;
+        }
+
+        match edited_content(file, &hunks) {
+            Some(content) => contents.push((file, content, hunks.len())),
+            None => outcome.conflicts.push(file.relative_path.clone()),
+        }
+    }
+    if !outcome.conflicts.is_empty() {
+        return Ok(outcome);
+    }
+
+    let mut temps = Vec::with_capacity(contents.len());
+    for (file, content, hunks) in &contents {
+        // a symlink is replaced through its target, renaming over it would detach it
+        let target = std::fs::canonicalize(&file.path).and_then(|target| {
+            let temp = write_temp(&target, content)?;
+            Ok((target, temp))
+        });
+        match target {
+            Ok((target, temp)) => temps.push((temp, target, *file, *hunks)),
+            Err(e) => {
+                remove_temps(temps.into_iter().map(|(temp, ..)| temp));
+                return Err(Error::WriteFile(file.path.clone(), e));
+            }
+        }
+    }
+
+    let mut temps = temps.into_iter();
+    for (temp, target, file, hunks) in temps.by_ref() {
+        if let Err(e) = std::fs::rename(&temp, &target) {
+            let _ = std::fs::remove_file(temp);
+            outcome.failed = Some(file.relative_path.clone());
+            outcome.error = Some(e.to_string());
+            break;
+        }
+        outcome.written.push(file.path.clone());
+        outcome.applied_hunks += hunks;
+    }
+    remove_temps(temps.map(|(temp, ..)| temp));
+    Ok(outcome)
+}
+
+fn remove_temps(temps: impl Iterator<Item = PathBuf>) {
+    for temp in temps {
+        let _ = std::fs::remove_file(temp);
+    }
+}
+
+/// Current content of the file with the hunks applied, `None` if any of them conflicts
+fn edited_content(file: &FileReplacement, hunks: &[&ReplaceHunk]) -> Option<Vec<u8>> {
+    let content = std::fs::read(&file.path).ok()?;
+    let has_bom = content.starts_with(b"\xEF\xBB\xBF");
+    let (text, encoding) = decode(content)?;
+    if encoding != file.encoding {
+        return None;
+    }
+
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    for hunk in hunks {
+        let line = lines.get_mut(hunk.lnum - 1)?;
+        let (current, line_end) = match line.strip_suffix('\r') {
+            Some(current) => (current, "\r"),
+            None => (line.as_str(), ""),
+        };
+        if current != hunk.old_text {
+            return None;
+        }
+        *line = format!("{}{}", hunk.new_text, line_end);
+    }
+
+    encode(&lines.join("\n"), encoding, has_bom)
+}
+
+/// Writes `content` next to `path` with its permissions, returning the temporary path
+fn write_temp(path: &Path, content: &[u8]) -> std::io::Result<PathBuf> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(TEMP_SUFFIX);
+    let temp = PathBuf::from(temp);
+
+    std::fs::write(&temp, content)?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        if let Err(e) = std::fs::set_permissions(&temp, metadata.permissions()) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+    }
+    Ok(temp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_and_apply() {
+        let dir = std::env::temp_dir().join("fff_test_replace");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.rs"), "let picker = Picker::new();\r\nkeep\n").unwrap();
+        std::fs::write(dir.join("b.txt"), b"picker caf\xE9\n").unwrap();
+        std::fs::write(dir.join("c.rs"), "old picker\n").unwrap();
+        let files: Vec<FileItem> = ["a.rs", "b.txt", "c.rs"]
+            .iter()
+            .map(|name| FileItem::new(dir.join(name), &dir, None))
+            .collect();
+
+        let plan = prepare(&files, "picker", "finder", None, 1024, false);
+        assert_eq!(plan.hunk_count(), 3);
+        assert_eq!(
+            plan.files[0].hunks[0].new_text,
+            "let finder = Picker::new();"
+        );
+        assert_eq!(plan.files[1].encoding, TextEncoding::Latin1);
+        assert_eq!(
+            prepare(&files, "picker", "finder", None, 1024, true).files[0].hunks[0].new_text,
+            "let finder = finder::new();"
+        );
+
+        let globs = GlobFilter::new(&["*.rs".to_string()]).unwrap();
+        assert_eq!(
+            prepare(&files, "picker", "finder", Some(&globs), 1024, false).hunk_count(),
+            2
+        );
+
+        std::fs::write(dir.join("c.rs"), "edited meanwhile\n").unwrap();
+        let outcome = apply(&plan, None).unwrap();
+        assert_eq!(outcome.conflicts, ["c.rs"]);
+        assert!(outcome.written.is_empty());
+
+        let outcome = apply(&plan, Some(&[0, 1])).unwrap();
+        assert_eq!(outcome.applied_hunks, 2);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.rs")).unwrap(),
+            "let finder = Picker::new();\r\nkeep\n"
+        );
+        assert_eq!(
+            std::fs::read(dir.join("b.txt")).unwrap(),
+            b"finder caf\xE9\n"
+        );
+        assert!(!dir.join("a.rs.fff-replace").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_failed_write_leaves_no_temp_files() {
+        let dir = std::env::temp_dir().join("fff_test_replace_failed");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.rs"), "picker\n").unwrap();
+        std::fs::write(dir.join("b.rs"), "picker\n").unwrap();
+        // the temporary file of b.rs can't be written over a directory
+        std::fs::create_dir(dir.join("b.rs.fff-replace")).unwrap();
+        let files: Vec<FileItem> = ["a.rs", "b.rs"]
+            .iter()
+            .map(|name| FileItem::new(dir.join(name), &dir, None))
+            .collect();
+
+        let plan = prepare(&files, "picker", "finder", None, 1024, false);
+        assert!(apply(&plan, None).is_err());
+        assert!(!dir.join("a.rs.fff-replace").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.rs")).unwrap(),
+            "picker\n"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_keeps_symlinks() {
+        let dir = std::env::temp_dir().join("fff_test_replace_symlink");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        std::fs::write(dir.join("shared/config.rs"), "picker\n").unwrap();
+        std::os::unix::fs::symlink("shared/config.rs", dir.join("config.rs")).unwrap();
+        let files = vec![FileItem::new(dir.join("config.rs"), &dir, None)];
+
+        let plan = prepare(&files, "picker", "finder", None, 1024, false);
+        let outcome = apply(&plan, None).unwrap();
+        assert_eq!(outcome.written, [dir.join("config.rs")]);
+        assert!(std::fs::symlink_metadata(dir.join("config.rs"))
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("shared/config.rs")).unwrap(),
+            "finder\n"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,357 @@
+//! Traces of the searches for reproducing ranking reports. While recording, the latest searches
+//! are kept with the index generation they ran against. The debug bundle writes them after the
+//! files of the index as `trace.jsonl`, and `replay` runs them again against the current code.
+//! Frecency, git statuses and diagnostics come from the trace, the session and time of day
+//! boosts are computed from the local frecency database so they only match on the same machine.
+
+use crate::debug_bundle::redact_path;
+use crate::error::Error;
+use crate::file_picker::FilePicker;
+use crate::git::Status;
+use crate::glob_filter::GlobFilter;
+use crate::languages::IndexStats;
+use crate::types::{DiagnosticsCount, FileItem, ScoringWeights, SearchResult};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+const TRACE_VERSION: u32 = 1;
+/// Name of the trace inside of the debug bundle
+pub const TRACE_ENTRY: &str = "trace.jsonl";
+
+/// Searches kept in the trace, 0 disables the recording
+static CAPACITY: AtomicUsize = AtomicUsize::new(0);
+static SEARCHES: Lazy<Mutex<VecDeque<TracedSearch>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// One line of a trace, the index comes first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum TraceLine {
+    Index(TracedIndex),
+    Search(TracedSearch),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedIndex {
+    pub version: u32,
+    pub generation: u64,
+    pub base_path: String,
+    pub files: Vec<TracedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+    /// Access, modification, jump, commit and total frecency scores
+    pub frecency: [i64; 5],
+    /// Bits of the git status
+    pub git_status: Option<u32>,
+    pub diagnostics: DiagnosticsCount,
+    pub category: Option<String>,
+    pub package: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracedSearch {
+    pub generation: u64,
+    pub query: String,
+    pub current_file: Option<String>,
+    pub current_package: Option<String>,
+    pub max_results: usize,
+    pub weights: ScoringWeights,
+    pub globs: Option<Vec<String>>,
+    /// Relative paths of the results in order
+    pub results: Vec<String>,
+}
+
+impl TracedIndex {
+    fn redact(&mut self) {
+        self.base_path = redact_path(&self.base_path);
+        for file in &mut self.files {
+            file.path = redact_path(&file.path);
+            file.package = file.package.as_deref().map(redact_path);
+        }
+    }
+}
+
+impl TracedFile {
+    fn new(file: &FileItem) -> Self {
+        Self {
+            path: file.relative_path.clone(),
+            size: file.size,
+            modified: file.modified,
+            frecency: [
+                file.access_frecency_score,
+                file.modification_frecency_score,
+                file.jump_frecency_score,
+                file.commit_frecency_score,
+                file.total_frecency_score,
+            ],
+            git_status: file.git_status.map(|status| status.bits()),
+            diagnostics: file.diagnostics,
+            category: file.category.as_deref().map(str::to_string),
+            package: file.package.as_deref().map(str::to_string),
+        }
+    }
+
+    fn file_item(&self, base_path: &Path) -> FileItem {
+        let mut file = FileItem::listed(
+            base_path.join(&self.path),
+            base_path,
+            self.size,
+            self.modified,
+        );
+        [
+            file.access_frecency_score,
+            file.modification_frecency_score,
+            file.jump_frecency_score,
+            file.commit_frecency_score,
+            file.total_frecency_score,
+        ] = self.frecency;
+        file.git_status = self.git_status.map(Status::from_bits_truncate);
+        file.diagnostics = self.diagnostics;
+        file.category = self.category.as_deref().map(Arc::from);
+        file.package = self.package.as_deref().map(Arc::from);
+        file
+    }
+}
+
+impl TracedSearch {
+    fn redact(&mut self) {
+        self.query = redact_path(&self.query);
+        self.current_file = self.current_file.as_deref().map(redact_path);
+        self.current_package = self.current_package.as_deref().map(redact_path);
+        if let Some(globs) = &mut self.globs {
+            globs.iter_mut().for_each(|glob| *glob = redact_path(glob));
+        }
+        self.results
+            .iter_mut()
+            .for_each(|path| *path = redact_path(path));
+    }
+
+    pub fn result_paths(results: &SearchResult) -> Vec<String> {
+        results
+            .items
+            .iter()
+            .map(|file| file.relative_path.clone())
+            .collect()
+    }
+}
+
+/// Keeps the latest `capacity` searches, 0 stops the recording and drops the recorded ones
+pub fn set_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    let mut searches = SEARCHES.lock().unwrap_or_else(|e| e.into_inner());
+    while searches.len() > capacity {
+        searches.pop_front();
+    }
+}
+
+/// Checked before building a `TracedSearch`, so searches cost nothing while not recording
+pub fn is_recording() -> bool {
+    CAPACITY.load(Ordering::Relaxed) > 0
+}
+
+pub fn record_search(search: TracedSearch) {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    if capacity == 0 {
+        return;
+    }
+
+    let mut searches = SEARCHES.lock().unwrap_or_else(|e| e.into_inner());
+    while searches.len() >= capacity {
+        searches.pop_front();
+    }
+    searches.push_back(search);
+}
+
+/// The index of the picker and the recorded searches as json lines, `None` without searches.
+/// Without `include_paths` the paths, packages and queries are replaced by their hashes.
+pub fn write_trace(picker: &FilePicker, include_paths: bool) -> Result<Option<String>, Error> {
+    let searches = SEARCHES.lock().unwrap_or_else(|e| e.into_inner());
+    if searches.is_empty() {
+        return Ok(None);
+    }
+
+    let mut index = TracedIndex {
+        version: TRACE_VERSION,
+        generation: picker.index_generation(),
+        base_path: picker.base_path().to_string_lossy().into_owned(),
+        files: picker.get_files().iter().map(TracedFile::new).collect(),
+    };
+    let mut searches: Vec<TracedSearch> = searches.iter().cloned().collect();
+    if !include_paths {
+        index.redact();
+        searches.iter_mut().for_each(TracedSearch::redact);
+    }
+
+    let mut trace = String::new();
+    let index = TraceLine::Index(index);
+    for line in std::iter::once(index).chain(searches.into_iter().map(TraceLine::Search)) {
+        let line = serde_json::to_string(&line).map_err(|e| Error::InvalidTrace(e.to_string()))?;
+        let _ = writeln!(trace, "{}", line);
+    }
+    Ok(Some(trace))
+}
+
+/// Reads a trace written by `write_trace`, either on its own or inside a debug bundle
+pub fn read_trace(path: &Path) -> Result<(TracedIndex, Vec<TracedSearch>), Error> {
+    let mut contents = String::new();
+    if path.extension().is_some_and(|extension| extension == "tar") {
+        let mut archive = tar::Archive::new(File::open(path)?);
+        let mut entry = archive
+            .entries()?
+            .filter_map(Result::ok)
+            .find(|entry| {
+                entry
+                    .path()
+                    .is_ok_and(|path| path == Path::new(TRACE_ENTRY))
+            })
+            .ok_or_else(|| Error::InvalidTrace(format!("no {} in the bundle", TRACE_ENTRY)))?;
+        entry.read_to_string(&mut contents)?;
+    } else {
+        File::open(path)?.read_to_string(&mut contents)?;
+    }
+
+    let mut index = None;
+    let mut searches = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line = serde_json::from_str(line)
+            .map_err(|e| Error::InvalidTrace(format!("line {}: {}", number + 1, e)))?;
+        match line {
+            TraceLine::Index(traced) if traced.version != TRACE_VERSION => {
+                return Err(Error::InvalidTrace(format!(
+                    "version {} is not supported",
+                    traced.version
+                )));
+            }
+            TraceLine::Index(traced) => index = Some(traced),
+            TraceLine::Search(search) => searches.push(search),
+        }
+    }
+
+    let index = index.ok_or_else(|| Error::InvalidTrace("no index in the trace".to_string()))?;
+    Ok((index, searches))
+}
+
+/// A recorded search and the results the current code ranks for it
+#[derive(Debug, Clone)]
+pub struct ReplayedSearch {
+    pub search: TracedSearch,
+    pub results: Vec<String>,
+    /// The search ran against the traced index, otherwise the index changed in between and
+    /// differences are expected
+    pub same_generation: bool,
+}
+
+impl ReplayedSearch {
+    pub fn is_reproduced(&self) -> bool {
+        self.results == self.search.results
+    }
+
+    /// Rank of the first result that differs from the recorded ones
+    pub fn first_difference(&self) -> Option<usize> {
+        let recorded = &self.search.results;
+        (0..recorded.len().max(self.results.len()))
+            .find(|&rank| recorded.get(rank) != self.results.get(rank))
+    }
+}
+
+/// Runs the searches of the trace against its index with the current scoring
+pub fn replay(path: &Path) -> Result<Vec<ReplayedSearch>, Error> {
+    let (index, searches) = read_trace(path)?;
+    let base_path = Path::new(&index.base_path);
+    let files: Vec<FileItem> = index
+        .files
+        .iter()
+        .map(|file| file.file_item(base_path))
+        .collect();
+    let index_stats = IndexStats::collect(&files);
+
+    searches
+        .into_iter()
+        .map(|search| {
+            let glob_filter = search.globs.as_deref().map(GlobFilter::new).transpose()?;
+            let results = FilePicker::fuzzy_search_weighted(
+                &files,
+                &search.query,
+                search.max_results,
+                1,
+                search.current_file.as_deref(),
+                search.weights,
+                search.current_package.as_deref(),
+                None,
+                None,
+                index_stats.entry_points(),
+                None,
+                glob_filter.as_ref(),
+                None,
+            );
+
+            Ok(ReplayedSearch {
+                results: TracedSearch::result_paths(&results),
+                same_generation: search.generation == index.generation,
+                search,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_roundtrip() {
+        let base_path = Path::new("/project");
+        let files: Vec<FileItem> = ["src/main.rs", "src/lib.rs", "README.md"]
+            .iter()
+            .map(|path| FileItem::listed(base_path.join(path), base_path, 10, 0))
+            .collect();
+        let results = FilePicker::fuzzy_search(&files, "main", 10, 1, None);
+
+        let search = TracedSearch {
+            generation: 1,
+            query: "main".to_string(),
+            current_file: None,
+            current_package: None,
+            max_results: 10,
+            weights: ScoringWeights::default(),
+            globs: None,
+            results: TracedSearch::result_paths(&results),
+        };
+        let index = TracedIndex {
+            version: TRACE_VERSION,
+            generation: 1,
+            base_path: "/project".to_string(),
+            files: files.iter().map(TracedFile::new).collect(),
+        };
+
+        let trace_path = std::env::temp_dir().join("fff_test_replay_trace.jsonl");
+        let trace = [TraceLine::Index(index), TraceLine::Search(search)]
+            .iter()
+            .map(|line| serde_json::to_string(line).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(&trace_path, trace).unwrap();
+
+        let replayed = replay(&trace_path).unwrap();
+        std::fs::remove_file(&trace_path).ok();
+        assert_eq!(replayed.len(), 1);
+        assert!(replayed[0].same_generation);
+        assert!(replayed[0].is_reproduced());
+        assert_eq!(replayed[0].results, vec!["src/main.rs"]);
+        assert_eq!(replayed[0].first_difference(), None);
+    }
+}
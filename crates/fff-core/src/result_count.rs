@@ -0,0 +1,81 @@
+//! How many results a search returns when the frontend passes `max_results = 0`. Every result
+//! is converted to a lua table even if it's never scrolled to, so the count follows the rows
+//! of the viewport the frontend reported and what converting a result measurably costs.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Rows assumed until the frontend reports its viewport
+const DEFAULT_VIEWPORT_ROWS: usize = 40;
+/// Results are returned for this many screens at most, enough to scroll without searching again
+const MAX_PAGES: usize = 4;
+/// Time converting the results may take, the count shrinks down to one screen to stay within it
+const CONVERSION_BUDGET: Duration = Duration::from_millis(2);
+
+static VIEWPORT_ROWS: AtomicUsize = AtomicUsize::new(0);
+/// Moving average of converting one result, 0 until measured
+static ITEM_CONVERSION_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Rows of the list showing the results, 0 forgets them
+pub fn set_viewport(rows: usize) {
+    VIEWPORT_ROWS.store(rows, Ordering::Relaxed);
+}
+
+/// Feeds the time it took to convert `items` results into the average
+pub fn record_conversion(items: usize, elapsed: Duration) {
+    if items == 0 {
+        return;
+    }
+
+    let sample = (elapsed.as_nanos() / items as u128).min(u64::MAX as u128) as u64;
+    let average = ITEM_CONVERSION_NANOS.load(Ordering::Relaxed);
+    let average = if average == 0 {
+        sample
+    } else {
+        (average * 7 + sample) / 8
+    };
+    ITEM_CONVERSION_NANOS.store(average.max(1), Ordering::Relaxed);
+}
+
+/// `max_results` itself unless it is 0
+pub fn resolve(max_results: usize) -> usize {
+    if max_results > 0 {
+        return max_results;
+    }
+
+    adaptive_count(
+        VIEWPORT_ROWS.load(Ordering::Relaxed),
+        ITEM_CONVERSION_NANOS.load(Ordering::Relaxed),
+    )
+}
+
+/// Whole screens only, so that small changes of the measured cost don't change the count and
+/// the results of a search stay comparable to the previous one
+fn adaptive_count(viewport_rows: usize, item_nanos: u64) -> usize {
+    let rows = match viewport_rows {
+        0 => DEFAULT_VIEWPORT_ROWS,
+        rows => rows,
+    };
+    let pages = match item_nanos {
+        0 => MAX_PAGES,
+        nanos => (CONVERSION_BUDGET.as_nanos() as u64 / nanos / rows as u64) as usize,
+    };
+
+    rows * pages.clamp(1, MAX_PAGES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_count() {
+        assert_eq!(adaptive_count(0, 0), DEFAULT_VIEWPORT_ROWS * MAX_PAGES);
+        assert_eq!(adaptive_count(30, 0), 120);
+        // 2ms fit 400 results of 5µs, 3 screens of 120 rows
+        assert_eq!(adaptive_count(120, 5_000), 360);
+        // slower than a screen per budget still fills the screen
+        assert_eq!(adaptive_count(30, 1_000_000), 30);
+        assert_eq!(adaptive_count(30, 10), 120);
+    }
+}
@@ -0,0 +1,134 @@
+//! Edit script between the results of consecutive searches of a picker. Frontends redraw the
+//! rows that moved, appeared or disappeared instead of the whole list on every keystroke.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Larger result sets are not diffed, they come back as everything removed and inserted
+const MAX_DIFF_CELLS: usize = 1 << 20;
+
+/// Paths of the last results returned to each picker
+static PREVIOUS_RESULTS: Lazy<Mutex<HashMap<String, Vec<String>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Positions are 0-based indices into the previous and the current results
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultDiff {
+    /// `(previous, current)` positions of the results in both sets, in order
+    pub kept: Vec<(usize, usize)>,
+    /// Positions in the current results of the ones the previous results did not have
+    pub inserted: Vec<usize>,
+    /// Positions in the previous results of the ones that are gone
+    pub removed: Vec<usize>,
+}
+
+impl ResultDiff {
+    /// Same results in the same order
+    pub fn is_unchanged(&self) -> bool {
+        self.inserted.is_empty()
+            && self.removed.is_empty()
+            && self
+                .kept
+                .iter()
+                .all(|(previous, current)| previous == current)
+    }
+}
+
+/// Longest common subsequence of the two result lists, every result path is unique within a
+/// list so it's the smallest script turning one into the other
+pub fn diff<A: AsRef<str>, B: AsRef<str>>(previous: &[A], current: &[B]) -> ResultDiff {
+    let (n, m) = (previous.len(), current.len());
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        return ResultDiff {
+            kept: Vec::new(),
+            inserted: (0..m).collect(),
+            removed: (0..n).collect(),
+        };
+    }
+
+    // lengths of the common subsequences of the suffixes
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[at(i, j)] = if previous[i].as_ref() == current[j].as_ref() {
+                lengths[at(i + 1, j + 1)] + 1
+            } else {
+                lengths[at(i + 1, j)].max(lengths[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut result = ResultDiff::default();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if previous[i].as_ref() == current[j].as_ref() {
+            result.kept.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[at(i + 1, j)] >= lengths[at(i, j + 1)] {
+            result.removed.push(i);
+            i += 1;
+        } else {
+            result.inserted.push(j);
+            j += 1;
+        }
+    }
+    result.removed.extend(i..n);
+    result.inserted.extend(j..m);
+    result
+}
+
+/// Diffs the results against the previous ones of the picker and remembers them for the next
+/// search, the first search of a picker inserts everything
+pub fn record(picker_id: &str, current: Vec<String>) -> ResultDiff {
+    let mut previous_results = PREVIOUS_RESULTS.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = previous_results
+        .get(picker_id)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let result = diff(previous, &current);
+    previous_results.insert(picker_id.to_string(), current);
+    result
+}
+
+/// Forgets the results of the picker, e.g. once it's closed
+pub fn forget(picker_id: &str) {
+    PREVIOUS_RESULTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(picker_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let result = diff(&["a", "b", "c", "d"], &["a", "c", "e", "d"]);
+        assert_eq!(result.kept, vec![(0, 0), (2, 1), (3, 3)]);
+        assert_eq!(result.removed, vec![1]);
+        assert_eq!(result.inserted, vec![2]);
+        assert!(!result.is_unchanged());
+
+        assert!(diff(&["a", "b"], &["a", "b"]).is_unchanged());
+        let empty: [&str; 0] = [];
+        assert_eq!(diff(&empty, &["a"]).inserted, vec![0]);
+    }
+
+    #[test]
+    fn test_record_per_picker() {
+        let paths = |paths: &[&str]| paths.iter().map(|path| path.to_string()).collect();
+
+        assert_eq!(record("test_diff", paths(&["a", "b"])).inserted, vec![0, 1]);
+        assert!(record("test_diff", paths(&["a", "b"])).is_unchanged());
+        assert_eq!(record("test_diff_other", paths(&["a"])).inserted, vec![0]);
+        assert_eq!(record("test_diff", paths(&["b"])).removed, vec![0]);
+
+        forget("test_diff");
+        assert_eq!(record("test_diff", paths(&["b"])).inserted, vec![0]);
+    }
+}
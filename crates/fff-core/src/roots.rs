@@ -0,0 +1,101 @@
+//! Short labels of the indexed roots, telling apart identical relative paths of files coming
+//! from different roots like `[api] src/main.rs` and `[web] src/main.rs`.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default)]
+pub struct RootLabels {
+    /// Extra roots first, so a root nested in another one wins over it
+    roots: Vec<(PathBuf, Arc<str>)>,
+}
+
+impl RootLabels {
+    /// Labels are the directory names of the roots, extended with their parent directory names
+    /// while they clash. A single root needs no label, so nothing is labeled then.
+    pub fn new(base_path: &Path, extra_paths: &[PathBuf]) -> Self {
+        if extra_paths.is_empty() {
+            return Self::default();
+        }
+
+        let roots: Vec<&Path> = extra_paths
+            .iter()
+            .map(PathBuf::as_path)
+            .chain(std::iter::once(base_path))
+            .collect();
+        let max_depth = roots
+            .iter()
+            .map(|root| root.components().count())
+            .max()
+            .unwrap_or(1);
+
+        let label = |root: &Path, depth: usize| -> String {
+            let mut names: Vec<_> = root
+                .components()
+                .rev()
+                .take(depth)
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect();
+            names.reverse();
+            names.join("/")
+        };
+        let mut depths = vec![1; roots.len()];
+        for _ in 1..max_depth {
+            let labels: Vec<String> = roots
+                .iter()
+                .zip(&depths)
+                .map(|(root, depth)| label(root, *depth))
+                .collect();
+            let mut clashed = false;
+            for (index, depth) in depths.iter_mut().enumerate() {
+                if labels
+                    .iter()
+                    .filter(|other| **other == labels[index])
+                    .count()
+                    > 1
+                {
+                    *depth += 1;
+                    clashed = true;
+                }
+            }
+            if !clashed {
+                break;
+            }
+        }
+
+        let roots = roots
+            .iter()
+            .zip(depths)
+            .map(|(root, depth)| (root.to_path_buf(), Arc::from(label(root, depth))))
+            .collect();
+        Self { roots }
+    }
+
+    pub fn label_of(&self, path: &Path) -> Option<Arc<str>> {
+        self.roots
+            .iter()
+            .find(|(root, _)| path.starts_with(root))
+            .map(|(_, label)| Arc::clone(label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_labels() {
+        let single = RootLabels::new(Path::new("/work/api"), &[]);
+        assert_eq!(single.label_of(Path::new("/work/api/src/main.rs")), None);
+
+        let labels = RootLabels::new(
+            Path::new("/work/api"),
+            &[PathBuf::from("/work/web"), PathBuf::from("/old/api")],
+        );
+        let label = |path: &str| labels.label_of(Path::new(path)).map(|l| l.to_string());
+        assert_eq!(label("/work/web/src/main.rs").as_deref(), Some("web"));
+        assert_eq!(label("/work/api/src/main.rs").as_deref(), Some("work/api"));
+        assert_eq!(label("/old/api/src/main.rs").as_deref(), Some("old/api"));
+        assert_eq!(label("/elsewhere/main.rs"), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! Files found by a running scan, grouped into batches for progress displays like
+//! "indexing src/… 42,113 files". The scan threads can't call into the frontend, so batches are
+//! queued here until polled, and only while someone subscribed.
+
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A batch is closed once it is this old, about the refresh rate of a progress display
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+/// Latest paths kept per batch
+const MAX_SAMPLES: usize = 3;
+/// Oldest batches are dropped once a frontend stops polling
+const MAX_QUEUED_BATCHES: usize = 50;
+
+static SUBSCRIBED: AtomicBool = AtomicBool::new(false);
+static FEED: Lazy<Mutex<ScanFeed>> = Lazy::new(|| Mutex::new(ScanFeed::default()));
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanBatch {
+    /// Files found since the previous batch
+    pub count: usize,
+    /// Files found by the scan so far
+    pub total: usize,
+    /// Latest paths of the batch relative to the base path
+    pub samples: Vec<String>,
+    /// Last batch of the scan
+    pub done: bool,
+}
+
+#[derive(Debug, Default)]
+struct ScanFeed {
+    current: ScanBatch,
+    started: Option<Instant>,
+    queue: VecDeque<ScanBatch>,
+}
+
+impl ScanFeed {
+    fn push(&mut self, batch: ScanBatch) {
+        if self.queue.len() >= MAX_QUEUED_BATCHES {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(batch);
+    }
+
+    fn record(&mut self, relative_path: &str, count: usize, total: usize) {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let batch = &mut self.current;
+        batch.count += count;
+        batch.total = batch.total.max(total);
+        if batch.samples.len() >= MAX_SAMPLES {
+            batch.samples.remove(0);
+        }
+        batch.samples.push(relative_path.to_string());
+
+        if started.elapsed() >= BATCH_INTERVAL {
+            let batch = std::mem::take(&mut self.current);
+            self.current.total = batch.total;
+            self.started = None;
+            self.push(batch);
+        }
+    }
+
+    fn finish(&mut self, total: usize) {
+        let mut batch = std::mem::take(&mut self.current);
+        batch.total = total;
+        batch.done = true;
+        self.started = None;
+        self.push(batch);
+    }
+}
+
+fn feed() -> std::sync::MutexGuard<'static, ScanFeed> {
+    FEED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Starts queueing batches of the scans from now on
+pub fn subscribe() {
+    SUBSCRIBED.store(true, Ordering::Relaxed);
+}
+
+/// Stops queueing batches and drops the ones not polled yet
+pub fn unsubscribe() {
+    SUBSCRIBED.store(false, Ordering::Relaxed);
+    *feed() = ScanFeed::default();
+}
+
+pub fn is_subscribed() -> bool {
+    SUBSCRIBED.load(Ordering::Relaxed)
+}
+
+/// Records `count` files found at once, `total` counting them too. Free without subscribers.
+pub fn record(relative_path: &str, count: usize, total: usize) {
+    if !is_subscribed() {
+        return;
+    }
+
+    feed().record(relative_path, count, total);
+}
+
+/// Queues the files not reported yet together with the end of the scan
+pub fn finish(total: usize) {
+    if !is_subscribed() {
+        return;
+    }
+
+    feed().finish(total);
+}
+
+/// Takes all the queued batches, oldest first
+pub fn drain_batches() -> Vec<ScanBatch> {
+    feed().queue.drain(..).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_close_by_interval() {
+        let mut feed = ScanFeed::default();
+        for i in 1..=5 {
+            feed.record(&format!("src/{}.rs", i), 1, i);
+        }
+        std::thread::sleep(BATCH_INTERVAL);
+        feed.record("src/6.rs", 1, 6);
+        feed.record("src/7.rs", 1, 7);
+        feed.finish(7);
+
+        let batches: Vec<_> = feed.queue.drain(..).collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].count, 6);
+        assert_eq!(batches[0].total, 6);
+        assert_eq!(batches[0].samples, ["src/4.rs", "src/5.rs", "src/6.rs"]);
+        assert!(!batches[0].done);
+        assert_eq!(batches[1].count, 1);
+        assert_eq!(batches[1].total, 7);
+        assert!(batches[1].done);
+    }
+}
@@ -0,0 +1,602 @@
+use std::cmp::Ordering;
+use std::path::MAIN_SEPARATOR;
+
+use crate::{
+    git::is_modified_status,
+    languages::is_entry_point_file,
+    path_utils::{
+        calculate_depth_penalty, calculate_distance_penalty, calculate_tail_match_ratio,
+        contains_terms_in_order, matches_dir_abbreviation, parse_dir_abbreviation,
+    },
+    types::{FileItem, PackageScope, Score, ScoringContext, TieBreak, TieBreaks},
+    typo::matches_with_cheap_typo,
+};
+use rayon::prelude::*;
+
+/// Computing matched positions is way slower than scoring so the path component weighting is
+/// only applied to the best candidates which are the only ones that can make it to the results
+const MIN_COMPONENT_WEIGHTING_CANDIDATES: usize = 50;
+/// Matches fully scored at most, a two character query matches almost every file of a large
+/// index and scoring a million of them would take seconds
+const MAX_SCORED_MATCHES: usize = 50_000;
+/// Times committed together in the mined history after which a file gets the whole history
+/// share of the co-change bonus
+const MAX_HISTORY_CO_CHANGES: u32 = 10;
+
+/// Returns the best results, the number of matched files and whether the matches were capped
+/// to `MAX_SCORED_MATCHES` before scoring
+pub fn match_and_score_files<'a>(
+    files: &'a [FileItem],
+    context: &ScoringContext,
+) -> (Vec<&'a FileItem>, Vec<Score>, usize, bool) {
+    let files: Vec<&'a FileItem> = match context.candidates {
+        Some(candidates) => candidates
+            .iter()
+            .filter_map(|&index| files.get(index as usize))
+            .collect(),
+        None => files.iter().collect(),
+    };
+    let files: Vec<&'a FileItem> = match context.narrowed_to {
+        Some(paths) => files
+            .into_par_iter()
+            .filter(|file| paths.contains(&file.path))
+            .collect(),
+        None => files,
+    };
+    let files: Vec<&'a FileItem> = match context.category_filter {
+        Some(filter) => files
+            .into_par_iter()
+            .filter(|file| filter.matches(file.category.as_deref()))
+            .collect(),
+        None => files,
+    };
+    let files: Vec<&'a FileItem> = match context.profile {
+        Some(profile) => files
+            .into_par_iter()
+            .filter(|file| profile.matches(file))
+            .collect(),
+        None => files,
+    };
+    let files: Vec<&'a FileItem> = match context.glob_filter {
+        Some(filter) => files
+            .into_par_iter()
+            .filter(|file| filter.matches(&file.relative_path))
+            .collect(),
+        None => files,
+    };
+    let files: Vec<&'a FileItem> = match context.file_filter {
+        Some(filter) => files
+            .into_par_iter()
+            .filter(|file| filter.matches(file))
+            .collect(),
+        None => files,
+    };
+    let files: Vec<&'a FileItem> = match (context.weights.package_scope, context.current_package) {
+        (PackageScope::Restrict, Some(package)) => files
+            .into_par_iter()
+            .filter(|file| file.package.as_deref() == Some(package))
+            .collect(),
+        _ => files,
+    };
+
+    if context.query.len() < 2 {
+        let (items, scores, total_matched) = score_all_by_frecency(&files, context);
+        return (items, scores, total_matched, false);
+    }
+
+    if files.is_empty() {
+        return (vec![], vec![], 0, false);
+    }
+
+    let options = neo_frizbee::Options {
+        prefilter: true,
+        max_typos: Some(context.max_typos),
+        sort: false,
+    };
+
+    let query_contains_path_separator = context.query.contains(MAIN_SEPARATOR);
+    let query_terms: Vec<&str> = if context.weights.word_order != 0 {
+        context.query.split_whitespace().collect()
+    } else {
+        Vec::new()
+    };
+    let abbreviation = parse_dir_abbreviation(context.query);
+    let haystack: Vec<&str> = files.iter().map(|f| f.relative_path.as_str()).collect();
+    tracing::debug!(
+        "Starting fuzzy search for query '{}' in {} files",
+        context.query,
+        haystack.len()
+    );
+    let mut path_matches = neo_frizbee::match_list(context.query, &haystack, options);
+    tracing::debug!(
+        "Matched {} files for query '{}'",
+        path_matches.len(),
+        context.query
+    );
+    if context.is_superseded() {
+        return (vec![], vec![], 0, false);
+    }
+    let total_matched = path_matches.len();
+    let approximate = cap_matches(&mut path_matches, &files);
+
+    // assume that filename should only match if the path matches
+    // we should actually incorporate this bonus by getting this information from neo_frizbee directly
+    // instead of spawning a separate matching process, but it's okay for the beta
+    let haystack_of_filenames = path_matches
+        .par_iter()
+        .filter_map(|m| {
+            files
+                .get(m.index_in_haystack as usize)
+                .map(|f| f.file_name.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    // if there is a / in the query we don't even match filenames
+    let filename_matches = if query_contains_path_separator {
+        vec![]
+    } else {
+        let mut list = neo_frizbee::match_list_parallel(
+            context.query,
+            &haystack_of_filenames,
+            options,
+            context.max_threads,
+        );
+
+        list.par_sort_unstable_by_key(|m| m.index_in_haystack);
+
+        list
+    };
+
+    let mut next_filename_match_index = 0;
+    let mut results: Vec<_> = path_matches
+        .into_iter()
+        .enumerate()
+        .take_while(|_| !context.is_superseded())
+        .map(|(index, path_match)| {
+            let file_idx = path_match.index_in_haystack as usize;
+            let file = files[file_idx];
+
+            let mut base_score = path_match.score as i32;
+            let frecency_boost = weighted(
+                base_score.saturating_mul(file.total_frecency_score as i32) / 100,
+                context.weights.frecency,
+            );
+            let distance_penalty = weighted(
+                calculate_distance_penalty(context.current_file, &file.relative_path),
+                context.weights.distance,
+            );
+            let depth_penalty = weighted(
+                calculate_depth_penalty(&file.relative_path),
+                context.weights.depth,
+            );
+
+            let filename_match = filename_matches
+                .get(next_filename_match_index)
+                .and_then(|m| {
+                    if m.index_in_haystack == index as u32 {
+                        next_filename_match_index += 1;
+                        Some(m)
+                    } else {
+                        None
+                    }
+                });
+
+            let mut has_special_filename_bonus = false;
+            let filename_bonus = match filename_match {
+                Some(filename_match) if filename_match.exact => {
+                    filename_match.score as i32 / 5 * 2 // 40% bonus for exact filename match
+                }
+                // 16% bonus for fuzzy filename match but only if the score of matched path is
+                // equal or greater than the score of matched filename, thus we are not allowing
+                // typoed filename to score higher than the path match
+                Some(filename_match)
+                    if filename_match.score >= path_match.score
+                        && !query_contains_path_separator =>
+                {
+                    base_score = filename_match.score as i32;
+
+                    (base_score / 6)
+                        // for large queries around ~300 score the bonus is too big
+                        // it might lead to situations when much more fitting path with a larger
+                        // base score getting filtered out by combination of score + filename bonus
+                        // so we cap it at 10% of the roughly largest score you can get
+                        .min(30)
+                }
+                // 5% bonus for special file but not as much as file name to avoid sitatuions
+                // when you have /user_service/server.rs and /user_service/server/mod.rs
+                None if is_entry_point_file(&file.file_name, context.entry_points) => {
+                    has_special_filename_bonus = true;
+                    base_score * 5 / 100
+                }
+                _ => 0,
+            };
+            let filename_bonus = weighted(filename_bonus, context.weights.filename);
+            // up to 10% of the base score for files with many errors
+            let diagnostics_bonus = weighted(
+                base_score * file.diagnostics.severity() / 100,
+                context.weights.diagnostics,
+            );
+            let package_bonus = if is_in_current_package(file, context) {
+                base_score / 10
+            } else {
+                0
+            };
+            // 10% of the base score when `dir file` follows the path from left to right
+            let word_order_bonus = if query_terms.len() > 1
+                && contains_terms_in_order(&file.relative_path, &query_terms)
+            {
+                weighted(base_score / 10, context.weights.word_order)
+            } else {
+                0
+            };
+
+            // 50% of the base score, a matching abbreviation is a much stronger signal than the
+            // scattered fuzzy match of the same characters
+            let abbreviation_bonus = match &abbreviation {
+                Some(segments) if matches_dir_abbreviation(&file.relative_path, segments) => {
+                    base_score / 2
+                }
+                _ => 0,
+            };
+
+            // 10% of the base score, like the other contextual bonuses
+            let co_change_bonus = calculate_co_change_bonus(file, context, base_score / 10);
+            let session_bonus = calculate_session_bonus(file, context, base_score / 10);
+            // scaled like the frecency boost
+            let time_of_day_boost = weighted(
+                base_score.saturating_mul(time_of_day_score(file, context)) / 100,
+                context.weights.time_of_day,
+            );
+
+            let total = base_score
+                .saturating_add(co_change_bonus)
+                .saturating_add(session_bonus)
+                .saturating_add(time_of_day_boost)
+                .saturating_add(frecency_boost)
+                .saturating_add(distance_penalty)
+                .saturating_add(depth_penalty)
+                .saturating_add(filename_bonus)
+                .saturating_add(diagnostics_bonus)
+                .saturating_add(package_bonus)
+                .saturating_add(word_order_bonus)
+                .saturating_add(abbreviation_bonus);
+
+            let score = Score {
+                total,
+                base_score,
+                filename_bonus,
+                special_filename_bonus: if has_special_filename_bonus {
+                    filename_bonus
+                } else {
+                    0
+                },
+                frecency_boost,
+                distance_penalty,
+                depth_penalty,
+                path_component_bonus: 0,
+                diagnostics_bonus,
+                package_bonus,
+                word_order_bonus,
+                abbreviation_bonus,
+                typo_bonus: 0,
+                co_change_bonus,
+                session_bonus,
+                time_of_day_boost,
+                normalized: None,
+                match_type: match filename_match {
+                    Some(filename_match) if filename_match.exact => "exact_filename",
+                    Some(_) => "fuzzy_filename",
+                    None => "fuzzy_path",
+                },
+            };
+
+            (file, score)
+        })
+        .collect();
+
+    let tie_breaks = context.weights.tie_breaks;
+    results.sort_by(|a, b| compare_results(a, b, &tie_breaks));
+
+    apply_path_component_weighting(&mut results, context, options);
+    if context.is_superseded() {
+        return (vec![], vec![], 0, false);
+    }
+
+    results.truncate(context.max_results);
+    let (items, scores) = results.into_iter().unzip();
+    (items, scores, total_matched, approximate)
+}
+
+/// Keeps `MAX_SCORED_MATCHES` of the matches, half of them with the best match scores so an
+/// exact hit is never dropped and the other half of the frecent and recently modified files
+/// the user most likely looks for. Returns whether any match was dropped.
+fn cap_matches(matches: &mut Vec<neo_frizbee::Match>, files: &[&FileItem]) -> bool {
+    if matches.len() <= MAX_SCORED_MATCHES {
+        return false;
+    }
+
+    let by_score = MAX_SCORED_MATCHES / 2;
+    matches.select_nth_unstable_by(by_score, |a, b| b.score.cmp(&a.score));
+    let by_recency = MAX_SCORED_MATCHES - by_score;
+    matches[by_score..].select_nth_unstable_by_key(by_recency, |m| {
+        let file = files[m.index_in_haystack as usize];
+        std::cmp::Reverse((file.total_frecency_score, file.modified))
+    });
+    matches.truncate(MAX_SCORED_MATCHES);
+    true
+}
+
+/// Boosts the top candidates whose match is concentrated in the file name or its parent
+/// directory, so `user.rs` ranks `models/user.rs` above `user/legacy/old.rs`. Candidates that
+/// only match thanks to a cheap typo get a part of the typo cost back, so `fiel_picker` keeps
+/// `file_picker.rs` above junk matched with arbitrary typos.
+///
+/// The bonus is never negative, so boosted candidates can't fall below the ones that were not
+/// considered and only the weighted prefix needs to be sorted again.
+fn apply_path_component_weighting(
+    results: &mut [(&FileItem, Score)],
+    context: &ScoringContext,
+    options: neo_frizbee::Options,
+) {
+    let candidates_count = results
+        .len()
+        .min(context.max_results.max(MIN_COMPONENT_WEIGHTING_CANDIDATES));
+    let candidates = &mut results[..candidates_count];
+
+    candidates.par_iter_mut().for_each(|(file, score)| {
+        if context.is_superseded() {
+            return;
+        }
+
+        // 10% of the base score, roughly what the matcher charges for a single typo
+        if matches_with_cheap_typo(context.query, &file.relative_path) {
+            score.typo_bonus = score.base_score / 10;
+            score.total = score.total.saturating_add(score.typo_bonus);
+        }
+
+        let Some(matched) = neo_frizbee::match_indices(context.query, &file.relative_path, options)
+        else {
+            return;
+        };
+
+        let tail_ratio = calculate_tail_match_ratio(&file.relative_path, &matched.indices);
+        // up to 10% of the base score when the whole match is within the file name
+        score.path_component_bonus = weighted(
+            score.base_score * tail_ratio / 1000,
+            context.weights.path_component,
+        );
+        score.total = score.total.saturating_add(score.path_component_bonus);
+    });
+
+    let tie_breaks = context.weights.tie_breaks;
+    candidates.sort_by(|a, b| compare_results(a, b, &tie_breaks));
+}
+
+/// Highest total first, equal totals are ordered by the configured tie breaks
+fn compare_results(
+    (a_file, a_score): &(&FileItem, Score),
+    (b_file, b_score): &(&FileItem, Score),
+    tie_breaks: &TieBreaks,
+) -> Ordering {
+    b_score.total.cmp(&a_score.total).then_with(|| {
+        tie_breaks
+            .as_slice()
+            .iter()
+            .map(|tie_break| match tie_break {
+                TieBreak::Frecency => b_file
+                    .total_frecency_score
+                    .cmp(&a_file.total_frecency_score),
+                TieBreak::Modified => b_file.modified.cmp(&a_file.modified),
+                TieBreak::PathLength => a_file.relative_path.len().cmp(&b_file.relative_path.len()),
+                TieBreak::Alphabetical => a_file.relative_path.cmp(&b_file.relative_path),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    })
+}
+
+fn score_all_by_frecency<'a>(
+    files: &[&'a FileItem],
+    context: &ScoringContext,
+) -> (Vec<&'a FileItem>, Vec<Score>, usize) {
+    let mut results: Vec<_> = files
+        .par_iter()
+        .map(|&file| {
+            if context.is_superseded() {
+                return None;
+            }
+
+            let total_frecency_score = weighted(
+                file.access_frecency_score as i32
+                    + file.jump_frecency_score as i32
+                    + (file.modification_frecency_score as i32).saturating_mul(4),
+                context.weights.frecency,
+            );
+
+            let distance_penalty = weighted(
+                calculate_distance_penalty(context.current_file, &file.relative_path),
+                context.weights.distance,
+            );
+
+            let diagnostics_bonus =
+                weighted(file.diagnostics.severity(), context.weights.diagnostics);
+
+            let package_bonus = if is_in_current_package(file, context) {
+                5
+            } else {
+                0
+            };
+            let co_change_bonus = calculate_co_change_bonus(file, context, 10);
+            // about a file opened a couple of times today, the morning's files come back first
+            let session_bonus = calculate_session_bonus(file, context, 30);
+            let time_of_day_boost = weighted(
+                time_of_day_score(file, context),
+                context.weights.time_of_day,
+            );
+
+            let total = total_frecency_score
+                .saturating_add(co_change_bonus)
+                .saturating_add(session_bonus)
+                .saturating_add(time_of_day_boost)
+                .saturating_add(distance_penalty)
+                .saturating_add(diagnostics_bonus)
+                .saturating_add(package_bonus)
+                .saturating_add(calculate_file_bonus(file, context));
+
+            let score = Score {
+                total,
+                base_score: 0,
+                filename_bonus: 0,
+                special_filename_bonus: 0,
+                frecency_boost: total_frecency_score,
+                distance_penalty,
+                depth_penalty: 0,
+                path_component_bonus: 0,
+                diagnostics_bonus,
+                package_bonus,
+                word_order_bonus: 0,
+                abbreviation_bonus: 0,
+                typo_bonus: 0,
+                co_change_bonus,
+                session_bonus,
+                time_of_day_boost,
+                normalized: None,
+                match_type: "frecency",
+            };
+
+            Some((file, score))
+        })
+        .while_some()
+        .collect();
+    if context.is_superseded() {
+        return (vec![], vec![], 0);
+    }
+
+    let tie_breaks = context.weights.tie_breaks;
+    results.sort_by(|a, b| compare_results(a, b, &tie_breaks));
+    let total_matched = results.len();
+    results.truncate(context.max_results);
+    let (items, scores) = results.into_iter().unzip();
+    (items, scores, total_matched)
+}
+
+/// Rescales the totals against the best one, which becomes 100. Results whose total is not
+/// positive get 0 since there is nothing meaningful to compare them with.
+pub fn normalize_scores(scores: &mut [Score]) {
+    let best = scores.iter().map(|score| score.total).max().unwrap_or(0);
+    for score in scores {
+        let normalized = if best > 0 {
+            (score.total.max(0) as i64 * 100 / best as i64) as u8
+        } else {
+            0
+        };
+        score.normalized = Some(normalized);
+    }
+}
+
+/// Scales a score component by its percentage weight
+#[inline]
+fn weighted(value: i32, weight: i32) -> i32 {
+    if weight == 100 {
+        return value;
+    }
+
+    value.saturating_mul(weight) / 100
+}
+
+#[inline]
+fn is_in_current_package(file: &FileItem, context: &ScoringContext) -> bool {
+    context.weights.package_scope == PackageScope::Boost
+        && context.current_package.is_some()
+        && file.package.as_deref() == context.current_package
+}
+
+#[inline]
+/// The full bonus goes to modified files committed together with the modified current file,
+/// files the mined history relates to the current one get up to half of it by how often
+fn calculate_co_change_bonus(file: &FileItem, context: &ScoringContext, full_bonus: i32) -> i32 {
+    let co_changed = context.co_changed.is_some_and(|co_changed| {
+        file.git_status.is_some_and(is_modified_status) && co_changed.contains(&file.path)
+    });
+    if co_changed {
+        return full_bonus;
+    }
+
+    context
+        .related_by_history
+        .and_then(|related| related.get(&file.path))
+        .map_or(0, |&count| {
+            full_bonus * count.min(MAX_HISTORY_CO_CHANGES) as i32
+                / (2 * MAX_HISTORY_CO_CHANGES) as i32
+        })
+}
+
+#[inline]
+fn calculate_session_bonus(file: &FileItem, context: &ScoringContext, full_bonus: i32) -> i32 {
+    match context.session_files {
+        Some(session_files) if session_files.contains(&file.path) => {
+            weighted(full_bonus, context.weights.session)
+        }
+        _ => 0,
+    }
+}
+
+#[inline]
+fn time_of_day_score(file: &FileItem, context: &ScoringContext) -> i32 {
+    context
+        .time_of_day_scores
+        .and_then(|scores| scores.get(&file.path))
+        .map_or(0, |&score| score as i32)
+}
+
+#[inline]
+fn calculate_file_bonus(file: &FileItem, context: &ScoringContext) -> i32 {
+    let mut bonus = 0i32;
+
+    if let Some(current) = context.current_file {
+        if file.relative_path == *current {
+            bonus -= match file.git_status {
+                Some(status) if is_modified_status(status) => 150,
+                _ => 300,
+            };
+        }
+    }
+
+    bonus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_cap_matches_keeps_best_and_recent() {
+        let count = MAX_SCORED_MATCHES + 10;
+        let files: Vec<FileItem> = (0..count)
+            .map(|i| {
+                let mut file = FileItem::new(format!("src/{}.rs", i).into(), "".as_ref(), None);
+                file.modified = i as u64;
+                file
+            })
+            .collect();
+        let files: Vec<&FileItem> = files.iter().collect();
+        let mut matches: Vec<neo_frizbee::Match> = (0..count as u32)
+            .map(|index| neo_frizbee::Match {
+                score: if index == 0 { 500 } else { 10 },
+                index_in_haystack: index,
+                exact: false,
+            })
+            .collect();
+
+        assert!(cap_matches(&mut matches, &files));
+        assert_eq!(matches.len(), MAX_SCORED_MATCHES);
+        let kept: HashSet<u32> = matches.iter().map(|m| m.index_in_haystack).collect();
+        assert!(kept.contains(&0));
+        assert!(kept.contains(&(count as u32 - 1)));
+
+        let mut few = matches[..10].to_vec();
+        assert!(!cap_matches(&mut few, &files));
+        assert_eq!(few.len(), 10);
+    }
+}
@@ -0,0 +1,250 @@
+//! Scoring implementations selectable per search. Alternative scorers are registered by name
+//! next to the default one, and a search can run a second scorer on the same files to compare
+//! both rankings on real queries before the alternative becomes the default.
+
+use crate::error::Error;
+use crate::score::match_and_score_files;
+use crate::types::{FileItem, Score, ScoringContext, ScoringWeights, SearchResult};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Name of the built in scorer, it can't be replaced
+pub const DEFAULT_SCORER: &str = "default";
+
+static SCORERS: Lazy<RwLock<HashMap<String, Arc<dyn Scorer>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// The best results, the number of matched files and whether the matches were capped before
+/// scoring, see `match_and_score_files`
+pub type ScoredFiles<'a> = (Vec<&'a FileItem>, Vec<Score>, usize, bool);
+
+/// Matches and ranks the files for the query of the context. The filters of the context are
+/// applied by the implementation, the results are normalized afterwards when the weights ask.
+pub trait Scorer: Send + Sync {
+    fn match_and_score<'a>(
+        &self,
+        files: &'a [FileItem],
+        context: &ScoringContext,
+    ) -> ScoredFiles<'a>;
+}
+
+/// The scoring of `score.rs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultScorer;
+
+impl Scorer for DefaultScorer {
+    fn match_and_score<'a>(
+        &self,
+        files: &'a [FileItem],
+        context: &ScoringContext,
+    ) -> ScoredFiles<'a> {
+        match_and_score_files(files, context)
+    }
+}
+
+/// The default scoring with its own weights in place of the weights of the picker, the
+/// alternative scorer that can be registered from lua
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedScorer {
+    pub weights: ScoringWeights,
+}
+
+impl Scorer for WeightedScorer {
+    fn match_and_score<'a>(
+        &self,
+        files: &'a [FileItem],
+        context: &ScoringContext,
+    ) -> ScoredFiles<'a> {
+        let context = ScoringContext {
+            weights: self.weights,
+            ..context.clone()
+        };
+        match_and_score_files(files, &context)
+    }
+}
+
+/// Registers the scorer under the name, replacing the previous one. Returns false for the
+/// name of the default scorer.
+pub fn register(name: &str, scorer: Arc<dyn Scorer>) -> bool {
+    if name == DEFAULT_SCORER {
+        return false;
+    }
+
+    SCORERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), scorer);
+    true
+}
+
+/// Returns whether there was a scorer with the name
+pub fn unregister(name: &str) -> bool {
+    SCORERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(name)
+        .is_some()
+}
+
+/// The scorer registered under the name, the default one for its name
+pub fn get(name: &str) -> Option<Arc<dyn Scorer>> {
+    if name == DEFAULT_SCORER {
+        return Some(Arc::new(DefaultScorer));
+    }
+
+    SCORERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()
+}
+
+/// The scorer registered under the name, an error naming it otherwise
+pub fn require(name: &str) -> Result<Arc<dyn Scorer>, Error> {
+    get(name).ok_or_else(|| {
+        Error::InvalidSearchOptions(format!("no scorer is registered as {:?}", name))
+    })
+}
+
+/// Names of the registered scorers, the default one first
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = SCORERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect();
+    names.sort_unstable();
+    names.insert(0, DEFAULT_SCORER.to_string());
+    names
+}
+
+/// How far the ranking of a candidate scorer is from the baseline one for the same search
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Divergence {
+    /// Results ranked by both scorers
+    pub shared: usize,
+    /// Results only the baseline ranked
+    pub only_baseline: usize,
+    /// Results only the candidate ranked
+    pub only_candidate: usize,
+    /// 0-based rank of the first result that differs, `None` for the same ranking
+    pub first_difference: Option<usize>,
+    /// Mean distance between the ranks of the shared results
+    pub mean_displacement: f64,
+}
+
+impl Divergence {
+    pub fn between<A: AsRef<str>, B: AsRef<str>>(baseline: &[A], candidate: &[B]) -> Self {
+        let candidate_ranks: HashMap<&str, usize> = candidate
+            .iter()
+            .enumerate()
+            .map(|(rank, path)| (path.as_ref(), rank))
+            .collect();
+
+        let displacements: Vec<usize> = baseline
+            .iter()
+            .enumerate()
+            .filter_map(|(rank, path)| Some(candidate_ranks.get(path.as_ref())?.abs_diff(rank)))
+            .collect();
+        let shared = displacements.len();
+
+        Self {
+            shared,
+            only_baseline: baseline.len() - shared,
+            only_candidate: candidate.len() - shared,
+            first_difference: (0..baseline.len().max(candidate.len())).find(|&rank| {
+                baseline.get(rank).map(AsRef::as_ref) != candidate.get(rank).map(AsRef::as_ref)
+            }),
+            mean_displacement: if shared == 0 {
+                0.0
+            } else {
+                displacements.iter().sum::<usize>() as f64 / shared as f64
+            },
+        }
+    }
+
+    pub fn of_results(baseline: &SearchResult, candidate: &SearchResult) -> Self {
+        let paths = |result: &SearchResult| -> Vec<String> {
+            result
+                .items
+                .iter()
+                .map(|file| file.relative_path.clone())
+                .collect()
+        };
+        Self::between(&paths(baseline), &paths(candidate))
+    }
+
+    pub fn is_same_ranking(&self) -> bool {
+        self.first_difference.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_picker::FilePicker;
+    use std::path::Path;
+
+    #[test]
+    fn test_divergence() {
+        let divergence = Divergence::between(&["a", "b", "c"], &["a", "c", "d"]);
+        assert_eq!(divergence.shared, 2);
+        assert_eq!(divergence.only_baseline, 1);
+        assert_eq!(divergence.only_candidate, 1);
+        assert_eq!(divergence.first_difference, Some(1));
+        assert_eq!(divergence.mean_displacement, 0.5);
+
+        assert!(Divergence::between(&["a", "b"], &["a", "b"]).is_same_ranking());
+    }
+
+    #[test]
+    fn test_registered_scorer() {
+        assert!(!register(DEFAULT_SCORER, Arc::new(DefaultScorer)));
+        let weights = ScoringWeights {
+            filename: 0,
+            ..ScoringWeights::default()
+        };
+        assert!(register(
+            "test_no_filename",
+            Arc::new(WeightedScorer { weights })
+        ));
+        assert!(names().starts_with(&[DEFAULT_SCORER.to_string()]));
+        assert!(names().contains(&"test_no_filename".to_string()));
+
+        let base_path = Path::new("/project");
+        let files: Vec<FileItem> = ["src/main.rs", "main/lib.rs"]
+            .iter()
+            .map(|path| FileItem::listed(base_path.join(path), base_path, 10, 0))
+            .collect();
+        let scorer = get("test_no_filename").unwrap();
+        let baseline = FilePicker::fuzzy_search(&files, "main.rs", 10, 1, None);
+        let candidate = FilePicker::fuzzy_search_weighted(
+            &files,
+            "main.rs",
+            10,
+            1,
+            None,
+            ScoringWeights::default(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(scorer.as_ref()),
+        );
+        assert!(baseline.scores[0].filename_bonus > 0);
+        assert!(candidate
+            .scores
+            .iter()
+            .all(|score| score.filename_bonus == 0));
+        assert!(Divergence::of_results(&baseline, &candidate).shared > 0);
+
+        assert!(unregister("test_no_filename"));
+        assert!(get("test_no_filename").is_none());
+        assert!(require("test_no_filename").is_err());
+        assert!(get(DEFAULT_SCORER).is_some());
+    }
+}
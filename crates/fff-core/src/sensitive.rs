@@ -0,0 +1,226 @@
+//! Scan-time flagging of files that should not show up on a shared screen: keys and secrets
+//! matched by name, and on unix files owned by another user or writable by everyone. Flagged
+//! files are kept, kept without previews and content search, or left out of the index.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+use tracing::warn;
+
+pub fn default_sensitive_patterns() -> Vec<String> {
+    [
+        "**/id_rsa*",
+        "**/id_dsa*",
+        "**/id_ecdsa*",
+        "**/id_ed25519*",
+        "**/.env",
+        "**/.env.*",
+        "**/*.pem",
+        "**/*.key",
+        "**/*.p12",
+        "**/*.pfx",
+        "**/.netrc",
+        "**/.pgpass",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect()
+}
+
+/// Why a file was flagged, the first matching reason in this order wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensitivity {
+    /// The path matches one of `SensitiveOptions::patterns`
+    Secret,
+    /// Owned by another user than the one running the picker
+    ForeignOwner,
+    /// Anyone can write the file
+    WorldWritable,
+}
+
+impl Sensitivity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sensitivity::Secret => "secret",
+            Sensitivity::ForeignOwner => "foreign_owner",
+            Sensitivity::WorldWritable => "world_writable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SensitiveMode {
+    /// Nothing is flagged
+    #[default]
+    Off,
+    /// Flagged files are indexed and previewed as usual, frontends can badge them
+    Flag,
+    /// Flagged files are listed but neither previewed nor searched by grep
+    HidePreview,
+    /// Flagged files are left out of the index
+    Exclude,
+}
+
+impl SensitiveMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SensitiveMode::Off => "off",
+            SensitiveMode::Flag => "flag",
+            SensitiveMode::HidePreview => "hide_preview",
+            SensitiveMode::Exclude => "exclude",
+        }
+    }
+
+    pub fn hides_content(&self) -> bool {
+        matches!(self, SensitiveMode::HidePreview | SensitiveMode::Exclude)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensitiveOptions {
+    pub mode: SensitiveMode,
+    /// Globs matched against the path relative to the base path
+    pub patterns: Vec<String>,
+    /// Also flag files of other users and world writable files, costs a metadata read per file
+    pub check_ownership: bool,
+}
+
+impl Default for SensitiveOptions {
+    fn default() -> Self {
+        Self {
+            mode: SensitiveMode::Off,
+            patterns: default_sensitive_patterns(),
+            check_ownership: false,
+        }
+    }
+}
+
+/// Compiled `SensitiveOptions`
+#[derive(Debug, Default)]
+pub struct SensitiveGuard {
+    mode: SensitiveMode,
+    patterns: Option<GlobSet>,
+    /// Owner the files are compared with, `None` skips the ownership checks
+    uid: Option<u32>,
+}
+
+impl SensitiveGuard {
+    /// Invalid globs are logged and skipped like the ones of the category rules
+    pub fn new(options: &SensitiveOptions) -> Self {
+        if options.mode == SensitiveMode::Off {
+            return Self::default();
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &options.patterns {
+            match Glob::new(pattern) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => warn!("Invalid sensitive pattern {:?}: {}", pattern, e),
+            }
+        }
+        let patterns = builder
+            .build()
+            .inspect_err(|e| warn!("Failed to build the sensitive patterns: {}", e))
+            .ok();
+
+        Self {
+            mode: options.mode,
+            patterns,
+            uid: options.check_ownership.then(current_uid).flatten(),
+        }
+    }
+
+    pub fn mode(&self) -> SensitiveMode {
+        self.mode
+    }
+
+    pub fn classify(&self, relative_path: &str, path: &Path) -> Option<Sensitivity> {
+        if self.mode == SensitiveMode::Off {
+            return None;
+        }
+        if self
+            .patterns
+            .as_ref()
+            .is_some_and(|patterns| patterns.is_match(relative_path))
+        {
+            return Some(Sensitivity::Secret);
+        }
+
+        self.uid.and_then(|uid| ownership(path, uid))
+    }
+
+    /// The file is flagged and flagged files are not indexed
+    pub fn excludes(&self, sensitivity: Option<Sensitivity>) -> bool {
+        sensitivity.is_some() && self.mode == SensitiveMode::Exclude
+    }
+
+    /// Classifies the file only if flagged files are not indexed, for sources dropping them
+    /// before their names are reported anywhere
+    pub fn excludes_path(&self, relative_path: &str, path: &Path) -> bool {
+        self.mode == SensitiveMode::Exclude && self.classify(relative_path, path).is_some()
+    }
+}
+
+/// Owner of the running process, the files it creates are owned by it
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::metadata("/proc/self")
+        .ok()
+        .or_else(|| std::fs::metadata(std::env::var_os("HOME")?).ok())
+        .map(|metadata| metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn ownership(path: &Path, uid: u32) -> Option<Sensitivity> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if metadata.uid() != uid {
+        Some(Sensitivity::ForeignOwner)
+    } else if metadata.mode() & 0o002 != 0 {
+        Some(Sensitivity::WorldWritable)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn ownership(_: &Path, _: u32) -> Option<Sensitivity> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let options = SensitiveOptions {
+            mode: SensitiveMode::HidePreview,
+            ..SensitiveOptions::default()
+        };
+        let guard = SensitiveGuard::new(&options);
+        let classify = |relative: &str| guard.classify(relative, Path::new(relative));
+
+        assert_eq!(classify(".ssh/id_rsa.pub"), Some(Sensitivity::Secret));
+        assert_eq!(
+            classify("deploy/.env.production"),
+            Some(Sensitivity::Secret)
+        );
+        assert_eq!(classify("certs/server.pem"), Some(Sensitivity::Secret));
+        assert_eq!(classify("src/env.rs"), None);
+        assert_eq!(classify(".envrc"), None);
+        assert!(!guard.excludes(Some(Sensitivity::Secret)));
+
+        let off = SensitiveGuard::new(&SensitiveOptions::default());
+        assert_eq!(off.classify("id_rsa", Path::new("id_rsa")), None);
+    }
+}
@@ -0,0 +1,91 @@
+//! Tears the globals down in an order that leaves the databases clean when the process exits.
+//! The watcher goes first so nothing writes behind the steps, the index snapshot is stored
+//! while the picker is still there and the frecency tracker flushes its last accesses when
+//! dropped. A scan still running never gets to store its snapshot or replace the index.
+
+use crate::{FILE_PICKER, FRECENCY, SNAPSHOTS};
+use std::sync::mpsc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+const STEPS: [(&str, fn()); 5] = [
+    ("stop watcher", stop_watcher),
+    ("store snapshot", store_snapshot),
+    ("drop picker", drop_picker),
+    ("flush frecency", drop_frecency),
+    ("close snapshots", drop_snapshots),
+];
+
+/// Runs every step even if an earlier one timed out, each waits at most `step_timeout` so a
+/// stuck thread join can't keep the process from exiting. Returns whether all steps finished.
+pub fn shutdown(step_timeout: Duration) -> bool {
+    let mut finished = true;
+    for (name, step) in STEPS {
+        finished &= run_step(name, step, step_timeout);
+    }
+
+    info!(finished, "Shutdown completed");
+    finished
+}
+
+fn run_step(name: &'static str, step: fn(), timeout: Duration) -> bool {
+    let (done, finished) = mpsc::channel();
+    std::thread::spawn(move || {
+        step();
+        let _ = done.send(());
+    });
+
+    match finished.recv_timeout(timeout) {
+        Ok(()) => true,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            warn!(name, ?timeout, "Shutdown step timed out");
+            false
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            error!(name, "Shutdown step panicked");
+            false
+        }
+    }
+}
+
+fn stop_watcher() {
+    if let Ok(mut file_picker) = FILE_PICKER.write() {
+        if let Some(picker) = file_picker.as_mut() {
+            picker.stop_background_monitor();
+        }
+    }
+}
+
+/// The watcher kept the index up to date since the last scan stored it
+fn store_snapshot() {
+    if let Ok(file_picker) = FILE_PICKER.read() {
+        if let Some(picker) = file_picker.as_ref() {
+            picker.store_snapshot();
+        }
+    }
+}
+
+fn drop_picker() {
+    let picker = FILE_PICKER
+        .write()
+        .ok()
+        .and_then(|mut picker| picker.take());
+    drop(picker);
+}
+
+fn drop_frecency() {
+    let tracker = FRECENCY.write().ok().and_then(|mut tracker| tracker.take());
+    if let Some(tracker) = tracker {
+        if let Err(e) = tracker.flush() {
+            error!(?e, "Failed to flush frecency on shutdown");
+        }
+    }
+}
+
+fn drop_snapshots() {
+    let snapshots = SNAPSHOTS
+        .write()
+        .ok()
+        .and_then(|mut snapshots| snapshots.take());
+    drop(snapshots);
+}
@@ -0,0 +1,279 @@
+//! Read-only index snapshots persisted per base path and sensitive mode.
+//!
+//! A snapshot is a single LMDB value laid out as a header, a table of offsets and an arena of
+//! relative paths. LMDB memory maps its file, so searches borrow the paths straight from the map
+//! without copying them. Snapshots are written after every full scan and serve searches only
+//! while the next instance is still running its initial scan, afterwards every instance
+//! searches the paths of its own index. Instances share the memory of one index by attaching
+//! to the `fff --listen` daemon instead.
+
+use crate::error::Error;
+use crate::sensitive::SensitiveMode;
+use crate::types::{FileItem, Score};
+use heed::types::{Bytes, Str};
+use heed::{Database, Env, EnvOpenOptions};
+use rayon::prelude::*;
+use std::fs;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"FFFS";
+const VERSION: u32 = 1;
+/// magic, version, paths count and arena length
+const HEADER_LEN: usize = 16;
+// the map is only reserved address space, the file grows with the stored snapshots
+const MAP_SIZE: usize = 1024 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct IndexSnapshots {
+    env: Env,
+    db: Database<Str, Bytes>,
+}
+
+/// Paths of a snapshot borrowed from the memory map
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotView<'a> {
+    offsets: &'a [u8],
+    arena: &'a [u8],
+    len: usize,
+}
+
+/// Matches of a snapshot search, items are rebuilt from the filesystem so they are owned
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotSearch {
+    pub items: Vec<FileItem>,
+    pub scores: Vec<Score>,
+    pub total_matched: usize,
+    pub total_files: usize,
+}
+
+/// Serializes the relative paths of the files, they are expected to be sorted already
+pub fn encode_snapshot(files: &[FileItem]) -> Vec<u8> {
+    let arena_len: usize = files.iter().map(|file| file.relative_path.len()).sum();
+    let mut bytes = Vec::with_capacity(HEADER_LEN + (files.len() + 1) * 4 + arena_len);
+
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(arena_len as u32).to_le_bytes());
+
+    let mut offset = 0u32;
+    bytes.extend_from_slice(&offset.to_le_bytes());
+    for file in files {
+        offset += file.relative_path.len() as u32;
+        bytes.extend_from_slice(&offset.to_le_bytes());
+    }
+
+    for file in files {
+        bytes.extend_from_slice(file.relative_path.as_bytes());
+    }
+
+    bytes
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+impl<'a> SnapshotView<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err(Error::CorruptedSnapshot("missing header"));
+        }
+        if read_u32(bytes, 4) != VERSION {
+            return Err(Error::CorruptedSnapshot("unsupported version"));
+        }
+
+        let len = read_u32(bytes, 8) as usize;
+        let arena_len = read_u32(bytes, 12) as usize;
+        let arena_start = HEADER_LEN + (len + 1) * 4;
+        if bytes.len() != arena_start + arena_len {
+            return Err(Error::CorruptedSnapshot("truncated"));
+        }
+
+        Ok(Self {
+            offsets: &bytes[HEADER_LEN..arena_start],
+            arena: &bytes[arena_start..],
+            len,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Relative path at `index`, empty if the offsets do not point to valid UTF-8
+    pub fn path(&self, index: usize) -> &'a str {
+        let start = read_u32(self.offsets, index * 4) as usize;
+        let end = read_u32(self.offsets, (index + 1) * 4) as usize;
+        self.arena
+            .get(start..end)
+            .and_then(|path| std::str::from_utf8(path).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &'a str> + '_ {
+        (0..self.len).map(|index| self.path(index))
+    }
+}
+
+impl IndexSnapshots {
+    pub fn new(db_path: &str) -> Result<Self, Error> {
+        fs::create_dir_all(db_path).map_err(Error::CreateDir)?;
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(MAP_SIZE)
+                .open(db_path)
+                .map_err(Error::SnapshotDb)?
+        };
+        env.clear_stale_readers().map_err(Error::SnapshotDb)?;
+
+        let mut wtxn = env.write_txn().map_err(Error::SnapshotDb)?;
+        let db = env
+            .create_database(&mut wtxn, None)
+            .map_err(Error::SnapshotDb)?;
+        wtxn.commit().map_err(Error::SnapshotDb)?;
+
+        Ok(Self { env, db })
+    }
+
+    /// An index scanned with sensitive files excluded must not be served to an instance
+    /// including them and the other way around
+    fn key(base_path: &Path, sensitive: SensitiveMode) -> String {
+        format!("{}\0{}", base_path.to_string_lossy(), sensitive.as_str())
+    }
+
+    pub fn store(
+        &self,
+        base_path: &Path,
+        sensitive: SensitiveMode,
+        files: &[FileItem],
+    ) -> Result<(), Error> {
+        let bytes = encode_snapshot(files);
+        let mut wtxn = self.env.write_txn().map_err(Error::SnapshotDb)?;
+        self.db
+            .put(&mut wtxn, &Self::key(base_path, sensitive), &bytes)
+            .map_err(Error::SnapshotDb)?;
+        wtxn.commit().map_err(Error::SnapshotDb)?;
+
+        tracing::info!(
+            base_path = %base_path.display(),
+            files = files.len(),
+            bytes = bytes.len(),
+            "Stored index snapshot"
+        );
+        Ok(())
+    }
+
+    /// Fuzzy matches the snapshot paths in place, only the returned items are copied out of
+    /// the map. Returns `None` if there is no snapshot for the base path in the sensitive mode.
+    pub fn search(
+        &self,
+        base_path: &Path,
+        sensitive: SensitiveMode,
+        query: &str,
+        max_results: usize,
+        max_threads: usize,
+    ) -> Result<Option<SnapshotSearch>, Error> {
+        let rtxn = self.env.read_txn().map_err(Error::SnapshotDb)?;
+        let Some(bytes) = self
+            .db
+            .get(&rtxn, &Self::key(base_path, sensitive))
+            .map_err(Error::SnapshotDb)?
+        else {
+            return Ok(None);
+        };
+
+        let view = SnapshotView::parse(bytes)?;
+        let haystack: Vec<&str> = view.paths().collect();
+
+        let mut matches: Vec<(usize, i32)> = if query.len() < 2 {
+            (0..haystack.len()).map(|index| (index, 0)).collect()
+        } else {
+            let options = neo_frizbee::Options {
+                prefilter: true,
+                max_typos: Some(2),
+                sort: false,
+            };
+            neo_frizbee::match_list_parallel(query, &haystack, options, max_threads)
+                .into_iter()
+                .map(|m| (m.index_in_haystack as usize, m.score as i32))
+                .collect()
+        };
+        matches.par_sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let total_matched = matches.len();
+        matches.truncate(max_results);
+        let (items, scores) = matches
+            .into_iter()
+            .map(|(index, score)| {
+                let item = FileItem::new(base_path.join(haystack[index]), base_path, None);
+                let score = Score {
+                    total: score,
+                    base_score: score,
+                    match_type: "snapshot",
+                    ..Default::default()
+                };
+                (item, score)
+            })
+            .unzip();
+
+        Ok(Some(SnapshotSearch {
+            items,
+            scores,
+            total_matched,
+            total_files: view.len(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let base = PathBuf::from("/nonexistent");
+        let files: Vec<FileItem> = ["Cargo.toml", "src/lib.rs", "src/ünïcode.rs"]
+            .iter()
+            .map(|path| FileItem::new(base.join(path), &base, None))
+            .collect();
+
+        let bytes = encode_snapshot(&files);
+        let view = SnapshotView::parse(&bytes).unwrap();
+        let paths: Vec<&str> = view.paths().collect();
+        assert_eq!(paths, ["Cargo.toml", "src/lib.rs", "src/ünïcode.rs"]);
+
+        assert!(SnapshotView::parse(&bytes[..bytes.len() - 1]).is_err());
+        assert!(SnapshotView::parse(b"nope").is_err());
+    }
+
+    #[test]
+    fn test_snapshots_keyed_by_sensitive_mode() {
+        let db_path = std::env::temp_dir().join("fff_test_snapshots_sensitive");
+        let _ = fs::remove_dir_all(&db_path);
+        let snapshots = IndexSnapshots::new(&db_path.to_string_lossy()).unwrap();
+        let base = PathBuf::from("/nonexistent");
+        let files: Vec<FileItem> = [".env", "src/lib.rs"]
+            .iter()
+            .map(|path| FileItem::new(base.join(path), &base, None))
+            .collect();
+
+        snapshots.store(&base, SensitiveMode::Off, &files).unwrap();
+        let search = |mode| snapshots.search(&base, mode, "", 10, 1).unwrap();
+        assert_eq!(search(SensitiveMode::Off).unwrap().total_files, 2);
+        assert!(search(SensitiveMode::Exclude).is_none());
+
+        snapshots
+            .store(&base, SensitiveMode::Exclude, &files[1..])
+            .unwrap();
+        assert_eq!(search(SensitiveMode::Exclude).unwrap().total_files, 1);
+
+        drop(snapshots);
+        let _ = fs::remove_dir_all(&db_path);
+    }
+}
@@ -0,0 +1,493 @@
+//! Where the indexed files come from. The disk walker is the default, other sources like
+//! archives, container filesystems or listings pushed from lua feed the same index, search and
+//! scoring without touching the disk.
+
+use crate::archive::{entry_path, list_entries};
+use crate::error::Error;
+use crate::path_utils::is_git_file;
+use crate::scan_batches;
+use crate::sensitive::SensitiveGuard;
+use crate::types::{FileItem, ScanOptions};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+/// A throttled scan checks its pace once per this many files instead of sleeping for each
+const THROTTLE_BATCH: usize = 64;
+
+pub trait FileSource: Send + Sync + std::fmt::Debug {
+    /// Lists the files under the base path and the extra paths, bumping `counter` for each file
+    /// found so the scan progress can be reported. Files excluded by `ScanOptions::sensitive`
+    /// are skipped before they are counted or recorded in a scan batch. Categories, git
+    /// statuses and frecency are filled in afterwards.
+    fn collect_files(
+        &self,
+        base_path: &Path,
+        extra_paths: &[PathBuf],
+        scan_options: &ScanOptions,
+        counter: &AtomicUsize,
+    ) -> Result<Vec<FileItem>, Error>;
+
+    /// Files live on the local disk, so they can be watched and looked up in git
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Walks the local filesystem honoring the gitignore and custom ignore files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskWalker;
+
+impl FileSource for DiskWalker {
+    fn collect_files(
+        &self,
+        base_path: &Path,
+        extra_paths: &[PathBuf],
+        scan_options: &ScanOptions,
+        counter: &AtomicUsize,
+    ) -> Result<Vec<FileItem>, Error> {
+        use ignore::{WalkBuilder, WalkState};
+
+        let mut walk_builder = WalkBuilder::new(base_path);
+        for extra_path in extra_paths {
+            walk_builder.add(extra_path);
+        }
+        walk_builder
+            .hidden(false)
+            .git_ignore(true)
+            .git_exclude(true)
+            .git_global(true)
+            .ignore(true)
+            .follow_links(false)
+            .max_depth(scan_options.max_depth);
+        if let Some(throttle) = scan_options.throttle {
+            info!(?throttle, "SCAN: Throttling the scan");
+            walk_builder.threads(throttle.threads);
+        }
+        let pacer = ScanPacer::new(
+            scan_options
+                .throttle
+                .map_or(0, |throttle| throttle.files_per_second),
+        );
+
+        for file_name in &scan_options.custom_ignore_filenames {
+            walk_builder.add_custom_ignore_filename(file_name);
+        }
+        for ignore_file in scan_options.user_ignore_files() {
+            if let Some(error) = walk_builder.add_ignore(&ignore_file) {
+                warn!(?error, "Failed to parse {}", ignore_file.display());
+            }
+        }
+
+        let walker = walk_builder.build_parallel();
+        let sensitive_guard = SensitiveGuard::new(&scan_options.sensitive);
+
+        let files = Arc::new(Mutex::new(Vec::new()));
+        walker.run(|| {
+            let files = Arc::clone(&files);
+            let pacer = &pacer;
+            let sensitive_guard = &sensitive_guard;
+
+            Box::new(move |result| {
+                if let Ok(entry) = result {
+                    if entry.file_type().is_some_and(|ft| ft.is_file()) {
+                        let path = entry.path();
+
+                        if is_git_file(path) {
+                            return WalkState::Continue;
+                        }
+
+                        pacer.pace();
+                        let file_item = FileItem::new(
+                            path.to_path_buf(),
+                            base_path,
+                            None, // Git status will be added after join
+                        );
+                        if sensitive_guard.excludes_path(&file_item.relative_path, path) {
+                            return WalkState::Continue;
+                        }
+
+                        let relative_path =
+                            scan_batches::is_subscribed().then(|| file_item.relative_path.clone());
+                        if let Ok(mut files_vec) = files.lock() {
+                            files_vec.push(file_item);
+                            let total = counter.fetch_add(1, Ordering::Relaxed) + 1;
+                            if let Some(relative_path) = relative_path {
+                                scan_batches::record(&relative_path, 1, total);
+                            }
+                        }
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+
+        Ok(Arc::try_unwrap(files).unwrap().into_inner().unwrap())
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+/// Adds the entries of the archives listed by the inner source, with `archive_extensions` of
+/// the scan options empty it lists the same files as the inner source. The entries are read
+/// once per scan, a modified archive is picked up by the next rescan.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveEntries<S> {
+    inner: S,
+}
+
+impl<S: FileSource> ArchiveEntries<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: FileSource> FileSource for ArchiveEntries<S> {
+    fn collect_files(
+        &self,
+        base_path: &Path,
+        extra_paths: &[PathBuf],
+        scan_options: &ScanOptions,
+        counter: &AtomicUsize,
+    ) -> Result<Vec<FileItem>, Error> {
+        let mut files = self
+            .inner
+            .collect_files(base_path, extra_paths, scan_options, counter)?;
+        if scan_options.archive_extensions.is_empty() {
+            return Ok(files);
+        }
+
+        let sensitive_guard = SensitiveGuard::new(&scan_options.sensitive);
+        let entries: Vec<FileItem> = files
+            .par_iter()
+            .filter(|file| scan_options.is_archive(&file.file_name))
+            .flat_map_iter(|archive| {
+                let entries: Vec<FileItem> = list_entries(&archive.path)
+                    .inspect_err(|e| debug!(?e, "Failed to list {}", archive.path.display()))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|entry| {
+                        let path = entry_path(&archive.path, &entry.name);
+                        FileItem::listed(path, base_path, entry.size, entry.modified)
+                    })
+                    .filter(|entry| {
+                        !sensitive_guard.excludes_path(&entry.relative_path, &entry.path)
+                    })
+                    .collect();
+                let total = counter.fetch_add(entries.len(), Ordering::Relaxed) + entries.len();
+                scan_batches::record(&archive.relative_path, entries.len(), total);
+                entries
+            })
+            .collect();
+        files.extend(entries);
+        Ok(files)
+    }
+
+    fn is_local(&self) -> bool {
+        self.inner.is_local()
+    }
+}
+
+/// File of a listing produced elsewhere, e.g. by `docker exec find`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListedFile {
+    /// Absolute or relative to the base path
+    pub path: PathBuf,
+    pub size: u64,
+    /// Seconds since the unix epoch, 0 when unknown
+    pub modified: u64,
+}
+
+/// Fixed listing of files that are not on the local disk. It is never watched, pushing a new
+/// listing replaces the index.
+#[derive(Debug, Clone, Default)]
+pub struct ListedFiles {
+    files: Vec<ListedFile>,
+}
+
+impl ListedFiles {
+    pub fn new(files: Vec<ListedFile>) -> Self {
+        Self { files }
+    }
+}
+
+impl ListedFile {
+    /// Parses a line of `find -printf '%s %T@ %p\n'`, lines that don't start with a size and
+    /// a modification time are taken as a bare path like the output of `fd`
+    pub fn parse(line: &str) -> Self {
+        let mut parts = line.splitn(3, ' ');
+        if let (Some(size), Some(modified), Some(path)) = (parts.next(), parts.next(), parts.next())
+        {
+            if let (Ok(size), Ok(modified)) = (size.parse::<u64>(), modified.parse::<f64>()) {
+                return Self {
+                    path: path.into(),
+                    size,
+                    modified: modified as u64,
+                };
+            }
+        }
+
+        Self {
+            path: line.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl FileSource for ListedFiles {
+    fn collect_files(
+        &self,
+        base_path: &Path,
+        _extra_paths: &[PathBuf],
+        scan_options: &ScanOptions,
+        counter: &AtomicUsize,
+    ) -> Result<Vec<FileItem>, Error> {
+        let sensitive_guard = SensitiveGuard::new(&scan_options.sensitive);
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .map(|file| (base_path.join(&file.path), file))
+            .filter(|(path, _)| !is_git_file(path))
+            .map(|(path, file)| FileItem::listed(path, base_path, file.size, file.modified))
+            .filter(|file| !sensitive_guard.excludes_path(&file.relative_path, &file.path))
+            .collect();
+        counter.fetch_add(files.len(), Ordering::Relaxed);
+        Ok(files)
+    }
+}
+
+/// Lists the files of a remote host over ssh, the listing is streamed so the scan progress
+/// moves while a large tree is still being walked on the other end. Like `ListedFiles` it is
+/// never watched, a rescan runs the listing again.
+#[derive(Debug, Clone)]
+pub struct RemoteListing {
+    /// Anything `ssh` accepts, e.g. `user@host` or an alias from `~/.ssh/config`
+    host: String,
+    /// Shell command run on the host printing one file per line, `None` runs `find` in the
+    /// base path skipping `.git`
+    command: Option<String>,
+}
+
+impl RemoteListing {
+    pub fn new(host: String, command: Option<String>) -> Self {
+        Self { host, command }
+    }
+
+    fn remote_command(&self, base_path: &Path) -> String {
+        if let Some(command) = &self.command {
+            return command.clone();
+        }
+
+        let quoted = base_path.to_string_lossy().replace('\'', r"'\''");
+        format!(
+            "find '{}' -name .git -prune -o -type f -printf '%s %T@ %p\\n'",
+            quoted
+        )
+    }
+}
+
+impl FileSource for RemoteListing {
+    fn collect_files(
+        &self,
+        base_path: &Path,
+        _extra_paths: &[PathBuf],
+        scan_options: &ScanOptions,
+        counter: &AtomicUsize,
+    ) -> Result<Vec<FileItem>, Error> {
+        use std::io::BufRead;
+        use std::process::{Command, Stdio};
+
+        let remote_command = self.remote_command(base_path);
+        info!(
+            host = self.host,
+            remote_command, "SCAN: Listing remote files"
+        );
+        let mut child = Command::new("ssh")
+            .args(["-o", "BatchMode=yes", &self.host, &remote_command])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::RemoteListing(format!("failed to run ssh: {}", e)))?;
+
+        let sensitive_guard = SensitiveGuard::new(&scan_options.sensitive);
+        let mut files = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            for line in std::io::BufReader::new(stdout).lines() {
+                let line = line.map_err(|e| Error::RemoteListing(e.to_string()))?;
+                if line.is_empty() {
+                    continue;
+                }
+
+                let file = ListedFile::parse(&line);
+                let path = base_path.join(&file.path);
+                if is_git_file(&path) {
+                    continue;
+                }
+                let file = FileItem::listed(path, base_path, file.size, file.modified);
+                if sensitive_guard.excludes_path(&file.relative_path, &file.path) {
+                    continue;
+                }
+                let total = counter.fetch_add(1, Ordering::Relaxed) + 1;
+                scan_batches::record(&file.relative_path, 1, total);
+                files.push(file);
+            }
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::RemoteListing(e.to_string()))?;
+        // find exits with 1 on unreadable directories, which still lists everything else
+        if files.is_empty() && !output.status.success() {
+            return Err(Error::RemoteListing(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Ok(files)
+    }
+}
+
+/// Spreads the files of a throttled scan evenly over time. Threads sleep between batches until
+/// the scan is back at the allowed rate, which keeps the IO bounded without a shared queue.
+struct ScanPacer {
+    started: std::time::Instant,
+    visited: AtomicUsize,
+    files_per_second: usize,
+}
+
+impl ScanPacer {
+    fn new(files_per_second: usize) -> Self {
+        Self {
+            started: std::time::Instant::now(),
+            visited: AtomicUsize::new(0),
+            files_per_second,
+        }
+    }
+
+    fn pace(&self) {
+        if self.files_per_second == 0 {
+            return;
+        }
+
+        let visited = self.visited.fetch_add(1, Ordering::Relaxed) + 1;
+        if !visited.is_multiple_of(THROTTLE_BATCH) {
+            return;
+        }
+
+        let expected =
+            std::time::Duration::from_secs_f64(visited as f64 / self.files_per_second as f64);
+        if let Some(ahead) = expected.checked_sub(self.started.elapsed()) {
+            std::thread::sleep(ahead);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensitive::{SensitiveMode, SensitiveOptions};
+
+    #[test]
+    fn test_listed_files() {
+        let source = ListedFiles::new(vec![
+            ListedFile {
+                path: "src/main.rs".into(),
+                size: 120,
+                modified: 1_700_000_000,
+            },
+            ListedFile {
+                path: "/app/README.md".into(),
+                ..Default::default()
+            },
+            ListedFile {
+                path: ".git/HEAD".into(),
+                ..Default::default()
+            },
+        ]);
+        let counter = AtomicUsize::new(0);
+        let files = source
+            .collect_files(Path::new("/app"), &[], &ScanOptions::default(), &counter)
+            .unwrap();
+
+        assert!(!source.is_local());
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+        assert_eq!(files[0].path, PathBuf::from("/app/src/main.rs"));
+        assert_eq!(files[0].relative_path, "src/main.rs");
+        assert_eq!((files[0].size, files[0].modified), (120, 1_700_000_000));
+        assert_eq!(files[1].relative_path, "README.md");
+        assert_eq!(files[1].file_name, "README.md");
+    }
+
+    #[test]
+    fn test_sources_skip_excluded_files() {
+        let scan_options = ScanOptions {
+            sensitive: SensitiveOptions {
+                mode: SensitiveMode::Exclude,
+                ..SensitiveOptions::default()
+            },
+            ..ScanOptions::default()
+        };
+
+        let source = ListedFiles::new(vec![
+            ListedFile {
+                path: "src/main.rs".into(),
+                ..Default::default()
+            },
+            ListedFile {
+                path: "deploy/.env".into(),
+                ..Default::default()
+            },
+        ]);
+        let counter = AtomicUsize::new(0);
+        let files = source
+            .collect_files(Path::new("/app"), &[], &scan_options, &counter)
+            .unwrap();
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].relative_path, "src/main.rs");
+
+        let dir = std::env::temp_dir().join("fff_test_source_excluded");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(".ssh")).unwrap();
+        std::fs::write(dir.join("main.rs"), "").unwrap();
+        std::fs::write(dir.join(".ssh/id_rsa"), "").unwrap();
+        let counter = AtomicUsize::new(0);
+        let files = DiskWalker
+            .collect_files(&dir, &[], &scan_options, &counter)
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].relative_path, "main.rs");
+    }
+
+    #[test]
+    fn test_parse_listing_lines() {
+        assert_eq!(
+            ListedFile::parse("120 1700000000.5 /srv/app/src/main.rs"),
+            ListedFile {
+                path: "/srv/app/src/main.rs".into(),
+                size: 120,
+                modified: 1_700_000_000,
+            }
+        );
+        assert_eq!(
+            ListedFile::parse("src/file with spaces.rs"),
+            ListedFile {
+                path: "src/file with spaces.rs".into(),
+                ..Default::default()
+            }
+        );
+
+        let remote = RemoteListing::new("dev".to_string(), None);
+        assert_eq!(
+            remote.remote_command(Path::new("/srv/it's")),
+            r"find '/srv/it'\''s' -name .git -prune -o -type f -printf '%s %T@ %p\n'"
+        );
+    }
+}
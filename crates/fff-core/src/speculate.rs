@@ -0,0 +1,209 @@
+//! Ahead-of-type matching while the picker waits for the first keystrokes. Queries shorter
+//! than two characters are ranked by frecency alone, so the first keystroke reuses the results
+//! of the empty query the picker opened with. The two character prefixes the user typed most
+//! often are matched in the background right after, making the first real match instant too.
+
+use crate::types::{FileItem, Score, ScoringWeights, SearchResult};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Prefixes matched ahead of time when the picker opens
+pub const MAX_SPECULATED_PREFIXES: usize = 3;
+/// Prefix counts are halved once their total exceeds this, recent habits win over old ones
+const MAX_PREFIX_COUNT: u32 = 1_000;
+
+/// Everything the results depend on besides the query, speculated results are only reused
+/// for searches with the same key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeculationKey {
+    /// Index, git status and change log generations, any index update invalidates the results
+    pub generations: (u64, u64, u64),
+    pub scope: Option<PathBuf>,
+    pub current_file: Option<String>,
+    pub max_results: usize,
+    pub weights: ScoringWeights,
+    pub narrowing_depth: usize,
+}
+
+#[derive(Debug)]
+struct SpeculatedResult {
+    /// Indices of the items in the searched files
+    items: Vec<usize>,
+    scores: Vec<Score>,
+    total_matched: usize,
+    total_files: usize,
+    approximate: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Speculation {
+    key: Option<SpeculationKey>,
+    results: HashMap<String, SpeculatedResult>,
+    /// How often each lowercase two character prefix was typed
+    prefixes: HashMap<String, u32>,
+}
+
+/// Queries ranked by frecency alone share the results of the empty query
+fn speculated_query(query: &str) -> &str {
+    if query.chars().count() < 2 {
+        ""
+    } else {
+        query
+    }
+}
+
+impl Speculation {
+    /// Counts the prefix of a query typed up to its second character
+    pub fn record_query(&mut self, query: &str) {
+        if query.chars().count() != 2 || query.contains(char::is_whitespace) {
+            return;
+        }
+
+        *self.prefixes.entry(query.to_lowercase()).or_default() += 1;
+        if self.prefixes.values().sum::<u32>() > MAX_PREFIX_COUNT {
+            self.prefixes.retain(|_, count| {
+                *count /= 2;
+                *count > 0
+            });
+        }
+    }
+
+    /// Most often typed prefixes first
+    pub fn likely_prefixes(&self, count: usize) -> Vec<String> {
+        let mut prefixes: Vec<_> = self.prefixes.iter().collect();
+        prefixes.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+        prefixes
+            .into_iter()
+            .take(count)
+            .map(|(prefix, _)| prefix.clone())
+            .collect()
+    }
+
+    /// Starts over with the results of the empty query the picker opened with
+    pub fn reset(&mut self, key: SpeculationKey, files: &[FileItem], result: &SearchResult) {
+        self.key = Some(key);
+        self.results.clear();
+        self.results
+            .insert(String::new(), SpeculatedResult::new(files, result));
+    }
+
+    /// Keeps results matched ahead of time, dropped if the key changed in the meantime
+    pub fn store(
+        &mut self,
+        key: &SpeculationKey,
+        query: &str,
+        files: &[FileItem],
+        result: &SearchResult,
+    ) {
+        if self.key.as_ref() == Some(key) && !result.superseded {
+            self.results
+                .insert(query.to_string(), SpeculatedResult::new(files, result));
+        }
+    }
+
+    /// Results of the query if they were computed ahead of time with the same key, `files` must
+    /// be the files they were computed from
+    pub fn lookup<'a>(
+        &self,
+        key: &SpeculationKey,
+        query: &str,
+        files: &'a [FileItem],
+    ) -> Option<SearchResult<'a>> {
+        if self.key.as_ref() != Some(key) {
+            return None;
+        }
+
+        let speculated = self.results.get(speculated_query(query))?;
+        Some(SearchResult {
+            items: speculated
+                .items
+                .iter()
+                .map(|&index| files.get(index))
+                .collect::<Option<_>>()?,
+            scores: speculated.scores.clone(),
+            total_matched: speculated.total_matched,
+            total_files: speculated.total_files,
+            superseded: false,
+            approximate: speculated.approximate,
+            groups: None,
+            display_prefix: None,
+        })
+    }
+}
+
+impl SpeculatedResult {
+    fn new(files: &[FileItem], result: &SearchResult) -> Self {
+        let start = files.as_ptr() as usize;
+        let items = result
+            .items
+            .iter()
+            .map(|&item| (item as *const FileItem as usize - start) / size_of::<FileItem>())
+            .collect();
+
+        Self {
+            items,
+            scores: result.scores.clone(),
+            total_matched: result.total_matched,
+            total_files: result.total_files,
+            approximate: result.approximate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(max_results: usize) -> SpeculationKey {
+        SpeculationKey {
+            generations: (1, 1, 1),
+            scope: None,
+            current_file: None,
+            max_results,
+            weights: ScoringWeights::default(),
+            narrowing_depth: 0,
+        }
+    }
+
+    #[test]
+    fn test_reuses_speculated_results() {
+        let files: Vec<FileItem> = ["a.rs", "b.rs", "c.rs"]
+            .iter()
+            .map(|name| FileItem::new(name.into(), "".as_ref(), None))
+            .collect();
+        let result = |items: Vec<&'static str>| SearchResult {
+            items: items
+                .iter()
+                .map(|name| files.iter().find(|f| f.file_name == *name).unwrap())
+                .collect(),
+            scores: vec![Score::default(); items.len()],
+            total_matched: items.len(),
+            total_files: files.len(),
+            superseded: false,
+            approximate: false,
+            groups: None,
+            display_prefix: None,
+        };
+
+        let mut speculation = Speculation::default();
+        for query in ["ma", "ma", "Ma", "sr", "main", "m", "a b"] {
+            speculation.record_query(query);
+        }
+        assert_eq!(speculation.likely_prefixes(1), vec!["ma"]);
+        assert_eq!(speculation.likely_prefixes(5), vec!["ma", "sr"]);
+
+        speculation.reset(key(10), &files, &result(vec!["c.rs", "a.rs"]));
+        speculation.store(&key(10), "ma", &files, &result(vec!["b.rs"]));
+        speculation.store(&key(20), "sr", &files, &result(vec!["b.rs"]));
+
+        let first_keystroke = speculation.lookup(&key(10), "x", &files).unwrap();
+        let names: Vec<_> = first_keystroke.items.iter().map(|f| &f.file_name).collect();
+        assert_eq!(names, ["c.rs", "a.rs"]);
+        assert_eq!(
+            speculation.lookup(&key(10), "ma", &files).unwrap().items[0].file_name,
+            "b.rs"
+        );
+        assert!(speculation.lookup(&key(10), "sr", &files).is_none());
+        assert!(speculation.lookup(&key(20), "x", &files).is_none());
+    }
+}
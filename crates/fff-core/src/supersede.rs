@@ -0,0 +1,84 @@
+//! Cooperative cancellation of searches that were superseded by a newer query of the same
+//! picker. Every search takes a ticket, starting a new one for the same picker marks all the
+//! older tickets as superseded and the scoring loops stop as soon as they notice.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+static PICKERS: Lazy<Mutex<HashMap<String, Arc<PickerSearches>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Default)]
+struct PickerSearches {
+    latest: AtomicU64,
+    in_flight: AtomicUsize,
+}
+
+#[derive(Debug)]
+pub struct SearchTicket {
+    generation: u64,
+    searches: Arc<PickerSearches>,
+}
+
+impl SearchTicket {
+    /// Registers a new search of the picker, superseding the ones still running
+    pub fn begin(picker_id: &str) -> Self {
+        let searches = {
+            let mut pickers = PICKERS.lock().unwrap_or_else(|e| e.into_inner());
+            pickers.entry(picker_id.to_string()).or_default().clone()
+        };
+
+        let generation = searches.latest.fetch_add(1, Ordering::AcqRel) + 1;
+        searches.in_flight.fetch_add(1, Ordering::AcqRel);
+        Self {
+            generation,
+            searches,
+        }
+    }
+
+    /// Cheap enough to be checked for every scored file
+    #[inline]
+    pub fn is_superseded(&self) -> bool {
+        self.searches.latest.load(Ordering::Relaxed) != self.generation
+    }
+}
+
+impl Drop for SearchTicket {
+    fn drop(&mut self) {
+        self.searches.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Searches of the picker that are still running, including the superseded ones that did not
+/// reach a cancellation point yet
+pub fn in_flight_searches(picker_id: &str) -> usize {
+    PICKERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(picker_id)
+        .map_or(0, |searches| searches.in_flight.load(Ordering::Acquire))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newer_ticket_supersedes_older() {
+        let first = SearchTicket::begin("test_supersede");
+        assert!(!first.is_superseded());
+        assert_eq!(in_flight_searches("test_supersede"), 1);
+
+        let second = SearchTicket::begin("test_supersede");
+        let other_picker = SearchTicket::begin("test_supersede_other");
+        assert!(first.is_superseded());
+        assert!(!second.is_superseded());
+        assert!(!other_picker.is_superseded());
+        assert_eq!(in_flight_searches("test_supersede"), 2);
+
+        drop(first);
+        assert_eq!(in_flight_searches("test_supersede"), 1);
+    }
+}
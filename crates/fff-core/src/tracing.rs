@@ -1,10 +1,16 @@
 use crate::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing_appender::non_blocking;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 static TRACING_INITIALIZED: std::sync::OnceLock<tracing_appender::non_blocking::WorkerGuard> =
     std::sync::OnceLock::new();
+static LOG_FILE_PATH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// Path of the log file tracing was initialized with, if it was initialized at all
+pub fn log_file_path() -> Option<&'static Path> {
+    LOG_FILE_PATH.get().map(PathBuf::as_path)
+}
 
 /// Initialize tracing with single log file
 ///
@@ -40,6 +46,7 @@ pub fn init_tracing(log_file_path: &str, log_level: Option<&str>) -> Result<Stri
     };
 
     TRACING_INITIALIZED.get_or_init(|| {
+        let _ = LOG_FILE_PATH.set(log_path.to_path_buf());
         let (non_blocking_appender, guard) = non_blocking(file_appender);
 
         let subscriber = tracing_subscriber::registry()
@@ -0,0 +1,803 @@
+use crate::category::{default_category_rules, CategoryFilter, CategoryRule};
+use crate::error::Error;
+use crate::filter::FileFilter;
+use crate::git::Status;
+use crate::glob_filter::GlobFilter;
+use crate::profile::ProfileFilter;
+use crate::scorer;
+use crate::sensitive::{SensitiveOptions, Sensitivity};
+use crate::supersede::SearchTicket;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct FileItem {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub file_name: String,
+    pub size: u64,
+    pub modified: u64,
+    pub access_frecency_score: i64,
+    pub modification_frecency_score: i64,
+    /// Recency from the editor jumplist/changelist, a weaker signal than explicit opens
+    pub jump_frecency_score: i64,
+    /// Decaying bonus for files touched by the author's latest commits
+    pub commit_frecency_score: i64,
+    pub total_frecency_score: i64,
+    pub git_status: Option<Status>,
+    pub diagnostics: DiagnosticsCount,
+    /// Role of the file assigned at scan time from `ScanOptions::categories`
+    pub category: Option<Arc<str>>,
+    /// Nearest directory with a package manifest relative to the base path, `""` for the root
+    pub package: Option<Arc<str>>,
+    /// Label of the indexed root holding the file, only set while extra roots are indexed
+    pub root: Option<Arc<str>>,
+    /// Why the file shouldn't be shown on a shared screen, see `ScanOptions::sensitive`
+    pub sensitivity: Option<Sensitivity>,
+    /// Arbitrary annotations attached by the frontend, boxed to keep unannotated items small
+    pub meta: Option<Box<FileMeta>>,
+}
+
+/// Result of comparing a sample of the index with the filesystem
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexDrift {
+    pub sampled: usize,
+    /// Indexed files which no longer exist, usually left by dropped watcher events
+    pub missing: usize,
+    /// Indexed files whose modification time changed without the index being updated
+    pub stale: usize,
+    pub rescan_triggered: bool,
+}
+
+impl IndexDrift {
+    pub fn percent(&self) -> usize {
+        if self.sampled == 0 {
+            return 0;
+        }
+
+        (self.missing + self.stale) * 100 / self.sampled
+    }
+}
+
+/// Order of the files listed by `FilePicker::list_files`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileListSort {
+    /// Index order, directories are contiguous
+    #[default]
+    Path,
+    /// Higher total frecency first
+    Frecency,
+    /// Most recently modified first
+    Modified,
+    /// Largest first
+    Size,
+    /// Conflicted then modified files first, see `git_status_rank`
+    GitStatus,
+}
+
+impl FileListSort {
+    pub fn parse(sort_by: &str) -> Option<Self> {
+        match sort_by {
+            "path" => Some(Self::Path),
+            "frecency" => Some(Self::Frecency),
+            "modified" => Some(Self::Modified),
+            "size" => Some(Self::Size),
+            "git_status" => Some(Self::GitStatus),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Frecency => "frecency",
+            Self::Modified => "modified",
+            Self::Size => "size",
+            Self::GitStatus => "git_status",
+        }
+    }
+}
+
+/// One page of the indexed files, so listing views don't copy the whole index at once
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileListOptions {
+    pub offset: usize,
+    /// Files on the page, `None` lists everything after the offset
+    pub limit: Option<usize>,
+    pub sort_by: FileListSort,
+    /// Only files whose relative path starts with it
+    pub prefix: Option<String>,
+}
+
+/// Arguments of a fuzzy search, fields that are left out keep their defaults so new ones can
+/// be added without breaking the callers
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchOptions {
+    pub query: String,
+    /// 0 returns about what fits the viewport reported by the frontend
+    pub max_results: usize,
+    pub max_threads: usize,
+    /// Path of the file open in the editor, it's ranked below the other matches
+    pub current_file: Option<String>,
+    /// Newer searches of the same picker supersede the older ones still running
+    pub picker_id: Option<String>,
+    pub group_by_directory: bool,
+    /// The root of the window, else the one of the buffer, scopes the search
+    pub context: SearchContext,
+    /// rg style globs the results have to pass
+    pub globs: Option<Vec<String>>,
+    /// Name of the registered scorer ranking the results, the default one when `None`
+    pub scorer: Option<String>,
+    /// Also ranks the files with this scorer and returns its results next to the others with
+    /// how much the two rankings diverge
+    pub compare_scorer: Option<String>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            max_results: 100,
+            max_threads: 4,
+            current_file: None,
+            picker_id: None,
+            group_by_directory: false,
+            context: SearchContext::default(),
+            globs: None,
+            scorer: None,
+            compare_scorer: None,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.max_threads == 0 {
+            return Err(Error::InvalidSearchOptions(
+                "max_threads must be at least 1".to_string(),
+            ));
+        }
+        if let Some(glob) = self
+            .globs
+            .iter()
+            .flatten()
+            .find(|glob| glob.trim().is_empty())
+        {
+            return Err(Error::InvalidSearchOptions(format!(
+                "globs can't be empty, got {:?}",
+                glob
+            )));
+        }
+        for name in [&self.scorer, &self.compare_scorer].into_iter().flatten() {
+            scorer::require(name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Window or buffer of the editor, neovim numbers both from 1 so an id alone is ambiguous
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ContextId {
+    Window(i64),
+    Buffer(i64),
+}
+
+/// Window and buffer a search is made from, `{ win = id, buf = id }`. A list is read as
+/// `{ win, buf }`, the way older releases passed them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchContext {
+    pub win: Option<i64>,
+    pub buf: Option<i64>,
+}
+
+impl SearchContext {
+    /// The window first, its root wins over the one of the buffer
+    pub fn ids(&self) -> impl Iterator<Item = ContextId> {
+        let window = self.win.map(ContextId::Window);
+        window.into_iter().chain(self.buf.map(ContextId::Buffer))
+    }
+
+    /// The id of a context naming exactly one window or buffer
+    pub fn single(&self) -> Option<ContextId> {
+        match (self.win, self.buf) {
+            (Some(win), None) => Some(ContextId::Window(win)),
+            (None, Some(buf)) => Some(ContextId::Buffer(buf)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileListPage<'a> {
+    pub items: Vec<&'a FileItem>,
+    /// Files matching the prefix across all the pages
+    pub total: usize,
+}
+
+/// Number of diagnostics reported by the editor for a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticsCount {
+    pub errors: u16,
+    pub warnings: u16,
+}
+
+impl DiagnosticsCount {
+    pub fn is_empty(&self) -> bool {
+        self.errors == 0 && self.warnings == 0
+    }
+
+    /// Errors weigh twice as much as warnings, capped at 10
+    pub fn severity(&self) -> i32 {
+        (self.errors as i32 * 2 + self.warnings as i32).min(10)
+    }
+}
+
+pub type FileMeta = BTreeMap<String, MetaValue>;
+
+/// A value of the per file metadata, mirrors what can be stored in a lua table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MetaValue {
+    Bool(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Array(Vec<MetaValue>),
+    Map(BTreeMap<String, MetaValue>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Score {
+    pub total: i32,
+    pub base_score: i32,
+    pub filename_bonus: i32,
+    pub special_filename_bonus: i32,
+    pub frecency_boost: i32,
+    pub distance_penalty: i32,
+    pub depth_penalty: i32,
+    pub path_component_bonus: i32,
+    pub diagnostics_bonus: i32,
+    pub package_bonus: i32,
+    pub word_order_bonus: i32,
+    /// Bonus for paths matching a `s/f/p` style directory abbreviation segment by segment
+    pub abbreviation_bonus: i32,
+    /// Refund for a query that only matches after fixing a swapped or neighbouring key typo
+    pub typo_bonus: i32,
+    /// Bonus for modified files committed together with the modified current file
+    pub co_change_bonus: i32,
+    /// Bonus for files of the current or the previous session, see `ScoringWeights::session`
+    pub session_bonus: i32,
+    /// Boost from the accesses at a similar time of day, see `ScoringWeights::time_of_day`
+    pub time_of_day_boost: i32,
+    /// Total on a 0-100 scale relative to the best result, set with `normalize_scores`
+    pub normalized: Option<u8>,
+    pub match_type: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoringContext<'a> {
+    pub query: &'a str,
+    pub current_file: Option<&'a str>,
+    pub max_results: usize,
+    pub max_typos: u16,
+    pub max_threads: usize,
+    pub weights: ScoringWeights,
+    pub category_filter: Option<&'a CategoryFilter>,
+    /// Size and binary filters from the query
+    pub file_filter: Option<&'a FileFilter>,
+    /// Extension and ignore filters of the profile selected in the query
+    pub profile: Option<&'a ProfileFilter>,
+    /// rg style globs passed along the query
+    pub glob_filter: Option<&'a GlobFilter>,
+    /// Package of the current file, used by `ScoringWeights::package_scope`
+    pub current_package: Option<&'a str>,
+    /// Abandons the scoring once a newer query of the same picker starts
+    pub ticket: Option<&'a SearchTicket>,
+    /// Files of the top of the narrowing stack, the only ones searched
+    pub narrowed_to: Option<&'a HashSet<PathBuf>>,
+    /// Files committed together with the current file, set while the current file is modified
+    pub co_changed: Option<&'a HashSet<PathBuf>>,
+    /// Files frequently committed together with the current file according to the mined
+    /// co-change graph, with how many times they were
+    pub related_by_history: Option<&'a HashMap<PathBuf, u32>>,
+    /// Files opened in the current or the previous session, set while `weights.session` is on
+    pub session_files: Option<&'a HashSet<PathBuf>>,
+    /// Access scores at the current time of day, set while `weights.time_of_day` is on
+    pub time_of_day_scores: Option<&'a HashMap<PathBuf, i64>>,
+    /// Entry point file names of the detected project languages, `None` uses all the known ones
+    pub entry_points: Option<&'a [&'static str]>,
+    /// Indices of the only files able to match the query, see `prefix_cache`
+    pub candidates: Option<&'a [u32]>,
+}
+
+impl ScoringContext<'_> {
+    #[inline]
+    pub fn is_superseded(&self) -> bool {
+        self.ticket.is_some_and(SearchTicket::is_superseded)
+    }
+}
+
+/// Percentage multipliers applied to the individual score components, 100 keeps the
+/// built in behaviour and 0 disables the component entirely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoringWeights {
+    pub frecency: i32,
+    pub distance: i32,
+    /// Penalty for deeply nested files between otherwise equal matches
+    pub depth: i32,
+    pub filename: i32,
+    pub path_component: i32,
+    /// Bonus for files with errors and warnings, disabled by default
+    pub diagnostics: i32,
+    pub package_scope: PackageScope,
+    /// Bonus for multi-term queries whose terms appear in the path in query order, disabled
+    /// by default
+    pub word_order: i32,
+    /// Bonus for files opened in the current or the previous editing session, disabled by
+    /// default
+    pub session: i32,
+    /// Boost from the accesses made at a similar time of the day as now, disabled by default
+    pub time_of_day: i32,
+    /// Fill `Score::normalized` so UIs can draw comparable score bars across queries
+    pub normalize_scores: bool,
+    /// Order of results with equal scores
+    pub tie_breaks: TieBreaks,
+}
+
+/// Criterion ordering results with equal scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreak {
+    /// Higher total frecency first
+    Frecency,
+    /// Most recently modified first
+    Modified,
+    /// Shorter relative path first
+    PathLength,
+    /// Relative path in byte order
+    Alphabetical,
+}
+
+/// Ordered tie-break criteria, kept in a fixed array so the weights stay `Copy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "Vec<TieBreak>", from = "Vec<TieBreak>")]
+pub struct TieBreaks {
+    order: [TieBreak; 4],
+    len: usize,
+}
+
+impl TieBreaks {
+    /// Keeps the first occurrence of every criterion
+    pub fn new(tie_breaks: impl IntoIterator<Item = TieBreak>) -> Self {
+        let mut order = [TieBreak::Alphabetical; 4];
+        let mut len = 0;
+        for tie_break in tie_breaks {
+            if !order[..len].contains(&tie_break) {
+                order[len] = tie_break;
+                len += 1;
+            }
+        }
+
+        Self { order, len }
+    }
+
+    pub fn as_slice(&self) -> &[TieBreak] {
+        &self.order[..self.len]
+    }
+}
+
+impl From<TieBreaks> for Vec<TieBreak> {
+    fn from(tie_breaks: TieBreaks) -> Self {
+        tie_breaks.as_slice().to_vec()
+    }
+}
+
+impl From<Vec<TieBreak>> for TieBreaks {
+    fn from(tie_breaks: Vec<TieBreak>) -> Self {
+        Self::new(tie_breaks)
+    }
+}
+
+impl Default for TieBreaks {
+    /// Frecency and the path come before the modification time so results don't reorder every
+    /// time the watcher sees a write
+    fn default() -> Self {
+        Self::new([
+            TieBreak::Frecency,
+            TieBreak::Modified,
+            TieBreak::PathLength,
+            TieBreak::Alphabetical,
+        ])
+    }
+}
+
+/// How results from other monorepo packages than the current file's one are treated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageScope {
+    #[default]
+    Off,
+    /// Files from the current package get a bonus
+    Boost,
+    /// Only files from the current package are returned
+    Restrict,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            frecency: 100,
+            distance: 100,
+            depth: 100,
+            filename: 100,
+            path_component: 100,
+            diagnostics: 0,
+            package_scope: PackageScope::Off,
+            word_order: 0,
+            session: 0,
+            time_of_day: 0,
+            normalize_scores: false,
+            tie_breaks: TieBreaks::default(),
+        }
+    }
+}
+
+/// Source of the file system events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WatcherBackend {
+    /// inotify, FSEvents or ReadDirectoryChangesW, polling when the platform refuses to watch
+    #[default]
+    Native,
+    /// Compares the modification times of the tree every `poll_interval_ms`, for containers
+    /// without inotify and network mounts that don't report changes
+    Poll,
+}
+
+/// Background watcher tuning that can be changed without rebuilding the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatcherOptions {
+    pub debounce_ms: u64,
+    pub backend: WatcherBackend,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for WatcherOptions {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 500,
+            backend: WatcherBackend::Native,
+            poll_interval_ms: 2000,
+        }
+    }
+}
+
+/// Soft limit of the index memory, exceeding it drops the least valuable data first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryOptions {
+    /// `None` disables the budget
+    pub budget_mb: Option<usize>,
+}
+
+impl MemoryOptions {
+    pub fn budget_bytes(&self) -> Option<usize> {
+        self.budget_mb.map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+}
+
+/// Parameters of the access score decay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrecencyOptions {
+    /// Number of days after which a single access is worth half as much
+    pub half_life_days: f64,
+    /// Accesses older than this are ignored and dropped on the next write
+    pub max_history_days: f64,
+    /// A longer break without opening any file starts a new session
+    pub session_gap_minutes: f64,
+    /// Decayed accesses counted in full, the ones above it grow along `access_curve`
+    pub access_soft_cap: f64,
+    /// Exponent of the growth above the soft cap, 0.5 is a square root and 1 counts every
+    /// access in full
+    pub access_curve: f64,
+    /// Ceiling of the total frecency score of a file, so the boost of a match is never more
+    /// than this percentage of its match score however often the file was opened
+    pub max_score: i64,
+    /// Projects neither opened nor with a file accessed for this many days are dropped from
+    /// the database, 0 keeps them forever
+    pub forget_projects_after_days: f64,
+}
+
+pub const DEFAULT_MAX_FRECENCY_SCORE: i64 = 50;
+
+impl Default for FrecencyOptions {
+    fn default() -> Self {
+        Self {
+            half_life_days: 10.0,
+            max_history_days: 30.0,
+            session_gap_minutes: 30.0,
+            access_soft_cap: 10.0,
+            access_curve: 0.5,
+            max_score: DEFAULT_MAX_FRECENCY_SCORE,
+            forget_projects_after_days: 180.0,
+        }
+    }
+}
+
+impl FrecencyOptions {
+    pub fn decay_constant(&self) -> f64 {
+        std::f64::consts::LN_2 / self.half_life_days.max(f64::EPSILON)
+    }
+
+    /// Access score of the sum of the decayed accesses
+    pub fn normalize_access(&self, total_frecency: f64) -> f64 {
+        let soft_cap = self.access_soft_cap.max(0.0);
+        if total_frecency <= soft_cap {
+            total_frecency
+        } else {
+            soft_cap + (total_frecency - soft_cap).powf(self.access_curve.clamp(0.0, 1.0))
+        }
+    }
+}
+
+/// Options controlling how the file picker walks the filesystem and which
+/// changes the background watcher reacts to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Extra ignore files honored next to `.gitignore` and `.ignore`, e.g. `.fdignore`
+    pub custom_ignore_filenames: Vec<String>,
+    /// Display result paths relative to the git root instead of the base path when the base
+    /// path is a subdirectory of the repository, matching still uses the base relative paths
+    pub relative_to_git_root: bool,
+    /// Also honor the global ignore files of fd and ripgrep, `~/.config/fd/ignore` and
+    /// `~/.ignore`, next to the global git excludes
+    pub global_ignore_files: bool,
+    /// Maximum directory depth to descend into, files directly in the base path are at depth 1
+    pub max_depth: Option<usize>,
+    /// Match watcher events and git statuses to indexed files ignoring case, needed on
+    /// case-insensitive filesystems where git may report `Foo.txt` for a walked `foo.txt`
+    pub case_insensitive: bool,
+    /// Ordered rules assigning a category to every scanned file, the first match wins
+    pub categories: Vec<CategoryRule>,
+    /// Number of the git author's latest commits whose files get a bonus, 0 disables it
+    pub recent_commits: usize,
+    /// Number of latest commits mined into the co-change graph after the scan, 0 disables it
+    pub co_change_commits: usize,
+    /// Slows the scan down for network filesystems and laptops on battery
+    pub throttle: Option<ScanThrottle>,
+    /// Extensions of the zip and tar archives whose entries are indexed as
+    /// `archive.zip::path/inside`, empty disables it
+    pub archive_extensions: Vec<String>,
+    /// Without a detected version control system, score recently modified files the way
+    /// modified files of a repository are scored
+    pub mtime_fallback: bool,
+    /// Flags keys, secrets and files of other users, optionally hiding them
+    pub sensitive: SensitiveOptions,
+}
+
+/// Bounds of a throttled scan, a scan without them uses every core as fast as it can
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanThrottle {
+    /// Threads walking the filesystem and processing the files, 0 keeps one per core
+    pub threads: usize,
+    /// Files read per second across all the threads, 0 does not limit the rate
+    pub files_per_second: usize,
+}
+
+impl Default for ScanThrottle {
+    fn default() -> Self {
+        Self {
+            threads: 2,
+            files_per_second: 0,
+        }
+    }
+}
+
+/// macOS and windows filesystems are case-insensitive by default
+const DEFAULT_CASE_INSENSITIVE: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            custom_ignore_filenames: Vec::new(),
+            global_ignore_files: false,
+            relative_to_git_root: false,
+            max_depth: None,
+            case_insensitive: DEFAULT_CASE_INSENSITIVE,
+            categories: default_category_rules(),
+            recent_commits: 20,
+            co_change_commits: 1000,
+            throttle: None,
+            archive_extensions: Vec::new(),
+            // builds without git only recognize jj, hg and svn repositories
+            mtime_fallback: !cfg!(feature = "git"),
+            sensitive: SensitiveOptions::default(),
+        }
+    }
+}
+
+impl ScanOptions {
+    /// Checks whether a path relative to the base path is shallow enough to be indexed
+    pub fn is_within_max_depth(&self, relative_path: &Path) -> bool {
+        self.max_depth
+            .is_none_or(|max_depth| relative_path.components().count() <= max_depth)
+    }
+
+    /// The existing global ignore files to honor, empty unless `global_ignore_files` is set
+    pub fn user_ignore_files(&self) -> Vec<PathBuf> {
+        if !self.global_ignore_files {
+            return Vec::new();
+        }
+
+        crate::ignore_cache::user_ignore_files()
+    }
+
+    pub fn is_archive(&self, file_name: &str) -> bool {
+        file_name.rsplit_once('.').is_some_and(|(_, extension)| {
+            self.archive_extensions
+                .iter()
+                .any(|archive| archive.eq_ignore_ascii_case(extension))
+        })
+    }
+}
+
+/// How often a file was opened within the frecency window, shown as a hint next to results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessCount {
+    pub count: usize,
+    /// Unix timestamp of the latest access, `None` if the file was never opened
+    pub last_access: Option<u64>,
+}
+
+/// A single stored access of a file and how much it contributes to the access score right now
+#[derive(Debug, Clone)]
+pub struct AccessRecord {
+    pub timestamp: u64,
+    pub days_ago: f64,
+    pub decay: f64,
+}
+
+/// Breakdown of the frecency score of a single file used for score inspection
+#[derive(Debug, Clone)]
+pub struct FrecencyDetails {
+    pub accesses: Vec<AccessRecord>,
+    /// Sum of all the decay contributions before diminishing returns are applied
+    pub raw_access_score: f64,
+    pub access_score: i64,
+    pub modified: Option<u64>,
+    pub seconds_since_modification: Option<u64>,
+    pub is_modified_git_status: bool,
+    /// Latest buffer edit reported by the editor, scored like a modification without git
+    pub last_edit: Option<u64>,
+    pub modification_score: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult<'a> {
+    pub items: Vec<&'a FileItem>,
+    pub scores: Vec<Score>,
+    pub total_matched: usize,
+    pub total_files: usize,
+    /// A newer query of the same picker started and the results were abandoned
+    pub superseded: bool,
+    /// The query matched too many files to score them all, only the frecent and recently
+    /// modified ones and the best matches were ranked. `total_matched` counts all of them.
+    pub approximate: bool,
+    /// Set on request with `group_by_directory`
+    pub groups: Option<Vec<ResultGroup>>,
+    /// Path of the base path relative to the git root, prepended to the displayed paths with
+    /// `relative_to_git_root`
+    pub display_prefix: Option<String>,
+}
+
+/// Results sharing a parent directory, `items` are indices into `SearchResult::items` in
+/// score order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultGroup {
+    /// Parent directory relative to the base path, `""` for the root
+    pub directory: String,
+    pub items: Vec<usize>,
+}
+
+impl SearchResult<'_> {
+    /// Groups the results by their parent directory, the groups are ordered by their best
+    /// result so the overall ranking is preserved
+    pub fn group_by_directory(&self) -> Vec<ResultGroup> {
+        let mut groups: Vec<ResultGroup> = Vec::new();
+        let mut group_indices: HashMap<&str, usize> = HashMap::new();
+        for (index, item) in self.items.iter().enumerate() {
+            let directory = Path::new(&item.relative_path)
+                .parent()
+                .and_then(Path::to_str)
+                .unwrap_or_default();
+            let group = *group_indices.entry(directory).or_insert_with(|| {
+                groups.push(ResultGroup {
+                    directory: self.display_path(directory).into_owned(),
+                    items: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            groups[group].items.push(index);
+        }
+
+        groups
+    }
+
+    /// Path relative to the base path as it is displayed, relative to the git root if the
+    /// result has a `display_prefix`
+    pub fn display_path<'p>(&self, relative_path: &'p str) -> Cow<'p, str> {
+        match &self.display_prefix {
+            Some(prefix) => Cow::Owned(
+                Path::new(prefix)
+                    .join(relative_path)
+                    .to_string_lossy()
+                    .trim_end_matches(MAIN_SEPARATOR)
+                    .to_string(),
+            ),
+            None => Cow::Borrowed(relative_path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_options() {
+        let parse = |json: serde_json::Value| serde_json::from_value::<SearchOptions>(json);
+
+        let options = parse(serde_json::json!({ "query": "main", "context": { "win": 1001 } }));
+        let options = options.unwrap();
+        assert_eq!(options.query, "main");
+        assert_eq!(options.context.single(), Some(ContextId::Window(1001)));
+        assert_eq!(options.max_threads, SearchOptions::default().max_threads);
+
+        let options = parse(serde_json::json!({ "context": [1001, 3], "globs": ["src/**"] }));
+        let ids: Vec<ContextId> = options.unwrap().context.ids().collect();
+        assert_eq!(ids, [ContextId::Window(1001), ContextId::Buffer(3)]);
+        assert_eq!(
+            parse(serde_json::json!({ "context": {} })).unwrap().context,
+            SearchContext::default()
+        );
+        assert!(parse(serde_json::json!({ "context": 1001 })).is_err());
+        assert!(parse(serde_json::json!({ "max_result": 10 })).is_err());
+
+        let options = parse(serde_json::json!({ "max_threads": 0 })).unwrap();
+        assert!(options.validate().is_err());
+        let options = parse(serde_json::json!({ "globs": ["src/**", " "] })).unwrap();
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_group_by_directory() {
+        let files: Vec<FileItem> = ["src/b.rs", "README.md", "src/a.rs", "lib/c.rs"]
+            .iter()
+            .map(|path| FileItem::new(Path::new("/repo").join(path), Path::new("/repo"), None))
+            .collect();
+        let result = SearchResult {
+            items: files.iter().collect(),
+            ..Default::default()
+        };
+
+        let groups = result.group_by_directory();
+        let groups: Vec<(&str, &[usize])> = groups
+            .iter()
+            .map(|group| (group.directory.as_str(), group.items.as_slice()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![("src", &[0, 2][..]), ("", &[1][..]), ("lib", &[3][..])]
+        );
+
+        let result = SearchResult {
+            display_prefix: Some("app".to_string()),
+            ..result
+        };
+        assert_eq!(result.display_path("src/b.rs"), "app/src/b.rs");
+        assert_eq!(result.group_by_directory()[1].directory, "app");
+    }
+}
@@ -0,0 +1,114 @@
+//! Typo model on top of the fuzzy matcher. The matcher charges every typo the same, here the
+//! most common slips, swapped neighbouring letters and a neighbouring key on the keyboard, are
+//! recognized so the scorer can refund part of their cost without allowing more typos.
+
+const KEYBOARD_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+/// Horizontal offset of every row on a staggered qwerty keyboard, in keys
+const ROW_OFFSETS: [f32; 3] = [0.0, 0.25, 0.75];
+
+fn key_position(key: char) -> Option<(usize, f32)> {
+    let key = key.to_ascii_lowercase();
+    KEYBOARD_ROWS.iter().enumerate().find_map(|(row, keys)| {
+        keys.find(key)
+            .map(|column| (row, column as f32 + ROW_OFFSETS[row]))
+    })
+}
+
+/// Checks whether two different letters are next to each other on a qwerty keyboard
+pub fn is_adjacent_key(a: char, b: char) -> bool {
+    if a.eq_ignore_ascii_case(&b) {
+        return false;
+    }
+
+    match (key_position(a), key_position(b)) {
+        (Some((a_row, a_x)), Some((b_row, b_x))) => {
+            a_row.abs_diff(b_row) <= 1 && (a_x - b_x).abs() <= 1.0
+        }
+        _ => false,
+    }
+}
+
+fn is_subsequence(query: &[char], haystack: &str) -> bool {
+    let mut query = query.iter().peekable();
+    for c in haystack.chars() {
+        if query.peek().is_some_and(|q| q.eq_ignore_ascii_case(&c)) {
+            query.next();
+        }
+    }
+
+    query.peek().is_none()
+}
+
+/// Returns true if the query does not match the haystack as is, but does after fixing a
+/// single transposition of neighbouring letters or a single press of a neighbouring key
+pub fn matches_with_cheap_typo(query: &str, haystack: &str) -> bool {
+    let mut query: Vec<char> = query.chars().collect();
+    if is_subsequence(&query, haystack) {
+        return false;
+    }
+
+    for i in 1..query.len() {
+        query.swap(i - 1, i);
+        let matched = is_subsequence(&query, haystack);
+        query.swap(i - 1, i);
+        if matched {
+            return true;
+        }
+    }
+
+    let candidates: Vec<char> = haystack
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .filter(char::is_ascii_alphabetic)
+        .collect();
+    for i in 0..query.len() {
+        let typed = query[i];
+        let mut tried = Vec::new();
+        for &key in &candidates {
+            if tried.contains(&key) || !is_adjacent_key(typed, key) {
+                continue;
+            }
+
+            tried.push(key);
+            query[i] = key;
+            let matched = is_subsequence(&query, haystack);
+            query[i] = typed;
+            if matched {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjacent_keys() {
+        assert!(is_adjacent_key('f', 'g'));
+        assert!(is_adjacent_key('f', 'R'));
+        assert!(is_adjacent_key('f', 'v'));
+        assert!(!is_adjacent_key('f', 'f'));
+        assert!(!is_adjacent_key('f', 'k'));
+        assert!(!is_adjacent_key('f', '_'));
+    }
+
+    #[test]
+    fn test_cheap_typos() {
+        let path = "src/file_picker.rs";
+        assert!(!matches_with_cheap_typo("file_picker", path), "no typo");
+        assert!(
+            matches_with_cheap_typo("fiel_picker", path),
+            "transposition"
+        );
+        assert!(matches_with_cheap_typo("fike_picker", path), "adjacent key");
+        assert!(
+            !matches_with_cheap_typo("fize_picker", path),
+            "arbitrary typo"
+        );
+        assert!(!matches_with_cheap_typo("fiel_pikcre", path), "two typos");
+    }
+}
@@ -0,0 +1,442 @@
+//! Working copy statuses of the version control system the base path lives in. Git is read
+//! through libgit2, jujutsu, mercurial and subversion through their command line tools. Their
+//! statuses are mapped onto the git ones, so modification scoring and the status badges work
+//! the same in every repository. Builds without the `git` feature don't recognize plain git
+//! repositories.
+
+use crate::git::{GitStatusCache, Status};
+#[cfg(feature = "git")]
+use git2::{Repository, StatusOptions};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcsKind {
+    Git,
+    Jujutsu,
+    Mercurial,
+    Subversion,
+}
+
+impl VcsKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VcsKind::Git => "git",
+            VcsKind::Jujutsu => "jj",
+            VcsKind::Mercurial => "hg",
+            VcsKind::Subversion => "svn",
+        }
+    }
+}
+
+pub trait StatusProvider: Send + Sync + std::fmt::Debug {
+    fn kind(&self) -> VcsKind;
+
+    /// Root of the working copy, the reported paths are absolute paths below it
+    fn root(&self) -> &Path;
+
+    /// Statuses of the changed, new and conflicted files, anything missing is unmodified
+    fn changed_files(&self) -> Option<GitStatusCache>;
+
+    /// Statuses of every file of `indexed`, clean files included so statuses of files that
+    /// were committed or reverted since the last read get cleared
+    fn all_files(&self, indexed: &mut dyn Iterator<Item = &Path>) -> Option<GitStatusCache> {
+        Some(self.changed_files()?.with_unmodified(indexed))
+    }
+}
+
+/// Finds the repository containing `base_path`. A git repository wins, so colocated jujutsu
+/// repositories are read through libgit2, otherwise the closest `.jj`, `.hg` or `.svn` does.
+pub fn detect(base_path: &Path) -> Option<Arc<dyn StatusProvider>> {
+    #[cfg(feature = "git")]
+    if let Some(workdir) = Repository::discover(base_path)
+        .ok()
+        .and_then(|repo| repo.workdir().map(Path::to_path_buf))
+    {
+        return Some(Arc::new(GitProvider { root: workdir }));
+    }
+
+    base_path.ancestors().find_map(|dir| {
+        let provider: Arc<dyn StatusProvider> = if dir.join(".jj").is_dir() {
+            Arc::new(JujutsuProvider {
+                root: dir.to_path_buf(),
+            })
+        } else if dir.join(".hg").is_dir() {
+            Arc::new(MercurialProvider {
+                root: dir.to_path_buf(),
+            })
+        } else if dir.join(".svn").is_dir() {
+            Arc::new(SubversionProvider {
+                root: dir.to_path_buf(),
+            })
+        } else {
+            return None;
+        };
+        Some(provider)
+    })
+}
+
+#[cfg(feature = "git")]
+#[derive(Debug, Clone)]
+pub struct GitProvider {
+    root: PathBuf,
+}
+
+#[cfg(feature = "git")]
+impl StatusProvider for GitProvider {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Git
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn changed_files(&self) -> Option<GitStatusCache> {
+        GitStatusCache::read_git_status(
+            Some(&self.root),
+            // do not include unmodified here to avoid extra cost
+            // we are treating all missing files as unmodified
+            StatusOptions::new()
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .exclude_submodules(true),
+        )
+    }
+
+    fn all_files(&self, _indexed: &mut dyn Iterator<Item = &Path>) -> Option<GitStatusCache> {
+        GitStatusCache::read_git_status(
+            Some(&self.root),
+            StatusOptions::new()
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                // when manually refreshing git status we want to include all unmodified file
+                // to make sure that their status is correctly updated when user
+                // commited/stashed/removed changes
+                .include_unmodified(true)
+                .exclude_submodules(true),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JujutsuProvider {
+    root: PathBuf,
+}
+
+impl StatusProvider for JujutsuProvider {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Jujutsu
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn changed_files(&self) -> Option<GitStatusCache> {
+        let output = run(&self.root, "jj", &["status", "--color=never", "--no-pager"])?;
+        Some(GitStatusCache::from_entries(parse_jj_status(
+            &self.root, &output,
+        )))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MercurialProvider {
+    root: PathBuf,
+}
+
+impl StatusProvider for MercurialProvider {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Mercurial
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn changed_files(&self) -> Option<GitStatusCache> {
+        let output = run(&self.root, "hg", &["status"])?;
+        // unresolved merge conflicts are only listed by resolve, status shows them as modified.
+        // The first status of a path is kept, so the conflicts go first.
+        let mut entries = run(&self.root, "hg", &["resolve", "--list"])
+            .map(|output| parse_hg_resolve(&self.root, &output))
+            .unwrap_or_default();
+        entries.extend(parse_hg_status(&self.root, &output));
+        Some(GitStatusCache::from_entries(entries))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubversionProvider {
+    root: PathBuf,
+}
+
+impl StatusProvider for SubversionProvider {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Subversion
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn changed_files(&self) -> Option<GitStatusCache> {
+        let output = run(&self.root, "svn", &["status", "--non-interactive"])?;
+        Some(GitStatusCache::from_entries(parse_svn_status(
+            &self.root, &output,
+        )))
+    }
+}
+
+/// Stdout of `program` run in `root`, `None` if it is not installed or fails
+fn run(root: &Path, program: &str, args: &[&str]) -> Option<String> {
+    let status_start = std::time::Instant::now();
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(root)
+        // plain, untranslated output of mercurial without pager and colors
+        .env("HGPLAIN", "1")
+        .output()
+        .map_err(|e| error!("Failed to run {}: {}", program, e))
+        .ok()?;
+    if !output.status.success() {
+        error!(
+            "{} {:?} failed: {}",
+            program,
+            args,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    debug!(
+        "{} {:?} completed in {:?}",
+        program,
+        args,
+        status_start.elapsed()
+    );
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `jj status` lists the working copy changes as `M path` lines, renames as
+/// `R dir/{old => new}` and the conflicts in their own section after the changes
+fn parse_jj_status(root: &Path, output: &str) -> Vec<(PathBuf, Status)> {
+    let mut entries = Vec::new();
+    let mut in_conflicts = false;
+    for line in output.lines() {
+        if line.contains("There are unresolved conflicts at these paths") {
+            in_conflicts = true;
+            continue;
+        }
+        if in_conflicts {
+            // `path    2-sided conflict`, the section ends at the first line without one
+            match line.rsplit_once("-sided conflict") {
+                Some((path, _)) => {
+                    let path = path
+                        .trim_end()
+                        .trim_end_matches(|c: char| c.is_ascii_digit());
+                    entries.push((root.join(path.trim_end()), Status::CONFLICTED));
+                    continue;
+                }
+                None => in_conflicts = false,
+            }
+        }
+
+        let Some((code, path)) = line.split_once(' ') else {
+            continue;
+        };
+        let status = match code {
+            "M" => Status::WT_MODIFIED,
+            // jj tracks new files on its own, there is nothing like an index to stage them in
+            "A" | "C" => Status::WT_NEW,
+            "D" => Status::WT_DELETED,
+            "R" => Status::WT_RENAMED,
+            _ => continue,
+        };
+        entries.push((root.join(renamed_path(path)), status));
+    }
+    entries
+}
+
+/// New path of a `dir/{old => new}.rs` rename
+fn renamed_path(path: &str) -> String {
+    let Some((prefix, rest)) = path.split_once('{') else {
+        return path.to_string();
+    };
+    let Some((renamed, suffix)) = rest.split_once('}') else {
+        return path.to_string();
+    };
+    let new = renamed.split_once(" => ").map_or(renamed, |(_, new)| new);
+    format!("{}{}{}", prefix, new, suffix).replace("//", "/")
+}
+
+/// `hg status` prints `M path` lines, `!` for missing files and `R` for removed ones
+fn parse_hg_status(root: &Path, output: &str) -> Vec<(PathBuf, Status)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (code, path) = line.split_once(' ')?;
+            let status = match code {
+                "M" => Status::WT_MODIFIED,
+                "A" => Status::INDEX_NEW,
+                "R" => Status::INDEX_DELETED,
+                "!" => Status::WT_DELETED,
+                "?" => Status::WT_NEW,
+                "I" => Status::IGNORED,
+                "C" => Status::CURRENT,
+                _ => return None,
+            };
+            Some((root.join(path), status))
+        })
+        .collect()
+}
+
+/// `hg resolve --list` marks the unresolved files of a merge with `U`
+fn parse_hg_resolve(root: &Path, output: &str) -> Vec<(PathBuf, Status)> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("U "))
+        .map(|path| (root.join(path), Status::CONFLICTED))
+        .collect()
+}
+
+/// `svn status` prints seven status columns and a space before the path. A conflict in the
+/// content, property or tree column wins over the content status.
+fn parse_svn_status(root: &Path, output: &str) -> Vec<(PathBuf, Status)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns = line.get(..7)?.as_bytes();
+            let path = line.get(7..)?.strip_prefix(' ')?.trim_start();
+            if path.is_empty() {
+                return None;
+            }
+            if columns[1] == b'C' || columns[6] == b'C' {
+                return Some((root.join(path), Status::CONFLICTED));
+            }
+
+            let status = match columns[0] {
+                b'M' | b'R' | b'~' => Status::WT_MODIFIED,
+                b'A' => Status::INDEX_NEW,
+                b'D' => Status::INDEX_DELETED,
+                b'C' => Status::CONFLICTED,
+                b'?' => Status::WT_NEW,
+                b'!' => Status::WT_DELETED,
+                b'I' => Status::IGNORED,
+                b' ' if columns[1] == b'M' => Status::WT_MODIFIED,
+                _ => return None,
+            };
+            Some((root.join(path), status))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(entries: Vec<(PathBuf, Status)>) -> Vec<(String, Status)> {
+        entries
+            .into_iter()
+            .map(|(path, status)| (path.to_string_lossy().into_owned(), status))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_jj_status() {
+        let output = "\
+Working copy changes:
+M src/lib.rs
+A src/new.rs
+D old.rs
+R src/{picker.rs => finder.rs}
+Working copy  (@) : kxqpvmzs 1a2b3c4d (no description set)
+Parent commit (@-): zzzzzzzz 00000000 (empty) (no description set)
+There are unresolved conflicts at these paths:
+src/merge.rs    2-sided conflict
+src/deep/file name.rs    3-sided conflict including 1 deletion
+";
+        assert_eq!(
+            statuses(parse_jj_status(Path::new("/repo"), output)),
+            [
+                ("/repo/src/lib.rs".to_string(), Status::WT_MODIFIED),
+                ("/repo/src/new.rs".to_string(), Status::WT_NEW),
+                ("/repo/old.rs".to_string(), Status::WT_DELETED),
+                ("/repo/src/finder.rs".to_string(), Status::WT_RENAMED),
+                ("/repo/src/merge.rs".to_string(), Status::CONFLICTED),
+                (
+                    "/repo/src/deep/file name.rs".to_string(),
+                    Status::CONFLICTED
+                ),
+            ]
+        );
+        assert_eq!(renamed_path("{a.rs => b/c.rs}"), "b/c.rs");
+    }
+
+    #[test]
+    fn test_parse_hg_and_svn_status() {
+        let hg = "M src/lib.rs\nA new.rs\n! gone.rs\n? scratch file.txt\n";
+        assert_eq!(
+            statuses(parse_hg_status(Path::new("/repo"), hg)),
+            [
+                ("/repo/src/lib.rs".to_string(), Status::WT_MODIFIED),
+                ("/repo/new.rs".to_string(), Status::INDEX_NEW),
+                ("/repo/gone.rs".to_string(), Status::WT_DELETED),
+                ("/repo/scratch file.txt".to_string(), Status::WT_NEW),
+            ]
+        );
+        assert_eq!(
+            statuses(parse_hg_resolve(
+                Path::new("/repo"),
+                "R done.rs\nU src/lib.rs\n"
+            )),
+            [("/repo/src/lib.rs".to_string(), Status::CONFLICTED)]
+        );
+
+        let svn = "\
+M       src/lib.rs
+?       notes.txt
+C       merge.rs
+ M      props.rs
+A  +    copied.rs
+      C tree.rs
+      >   local edit, incoming delete upon update
+Summary of conflicts:
+  Text conflicts: 1
+";
+        assert_eq!(
+            statuses(parse_svn_status(Path::new("/repo"), svn)),
+            [
+                ("/repo/src/lib.rs".to_string(), Status::WT_MODIFIED),
+                ("/repo/notes.txt".to_string(), Status::WT_NEW),
+                ("/repo/merge.rs".to_string(), Status::CONFLICTED),
+                ("/repo/props.rs".to_string(), Status::WT_MODIFIED),
+                ("/repo/copied.rs".to_string(), Status::INDEX_NEW),
+                ("/repo/tree.rs".to_string(), Status::CONFLICTED),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_unmodified() {
+        let cache = GitStatusCache::from_entries(vec![
+            (PathBuf::from("/repo/b.rs"), Status::WT_MODIFIED),
+            (PathBuf::from("/repo/a.rs"), Status::WT_NEW),
+        ]);
+        let indexed = [Path::new("/repo/c.rs"), Path::new("/repo/a.rs")];
+        let cache = cache.with_unmodified(indexed.into_iter());
+
+        assert_eq!(cache.statuses_len(), 3);
+        assert_eq!(
+            cache.lookup_status(Path::new("/repo/c.rs")),
+            Some(Status::CURRENT)
+        );
+        assert_eq!(
+            cache.lookup_status(Path::new("/repo/a.rs")),
+            Some(Status::WT_NEW)
+        );
+    }
+}
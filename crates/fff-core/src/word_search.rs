@@ -0,0 +1,153 @@
+//! "Where else does this appear" for the word under the cursor. Files whose path matches the
+//! word, files containing it and files usually edited together with the current one are ranked
+//! in one list, so a single jump command covers going to the definition file, the callers and
+//! the test next to it.
+
+use crate::grep::GrepResult;
+use crate::types::FileItem;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Points of the best path match, the following fuzzy matches get linearly less
+const NAME_POINTS: i32 = 100;
+/// Points per line containing the word, up to `MAX_COUNTED_LINES` lines
+const CONTENT_POINTS: i32 = 20;
+pub(crate) const MAX_COUNTED_LINES: usize = 5;
+/// Added to the files with a name or content match that were edited with the current file
+const CO_CHANGE_POINTS: i32 = 50;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WordHit {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub score: i32,
+    /// The path fuzzy matches the word
+    pub name_match: bool,
+    /// Lines containing the word, counted up to `MAX_COUNTED_LINES`
+    pub content_matches: usize,
+    /// First line containing the word, 1-based, 0 without content matches
+    pub lnum: usize,
+    pub col: usize,
+    /// Text of the first line containing the word
+    pub text: String,
+    pub co_changed: bool,
+}
+
+/// Merges the signals into hits, best first. `name_matches` are in fuzzy score order and
+/// `current_file` is left out, the caller is already there.
+pub fn rank(
+    name_matches: &[&FileItem],
+    content: GrepResult,
+    co_changed: &HashSet<PathBuf>,
+    current_file: Option<&Path>,
+    max_results: usize,
+) -> Vec<WordHit> {
+    let mut hits: HashMap<PathBuf, WordHit> = HashMap::new();
+    for (rank, file) in name_matches.iter().enumerate() {
+        let points = NAME_POINTS * (name_matches.len() - rank) as i32 / name_matches.len() as i32;
+        let hit = hits.entry(file.path.clone()).or_insert_with(|| WordHit {
+            path: file.path.clone(),
+            relative_path: file.relative_path.clone(),
+            ..WordHit::default()
+        });
+        hit.name_match = true;
+        hit.score += points;
+    }
+
+    for m in content.matches {
+        let hit = hits.entry(m.path.clone()).or_insert_with(|| WordHit {
+            path: m.path.clone(),
+            relative_path: m.relative_path.clone(),
+            ..WordHit::default()
+        });
+        if hit.content_matches == MAX_COUNTED_LINES {
+            continue;
+        }
+        if hit.content_matches == 0 {
+            hit.lnum = m.lnum;
+            hit.col = m.col;
+            hit.text = m.text;
+        }
+        hit.content_matches += 1;
+        hit.score += CONTENT_POINTS;
+    }
+
+    let mut hits: Vec<WordHit> = hits
+        .into_values()
+        .filter(|hit| current_file != Some(hit.path.as_path()))
+        .map(|mut hit| {
+            hit.co_changed = co_changed.contains(&hit.path);
+            if hit.co_changed {
+                hit.score += CO_CHANGE_POINTS;
+            }
+            hit
+        })
+        .collect();
+    hits.sort_unstable_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    hits.truncate(max_results);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grep::GrepMatch;
+
+    fn file(path: &str) -> FileItem {
+        FileItem::new(path.into(), "".as_ref(), None)
+    }
+
+    fn line(path: &str, lnum: usize) -> GrepMatch {
+        GrepMatch {
+            path: path.into(),
+            relative_path: path.to_string(),
+            lnum,
+            col: 1,
+            ..GrepMatch::default()
+        }
+    }
+
+    #[test]
+    fn test_rank_merges_signals() {
+        let picker = file("src/picker.rs");
+        let ui = file("lua/picker_ui.lua");
+        let content = GrepResult {
+            matches: vec![
+                line("src/main.rs", 3),
+                line("src/main.rs", 9),
+                line("src/picker.rs", 1),
+                line("src/lib.rs", 2),
+                line("src/current.rs", 5),
+            ],
+            ..GrepResult::default()
+        };
+        let co_changed = HashSet::from([PathBuf::from("src/lib.rs")]);
+
+        let hits = rank(
+            &[&picker, &ui],
+            content,
+            &co_changed,
+            Some(Path::new("src/current.rs")),
+            10,
+        );
+        let order: Vec<(&str, i32)> = hits
+            .iter()
+            .map(|hit| (hit.relative_path.as_str(), hit.score))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("src/picker.rs", 120),
+                ("src/lib.rs", 70),
+                ("lua/picker_ui.lua", 50),
+                ("src/main.rs", 40),
+            ]
+        );
+        assert_eq!((hits[3].lnum, hits[3].content_matches), (3, 2));
+        assert!(hits[1].co_changed && !hits[1].name_match);
+    }
+}
@@ -0,0 +1,85 @@
+#![no_main]
+
+use fff_core::score::match_and_score_files;
+use fff_core::types::{FileItem, ScoringContext, ScoringWeights};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use std::path::PathBuf;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    query: String,
+    current_file: Option<String>,
+    relative_paths: Vec<String>,
+    frecency_scores: Vec<i64>,
+    max_results: u8,
+    max_typos: u8,
+    max_threads: u8,
+}
+
+fn file_item(relative_path: String, frecency_score: i64) -> FileItem {
+    let file_name = relative_path
+        .rsplit(std::path::MAIN_SEPARATOR)
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    FileItem {
+        path: PathBuf::from("/fuzz").join(&relative_path),
+        relative_path,
+        file_name,
+        size: 0,
+        modified: 0,
+        access_frecency_score: frecency_score,
+        modification_frecency_score: 0,
+        jump_frecency_score: 0,
+        commit_frecency_score: 0,
+        total_frecency_score: frecency_score,
+        git_status: None,
+        diagnostics: Default::default(),
+        category: None,
+        package: None,
+        root: None,
+        sensitivity: None,
+        meta: None,
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let files = input
+        .relative_paths
+        .into_iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let frecency_score = input.frecency_scores.get(index).copied().unwrap_or(0);
+            file_item(path, frecency_score)
+        })
+        .collect::<Vec<_>>();
+
+    let context = ScoringContext {
+        query: &input.query,
+        current_file: input.current_file.as_deref(),
+        max_results: input.max_results as usize,
+        max_typos: input.max_typos as u16,
+        max_threads: (input.max_threads as usize % 4).max(1),
+        weights: ScoringWeights::default(),
+        category_filter: None,
+        file_filter: None,
+        profile: None,
+        glob_filter: None,
+        current_package: None,
+        ticket: None,
+        narrowed_to: None,
+        co_changed: None,
+        related_by_history: None,
+        session_files: None,
+        time_of_day_scores: None,
+        entry_points: None,
+        candidates: None,
+    };
+
+    let (items, scores, total_matched, _) = match_and_score_files(&files, &context);
+    assert_eq!(items.len(), scores.len());
+    assert!(items.len() <= context.max_results);
+    assert!(total_matched >= items.len());
+});
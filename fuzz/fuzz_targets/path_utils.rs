@@ -0,0 +1,25 @@
+#![no_main]
+
+use fff_core::path_utils::{calculate_distance_penalty, calculate_tail_match_ratio};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    current_file: Option<String>,
+    candidate_path: String,
+    matched_indices: Vec<u16>,
+}
+
+fuzz_target!(|input: Input| {
+    let penalty = calculate_distance_penalty(input.current_file.as_deref(), &input.candidate_path);
+    assert!((-20..=0).contains(&penalty));
+
+    let matched_indices = input
+        .matched_indices
+        .into_iter()
+        .map(usize::from)
+        .collect::<Vec<_>>();
+    let ratio = calculate_tail_match_ratio(&input.candidate_path, &matched_indices);
+    assert!((0..=100).contains(&ratio));
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use fff_core::category::CategoryFilter;
+use fff_core::filter::FileFilter;
+use fff_core::profile::ProfileFilter;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|query: &str| {
+    // the filter tokens are dropped from the remaining query, nothing is added to it
+    let (rest, _) = CategoryFilter::parse(query);
+    assert!(rest.len() <= query.len());
+
+    let (rest, _) = FileFilter::parse(query);
+    assert!(rest.len() <= query.len());
+
+    let (rest, _) = ProfileFilter::parse(query);
+    assert!(rest.len() <= query.len());
+});
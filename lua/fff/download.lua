@@ -0,0 +1,151 @@
+--- Installs the native library from the prebuilt release assets, for machines without a rust
+--- toolchain. Doesn't load the library itself so it works before anything was built.
+local M = {}
+
+--- `owner/name` of the GitHub repository whose releases are downloaded, the repository the plugin
+--- was cloned from when nil, e.g. a fork publishing its own builds
+M.repository = nil
+
+--- Releases of the upstream repository are used when the origin can't be read
+M.default_repository = 'dmtrKovalenko/fff.nvim'
+
+local plugin_root = debug.getinfo(1).source:match('@?(.*/)') .. '../../'
+
+--- Target triple of the release asset built for this machine and the extension of the library
+--- @return string|nil, string
+local function release_target()
+  local os_name = jit.os:lower()
+  local cpu = ({ x64 = 'x86_64', arm64 = 'aarch64' })[jit.arch:lower()]
+  if not cpu then return nil, 'no prebuilt library for the ' .. jit.arch .. ' architecture' end
+
+  if os_name == 'osx' or os_name == 'mac' then return cpu .. '-apple-darwin', '.dylib' end
+  if os_name == 'windows' then
+    if cpu ~= 'x86_64' then return nil, 'no prebuilt library for windows on ' .. cpu end
+    return 'x86_64-pc-windows-msvc', '.dll'
+  end
+  if os_name == 'linux' then
+    if (vim.env.PREFIX or ''):match('com%.termux') then return 'aarch64-linux-android', '.so' end
+    local musl = #vim.fn.glob('/lib/ld-musl-*', false, true) > 0
+    return cpu .. '-unknown-linux-' .. (musl and 'musl' or 'gnu'), '.so'
+  end
+
+  return nil, 'no prebuilt library for ' .. jit.os
+end
+
+--- Name `require('fff_nvim')` looks for, msvc builds have no lib prefix
+local function library_name(extension)
+  if extension == '.dll' then return 'fff_nvim.dll' end
+  return 'libfff_nvim' .. extension
+end
+
+local function run(cmd)
+  local result = vim.system(cmd, { text = true }):wait()
+  if result.code ~= 0 then return nil, vim.trim(result.stderr or '') end
+  return result.stdout
+end
+
+--- Body of the url, or writes it to `destination`
+local function curl(url, destination)
+  local cmd = { 'curl', '--fail', '--location', '--silent', '--show-error', '--max-time', '120', url }
+  if destination then vim.list_extend(cmd, { '-o', destination }) end
+  return run(cmd)
+end
+
+--- `owner/name` of the GitHub remote the plugin was cloned from
+--- @return string|nil
+local function origin_repository()
+  if vim.fn.executable('git') == 0 then return nil end
+  local url = run({ 'git', '-C', plugin_root, 'remote', 'get-url', 'origin' })
+  if not url then return nil end
+  url = vim.trim(url):gsub('%.git$', '')
+  return url:match('github%.com[:/]([^/]+/[^/]+)$')
+end
+
+--- Hex sha256 of a file with whatever tool the platform has
+local function file_sha256(path)
+  local tools = {
+    { 'sha256sum', path },
+    { 'shasum', '-a', '256', path },
+    { 'certutil', '-hashfile', path, 'SHA256' },
+  }
+  for _, cmd in ipairs(tools) do
+    if vim.fn.executable(cmd[1]) == 1 then
+      -- certutil prints a header line before the hash
+      for hash in (run(cmd) or ''):gmatch('%x+') do
+        if #hash == 64 then return hash:lower() end
+      end
+    end
+  end
+  return nil
+end
+
+--- Download the prebuilt library of a release, verify its checksum and install it where the
+--- plugin loads it from. A running neovim keeps using the library it already loaded.
+--- @param version string|nil Release tag like `v0.2.0`, the latest release when nil
+--- @param target_dir string|nil Directory of the library, `target/release` of the plugin when nil
+--- @param repository string|nil `owner/name` to download from, `M.repository` when nil
+--- @return string|nil Path of the installed library
+--- @return string|nil Error message
+function M.download_prebuilt(version, target_dir, repository)
+  local target, extension = release_target()
+  if not target then return nil, extension end
+  if vim.fn.executable('curl') == 0 then return nil, 'curl is required to download the library' end
+
+  repository = repository or M.repository or origin_repository() or M.default_repository
+  local asset = target .. extension
+  local release = version and ('download/' .. version) or 'latest/download'
+  local url = string.format('https://github.com/%s/releases/%s/%s', repository, release, asset)
+
+  target_dir = target_dir or (plugin_root .. 'target/release')
+  vim.fn.mkdir(target_dir, 'p')
+  local destination = target_dir .. '/' .. library_name(extension)
+  local download = destination .. '.download'
+
+  local checksum, err = curl(url .. '.sha256')
+  if not checksum then return nil, 'failed to download the checksum of ' .. asset .. ': ' .. err end
+  local expected = checksum:match('^%s*(%x+)')
+
+  local ok
+  ok, err = curl(url, download)
+  if not ok then
+    os.remove(download)
+    return nil, 'failed to download ' .. asset .. ': ' .. err
+  end
+
+  local actual = file_sha256(download)
+  if not expected or actual ~= expected:lower() then
+    os.remove(download)
+    if not actual then return nil, 'no sha256sum, shasum or certutil to verify ' .. asset end
+    return nil, string.format('checksum mismatch for %s: expected %s, got %s', asset, expected, actual)
+  end
+
+  -- windows can't replace or delete a loaded library but can rename it, so the installed one is
+  -- moved aside first and put back if the new one can't take its place. Libraries moved aside
+  -- by earlier installs are deleted once no instance has them loaded anymore.
+  for _, previous in ipairs(vim.fn.glob(destination .. '.old-*', false, true)) do
+    os.remove(previous)
+  end
+  local previous = nil
+  if vim.uv.fs_stat(destination) then
+    previous = string.format('%s.old-%d', destination, os.time())
+    ok, err = os.rename(destination, previous)
+    if not ok then
+      os.remove(download)
+      return nil, 'failed to move the installed library aside: ' .. err
+    end
+  end
+
+  ok, err = os.rename(download, destination)
+  if not ok then
+    os.remove(download)
+    if previous and not os.rename(previous, destination) then
+      return nil, string.format('failed to install the library: %s, the previous one is left at %s', err, previous)
+    end
+    return nil, 'failed to install the library: ' .. err
+  end
+  if previous then os.remove(previous) end
+
+  return destination
+end
+
+return M
@@ -85,35 +85,65 @@ end
 --- @param query string Search query
 --- @param max_results number Maximum number of results (optional)
 --- @param current_file string|nil Path to current file to deprioritize (optional)
+--- @param context table|nil `{ win = id, buf = id }` whose root set with `set_context_root` scopes the search
 --- @return table List of matching files
-function M.search_files(query, max_results, max_threads, current_file)
+function M.search_files(query, max_results, max_threads, current_file, context)
   if not M.state.initialized then return {} end
 
+  if context then context = fuzzy.context(context.win, context.buf) end
+
   max_results = max_results or M.config.max_results
   max_threads = max_threads or M.config.max_threads
 
-  local ok, search_result = pcall(fuzzy.fuzzy_search_files, query, max_results, max_threads, current_file)
+  local ok, search_result = pcall(fuzzy.search_files, {
+    query = query,
+    max_results = max_results,
+    max_threads = max_threads,
+    current_file = current_file,
+    picker_id = 'files',
+    context = context,
+    scorer = M.config.scorer,
+    compare_scorer = M.config.compare_scorer,
+  })
   if not ok then
     vim.notify('Failed to search files: ' .. tostring(search_result), vim.log.levels.ERROR)
     return {}
   end
 
+  -- a newer query of this picker is already running, its results will replace these
+  if search_result.superseded and M.state.last_search_result then return M.state.last_search_result.items end
+
   -- Store search metadata for UI display
   M.state.last_search_result = search_result
 
+  -- match the usual first keystrokes while the user looks at the opening results
+  if query == '' then pcall(fuzzy.speculate_search, max_results, max_threads, current_file, 'files', context) end
+
   return search_result.items
 end
 
 --- Get the last search result metadata
---- @return table Search metadata with total_matched and total_files
+--- @return table Search metadata with total_matched, total_files, approximate and the `comparison` of `compare_scorer`
 function M.get_search_metadata()
-  if not M.state.last_search_result then return { total_matched = 0, total_files = 0 } end
+  if not M.state.last_search_result then return { total_matched = 0, total_files = 0, approximate = false } end
   return {
     total_matched = M.state.last_search_result.total_matched,
     total_files = M.state.last_search_result.total_files,
+    approximate = M.state.last_search_result.approximate,
+    comparison = M.state.last_search_result.comparison,
   }
 end
 
+--- Edit script from the previous results of the picker to the last ones
+--- @return table|nil Diff with `kept` pairs of previous and current indices, `inserted` and `removed` indices and `unchanged`
+function M.get_result_diff()
+  if not M.state.last_search_result then return nil end
+  return M.state.last_search_result.diff
+end
+
+--- Start the result diffs over, the next search reports every result as inserted
+function M.forget_result_diff() pcall(fuzzy.forget_result_diff, 'files') end
+
 --- Get score information for a file by index (1-based)
 --- @param index number The index of the file in the last search results
 --- @return table|nil Score information or nil if not available
@@ -131,6 +161,17 @@ function M.get_file_score(index)
     special_filename_bonus = score.special_filename_bonus or 0,
     frecency_boost = score.frecency_boost or 0,
     distance_penalty = score.distance_penalty or 0,
+    depth_penalty = score.depth_penalty or 0,
+    path_component_bonus = score.path_component_bonus or 0,
+    diagnostics_bonus = score.diagnostics_bonus or 0,
+    package_bonus = score.package_bonus or 0,
+    word_order_bonus = score.word_order_bonus or 0,
+    abbreviation_bonus = score.abbreviation_bonus or 0,
+    typo_bonus = score.typo_bonus or 0,
+    co_change_bonus = score.co_change_bonus or 0,
+    session_bonus = score.session_bonus or 0,
+    time_of_day_boost = score.time_of_day_boost or 0,
+    normalized = score.normalized,
     match_type = score.match_type or 'unknown',
   }
 end
@@ -187,6 +228,15 @@ function M.get_scan_progress()
   return result
 end
 
+--- Report the rows of the result list, searches with `max_results = 0` return about what fits
+--- @param rows number Height of the list window
+function M.set_viewport(rows) pcall(fuzzy.set_viewport, rows) end
+
+--- Report the columns of the result list left for a path, results then carry `display_path`
+--- shortened to fit with `display_highlights` of the query
+--- @param columns number Width of a path in the list, 0 turns it off
+function M.set_path_width(columns) pcall(fuzzy.set_path_width, columns) end
+
 --- Refresh git status on cached files (call after git status loading completes)
 --- @return table List of files with updated git status
 function M.refresh_git_status()
@@ -22,6 +22,23 @@ local function append_buffer_lines(bufnr, lines)
   vim.api.nvim_buf_set_option(bufnr, 'modifiable', false)
 end
 
+local REDACTION_FAILED = 'Preview hidden, the credentials of the file could not be masked'
+
+local function redaction_enabled()
+  local config = require('fff.main').config
+  return config and config.redaction and config.redaction.enabled
+end
+
+--- Masks credentials of whole lines with the configured redaction patterns before they reach a
+--- preview buffer, the lines are replaced by a notice when they can't be masked
+local function redact(lines)
+  if not redaction_enabled() then return lines end
+
+  local ok, redacted = pcall(require('fff.fuzzy').redact_lines, lines)
+  if not ok then return { REDACTION_FAILED } end
+  return redacted
+end
+
 local function find_existing_buffer(file_path)
   local abs_path = vim.fn.resolve(vim.fn.fnamemodify(file_path, ':p'))
 
@@ -68,41 +85,73 @@ local function init_dynamic_loading_async(file_path, callback)
   end)
 end
 
+--- Reads and masks whole lines in rust, splitting raw chunks could cut a secret in two
+local function load_redacted_chunk_async(chunk_size, callback)
+  local file_path = M.state.file_operation.file_path
+  local position = M.state.file_operation.position
+
+  vim.schedule(function()
+    M.state.is_loading = false
+
+    local ok, lines, next_position, eof =
+      pcall(require('fff.fuzzy').read_redacted_lines, file_path, position, chunk_size)
+    if not ok then
+      M.state.has_more_content = false
+      cleanup_file_operation()
+      callback({}, REDACTION_FAILED .. ': ' .. tostring(lines))
+      return
+    end
+
+    if M.state.file_operation then M.state.file_operation.position = next_position end
+    if eof then
+      M.state.has_more_content = false
+      cleanup_file_operation()
+    end
+
+    callback(lines, nil)
+  end)
+end
+
 local function load_forward_chunk_async(target_size, callback)
   if not M.state.file_operation or not M.state.file_operation.fd then
-    callback('', 'No file handle available')
+    callback({}, 'No file handle available')
     return
   end
 
   M.state.is_loading = true
   local chunk_size = target_size or (M.config.chunk_size or 16384)
+  if redaction_enabled() then
+    load_redacted_chunk_async(chunk_size, callback)
+    return
+  end
 
   vim.uv.fs_read(M.state.file_operation.fd, chunk_size, M.state.file_operation.position, function(err, data)
     vim.schedule(function()
       M.state.is_loading = false
 
       if err then
-        callback('', 'Read error: ' .. err)
+        callback({}, 'Read error: ' .. err)
         return
       end
 
       if not data or #data == 0 then
         M.state.has_more_content = false
         cleanup_file_operation()
-        callback('', nil)
+        callback({}, nil)
         return
       end
 
       if M.state.file_operation then M.state.file_operation.position = M.state.file_operation.position + #data end
 
-      callback(data, nil)
+      -- there seems to be no other way to append the buffer other than the lines :(
+      callback(vim.split(data, '\n', { plain = true }), nil)
     end)
   end)
 end
 
 local function load_next_chunk_async(chunk_size, callback)
   if not M.state.file_operation or not M.state.has_more_content or M.state.is_loading then
-    callback('', nil)
+    callback({}, nil)
     return
   end
   load_forward_chunk_async(chunk_size, callback)
@@ -115,10 +164,8 @@ local function read_file_streaming_async(file_path, bufnr, callback)
       return
     end
 
-    load_next_chunk_async(M.config.chunk_size, function(data, err)
-      if data and data ~= '' then
-        -- there seems to be no other way to append the buffer other than the lines :(
-        local lines = vim.split(data, '\n', { plain = true })
+    load_next_chunk_async(M.config.chunk_size, function(lines, err)
+      if not err and #lines > 0 then
         M.state.loaded_lines = #lines
         M.state.content_height = #lines
 
@@ -144,7 +191,7 @@ local function ensure_content_loaded_async(target_line)
     append_buffer_lines(M.state.bufnr, { '', loading_line })
   end
 
-  load_next_chunk_async(M.config.chunk_size, function(data, err)
+  load_next_chunk_async(M.config.chunk_size, function(chunk_lines, err)
     if err then
       vim.notify('Error loading file content: ' .. err, vim.log.levels.ERROR)
       -- Remove loading message on error
@@ -156,8 +203,7 @@ local function ensure_content_loaded_async(target_line)
       return
     end
 
-    if data and data ~= '' then
-      local chunk_lines = vim.split(data, '\n', { plain = true })
+    if #chunk_lines > 0 then
       local total_lines = vim.api.nvim_buf_line_count(M.state.bufnr)
 
       if total_lines >= 2 then
@@ -184,7 +230,7 @@ local function ensure_content_loaded_async(target_line)
 end
 
 local function link_buffer_content(source_bufnr, target_bufnr)
-  local lines = vim.api.nvim_buf_get_lines(source_bufnr, 0, -1, false)
+  local lines = redact(vim.api.nvim_buf_get_lines(source_bufnr, 0, -1, false))
   set_buffer_lines(target_bufnr, lines)
 
   local source_ft = vim.api.nvim_buf_get_option(source_bufnr, 'filetype')
@@ -451,8 +497,25 @@ function M.create_file_info_content(file, info, file_index)
     )
     table.insert(
       lines,
-      string.format('Score Modifiers: frec_boost=%d, dist_penalty=%d', score.frecency_boost, score.distance_penalty)
+      string.format(
+        'Score Modifiers: frec_boost=%d, dist_penalty=%d, depth_penalty=%d, path_bonus=%d, diag_bonus=%d, pkg_bonus=%d, order_bonus=%d, abbrev_bonus=%d, typo_bonus=%d, co_change_bonus=%d, session_bonus=%d, time_boost=%d',
+        score.frecency_boost,
+        score.distance_penalty,
+        score.depth_penalty or 0,
+        score.path_component_bonus or 0,
+        score.diagnostics_bonus or 0,
+        score.package_bonus or 0,
+        score.word_order_bonus or 0,
+        score.abbreviation_bonus or 0,
+        score.typo_bonus or 0,
+        score.co_change_bonus or 0,
+        score.session_bonus or 0,
+        score.time_of_day_boost or 0
+      )
     )
+    if score.normalized then
+      table.insert(lines, string.format('Relative Score: %d%% of the best match', score.normalized))
+    end
   else
     table.insert(lines, 'Score Breakdown: N/A (no score data available)')
   end
@@ -553,6 +616,88 @@ end
 --- @param file_path string Path to the file
 --- @param bufnr number Buffer number for preview
 --- @return boolean Success status
+--- Entries of archives are not extracted for the preview, they are read when opened
+--- @param file_path string Indexed path like `archive.zip::path/inside`
+--- @param bufnr number Buffer number for preview
+function M.preview_archive_entry(file_path, bufnr)
+  local archive, entry = file_path:match('^(.-)::(.+)$')
+  set_buffer_lines(bufnr, {
+    'Archive entry: ' .. entry,
+    'Archive: ' .. vim.fn.fnamemodify(archive, ':.'),
+    '',
+    'Open it to read the entry without extracting the archive',
+  })
+  vim.api.nvim_buf_set_option(bufnr, 'filetype', 'text')
+  vim.api.nvim_buf_set_option(bufnr, 'modifiable', false)
+  return true
+end
+
+--- Show a placeholder instead of a file flagged as sensitive
+--- @param file_path string Path to the file
+--- @param sensitivity string Why the file was flagged: 'secret', 'foreign_owner' or 'world_writable'
+--- @param bufnr number Buffer number for preview
+--- @return boolean Success status
+function M.preview_sensitive(file_path, sensitivity, bufnr)
+  if M.state.file_handle then
+    M.state.file_handle:close()
+    M.state.file_handle = nil
+  end
+  M.state.current_file = file_path
+  M.state.bufnr = bufnr
+
+  M.clear_preview_visual_state(bufnr)
+  set_buffer_lines(bufnr, {
+    'Preview hidden: ' .. sensitivity:gsub('_', ' ') .. ' file',
+    '',
+    'Set scan.sensitive.mode to "flag" or "off" to preview it.',
+  })
+  vim.api.nvim_buf_set_option(bufnr, 'filetype', 'text')
+  M.state.content_height = 3
+  M.state.scroll_offset = 0
+  return true
+end
+
+--- Preview a file of a remote host, only the head of the file is fetched over ssh
+--- @param host string Host the file is on
+--- @param file_path string Path of the file on the host
+--- @param bufnr number Buffer number for preview
+--- @return boolean Success status
+function M.preview_remote(host, file_path, bufnr)
+  if M.state.file_handle then
+    M.state.file_handle:close()
+    M.state.file_handle = nil
+  end
+  M.state.current_file = file_path
+  M.state.bufnr = bufnr
+  set_buffer_lines(bufnr, { 'Fetching ' .. host .. ':' .. file_path .. '...' })
+
+  local max_bytes = math.min(M.config.max_size or 10 * 1024 * 1024, 256 * 1024)
+  local remote_command = string.format('head -c %d %s', max_bytes, vim.fn.shellescape(file_path))
+  vim.system({ 'ssh', '-o', 'BatchMode=yes', host, remote_command }, { text = true }, function(result)
+    vim.schedule(function()
+      if M.state.current_file ~= file_path or not vim.api.nvim_buf_is_valid(bufnr) then return end
+
+      if result.code ~= 0 then
+        set_buffer_lines(bufnr, { 'Failed to fetch ' .. host .. ':' .. file_path, result.stderr or '' })
+        return
+      end
+
+      local content = redact(vim.split(result.stdout or '', '\n', { plain = true }))
+      M.clear_preview_visual_state(bufnr)
+      set_buffer_lines(bufnr, content)
+
+      local file_config = M.get_file_config(file_path)
+      vim.api.nvim_buf_set_option(bufnr, 'filetype', vim.filetype.match({ filename = file_path }) or 'text')
+      vim.api.nvim_buf_set_option(bufnr, 'wrap', file_config.wrap_lines or M.config.wrap_lines)
+      vim.api.nvim_buf_set_option(bufnr, 'number', M.config.line_numbers)
+      M.state.content_height = #content
+      M.state.scroll_offset = 0
+    end)
+  end)
+
+  return true
+end
+
 function M.preview_binary_file(file_path, bufnr)
   local info = M.get_file_info(file_path)
   local lines = {}
@@ -649,6 +794,8 @@ function M.preview(file_path, bufnr)
     local win_height = vim.api.nvim_win_get_height(M.state.winid) - 2
 
     return image.display_image(file_path, bufnr, win_width, win_height)
+  elseif utils.archive_entry_url(file_path) then
+    return M.preview_archive_entry(file_path, bufnr)
   elseif M.is_binary_file(file_path) then
     return M.preview_binary_file(file_path, bufnr)
   else
@@ -4,9 +4,37 @@ local M = {}
 local ok, rust_module = pcall(require, 'fff.rust')
 if not ok then error('Failed to load fff.rust module: ' .. rust_module) end
 
+--- Major version of the library interface these lua files are written against
+M.REQUIRED_API_MAJOR = 1
+
+-- libraries built before the handshake have no version and none of the capabilities
+M.api = rust_module.get_api_version and rust_module.get_api_version()
+  or { major = 0, minor = 0, patch = 0, version = '0.0.0', capabilities = 0, features = {} }
+
+if M.api.major ~= M.REQUIRED_API_MAJOR then
+  vim.schedule(function()
+    vim.notify(
+      string.format(
+        'fff.nvim: native library interface %s, %d.x expected: rebuild it with `cargo build --release`',
+        M.api.version,
+        M.REQUIRED_API_MAJOR
+      ),
+      vim.log.levels.WARN
+    )
+  end)
+end
+
+--- Whether the loaded library has a capability, the names are listed in lua/fff/rust/api.rs
+--- @param capability string
+--- @return boolean
+function M.has(capability) return M.api.features[capability] == true end
+
 -- export all functions from the Rust module
 M.init_db = rust_module.init_db
 M.destroy_db = rust_module.destroy_db
+M.flush_db = rust_module.flush_db
+M.shutdown = rust_module.shutdown
+M.compact_db = rust_module.compact_db
 M.access = rust_module.access
 M.set_provider_items = rust_module.set_provider_items
 M.fuzzy = rust_module.fuzzy
@@ -16,20 +44,96 @@ M.guess_edit_range = rust_module.guess_edit_range
 M.get_words = rust_module.get_words
 M.init_file_picker = rust_module.init_file_picker
 M.restart_index_in_path = rust_module.restart_index_in_path
+M.index_file_list = rust_module.index_file_list
+M.index_remote = rust_module.index_remote
+M.set_context_root = rust_module.set_context_root
 M.scan_files = rust_module.scan_files
 M.get_cached_files = rust_module.get_cached_files
+M.live_grep = rust_module.live_grep
+M.search_word = rust_module.search_word
+M.prepare_replace = rust_module.prepare_replace
+M.apply_replace = rust_module.apply_replace
 M.fuzzy_search_files = rust_module.fuzzy_search_files
+M.speculate_search = rust_module.speculate_search
+M.push_filter = rust_module.push_filter
+M.pop_filter = rust_module.pop_filter
+M.set_list_entries = rust_module.set_list_entries
+M.fuzzy_search_list = rust_module.fuzzy_search_list
 M.track_access = rust_module.track_access
+M.track_edit = rust_module.track_edit
+M.migrate_frecency_prefix = rust_module.migrate_frecency_prefix
+M.forget_frecency_project = rust_module.forget_frecency_project
+M.register_scorer = rust_module.register_scorer
+M.unregister_scorer = rust_module.unregister_scorer
+M.list_scorers = rust_module.list_scorers
+M.watch_extra_path = rust_module.watch_extra_path
+M.get_frecency_details = rust_module.get_frecency_details
+M.get_access_counts = rust_module.get_access_counts
+M.get_related_by_history = rust_module.get_related_by_history
 M.add_file = rust_module.add_file
 M.remove_file = rust_module.remove_file
+M.set_file_meta = rust_module.set_file_meta
+M.set_diagnostics_counts = rust_module.set_diagnostics_counts
+M.set_jump_positions = rust_module.set_jump_positions
+M.configure = rust_module.configure
+M.redact_lines = rust_module.redact_lines
+M.read_redacted_lines = rust_module.read_redacted_lines
 M.cancel_scan = rust_module.cancel_scan
 M.get_scan_progress = rust_module.get_scan_progress
+M.check_index_drift = rust_module.check_index_drift
+M.get_index_drift = rust_module.get_index_drift
+M.get_metrics = rust_module.get_metrics
+M.poll_events = rust_module.poll_events
+M.set_viewport = rust_module.set_viewport
+M.set_path_width = rust_module.set_path_width
+M.set_search_trace = rust_module.set_search_trace
+M.forget_result_diff = rust_module.forget_result_diff
+M.subscribe_scan_batches = rust_module.subscribe_scan_batches
+M.unsubscribe_scan_batches = rust_module.unsubscribe_scan_batches
+M.poll_scan_batches = rust_module.poll_scan_batches
+M.get_index_stats = rust_module.get_index_stats
+M.diff_index = rust_module.diff_index
+M.init_snapshots = rust_module.init_snapshots
+M.attach_daemon = rust_module.attach_daemon
+M.detach_daemon = rust_module.detach_daemon
+M.is_daemon_attached = rust_module.is_daemon_attached
 M.is_scanning = rust_module.is_scanning
 M.refresh_git_status = rust_module.refresh_git_status
+M.notify_focus_gained = rust_module.notify_focus_gained
 M.update_single_file_frecency = rust_module.update_single_file_frecency
 M.stop_background_monitor = rust_module.stop_background_monitor
 M.cleanup_file_picker = rust_module.cleanup_file_picker
 M.init_tracing = rust_module.init_tracing
 M.wait_for_initial_scan = rust_module.wait_for_initial_scan
+M.generate_debug_bundle = rust_module.generate_debug_bundle
+M.get_api_version = rust_module.get_api_version
+
+--- Fuzzy search with a `SearchOptions` table, spread into the positional arguments for
+--- libraries that predate it
+--- @param options table
+function M.search_files(options)
+  if M.has('search_options') then return rust_module.fuzzy_search_files(options) end
+  return rust_module.fuzzy_search_files(
+    options.query,
+    options.max_results or 100,
+    options.max_threads or 4,
+    options.current_file,
+    options.picker_id,
+    options.group_by_directory,
+    options.context,
+    options.globs
+  )
+end
+
+--- Context of a window and buffer the way the library takes it, `{ win = id, buf = id }`.
+--- Libraries that predate `context_kinds` take a single id or a list of the window then the
+--- buffer.
+--- @param win number|nil
+--- @param buf number|nil
+function M.context(win, buf)
+  if M.has('context_kinds') then return { win = win, buf = buf } end
+  if win and buf then return { win, buf } end
+  return win or buf
+end
 
 return M
@@ -8,6 +8,8 @@ M.highlights = {
   staged_new = 'FFFGitStaged',
   staged_modified = 'FFFGitStaged',
   staged_deleted = 'FFFGitStaged',
+  staged_renamed = 'FFFGitStaged',
+  conflicted = 'FFFGitConflicted',
   ignored = 'FFFGitIgnored',
   clean = '',
   clear = '',
@@ -25,6 +27,8 @@ M.border_chars = {
   staged_new = '┃', -- Vertical line
   staged_modified = '┃', -- Vertical line
   staged_deleted = '▁', -- Bottom horizontal line
+  staged_renamed = '┃', -- Vertical line
+  conflicted = '┃', -- Vertical line
   clean = '',
   clear = '',
 }
@@ -37,6 +41,8 @@ M.border_highlights = {
   staged_new = 'FFFGitSignStaged',
   staged_modified = 'FFFGitSignStaged',
   staged_deleted = 'FFFGitSignStaged',
+  staged_renamed = 'FFFGitSignStaged',
+  conflicted = 'FFFGitSignConflicted',
   ignored = 'FFFGitSignIgnored',
   clean = '',
   clear = '',
@@ -51,6 +57,8 @@ M.border_highlights_selected = {
   staged_new = 'FFFGitSignStagedSelected',
   staged_modified = 'FFFGitSignStagedSelected',
   staged_deleted = 'FFFGitSignStagedSelected',
+  staged_renamed = 'FFFGitSignStagedSelected',
+  conflicted = 'FFFGitSignConflictedSelected',
   ignored = 'FFFGitSignIgnoredSelected',
   clean = '',
   clear = '',
@@ -73,6 +81,8 @@ function M.should_show_border(git_status)
     or git_status == 'deleted'
     or git_status == 'staged_deleted'
     or git_status == 'renamed'
+    or git_status == 'staged_renamed'
+    or git_status == 'conflicted'
 end
 
 function M.setup_highlights()
@@ -84,6 +94,7 @@ function M.setup_highlights()
     highlight default FFFGitRenamed guifg=#8B5CF6 ctermfg=5
     highlight default FFFGitUntracked guifg=#10B981 ctermfg=2
     highlight default FFFGitIgnored guifg=#4B5563 ctermfg=8
+    highlight default FFFGitConflicted guifg=#EC4899 ctermfg=13
     
     " Thin border highlights 
     highlight default FFFGitSignStaged guifg=#10B981 ctermfg=2
@@ -92,6 +103,7 @@ function M.setup_highlights()
     highlight default FFFGitSignRenamed guifg=#8B5CF6 ctermfg=5
     highlight default FFFGitSignUntracked guifg=#10B981 ctermfg=2
     highlight default FFFGitSignIgnored guifg=#4B5563 ctermfg=8
+    highlight default FFFGitSignConflicted guifg=#EC4899 ctermfg=13
     
     " Fallback to GitSigns highlights if they exist
     highlight default link FFFGitSignStaged GitSignsAdd
@@ -108,6 +120,7 @@ function M.setup_highlights()
     { 'FFFGitSignRenamed', 'FFFGitSignRenamedSelected', '#8B5CF6', 5 },
     { 'FFFGitSignUntracked', 'FFFGitSignUntrackedSelected', '#10B981', 2 },
     { 'FFFGitSignIgnored', 'FFFGitSignIgnoredSelected', '#4B5563', 8 },
+    { 'FFFGitSignConflicted', 'FFFGitSignConflictedSelected', '#EC4899', 13 },
   }
 
   for _, hl in ipairs(git_highlights) do
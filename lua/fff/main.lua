@@ -32,6 +32,11 @@ function M.setup(config)
         text = { wrap_lines = true },
       },
     },
+    redaction = {
+      enabled = false,
+      patterns = nil,
+      mask = '*',
+    },
     keymaps = {
       close = '<Esc>',
       select = '<CR>',
@@ -65,10 +70,101 @@ function M.setup(config)
     frecency = {
       enabled = true,
       db_path = vim.fn.stdpath('cache') .. '/fff_nvim',
+      half_life_days = 10,
+      max_history_days = 30,
+      session_gap_minutes = 30,
+      access_soft_cap = 10, -- decayed accesses counted in full
+      access_curve = 0.5, -- growth above the soft cap, 0.5 is a square root
+      max_score = 50, -- ceiling of the frecency score of a file
+      forget_projects_after_days = 180, -- drop projects unused this long, 0 keeps them
+      track_edits = true,
+    },
+    scan = {
+      custom_ignore_filenames = {},
+      global_ignore_files = false,
+      relative_to_git_root = false,
+      max_depth = nil,
+      case_insensitive = nil,
+      categories = nil,
+      recent_commits = 20,
+      co_change_commits = 1000,
+      throttle = nil, -- e.g. { threads = 2, files_per_second = 2000 } on network filesystems
+      archive_extensions = {}, -- e.g. { 'zip', 'jar', 'tar' } to index the entries of archives
+      mtime_fallback = nil, -- nil enables it only in builds without git support
+      sensitive = {
+        mode = 'off',
+        patterns = nil,
+        check_ownership = false,
+      },
+    },
+    watcher = {
+      debounce_ms = 500,
+      backend = 'native', -- 'poll' where inotify/FSEvents are unavailable
+      poll_interval_ms = 2000,
+    },
+    memory = {
+      budget_mb = nil,
+    },
+    daemon = {
+      enabled = false,
+      cmd = 'fff',
+      idle_exit_secs = 300,
+      start_timeout_ms = 2000,
+    },
+    snapshot = {
+      enabled = false,
+      db_path = vim.fn.stdpath('cache') .. '/fff_snapshots',
+    },
+    git = {
+      refresh_interval_ms = 0,
+      refresh_on_focus = true,
+    },
+    index_health = {
+      check_interval_ms = 60000,
+      sample_size = 200,
+      drift_threshold_percent = 5,
+    },
+    scoring = {
+      frecency = 100,
+      distance = 100,
+      depth = 100,
+      filename = 100,
+      path_component = 100,
+      diagnostics = 0,
+      package_scope = 'off',
+      word_order = 0,
+      session = 0,
+      time_of_day = 0,
+      normalize_scores = false,
+      tie_breaks = { 'frecency', 'modified', 'path_length', 'alphabetical' },
+    },
+    -- selected with `profile:<name>` in the query or `find_files({ profile = '<name>' })`, e.g.
+    -- code = { extensions = { 'rs', 'lua' }, ignore = { 'vendor/**' }, scoring = { frecency = 50 } }
+    profiles = {},
+    -- alternative weights merged over `scoring` by name, e.g. no_depth = { depth = 0 }
+    scorers = {},
+    scorer = nil, -- name of the scorer ranking the picker results
+    compare_scorer = nil, -- also rank with this scorer and report how far the rankings diverge
+    grep = {
+      before_context = 0,
+      after_context = 0,
+      max_matches_per_file = 20,
+      max_matches = 1000,
+      max_file_size = 10 * 1024 * 1024,
+    },
+    diagnostics = {
+      enabled = false,
+      debounce_ms = 200,
+    },
+    recency = {
+      jumplist = true,
+      changelist = true,
     },
     debug = {
       enabled = false,
       show_scores = false,
+      trace_searches = 0, -- latest searches added to debug bundles for replaying them
+      selection_history = nil, -- jsonl file the picked files are appended to, see fff_repl --eval
     },
     logging = {
       enabled = true,
@@ -104,16 +200,49 @@ function M.setup(config)
     end
   end
 
+  if merged_config.debug.trace_searches > 0 and fuzzy.has('search_trace') then
+    fuzzy.set_search_trace(merged_config.debug.trace_searches)
+  end
+
+  if next(merged_config.scorers) and fuzzy.has('scorers') then
+    for name, weights in pairs(merged_config.scorers) do
+      local ok, err = pcall(fuzzy.register_scorer, name, vim.tbl_deep_extend('force', merged_config.scoring, weights))
+      if not ok then vim.notify('Failed to register scorer ' .. name .. ': ' .. tostring(err), vim.log.levels.WARN) end
+    end
+  end
+
   local db_path = merged_config.frecency.db_path or (vim.fn.stdpath('cache') .. '/fff_nvim')
   local ok, result = pcall(fuzzy.init_db, db_path, true)
   if not ok then vim.notify('Failed to initialize frecency database: ' .. result, vim.log.levels.WARN) end
 
-  ok, result = pcall(fuzzy.init_file_picker, merged_config.base_path)
-  if not ok then
-    vim.notify('Failed to initialize file picker: ' .. result, vim.log.levels.ERROR)
-    return false
+  if merged_config.snapshot.enabled then
+    ok, result = pcall(fuzzy.init_snapshots, merged_config.snapshot.db_path)
+    if not ok then vim.notify('Failed to initialize index snapshots: ' .. result, vim.log.levels.WARN) end
+  end
+
+  local attached = merged_config.daemon.enabled and M.attach_daemon(merged_config.base_path)
+  if merged_config.daemon.enabled and not attached then
+    vim.notify('Failed to attach to the fff daemon, indexing in this instance', vim.log.levels.WARN)
+  end
+
+  if not attached then
+    ok, result = pcall(fuzzy.init_file_picker, merged_config.base_path, merged_config.scan)
+    if not ok then
+      vim.notify('Failed to initialize file picker: ' .. result, vim.log.levels.ERROR)
+      return false
+    end
   end
 
+  ok, result = pcall(fuzzy.configure, {
+    scoring = merged_config.scoring,
+    watcher = merged_config.watcher,
+    frecency = merged_config.frecency,
+    memory = merged_config.memory,
+    profiles = M.resolve_profiles(merged_config),
+    redaction = merged_config.redaction,
+  })
+  if not ok then vim.notify('Failed to apply configuration: ' .. result, vim.log.levels.WARN) end
+
   M.state.initialized = true
   M.config = merged_config
 
@@ -153,12 +282,89 @@ function M.setup_global_autocmds()
     })
   end
 
+  if M.config.frecency.enabled and M.config.frecency.track_edits then
+    -- edits score like git modifications, so files outside of git get the modification boost too
+    local last_tracked = {}
+    vim.api.nvim_create_autocmd({ 'TextChanged', 'TextChangedI' }, {
+      group = group,
+      desc = 'Track buffer edits for FFF modification scoring',
+      callback = function(args)
+        if vim.bo[args.buf].buftype ~= '' then return end
+        local file_path = vim.api.nvim_buf_get_name(args.buf)
+        if file_path == '' or vim.fn.filereadable(file_path) == 0 then return end
+
+        -- the score only changes by the minute, typing doesn't need to report every keystroke
+        local now = os.time()
+        if last_tracked[args.buf] and now - last_tracked[args.buf] < 30 then return end
+        last_tracked[args.buf] = now
+
+        local ok, track_err = pcall(fuzzy.track_edit, file_path, now)
+        if not ok then vim.notify('FFF: Failed to track edit: ' .. tostring(track_err), vim.log.levels.WARN) end
+      end,
+    })
+    vim.api.nvim_create_autocmd('BufWipeout', {
+      group = group,
+      desc = 'Forget the FFF edit throttling of wiped buffers',
+      callback = function(args) last_tracked[args.buf] = nil end,
+    })
+  end
+
+  if M.config.diagnostics.enabled then
+    local diagnostics_timer = vim.uv.new_timer()
+    vim.api.nvim_create_autocmd('DiagnosticChanged', {
+      group = group,
+      desc = 'Push diagnostics counts to FFF',
+      callback = function()
+        diagnostics_timer:stop()
+        diagnostics_timer:start(M.config.diagnostics.debounce_ms, 0, vim.schedule_wrap(M.push_diagnostics_counts))
+      end,
+    })
+  end
+
+  if M.config.git.refresh_on_focus then
+    vim.api.nvim_create_autocmd('FocusGained', {
+      group = group,
+      desc = 'Refresh FFF git statuses changed outside of the editor',
+      callback = function() pcall(fuzzy.notify_focus_gained) end,
+    })
+  end
+
+  local refresh_interval_ms = M.config.git.refresh_interval_ms
+  if refresh_interval_ms > 0 then
+    if M.git_refresh_timer then M.git_refresh_timer:stop() end
+    M.git_refresh_timer = vim.uv.new_timer()
+    M.git_refresh_timer:start(
+      refresh_interval_ms,
+      refresh_interval_ms,
+      vim.schedule_wrap(function() pcall(fuzzy.notify_focus_gained) end)
+    )
+  end
+
+  local index_health = M.config.index_health
+  if index_health.check_interval_ms > 0 then
+    if M.drift_timer then M.drift_timer:stop() end
+    M.drift_timer = vim.uv.new_timer()
+    M.drift_timer:start(
+      index_health.check_interval_ms,
+      index_health.check_interval_ms,
+      vim.schedule_wrap(
+        function() pcall(fuzzy.check_index_drift, index_health.sample_size, index_health.drift_threshold_percent) end
+      )
+    )
+  end
+
   -- make sure that this won't work correctly if autochdir plugins are enabled
   -- using a pure :cd command but will work using lua api or :e command
   vim.api.nvim_create_autocmd('DirChanged', {
     group = group,
     callback = function()
       local new_cwd = vim.v.event.cwd
+      -- :lcd and :tcd only scope the searches of the window instead of moving the whole index
+      if vim.v.event.scope ~= 'global' then
+        if new_cwd then M.set_context_root({ win = vim.api.nvim_get_current_win() }, new_cwd) end
+        return
+      end
+
       if M.is_initialized() and new_cwd and new_cwd ~= M.config.base_path then
         vim.schedule(function()
           local ok, err = pcall(M.change_indexing_directory, new_cwd)
@@ -173,10 +379,30 @@ function M.setup_global_autocmds()
     desc = 'Automatically sync FFF directory changes',
   })
 
+  -- shells reporting their cwd with OSC 7 scope the searches from their terminal buffer
+  vim.api.nvim_create_autocmd('TermRequest', {
+    group = group,
+    callback = function(args)
+      local sequence = type(args.data) == 'table' and args.data.sequence or args.data
+      local cwd = type(sequence) == 'string' and sequence:match('^\027%]7;file://[^/]*(/[^\027\007]*)')
+      if cwd then M.set_context_root({ buf = args.buf }, vim.uri_decode(cwd)) end
+    end,
+    desc = 'Scope FFF searches from a terminal to its cwd',
+  })
+
+  vim.api.nvim_create_autocmd({ 'WinClosed', 'BufWipeout' }, {
+    group = group,
+    callback = function(args)
+      local context = args.event == 'WinClosed' and fuzzy.context(tonumber(args.match)) or fuzzy.context(nil, args.buf)
+      if context then pcall(fuzzy.set_context_root, context, nil) end
+    end,
+    desc = 'Forget the FFF search roots of closed windows and wiped buffers',
+  })
+
   vim.api.nvim_create_autocmd('VimLeavePre', {
     group = group,
-    callback = function() pcall(fuzzy.cleanup_file_picker) end,
-    desc = 'Cleanup FFF background threads on Neovim exit',
+    callback = function() M.shutdown() end,
+    desc = 'Shut FFF down cleanly on Neovim exit',
   })
 end
 
@@ -221,10 +447,30 @@ function M.setup_commands()
     desc = 'Clear FFF caches (all|frecency|files)',
   })
 
+  vim.api.nvim_create_user_command('FFFForgetProject', function(opts)
+    local forgotten = M.forget_frecency_project(opts.args ~= '' and opts.args or vim.uv.cwd())
+    if forgotten then vim.notify('Forgot the frecency of ' .. forgotten .. ' files', vim.log.levels.INFO) end
+  end, {
+    nargs = '?',
+    complete = 'dir',
+    desc = 'Drop the frecency history of a project, the current directory by default',
+  })
+
   vim.api.nvim_create_user_command('FFFHealth', function() M.health_check() end, {
     desc = 'Check FFF health',
   })
 
+  vim.api.nvim_create_user_command(
+    'FFFDebugBundle',
+    function(opts) M.generate_debug_bundle(opts.args ~= '' and opts.args or nil, { include_paths = opts.bang }) end,
+    {
+      nargs = '?',
+      bang = true,
+      complete = 'file',
+      desc = 'Write a debug bundle with logs and stats for bug reports, with ! the paths are not hashed',
+    }
+  )
+
   vim.api.nvim_create_user_command('FFFDebug', function(opts)
     if opts.args == 'toggle' or opts.args == '' then
       M.config.debug.show_scores = not M.config.debug.show_scores
@@ -260,10 +506,11 @@ function M.setup_commands()
 end
 
 --- Find files in current directory
-function M.find_files()
+--- @param opts table|nil Picker options, e.g. `{ profile = 'docs' }` to search with a profile
+function M.find_files(opts)
   local picker_ok, picker_ui = pcall(require, 'fff.picker_ui')
   if picker_ok then
-    picker_ui.open()
+    picker_ui.open(opts)
   else
     vim.notify('Failed to load picker UI', vim.log.levels.ERROR)
   end
@@ -298,10 +545,53 @@ end
 --- Search files programmatically
 --- @param query string Search query
 --- @param max_results number Maximum number of results
+--- @param opts table|nil `{ globs = { 'src/**', '!*.test.ts' } }` keeps the files passing rg style globs
 --- @return table List of matching files
-function M.search(query, max_results)
+function M.search(query, max_results, opts)
   max_results = max_results or M.config.max_results
-  local ok, search_result = pcall(fuzzy.fuzzy_search_files, query, max_results, nil, nil)
+  local ok, search_result = pcall(fuzzy.search_files, {
+    query = query,
+    max_results = max_results,
+    globs = opts and opts.globs,
+  })
+  if ok and search_result.items then return search_result.items end
+  return {}
+end
+
+--- Load the quickfix list, or the location list of a window, for `search_list`
+--- @param winid number|nil Window whose location list to load, quickfix list when nil
+--- @return number Number of loaded entries
+function M.set_quickfix_entries(winid)
+  local list = winid and vim.fn.getloclist(winid) or vim.fn.getqflist()
+  local entries = {}
+  for _, item in ipairs(list) do
+    local path = item.valid == 1 and item.bufnr > 0 and vim.api.nvim_buf_get_name(item.bufnr) or ''
+    if path ~= '' then
+      table.insert(entries, {
+        path = vim.fn.fnamemodify(path, ':.'),
+        lnum = item.lnum,
+        col = item.col,
+        text = item.text,
+      })
+    end
+  end
+
+  local ok, result = pcall(fuzzy.set_list_entries, entries)
+  if not ok then
+    vim.notify('Failed to load list entries: ' .. tostring(result), vim.log.levels.ERROR)
+    return 0
+  end
+
+  return result
+end
+
+--- Fuzzy search the entries loaded with `set_quickfix_entries` by path and text
+--- @param query string Search query
+--- @param max_results number|nil Maximum number of results
+--- @return table List of entries with path, lnum, col and text
+function M.search_list(query, max_results)
+  max_results = max_results or M.config.max_results
+  local ok, search_result = pcall(fuzzy.fuzzy_search_list, query, max_results, 4)
   if ok and search_result.items then return search_result.items end
   return {}
 end
@@ -363,17 +653,74 @@ function M.get_preview(file_path)
   return table.concat(lines, '\n')
 end
 
-function M.health_check()
+--- Collect health check results without reporting them
+--- @return table Health status with `ok` flag and `messages` list
+function M.collect_health()
   local health = {
     ok = true,
     messages = {},
   }
 
+  if fuzzy.api.major ~= fuzzy.REQUIRED_API_MAJOR then
+    health.ok = false
+    table.insert(
+      health.messages,
+      string.format(
+        'Native library interface %s, %d.x expected: rebuild it with `cargo build --release`',
+        fuzzy.api.version,
+        fuzzy.REQUIRED_API_MAJOR
+      )
+    )
+  else
+    table.insert(health.messages, string.format('✓ Native library interface %s', fuzzy.api.version))
+  end
+  if fuzzy.api.git == false then
+    table.insert(health.messages, 'Built without git support, git status and history are not read')
+  end
+
   if not M.is_initialized() then
     health.ok = false
     table.insert(health.messages, 'File picker not initialized')
   else
     table.insert(health.messages, '✓ File picker initialized')
+
+    local index_health = M.config.index_health
+    local ok, drift =
+      pcall(fuzzy.check_index_drift, index_health.sample_size, index_health.drift_threshold_percent)
+    if ok and drift.rescan_triggered then
+      table.insert(
+        health.messages,
+        string.format(
+          'Warning: index drifted %d%% (%d missing, %d outdated of %d sampled), rescan started',
+          drift.percent,
+          drift.missing,
+          drift.stale,
+          drift.sampled
+        )
+      )
+    elseif ok then
+      table.insert(
+        health.messages,
+        string.format('✓ Index in sync (%d%% drift in %d sampled files)', drift.percent, drift.sampled)
+      )
+    end
+
+    local metrics = M.get_metrics()
+    if metrics and metrics.over_budget then
+      table.insert(
+        health.messages,
+        string.format(
+          'Warning: index uses ~%d MB over the %d MB memory budget',
+          math.ceil(metrics.index_bytes / 1048576),
+          metrics.budget_bytes / 1048576
+        )
+      )
+    elseif metrics then
+      table.insert(
+        health.messages,
+        string.format('✓ Index of %d files uses ~%d MB', metrics.files, math.ceil(metrics.index_bytes / 1048576))
+      )
+    end
   end
 
   local optional_deps = {
@@ -391,6 +738,12 @@ function M.health_check()
     end
   end
 
+  return health
+end
+
+function M.health_check()
+  local health = M.collect_health()
+
   if health.ok then
     vim.notify('FFF health check passed ✓', vim.log.levels.INFO)
   else
@@ -399,7 +752,7 @@ function M.health_check()
 
   for _, message in ipairs(health.messages) do
     local level = message:match('^✓') and vim.log.levels.INFO
-      or message:match('^Optional:') and vim.log.levels.WARN
+      or (message:match('^Optional:') or message:match('^Warning:')) and vim.log.levels.WARN
       or vim.log.levels.ERROR
     vim.notify(message, level)
   end
@@ -409,6 +762,517 @@ end
 
 function M.is_initialized() return M.state and M.state.initialized or false end
 
+--- Write a tar archive with logs, health check, index stats, config and platform info for bug reports
+--- Paths and queries are replaced by their hashes unless `opts.include_paths` is set
+--- @param path string|nil Destination of the archive (defaults to a file in the cache directory)
+--- @param opts table|nil `{ include_paths = boolean }`
+--- @return string|nil Path of the written bundle or nil if failed
+function M.generate_debug_bundle(path, opts)
+  opts = opts or {}
+  path = path or (vim.fn.stdpath('cache') .. '/fff_debug_bundle_' .. os.date('%Y%m%d_%H%M%S') .. '.tar')
+  local health = M.collect_health()
+
+  local ok, result = pcall(fuzzy.generate_debug_bundle, vim.fn.expand(path), {
+    health = table.concat(health.messages, '\n'),
+    config = vim.inspect(M.config),
+    neovim = vim.inspect(vim.version()),
+  }, opts.include_paths == true)
+  if not ok then
+    vim.notify('Failed to generate debug bundle: ' .. tostring(result), vim.log.levels.ERROR)
+    return nil
+  end
+
+  vim.notify('FFF debug bundle written to ' .. result, vim.log.levels.INFO)
+  return result
+end
+
+--- Send the error and warning counts of every buffer to the index
+function M.push_diagnostics_counts()
+  local counts = {}
+  for _, diagnostic in ipairs(vim.diagnostic.get()) do
+    local severity = diagnostic.severity
+    local is_counted = severity == vim.diagnostic.severity.ERROR or severity == vim.diagnostic.severity.WARN
+    if is_counted and vim.api.nvim_buf_is_valid(diagnostic.bufnr) then
+      local path = vim.api.nvim_buf_get_name(diagnostic.bufnr)
+      if path ~= '' then
+        local count = counts[path] or { errors = 0, warnings = 0 }
+        if severity == vim.diagnostic.severity.ERROR then
+          count.errors = count.errors + 1
+        else
+          count.warnings = count.warnings + 1
+        end
+        counts[path] = count
+      end
+    end
+  end
+
+  local ok, result = pcall(fuzzy.set_diagnostics_counts, counts)
+  if not ok then vim.notify('FFF: Failed to update diagnostics: ' .. tostring(result), vim.log.levels.WARN) end
+end
+
+--- Send the jumplist and changelist locations to the index as a weak recency signal
+function M.push_jump_positions()
+  local recency = M.config.recency or {}
+  local paths = {}
+
+  -- changelists are per buffer without a global order, so they count as older than any jump
+  if recency.changelist then
+    for _, bufnr in ipairs(vim.api.nvim_list_bufs()) do
+      local name = vim.api.nvim_buf_get_name(bufnr)
+      if name ~= '' and vim.api.nvim_buf_is_loaded(bufnr) then
+        local changes = vim.fn.getchangelist(bufnr)[1] or {}
+        for _ = 1, math.min(#changes, 3) do
+          table.insert(paths, name)
+        end
+      end
+    end
+  end
+
+  if recency.jumplist then
+    for _, jump in ipairs(vim.fn.getjumplist()[1] or {}) do
+      if jump.bufnr and vim.api.nvim_buf_is_valid(jump.bufnr) then
+        local name = vim.api.nvim_buf_get_name(jump.bufnr)
+        if name ~= '' then table.insert(paths, name) end
+      end
+    end
+  end
+
+  pcall(fuzzy.set_jump_positions, paths)
+end
+
+--- Attach arbitrary metadata to an indexed file, returned as `item.meta` in search results
+--- @param path string Absolute path or path relative to the base path
+--- @param meta table|nil Key/value metadata, nil removes it
+--- @return boolean `true` if the file is indexed
+function M.set_file_meta(path, meta)
+  local ok, result = pcall(fuzzy.set_file_meta, path, meta)
+  if not ok then
+    vim.notify('Failed to set file metadata: ' .. tostring(result), vim.log.levels.ERROR)
+    return false
+  end
+
+  return result
+end
+
+local RUNTIME_SECTIONS = { 'scan', 'scoring', 'watcher', 'frecency', 'memory', 'profiles', 'redaction' }
+
+--- Profiles with their scoring weights merged over the global ones, so a profile only lists the
+--- weights it changes
+--- @param config table Full configuration
+--- @return table Profiles by name
+function M.resolve_profiles(config)
+  local profiles = {}
+  for name, profile in pairs(config.profiles or {}) do
+    profiles[name] = vim.tbl_extend('force', profile, {
+      scoring = profile.scoring and vim.tbl_deep_extend('force', config.scoring, profile.scoring),
+    })
+  end
+  return profiles
+end
+
+--- Update scan, scoring, watcher, frecency, memory, profile and redaction options at runtime without dropping the index.
+--- Only the sections present in `opts` are sent, merged over the current configuration.
+--- @param opts table Partial configuration, e.g. `{ scoring = { frecency = 50 } }`
+--- @return string[]|nil Names of the sections that changed or nil if failed
+function M.configure(opts)
+  opts = opts or {}
+  M.config = vim.tbl_deep_extend('force', M.config, opts)
+
+  local update = {}
+  for _, section in ipairs(RUNTIME_SECTIONS) do
+    if opts[section] ~= nil then update[section] = M.config[section] end
+  end
+  if update.profiles or update.scoring then update.profiles = M.resolve_profiles(M.config) end
+
+  local ok, result = pcall(fuzzy.configure, update)
+  if not ok then
+    vim.notify('Failed to apply configuration: ' .. tostring(result), vim.log.levels.ERROR)
+    return nil
+  end
+
+  return result
+end
+
+--- Attach to the shared daemon indexing `base_path`, starting it if no instance runs one yet.
+--- The daemon owns the scan and the watcher, this instance only forwards its queries.
+--- @param base_path string Directory to index
+--- @return boolean True if attached
+function M.attach_daemon(base_path)
+  if vim.fn.has('win32') == 1 then return false end
+
+  local daemon = M.config.daemon
+  local real_path = vim.uv.fs_realpath(base_path) or base_path
+  local socket_path =
+    string.format('%s/fff-%s.sock', vim.fn.stdpath('run'), vim.fn.sha256(real_path):sub(1, 16))
+
+  fuzzy.detach_daemon()
+  if fuzzy.attach_daemon(socket_path) then return true end
+
+  local cmd = { daemon.cmd, '--listen', socket_path, '--idle-exit', tostring(daemon.idle_exit_secs) }
+  if M.config.frecency.enabled then
+    vim.list_extend(cmd, { '--db', M.config.frecency.db_path })
+  else
+    table.insert(cmd, '--no-frecency')
+  end
+  table.insert(cmd, real_path)
+
+  local ok, job = pcall(vim.fn.jobstart, cmd, { detach = true })
+  if not ok or job <= 0 then return false end
+
+  return vim.wait(daemon.start_timeout_ms, function() return fuzzy.attach_daemon(socket_path) end, 50)
+end
+
+--- Estimated index size, memory budget state and what the allocator holds
+--- @return table|nil `{ files, index_bytes, meta_bytes, budget_bytes, over_budget, dropped_meta, allocator = { name, allocated, resident } }`
+function M.get_metrics()
+  local ok, metrics = pcall(fuzzy.get_metrics)
+  if not ok then return nil end
+  return metrics
+end
+
+--- Stop the watcher, store the index snapshot and flush pending frecency writes, e.g. before exiting
+--- @param step_timeout_ms number|nil How long each step may take before it's abandoned, 500 by default
+--- @return boolean Whether every step finished in time
+function M.shutdown(step_timeout_ms)
+  local ok, finished = pcall(fuzzy.shutdown, step_timeout_ms)
+  return ok and finished
+end
+
+--- Takes the events queued by the background watcher since the last poll, oldest first
+--- @return table[] `{ kind, path, timestamp }` where kind is 'root_removed' or 'watcher_restarted'
+function M.poll_events()
+  local ok, events = pcall(fuzzy.poll_events)
+  if not ok then return {} end
+  return events
+end
+
+local scan_batch_subscribers = {}
+local scan_batch_timer = nil
+
+--- Calls `callback` with batches of the files found by scans for live indexing progress, until unsubscribed
+--- @param callback function Called with `{ count, total, samples, done }`, `samples` being the latest relative paths found
+--- @return function Unsubscribes the callback
+function M.subscribe_scan_batches(callback)
+  if not scan_batch_timer then
+    if not pcall(fuzzy.subscribe_scan_batches) then return function() end end
+
+    scan_batch_timer = vim.uv.new_timer()
+    scan_batch_timer:start(
+      0,
+      100,
+      vim.schedule_wrap(function()
+        if not scan_batch_timer then return end
+        local ok, batches = pcall(fuzzy.poll_scan_batches)
+        if not ok then return end
+        for _, batch in ipairs(batches) do
+          for _, subscriber in pairs(scan_batch_subscribers) do
+            pcall(subscriber, batch)
+          end
+        end
+      end)
+    )
+  end
+
+  local id = {}
+  scan_batch_subscribers[id] = callback
+  return function()
+    scan_batch_subscribers[id] = nil
+    if next(scan_batch_subscribers) == nil and scan_batch_timer then
+      scan_batch_timer:stop()
+      scan_batch_timer:close()
+      scan_batch_timer = nil
+      pcall(fuzzy.unsubscribe_scan_batches)
+    end
+  end
+end
+
+--- Extension distribution and detected project languages of the latest full scan
+--- @return table|nil `{ files, extensions = { { extension, count } }, languages }`, most common first
+function M.get_index_stats()
+  local ok, stats = pcall(fuzzy.get_index_stats)
+  if not ok then return nil end
+  return stats
+end
+
+--- Files added, removed and changed in the index since a generation, for views updating incrementally
+--- @param since_generation number|nil `generation` of the previous diff, 0 for the first one
+--- @return table|nil `{ generation, added, removed, changed, reset }`, when `reset` is set the whole index has to be read again
+function M.diff_index(since_generation)
+  local ok, diff = pcall(fuzzy.diff_index, since_generation or 0)
+  if not ok then return nil end
+  return diff
+end
+
+--- Recent access counts for rendering hints like "opened 14x, last 2h ago" next to results
+--- @param paths string[] File paths, usually the `path` of the result items
+--- @return table<string, table> `{ count, last_access }` keyed by the given paths
+function M.get_access_counts(paths)
+  local ok, counts = pcall(fuzzy.get_access_counts, paths)
+  if not ok then return {} end
+  return counts
+end
+
+--- Files most frequently committed together with a file, mined from the git history after the scan
+--- @param path string File path
+--- @return table[] `{ path, count }` ordered by how many times they were committed together
+function M.get_related_by_history(path)
+  local ok, related = pcall(fuzzy.get_related_by_history, path)
+  if not ok then return {} end
+  return related
+end
+
+--- Move the frecency history of a relocated project or directory to its new path
+--- @param old_prefix string Path the files used to live under
+--- @param new_prefix string Path the files live under now
+--- @return number|nil Number of files whose history was migrated, nil on failure
+function M.migrate_frecency_prefix(old_prefix, new_prefix)
+  old_prefix = vim.fs.normalize(vim.fn.expand(old_prefix))
+  new_prefix = vim.fs.normalize(vim.fn.expand(new_prefix))
+
+  local ok, migrated = pcall(fuzzy.migrate_frecency_prefix, old_prefix, new_prefix)
+  if not ok then
+    vim.notify('Failed to migrate frecency: ' .. tostring(migrated), vim.log.levels.ERROR)
+    return nil
+  end
+  return migrated
+end
+
+--- Drop the frecency history of every file under a project, e.g. a repository that was deleted
+--- @param root string Root directory of the project
+--- @return number|nil Number of files whose history was dropped, nil on failure
+function M.forget_frecency_project(root)
+  if not fuzzy.has('forget_project') then
+    vim.notify('Forgetting projects needs a newer build of the fff library', vim.log.levels.WARN)
+    return nil
+  end
+  root = vim.fs.normalize(vim.fn.expand(root))
+
+  local ok, forgotten = pcall(fuzzy.forget_frecency_project, root)
+  if not ok then
+    vim.notify('Failed to forget frecency: ' .. tostring(forgotten), vim.log.levels.ERROR)
+    return nil
+  end
+  return forgotten
+end
+
+--- List the indexed files one page at a time, e.g. for an explorer view
+--- @param opts table|nil `{ offset = 0, limit = nil, sort_by = 'path'|'frecency'|'modified'|'size'|'git_status', prefix = nil }`, prefix matches the relative path
+--- @return table|nil `{ items, total }` where total counts the files matching the prefix across all pages
+function M.get_cached_files(opts)
+  local ok, page = pcall(fuzzy.get_cached_files, opts)
+  if not ok then return nil end
+  return page
+end
+
+--- Lines of the indexed files containing a literal pattern, smart case. Typing the pattern one
+--- character at a time only reads the files the previous pattern matched.
+--- @param pattern string Text to find, ascii case is ignored unless it contains uppercase letters
+--- @param opts table|nil Overrides of the `grep` configuration: before_context, after_context, max_matches_per_file, max_matches, max_file_size
+--- @return table|nil `{ matches, matched_files, searched_files, skipped_files, truncated }`, matches are quickfix items with `before`/`after` context lines and the `encoding` of their file
+function M.live_grep(pattern, opts)
+  opts = vim.tbl_extend('force', M.config.grep, opts or {})
+  local context = fuzzy.context(vim.api.nvim_get_current_win(), vim.api.nvim_get_current_buf())
+  local ok, result = pcall(fuzzy.live_grep, pattern, opts, context)
+  if not ok then
+    vim.notify('Failed to grep: ' .. tostring(result), vim.log.levels.ERROR)
+    return nil
+  end
+  return result
+end
+
+--- Grep the indexed files and load the matches into the quickfix list
+--- @param pattern string Text to find
+--- @param opts table|nil Same as `live_grep`
+--- @return number Number of loaded matches
+function M.grep_to_quickfix(pattern, opts)
+  local result = M.live_grep(pattern, opts)
+  if not result then return 0 end
+
+  vim.fn.setqflist({}, ' ', { title = 'fff grep: ' .. pattern, items = result.matches })
+  return #result.matches
+end
+
+--- Rank the files to jump to from a word: files whose path matches it, files containing it and
+--- files usually edited together with the current one
+--- @param word string|nil Word to look for, the word under the cursor by default
+--- @param current_file string|nil Path of the file the word comes from, the current buffer by default
+--- @return table|nil Quickfix items best first, with `score`, `name_match`, `content_matches` and `co_changed`
+function M.search_word(word, current_file)
+  word = word or vim.fn.expand('<cword>')
+  if word == '' then return nil end
+  if not current_file then
+    local name = vim.api.nvim_buf_get_name(0)
+    current_file = name ~= '' and name or nil
+  end
+
+  local ok, hits = pcall(fuzzy.search_word, word, current_file, 20)
+  if not ok then
+    vim.notify('Failed to search word: ' .. tostring(hits), vim.log.levels.ERROR)
+    return nil
+  end
+  return hits
+end
+
+--- Jump to the best file for the word under the cursor and load the others into the quickfix list
+--- @param word string|nil Word to look for, the word under the cursor by default
+--- @return boolean True if there was somewhere to go
+function M.goto_word(word)
+  word = word or vim.fn.expand('<cword>')
+  local hits = M.search_word(word)
+  if not hits or #hits == 0 then
+    vim.notify('No other file mentions ' .. word, vim.log.levels.INFO)
+    return false
+  end
+
+  vim.fn.setqflist({}, ' ', { title = 'fff word: ' .. word, items = hits })
+  vim.cmd('cfirst')
+  return true
+end
+
+--- Compute replacing `pattern` in the indexed files without writing anything, e.g. to show a
+--- rename preview and let the user pick the hunks to apply with `apply_replace`
+--- @param pattern string Text to replace, case sensitive
+--- @param replacement string Text inserted instead
+--- @param globs string[]|nil rg style globs restricting the files, e.g. `{ '*.lua', '!tests/**' }`
+--- @param opts table|nil `{ ignore_case }`, ignore_case also replaces differently cased ascii occurrences
+--- @return table|nil `{ pattern, replacement, hunk_count, files }`, every file has its `hunks` of `{ id, lnum, old_text, new_text }`
+function M.prepare_replace(pattern, replacement, globs, opts)
+  opts = opts or {}
+  local context = fuzzy.context(vim.api.nvim_get_current_win(), vim.api.nvim_get_current_buf())
+  local ok, plan = pcall(fuzzy.prepare_replace, pattern, replacement, globs, opts.ignore_case == true, context)
+  if not ok then
+    vim.notify('Failed to prepare replace: ' .. tostring(plan), vim.log.levels.ERROR)
+    return nil
+  end
+  return plan
+end
+
+--- Write the hunks of the last `prepare_replace`. Nothing is written when one of the files
+--- changed since, the caller should prepare again then.
+--- @param selection number[]|nil Ids of the hunks to apply, nil applies all of them
+--- @return table|nil `{ written, applied_hunks, conflicts, failed, error }`, conflicts lists the relative paths of the changed files, failed the file that couldn't be replaced after `written` were
+function M.apply_replace(selection)
+  local ok, outcome = pcall(fuzzy.apply_replace, selection)
+  if not ok then
+    vim.notify('Failed to apply replace: ' .. tostring(outcome), vim.log.levels.ERROR)
+    return nil
+  end
+
+  if #outcome.conflicts > 0 then
+    vim.notify(
+      'Files changed since the replace was prepared: ' .. table.concat(outcome.conflicts, ', '),
+      vim.log.levels.WARN
+    )
+    return outcome
+  end
+
+  if outcome.failed then
+    vim.notify(
+      string.format(
+        'Failed to replace %s after %d files: %s',
+        outcome.failed,
+        #outcome.written,
+        outcome.error
+      ),
+      vim.log.levels.ERROR
+    )
+  end
+  if #outcome.written > 0 then
+    -- reload the open buffers of the written files
+    vim.cmd('checktime')
+  end
+  return outcome
+end
+
+--- Restrict the following searches to the files currently matching the query, on top of the
+--- previous restrictions, for iterative refinement like `src` then `test` then `user`
+--- @param query string Query whose matches become the searched files
+--- @return number|nil Number of files left to search, nil on failure
+function M.push_filter(query)
+  local ok, count = pcall(fuzzy.push_filter, query)
+  if not ok then
+    vim.notify('Failed to push filter: ' .. tostring(count), vim.log.levels.ERROR)
+    return nil
+  end
+  return count
+end
+
+--- Drop the latest restriction added with `push_filter`
+--- @return boolean True if a restriction was dropped
+function M.pop_filter()
+  local ok, popped = pcall(fuzzy.pop_filter)
+  return ok and popped
+end
+
+--- Index and watch a directory outside the project along with it, e.g. a shared components
+--- folder symlinked into the project or generated code written elsewhere. Result items then
+--- carry a `root` label like 'web' telling apart identical relative paths of different roots
+--- @param path string Directory to add to the index
+--- @return boolean True if the directory was added, false if it is already indexed or on failure
+function M.watch_extra_path(path)
+  path = vim.fs.normalize(vim.fn.expand(path))
+
+  local ok, added = pcall(fuzzy.watch_extra_path, path)
+  if not ok then
+    vim.notify('Failed to watch ' .. path .. ': ' .. tostring(added), vim.log.levels.ERROR)
+    return false
+  end
+  return added
+end
+
+--- Replace the index with files listed elsewhere, e.g. inside a container. The listing is
+--- searched and scored like scanned files but never watched, call it again to refresh it
+--- @param base_path string Directory the listed paths are relative to, it doesn't have to exist locally
+--- @param files table List of paths or { path, size, modified } tables
+--- @return boolean True if the listing was indexed
+function M.index_file_list(base_path, files)
+  local ok, result = pcall(fuzzy.index_file_list, base_path, files)
+  if not ok then
+    vim.notify('Failed to index the file list: ' .. tostring(result), vim.log.levels.ERROR)
+    return false
+  end
+
+  M.config.base_path = base_path
+  M.state.remote_host = nil
+  return result
+end
+
+--- Replace the index with the files of a directory on a remote host, listed over ssh. Results
+--- open as `scp://` urls through netrw and previews are fetched when a file is selected
+--- @param host string Anything ssh accepts, e.g. 'user@host' or an alias from ~/.ssh/config
+--- @param base_path string Absolute path of the directory on the host
+--- @param opts table|nil { command = 'fd --type f . /srv/app' } to list the files with another command
+--- @return boolean True if the remote listing was started
+function M.index_remote(host, base_path, opts)
+  opts = opts or {}
+  local ok, result = pcall(fuzzy.index_remote, host, base_path, opts.command)
+  if not ok then
+    vim.notify('Failed to index ' .. host .. ':' .. base_path .. ': ' .. tostring(result), vim.log.levels.ERROR)
+    return false
+  end
+
+  M.config.base_path = base_path
+  M.state.remote_host = host
+  return result
+end
+
+--- Scope the searches made from a window or buffer to a directory of the index, e.g. the cwd
+--- of a terminal or a nested project, without reindexing. The picker uses the root of the
+--- window it is opened from, then the root of its buffer
+--- @param context table `{ win = id }` or `{ buf = id }`
+--- @param path string|nil Directory to scope the searches to, nil clears it
+--- @return boolean True if the root was set or cleared
+function M.set_context_root(context, path)
+  if path then path = vim.fs.normalize(vim.fn.expand(path)) end
+
+  local ok, result = pcall(fuzzy.set_context_root, fuzzy.context(context.win, context.buf), path)
+  if not ok then
+    vim.notify('Failed to set the search root: ' .. tostring(result), vim.log.levels.ERROR)
+    return false
+  end
+  return result
+end
+
 --- Find files in a specific directory
 --- @param directory string Directory path to search in
 function M.find_files_in_dir(directory)
@@ -443,6 +1307,11 @@ function M.change_indexing_directory(new_path)
     return false
   end
 
+  if fuzzy.is_daemon_attached() and M.attach_daemon(expanded_path) then
+    M.config.base_path = expanded_path
+    return true
+  end
+
   local ok, result = pcall(fuzzy.restart_index_in_path, expanded_path)
   if not ok then
     vim.notify('Failed to change directory: ' .. result, vim.log.levels.ERROR)
@@ -450,6 +1319,7 @@ function M.change_indexing_directory(new_path)
   end
 
   M.config.base_path = expanded_path
+  M.state.remote_host = nil
   return true
 end
 
@@ -4,6 +4,7 @@ local file_picker = require('fff.file_picker')
 local preview = require('fff.file_picker.preview')
 local icons = require('fff.file_picker.icons')
 local git_utils = require('fff.git_utils')
+local utils = require('fff.utils')
 local main = require('fff.main')
 
 if main.config and main.config.preview then preview.setup(main.config.preview) end
@@ -95,6 +96,9 @@ function M.create_ui()
     title = ' Files ',
     title_pos = 'left',
   })
+  file_picker.set_viewport(list_height)
+  local max_path_width = main.config.ui and main.config.ui.max_path_width or 80
+  file_picker.set_path_width(math.min(max_path_width, list_width - 3)) -- icon, space and border
 
   if debug_enabled_in_preview then
     M.state.file_info_win = vim.api.nvim_open_win(M.state.file_info_buf, false, {
@@ -387,18 +391,33 @@ function M.update_results_sync()
     end
   end
 
+  -- the profile the picker was opened with applies to every query typed
+  local query = M.state.query
+  if M.state.config.profile then query = 'profile:' .. M.state.config.profile .. ' ' .. query end
+
   local results = file_picker.search_files(
-    M.state.query,
+    query,
     M.state.config.max_results,
     M.state.config.max_threads,
-    M.state.current_file_cache
+    M.state.current_file_cache,
+    M.state.context
   )
 
+  -- the same files in the same order only need the counts refreshed
+  local diff = file_picker.get_result_diff()
+  local unchanged = diff and diff.unchanged and M.state.cursor == 1 and #M.state.items == #results
+
   -- because the actual files could be different even with same count
   M.state.items = results
   M.state.filtered_items = results
   M.state.cursor = 1
   M.state.top = 1
+  if unchanged then
+    vim.schedule(function()
+      if M.state.active then M.update_status() end
+    end)
+    return
+  end
   M.render_debounced()
 end
 
@@ -448,10 +467,19 @@ local function shrink_path(path, max_width)
 end
 
 local function format_file_display(item, max_width)
+  -- shortened in rust around the matched directories, the file name is always kept whole
+  if item.display_path then
+    local dir_path, filename = item.display_path:match('^(.*)/([^/]*)$')
+    if not dir_path then return item.display_path, '' end
+    return filename, dir_path
+  end
+
   local filename = item.name
   local dir_path = item.directory or ''
 
-  if dir_path == '' and item.relative_path then
+  if item.layout then
+    filename, dir_path = item.layout.filename, item.layout.directory
+  elseif dir_path == '' and item.relative_path then
     local parent_dir = vim.fn.fnamemodify(item.relative_path, ':h')
     if parent_dir ~= '.' and parent_dir ~= '' then dir_path = parent_dir end
   end
@@ -517,7 +545,13 @@ function M.render_list()
       frecency = string.format(' %s%d', indicator, total_frecency)
     end
 
-    local suffix = frecency
+    local diagnostics = ''
+    if main.config.diagnostics and main.config.diagnostics.enabled then
+      if (item.error_count or 0) > 0 then diagnostics = diagnostics .. string.format(' E%d', item.error_count) end
+      if (item.warning_count or 0) > 0 then diagnostics = diagnostics .. string.format(' W%d', item.warning_count) end
+    end
+
+    local suffix = diagnostics .. frecency
     local current_indicator = ''
     if item.is_current_file then current_indicator = ' (current)' end
 
@@ -537,6 +571,7 @@ function M.render_list()
     line_data[i] = {
       filename_len = #filename,
       dir_path_len = #dir_path,
+      display_highlights = not item.is_current_file and item.display_highlights or nil,
       icon_highlight = {
         hl_group = icon_hl_group,
         icon_length = vim.fn.strdisplaywidth(icon),
@@ -647,6 +682,27 @@ function M.render_list()
               prefix_len + dir_path_len
             )
           end
+
+          -- ranges of display_path, the line shows the file name before its directory
+          local filename_col = #icon_match + 1
+          local dir_col = filename_col + filename_len + 1
+          for _, range in ipairs(line_data[content_line_idx].display_highlights or {}) do
+            local start_col, end_col
+            if range[1] < dir_path_len then
+              start_col, end_col = dir_col + range[1], dir_col + math.min(range[2], dir_path_len)
+            else
+              local offset = dir_path_len > 0 and dir_path_len + 1 or 0
+              start_col, end_col = filename_col + range[1] - offset, filename_col + range[2] - offset
+            end
+            vim.api.nvim_buf_add_highlight(
+              M.state.list_buf,
+              M.state.ns_id,
+              M.state.config.hl.matched,
+              line_idx - 1,
+              start_col,
+              end_col
+            )
+          end
         end
 
         local is_cursor_line = line_idx == cursor_line
@@ -731,7 +787,14 @@ function M.update_preview()
   if M.state.file_info_buf then preview.update_file_info_buffer(item, M.state.file_info_buf, M.state.cursor) end
 
   preview.set_preview_window(M.state.preview_win)
-  preview.preview(item.path, M.state.preview_buf)
+  local sensitive_mode = main.config.scan and main.config.scan.sensitive and main.config.scan.sensitive.mode
+  if item.sensitivity and (sensitive_mode == 'hide_preview' or sensitive_mode == 'exclude') then
+    preview.preview_sensitive(item.path, item.sensitivity, M.state.preview_buf)
+  elseif main.state.remote_host then
+    preview.preview_remote(main.state.remote_host, item.path, M.state.preview_buf)
+  else
+    preview.preview(item.path, M.state.preview_buf)
+  end
 end
 
 --- Clear preview
@@ -772,13 +835,21 @@ function M.update_status(progress)
   local status_info
 
   if progress and progress.is_scanning then
-    status_info = string.format('Indexing files %d', progress.scanned_files_count)
+    local sample_dir = M.state.scan_sample and vim.fs.dirname(M.state.scan_sample)
+    if sample_dir and sample_dir ~= '.' then
+      status_info = string.format('Indexing %s/… %d', sample_dir, progress.scanned_files_count)
+    else
+      status_info = string.format('Indexing files %d', progress.scanned_files_count)
+    end
   else
     local search_metadata = file_picker.get_search_metadata()
     if #M.state.query < 2 then
       status_info = string.format('%d', search_metadata.total_files)
     else
-      status_info = string.format('%d/%d', search_metadata.total_matched, search_metadata.total_files)
+      -- too many matches to rank them all, only the likeliest ones were scored
+      local approximate = search_metadata.approximate and '~' or ''
+      status_info =
+        string.format('%s%d/%d', approximate, search_metadata.total_matched, search_metadata.total_files)
     end
   end
 
@@ -844,6 +915,22 @@ function M.scroll_preview_down()
   preview.scroll(scroll_lines)
 end
 
+--- Appends the query and the picked file to `debug.selection_history`, a corpus for
+--- `fff_repl --eval` to rank again after scoring changes
+function M.record_selection(item)
+  local history = main.config and main.config.debug and main.config.debug.selection_history
+  if not history or not item.relative_path then return end
+
+  local query = M.state.query
+  if M.state.config.profile then query = 'profile:' .. M.state.config.profile .. ' ' .. query end
+  local line = vim.json.encode({
+    query = query,
+    expected = item.relative_path,
+    current_file = M.state.current_file_cache or vim.NIL,
+  })
+  pcall(vim.fn.writefile, { line }, vim.fn.expand(history), 'a')
+end
+
 function M.select(action)
   if not M.state.active then return end
 
@@ -854,8 +941,10 @@ function M.select(action)
   if not item then return end
 
   action = action or 'edit'
+  M.record_selection(item)
 
-  local relative_path = vim.fn.fnamemodify(item.path, ':.')
+  local relative_path = utils.archive_entry_url(item.path) or vim.fn.fnamemodify(item.path, ':.')
+  if main.state.remote_host then relative_path = utils.remote_url(main.state.remote_host, item.path) end
   vim.cmd('stopinsert')
   M.close()
 
@@ -921,6 +1010,8 @@ function M.close()
   M.state.ns_id = nil
   M.state.last_preview_file = nil
   M.state.current_file_cache = nil
+  M.stop_scan_batches()
+  file_picker.forget_result_diff()
 
   if M.state.search_timer then
     M.state.search_timer:stop()
@@ -949,6 +1040,12 @@ function M.open(opts)
   end
 
   M.state.config = vim.tbl_deep_extend('force', main.config or {}, opts or {})
+  -- searches are scoped to the root of the window or buffer the picker is opened from
+  M.state.context = { win = vim.api.nvim_get_current_win(), buf = vim.api.nvim_get_current_buf() }
+
+  if main.config.recency and (main.config.recency.jumplist or main.config.recency.changelist) then
+    main.push_jump_positions()
+  end
 
   if not M.create_ui() then
     vim.notify('Failed to create picker UI', vim.log.levels.ERROR)
@@ -967,6 +1064,12 @@ function M.monitor_scan_progress(iteration)
   local progress = file_picker.get_scan_progress()
 
   if progress.is_scanning then
+    -- the latest directory the scan went through is shown next to the count
+    if not M.state.unsubscribe_scan_batches then
+      M.state.unsubscribe_scan_batches = main.subscribe_scan_batches(function(batch)
+        M.state.scan_sample = batch.samples[#batch.samples] or M.state.scan_sample
+      end)
+    end
     M.update_status(progress)
 
     local timeout
@@ -980,10 +1083,17 @@ function M.monitor_scan_progress(iteration)
 
     vim.defer_fn(function() M.monitor_scan_progress(iteration + 1) end, timeout)
   else
+    M.stop_scan_batches()
     M.update_results()
   end
 end
 
+function M.stop_scan_batches()
+  if M.state.unsubscribe_scan_batches then M.state.unsubscribe_scan_batches() end
+  M.state.unsubscribe_scan_batches = nil
+  M.state.scan_sample = nil
+end
+
 M.enabled_preview = function()
   local preview_state = nil
 
@@ -0,0 +1,86 @@
+//! Global allocator of the library, picked with the `mimalloc` (default) and `jemalloc` cargo
+//! features. Neovim sessions last for days and the index is rebuilt on every rescan, so the
+//! allocator returning freed pages to the system matters more than raw allocation speed.
+
+use mlua::prelude::*;
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Unused dirty pages are purged after a second instead of ten by a background thread, so the
+/// memory of a dropped index goes back to the system even while the editor sits idle
+#[cfg(feature = "jemalloc")]
+#[allow(non_upper_case_globals)]
+#[export_name = "_rjem_malloc_conf"]
+pub static malloc_conf: &[u8] = b"background_thread:true,dirty_decay_ms:1000,muzzy_decay_ms:1000\0";
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllocatorStats {
+    pub name: &'static str,
+    /// Bytes handed out to the program
+    pub allocated: Option<usize>,
+    /// Bytes of physical memory held by the allocator, freed pages not returned yet included
+    pub resident: Option<usize>,
+}
+
+#[cfg(feature = "jemalloc")]
+pub fn stats() -> AllocatorStats {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    // the statistics are a snapshot refreshed on every epoch advance
+    let _ = epoch::advance();
+    AllocatorStats {
+        name: "jemalloc",
+        allocated: stats::allocated::read().ok(),
+        resident: stats::resident::read().ok(),
+    }
+}
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+pub fn stats() -> AllocatorStats {
+    let (mut elapsed, mut user, mut system, mut rss, mut peak_rss) = (0, 0, 0, 0, 0);
+    let (mut commit, mut peak_commit, mut page_faults) = (0, 0, 0);
+    // SAFETY: every pointer is a valid local the call writes to
+    unsafe {
+        libmimalloc_sys::mi_process_info(
+            &mut elapsed,
+            &mut user,
+            &mut system,
+            &mut rss,
+            &mut peak_rss,
+            &mut commit,
+            &mut peak_commit,
+            &mut page_faults,
+        );
+    }
+
+    AllocatorStats {
+        name: "mimalloc",
+        // mimalloc doesn't count the allocated bytes outside of debug builds
+        allocated: None,
+        resident: Some(rss),
+    }
+}
+
+#[cfg(not(any(feature = "mimalloc", feature = "jemalloc")))]
+pub fn stats() -> AllocatorStats {
+    AllocatorStats {
+        name: "system",
+        ..AllocatorStats::default()
+    }
+}
+
+impl IntoLua for AllocatorStats {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("name", self.name)?;
+        table.set("allocated", self.allocated)?;
+        table.set("resident", self.resident)?;
+        Ok(LuaValue::Table(table))
+    }
+}
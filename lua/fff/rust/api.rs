@@ -0,0 +1,50 @@
+//! Version of the interface the lua frontend talks to. The lua files and the compiled library
+//! are upgraded separately, e.g. a plugin manager pulls new lua while the old library is still
+//! loaded or waits for a rebuild, so the frontend checks the version and the capabilities
+//! before relying on an export instead of calling a missing one.
+
+use mlua::prelude::*;
+
+/// Major changes break the existing exports, minor ones add exports or capabilities
+pub const API_VERSION: (u32, u32, u32) = (1, 3, 0);
+
+/// Bit positions never change, capabilities are only ever appended
+pub const CAPABILITIES: &[(&str, u64)] = &[
+    ("daemon", 1 << 0),
+    ("snapshots", 1 << 1),
+    ("scan_batches", 1 << 2),
+    ("vcs_providers", 1 << 3),
+    ("mtime_fallback", 1 << 4),
+    ("sensitive_files", 1 << 5),
+    ("redaction", 1 << 6),
+    ("display_path", 1 << 7),
+    ("layout_hints", 1 << 8),
+    ("result_diff", 1 << 9),
+    ("search_options", 1 << 10),
+    ("search_trace", 1 << 11),
+    ("forget_project", 1 << 12),
+    ("scorers", 1 << 13),
+    ("context_kinds", 1 << 14),
+];
+
+pub fn capability_bits() -> u64 {
+    CAPABILITIES.iter().fold(0, |bits, (_, bit)| bits | bit)
+}
+
+/// `{ major, minor, patch, version = "1.0.0", capabilities = bits, features = { name = true },
+/// git = false }` where `git` tells builds without libgit2 apart
+pub fn get_api_version(lua: &Lua, _: ()) -> LuaResult<LuaTable> {
+    let (major, minor, patch) = API_VERSION;
+    let table = lua.create_table()?;
+    table.set("major", major)?;
+    table.set("minor", minor)?;
+    table.set("patch", patch)?;
+    table.set("version", format!("{}.{}.{}", major, minor, patch))?;
+    table.set("capabilities", capability_bits())?;
+    table.set("git", cfg!(feature = "git"))?;
+    table.set(
+        "features",
+        lua.create_table_from(CAPABILITIES.iter().map(|(name, _)| (*name, true)))?,
+    )?;
+    Ok(table)
+}
@@ -0,0 +1,172 @@
+//! Client side of the shared daemon mode. A `fff --listen` process owns the scan and the
+//! watcher of a project and every attached instance forwards its queries over the socket
+//! instead of building its own index.
+
+use mlua::prelude::*;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+// a search on a warm index takes milliseconds, anything slower means the daemon is stuck
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub static DAEMON: Mutex<Option<DaemonClient>> = Mutex::new(None);
+
+#[cfg(unix)]
+pub struct DaemonClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+#[cfg(not(unix))]
+pub struct DaemonClient;
+
+impl DaemonClient {
+    #[cfg(unix)]
+    pub fn connect(socket_path: &str) -> io::Result<Self> {
+        let writer = UnixStream::connect(socket_path)?;
+        writer.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        writer.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+        Ok(Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            next_id: 0,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect(_: &str) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the shared daemon needs unix sockets",
+        ))
+    }
+
+    /// Sends one JSON-RPC request and waits for its response line
+    #[cfg(unix)]
+    pub fn call(&mut self, method: &str, params: Value) -> io::Result<Value> {
+        self.next_id += 1;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params,
+        });
+        serde_json::to_writer(&mut self.writer, &request)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut response: Value = serde_json::from_str(&line)?;
+        if let Some(error) = response.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown daemon error");
+            return Err(io::Error::other(message.to_string()));
+        }
+
+        Ok(response
+            .get_mut("result")
+            .map(Value::take)
+            .unwrap_or_default())
+    }
+
+    #[cfg(not(unix))]
+    pub fn call(&mut self, _: &str, _: Value) -> io::Result<Value> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Forwards the call to the daemon when one is attached, `None` means the local index has to
+/// be used. A broken connection detaches the daemon so the next call reports it only once.
+pub fn daemon_call(method: &str, params: Value) -> LuaResult<Option<Value>> {
+    let mut daemon = DAEMON
+        .lock()
+        .map_err(|_| LuaError::RuntimeError("Failed to lock daemon client".to_string()))?;
+    let Some(client) = daemon.as_mut() else {
+        return Ok(None);
+    };
+
+    match client.call(method, params) {
+        Ok(result) => Ok(Some(result)),
+        Err(e) => {
+            if e.kind() != io::ErrorKind::Other {
+                ::tracing::error!(?e, "Daemon connection lost, detaching");
+                *daemon = None;
+            }
+            Err(LuaError::RuntimeError(format!(
+                "Daemon request {} failed: {}",
+                method, e
+            )))
+        }
+    }
+}
+
+/// The daemon nests the score in every item while the bindings return them side by side
+pub fn split_search_scores(mut result: Value) -> Value {
+    let mut scores = Vec::new();
+    if let Some(items) = result.get_mut("items").and_then(Value::as_array_mut) {
+        for item in items.iter_mut() {
+            let score = item
+                .as_object_mut()
+                .and_then(|item| item.remove("score"))
+                .unwrap_or_default();
+            scores.push(score);
+        }
+    }
+
+    // group items index the lua result table which is 1-based
+    let group_items = result
+        .get_mut("groups")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(|group| group.get_mut("items").and_then(Value::as_array_mut));
+    for items in group_items {
+        for index in items.iter_mut() {
+            *index = Value::from(index.as_u64().unwrap_or_default() + 1);
+        }
+    }
+
+    if let Some(result) = result.as_object_mut() {
+        result.insert("scores".to_string(), Value::Array(scores));
+    }
+    result
+}
+
+pub fn json_to_lua(lua: &Lua, value: &Value) -> LuaResult<LuaValue> {
+    Ok(match value {
+        Value::Null => LuaValue::Nil,
+        Value::Bool(value) => LuaValue::Boolean(*value),
+        Value::Number(value) => match value.as_i64() {
+            Some(value) => LuaValue::Integer(value),
+            None => LuaValue::Number(value.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => LuaValue::String(lua.create_string(value)?),
+        Value::Array(values) => {
+            let table = lua.create_table_with_capacity(values.len(), 0)?;
+            for value in values {
+                table.push(json_to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+        Value::Object(map) => {
+            let table = lua.create_table_with_capacity(0, map.len())?;
+            for (key, value) in map {
+                table.set(key.as_str(), json_to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
@@ -24,7 +24,10 @@ package.cpath = package.cpath .. ';' .. table.concat(paths, ';')
 
 local ok, backend = pcall(require, 'fff_nvim')
 if not ok then
-  error('Failed to load fff rust backend. Make sure that it has been built with `cargo build --release`')
+  error(
+    'Failed to load fff rust backend. Make sure that it has been built with `cargo build --release` '
+      .. 'or download a prebuilt one with `:FFFDownload`'
+  )
 end
 
 return backend
@@ -1,28 +1,40 @@
-use crate::error::Error;
-use crate::file_picker::FilePicker;
-use crate::frecency::FrecencyTracker;
+mod allocator;
+mod api;
+mod daemon;
+
+use daemon::{daemon_call, json_to_lua, split_search_scores, DaemonClient, DAEMON};
+use fff_core::config::ConfigUpdate;
+use fff_core::display_path;
+use fff_core::error::Error;
+use fff_core::events::{self, PickerEvent};
+use fff_core::file_picker::FilePicker;
+use fff_core::frecency::FrecencyTracker;
+use fff_core::glob_filter::GlobFilter;
+use fff_core::grep::GrepOptions;
+use fff_core::list::ListEntry;
+use fff_core::replay::{self, TracedSearch};
+use fff_core::result_count;
+use fff_core::result_diff;
+use fff_core::scan_batches::{self, ScanBatch};
+use fff_core::scorer::{self, Divergence, WeightedScorer};
+use fff_core::snapshot::IndexSnapshots;
+use fff_core::source::{FileSource, ListedFile, ListedFiles, RemoteListing};
+use fff_core::supersede::SearchTicket;
+use fff_core::types::{
+    ContextId, DiagnosticsCount, FileListOptions, FileMeta, IndexDrift, ScanOptions,
+    ScoringContext, ScoringWeights, SearchContext, SearchOptions, SearchResult,
+};
 use mlua::prelude::*;
-use once_cell::sync::Lazy;
-use std::path::PathBuf;
-use std::sync::RwLock;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-mod background_watcher;
-mod error;
-pub mod file_picker;
-mod frecency;
-pub mod git;
-mod path_utils;
-pub mod score;
-mod tracing;
-pub mod types;
-use mimalloc::MiMalloc;
-
-#[global_allocator]
-static GLOBAL: MiMalloc = MiMalloc;
-
-pub static FRECENCY: Lazy<RwLock<Option<FrecencyTracker>>> = Lazy::new(|| RwLock::new(None));
-pub static FILE_PICKER: Lazy<RwLock<Option<FilePicker>>> = Lazy::new(|| RwLock::new(None));
+pub use fff_core::{
+    file_picker, git, list, path_utils, score, types, FILE_PICKER, FRECENCY, LIST_ENTRIES,
+    SNAPSHOTS,
+};
 
 pub fn init_db(_: &Lua, (db_path, use_unsafe_no_lock): (String, bool)) -> LuaResult<bool> {
     let mut frecency = FRECENCY.write().map_err(|_| Error::AcquireFrecencyLock)?;
@@ -33,55 +45,160 @@ pub fn init_db(_: &Lua, (db_path, use_unsafe_no_lock): (String, bool)) -> LuaRes
     Ok(true)
 }
 
+/// Grows the frecency database map before it fills up
+fn ensure_frecency_map_capacity() -> Result<(), Error> {
+    let frecency = FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)?;
+    if let Some(frecency) = frecency
+        .as_ref()
+        .filter(|frecency| frecency.needs_map_growth())
+    {
+        frecency.grow_map_size()?;
+    }
+
+    Ok(())
+}
+
+pub fn compact_db(_: &Lua, _: ()) -> LuaResult<bool> {
+    let mut frecency = FRECENCY.write().map_err(|_| Error::AcquireFrecencyLock)?;
+    let Some(tracker) = frecency.take() else {
+        return Ok(false);
+    };
+
+    match tracker.compact() {
+        Ok(tracker) => {
+            *frecency = Some(tracker);
+            Ok(true)
+        }
+        Err(failed) => {
+            *frecency = failed.tracker;
+            Err(failed.error.into())
+        }
+    }
+}
+
+/// Writes the accesses not yet flushed in the background, called before Neovim exits
+pub fn flush_db(_: &Lua, _: ()) -> LuaResult<bool> {
+    let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+        return Ok(false);
+    };
+
+    frecency.flush()?;
+    Ok(true)
+}
+
 pub fn destroy_db(_: &Lua, _: ()) -> LuaResult<bool> {
     let mut frecency = FRECENCY.write().map_err(|_| Error::AcquireFrecencyLock)?;
     *frecency = None;
     Ok(true)
 }
 
-pub fn init_file_picker(_: &Lua, base_path: String) -> LuaResult<bool> {
+pub fn init_file_picker(
+    _: &Lua,
+    (base_path, scan_options): (String, Option<ScanOptions>),
+) -> LuaResult<bool> {
     let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
     if file_picker.is_some() {
         return Ok(false);
     }
 
-    let picker = FilePicker::new(base_path)?;
+    let picker = FilePicker::new(base_path, scan_options.unwrap_or_default())?;
+    let project_root = picker.base_path().to_path_buf();
     *file_picker = Some(picker);
+    drop(file_picker);
+
+    touch_frecency_project(&project_root);
     Ok(true)
 }
 
+/// Records the project of a new picker and forgets the projects that haven't been used for
+/// `forget_projects_after_days`, failures only cost the database some space
+fn touch_frecency_project(root: &Path) {
+    let Ok(frecency) = FRECENCY.read() else {
+        return;
+    };
+    let Some(ref frecency) = *frecency else {
+        return;
+    };
+
+    if let Err(e) = frecency.touch_project(root) {
+        ::tracing::warn!(?root, ?e, "Failed to record the frecency project");
+    }
+    match frecency.age_out_projects() {
+        Ok(forgotten) if !forgotten.is_empty() => {
+            ::tracing::info!(?forgotten, "Forgot the frecency of unused projects");
+        }
+        Ok(_) => {}
+        Err(e) => ::tracing::warn!(?e, "Failed to age out frecency projects"),
+    }
+}
+
 fn reinit_file_picker_internal(path: std::path::PathBuf) -> Result<(), Error> {
     let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
 
     // drop should clean it anyway but just to be extra sure
-    if let Some(mut picker) = file_picker.take() {
-        picker.stop_background_monitor();
-    }
+    let scan_options = match file_picker.take() {
+        Some(mut picker) => {
+            picker.stop_background_monitor();
+            picker.scan_options().clone()
+        }
+        None => ScanOptions::default(),
+    };
 
-    let new_picker = FilePicker::new(path.to_string_lossy().to_string())?;
+    let new_picker = FilePicker::new(path.to_string_lossy().to_string(), scan_options)?;
+    let project_root = new_picker.base_path().to_path_buf();
     *file_picker = Some(new_picker);
+    drop(file_picker);
 
+    touch_frecency_project(&project_root);
     Ok(())
 }
 
 pub fn restart_index_in_path(_: &Lua, new_path: String) -> LuaResult<bool> {
-    let path = std::path::PathBuf::from(&new_path);
-    if !path.exists() {
-        return Err(LuaError::RuntimeError(format!(
-            "Path does not exist: {}",
-            new_path
-        )));
-    }
-
-    let canonical_path = path.canonicalize().map_err(|e| {
-        LuaError::RuntimeError(format!("Failed to canonicalize path '{}': {}", new_path, e))
+    let canonical_path = path_utils::resolve_path(&new_path).map_err(|e| {
+        LuaError::RuntimeError(format!("Failed to resolve path '{}': {}", new_path, e))
     })?;
 
     reinit_file_picker_internal(canonical_path)?;
     Ok(true)
 }
 
+/// Replaces the picker with one indexing the given source, keeping the scan options
+fn reinit_with_source(base_path: String, source: Arc<dyn FileSource>) -> Result<(), Error> {
+    let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
+    let scan_options = match file_picker.take() {
+        Some(mut picker) => {
+            picker.stop_background_monitor();
+            picker.scan_options().clone()
+        }
+        None => ScanOptions::default(),
+    };
+
+    *file_picker = Some(FilePicker::with_source(base_path, scan_options, source)?);
+    Ok(())
+}
+
+/// Replaces the index with files listed elsewhere, e.g. inside a container. The listing is
+/// searched and scored like scanned files but never watched.
+pub fn index_file_list(_: &Lua, (base_path, files): (String, Vec<ListedFile>)) -> LuaResult<bool> {
+    reinit_with_source(base_path, Arc::new(ListedFiles::new(files)))?;
+    Ok(true)
+}
+
+/// Replaces the index with the files of a directory on a remote host, listed over ssh in the
+/// background like a regular scan
+pub fn index_remote(
+    _: &Lua,
+    (host, base_path, command): (String, String, Option<String>),
+) -> LuaResult<bool> {
+    reinit_with_source(base_path, Arc::new(RemoteListing::new(host, command)))?;
+    Ok(true)
+}
+
 pub fn scan_files(_: &Lua, _: ()) -> LuaResult<()> {
+    if daemon_call("scan_files", Value::Null)?.is_some() {
+        return Ok(());
+    }
+
     let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
     let picker = file_picker
         .as_mut()
@@ -92,26 +209,699 @@ pub fn scan_files(_: &Lua, _: ()) -> LuaResult<()> {
     Ok(())
 }
 
-pub fn fuzzy_search_files(
+pub fn init_snapshots(_: &Lua, db_path: String) -> LuaResult<bool> {
+    let mut snapshots = SNAPSHOTS.write().map_err(|_| Error::AcquireItemLock)?;
+    if snapshots.is_some() {
+        return Ok(false);
+    }
+    *snapshots = Some(IndexSnapshots::new(&db_path)?);
+    Ok(true)
+}
+
+/// Serves searches from the persisted snapshot while the initial scan has not produced
+/// anything yet, `None` if there is no snapshot to fall back to
+fn search_snapshot(
     lua: &Lua,
-    (query, max_results, max_threads, current_file): (String, usize, usize, Option<String>),
+    picker: &FilePicker,
+    query: &str,
+    max_results: usize,
+    max_threads: usize,
+    group_by_directory: bool,
+) -> LuaResult<Option<LuaValue>> {
+    let snapshots = SNAPSHOTS.read().map_err(|_| Error::AcquireItemLock)?;
+    let Some(snapshots) = snapshots.as_ref() else {
+        return Ok(None);
+    };
+
+    let Some(search) = snapshots.search(
+        picker.base_path(),
+        picker.scan_options().sensitive.mode,
+        query,
+        max_results,
+        max_threads,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let mut result = SearchResult {
+        items: search.items.iter().collect(),
+        scores: search.scores,
+        total_matched: search.total_matched,
+        total_files: search.total_files,
+        superseded: false,
+        approximate: false,
+        groups: None,
+        display_prefix: picker.display_prefix(),
+    };
+    if group_by_directory {
+        result.groups = Some(result.group_by_directory());
+    }
+    result.into_lua(lua).map(Some)
+}
+
+pub fn attach_daemon(_: &Lua, socket_path: String) -> LuaResult<bool> {
+    let mut daemon = DAEMON
+        .lock()
+        .map_err(|_| LuaError::RuntimeError("Failed to lock daemon client".to_string()))?;
+
+    match DaemonClient::connect(&socket_path) {
+        Ok(client) => {
+            ::tracing::info!(socket_path, "Attached to the shared daemon");
+            *daemon = Some(client);
+            Ok(true)
+        }
+        Err(e) => {
+            ::tracing::debug!(socket_path, ?e, "Failed to attach to the shared daemon");
+            Ok(false)
+        }
+    }
+}
+
+pub fn detach_daemon(_: &Lua, _: ()) -> LuaResult<bool> {
+    let mut daemon = DAEMON
+        .lock()
+        .map_err(|_| LuaError::RuntimeError("Failed to lock daemon client".to_string()))?;
+    Ok(daemon.take().is_some())
+}
+
+pub fn is_daemon_attached(_: &Lua, _: ()) -> LuaResult<bool> {
+    let daemon = DAEMON
+        .lock()
+        .map_err(|_| LuaError::RuntimeError("Failed to lock daemon client".to_string()))?;
+    Ok(daemon.is_some())
+}
+
+/// Pickers of different instances attached to one daemon must not supersede each other
+fn search_picker_id(picker_id: Option<&str>) -> String {
+    format!("{}:{}", std::process::id(), picker_id.unwrap_or("default"))
+}
+
+/// Roots set with `set_context_root` per window and buffer
+static CONTEXT_ROOTS: Mutex<BTreeMap<ContextId, PathBuf>> = Mutex::new(BTreeMap::new());
+
+/// Scopes the searches made from a window or buffer to a directory of the index, e.g. the cwd
+/// of a terminal or the `:lcd` of a window, without reinitializing the picker. The context is
+/// `{ win = id }` or `{ buf = id }`, `nil` clears the root.
+pub fn set_context_root(
+    _: &Lua,
+    (context, path): (SearchContext, Option<String>),
+) -> LuaResult<bool> {
+    let Some(context) = context.single() else {
+        return Err(LuaError::RuntimeError(
+            "expected the context of one window or buffer, { win = id } or { buf = id }"
+                .to_string(),
+        ));
+    };
+    let mut roots = CONTEXT_ROOTS.lock().map_err(|_| Error::AcquireItemLock)?;
+    let Some(path) = path else {
+        return Ok(roots.remove(&context).is_some());
+    };
+
+    let root = path_utils::resolve_path(&path).unwrap_or_else(|_| path_utils::expand_path(&path));
+    roots.insert(context, root);
+    Ok(true)
+}
+
+/// Root of the window, else the one of the buffer
+fn context_root(context: &SearchContext) -> LuaResult<Option<PathBuf>> {
+    let roots = CONTEXT_ROOTS.lock().map_err(|_| Error::AcquireItemLock)?;
+    Ok(context.ids().find_map(|id| roots.get(&id)).cloned())
+}
+
+/// query, max_results, max_threads, current_file, picker_id, group_by_directory, context, globs
+type SearchArgs = (
+    String,
+    usize,
+    usize,
+    Option<String>,
+    Option<String>,
+    Option<bool>,
+    SearchContext,
+    Option<Vec<String>>,
+);
+
+/// Takes a `SearchOptions` table, the positional `SearchArgs` of the older releases are still
+/// accepted
+fn search_options(lua: &Lua, args: LuaMultiValue) -> LuaResult<SearchOptions> {
+    if let Some(LuaValue::Table(_)) = args.front() {
+        let (options, _): (SearchOptions, LuaMultiValue) = FromLuaMulti::from_lua_multi(args, lua)?;
+        return Ok(options);
+    }
+
+    let (
+        query,
+        max_results,
+        max_threads,
+        current_file,
+        picker_id,
+        group_by_directory,
+        context,
+        globs,
+    ): SearchArgs = FromLuaMulti::from_lua_multi(args, lua)?;
+    let options = SearchOptions {
+        query,
+        max_results,
+        max_threads,
+        current_file,
+        picker_id,
+        group_by_directory: group_by_directory.unwrap_or_default(),
+        context,
+        globs,
+        ..SearchOptions::default()
+    };
+    options.validate()?;
+    Ok(options)
+}
+
+/// With `group_by_directory` the result also holds `groups` of 1-based item indices sharing a
+/// parent directory, ordered by their best item
+pub fn fuzzy_search_files(lua: &Lua, args: LuaMultiValue) -> LuaResult<LuaValue> {
+    let SearchOptions {
+        query,
+        max_results,
+        max_threads,
+        current_file,
+        picker_id,
+        group_by_directory,
+        context,
+        globs,
+        scorer,
+        compare_scorer,
+    } = search_options(lua, args)?;
+    let max_results = result_count::resolve(max_results);
+    let scope = context_root(&context)?;
+    let picker_id = search_picker_id(picker_id.as_deref());
+    // the scorers are registered in this process, the daemon only ranks with its default one
+    if (scorer.is_some() || compare_scorer.is_some()) && is_daemon_attached(lua, ())? {
+        return Err(Error::InvalidSearchOptions(
+            "scorers can't be selected while a daemon is attached".to_string(),
+        )
+        .into());
+    }
+    let params = json!({
+        "query": query,
+        "max_results": max_results,
+        "max_threads": max_threads,
+        "current_file": current_file,
+        "picker_id": picker_id,
+        "group_by_directory": group_by_directory,
+        "scope": scope,
+        "globs": globs,
+    });
+    if let Some(result) = daemon_call("search", params)? {
+        let results = json_to_lua(lua, &split_search_scores(result))?;
+        return finish_search(lua, results, &query, &picker_id);
+    }
+
+    let ticket = SearchTicket::begin(&picker_id);
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    let glob_filter = globs.as_deref().map(GlobFilter::cached).transpose()?;
+    // the snapshot is only there to answer plain queries while the first scan runs
+    if picker.get_files().is_empty() && picker.is_scan_active() && glob_filter.is_none() {
+        if let Some(result) = search_snapshot(
+            lua,
+            picker,
+            &query,
+            max_results,
+            max_threads,
+            group_by_directory,
+        )? {
+            return finish_search(lua, result, &query, &picker_id);
+        }
+    }
+
+    let files = picker.scoped_files(scope.as_deref());
+    let key = picker.speculation_key(scope.as_deref(), current_file.as_deref(), max_results);
+    picker.speculation().record_query(&query);
+    // registered scorers may be gone since the options were validated
+    let scorer = scorer.as_deref().map(scorer::require).transpose()?;
+    // the empty query of a newly opened picker always runs, it starts the speculation over,
+    // speculated results never had the globs or the scorer of the caller applied
+    let speculated = if query.is_empty() || glob_filter.is_some() || scorer.is_some() {
+        None
+    } else {
+        picker.speculation().lookup(&key, &query, files)
+    };
+
+    let current_package = current_file
+        .as_deref()
+        .and_then(|current_file| picker.package_of(current_file));
+    let mut results = match speculated {
+        Some(results) => results,
+        None => FilePicker::fuzzy_search_weighted(
+            files,
+            &query,
+            max_results,
+            max_threads,
+            current_file.as_deref(),
+            picker.scoring_weights(),
+            current_package.as_deref(),
+            Some(&ticket),
+            picker.narrowed_paths(),
+            picker.index_stats().entry_points(),
+            Some(picker.prefix_candidates()),
+            glob_filter.as_deref(),
+            scorer.as_deref(),
+        ),
+    };
+    if query.is_empty() && glob_filter.is_none() && scorer.is_none() && !results.superseded {
+        picker.speculation().reset(key, files, &results);
+    }
+    let comparison = match compare_scorer {
+        Some(name) if !results.superseded => {
+            let compare_with = scorer::require(&name)?;
+            let compared = FilePicker::fuzzy_search_weighted(
+                files,
+                &query,
+                max_results,
+                max_threads,
+                current_file.as_deref(),
+                picker.scoring_weights(),
+                current_package.as_deref(),
+                Some(&ticket),
+                picker.narrowed_paths(),
+                picker.index_stats().entry_points(),
+                Some(picker.prefix_candidates()),
+                glob_filter.as_deref(),
+                Some(compare_with.as_ref()),
+            );
+            Some(scorer_comparison(lua, &name, &results, &compared)?)
+        }
+        _ => None,
+    };
+    if replay::is_recording() && scorer.is_none() && !results.superseded {
+        replay::record_search(TracedSearch {
+            generation: picker.index_generation(),
+            query: query.clone(),
+            current_file: current_file.clone(),
+            current_package: current_package.as_deref().map(str::to_string),
+            max_results,
+            weights: picker.scoring_weights(),
+            globs: globs.clone(),
+            results: TracedSearch::result_paths(&results),
+        });
+    }
+    results.display_prefix = picker.display_prefix();
+    if group_by_directory {
+        results.groups = Some(results.group_by_directory());
+    }
+
+    let items = results.items.len();
+    let conversion_start = std::time::Instant::now();
+    let results = results.into_lua(lua);
+    result_count::record_conversion(items, conversion_start.elapsed());
+    let results = results?;
+    if let (LuaValue::Table(table), Some(comparison)) = (&results, comparison) {
+        table.set("comparison", comparison)?;
+    }
+    finish_search(lua, results, &query, &picker_id)
+}
+
+/// `{ scorer, items, scores, divergence }` of the compared scorer, items are the relative paths
+/// of its results and scores their totals
+fn scorer_comparison(
+    lua: &Lua,
+    name: &str,
+    results: &SearchResult,
+    compared: &SearchResult,
+) -> LuaResult<LuaTable> {
+    let table = lua.create_table()?;
+    table.set("scorer", name)?;
+    table.set(
+        "items",
+        lua.create_sequence_from(
+            compared
+                .items
+                .iter()
+                .map(|file| file.relative_path.as_str()),
+        )?,
+    )?;
+    table.set(
+        "scores",
+        lua.create_sequence_from(compared.scores.iter().map(|score| score.total))?,
+    )?;
+    table.set("divergence", Divergence::of_results(results, compared))?;
+    Ok(table)
+}
+
+/// Registers the default scoring with its own weights under the name, searches select it with
+/// the `scorer` or `compare_scorer` options
+pub fn register_scorer(_: &Lua, (name, weights): (String, ScoringWeights)) -> LuaResult<bool> {
+    Ok(scorer::register(
+        &name,
+        Arc::new(WeightedScorer { weights }),
+    ))
+}
+
+pub fn unregister_scorer(_: &Lua, name: String) -> LuaResult<bool> {
+    Ok(scorer::unregister(&name))
+}
+
+pub fn list_scorers(_: &Lua, _: ()) -> LuaResult<Vec<String>> {
+    Ok(scorer::names())
+}
+
+/// Decorations of the search results that apply to the daemon, snapshot and index results
+fn finish_search(
+    lua: &Lua,
+    results: LuaValue,
+    query: &str,
+    picker_id: &str,
 ) -> LuaResult<LuaValue> {
+    let results = with_display_paths(results, query)?;
+    with_result_diff(lua, results, picker_id)
+}
+
+/// Sets `diff`, the edit script from the previous results of the picker to these ones.
+/// Superseded results are never shown, they neither get one nor become the previous results.
+fn with_result_diff(lua: &Lua, results: LuaValue, picker_id: &str) -> LuaResult<LuaValue> {
+    let LuaValue::Table(ref table) = results else {
+        return Ok(results);
+    };
+    let Some(items) = table.get::<Option<LuaTable>>("items")? else {
+        return Ok(results);
+    };
+    if table.get::<Option<bool>>("superseded")?.unwrap_or_default() {
+        return Ok(results);
+    }
+
+    let paths = items
+        .sequence_values::<LuaTable>()
+        .map(|item| item?.get::<String>("relative_path"))
+        .collect::<LuaResult<Vec<_>>>()?;
+    table.set("diff", result_diff::record(picker_id, paths).into_lua(lua)?)?;
+    Ok(results)
+}
+
+/// Starts the result diffs of the picker over, the next search inserts every result
+pub fn forget_result_diff(_: &Lua, picker_id: Option<String>) -> LuaResult<bool> {
+    result_diff::forget(&search_picker_id(picker_id.as_deref()));
+    Ok(true)
+}
+
+/// Sets `display_path` and `display_highlights` on every item of the search results once the
+/// frontend reported the width of its paths with `set_path_width`
+fn with_display_paths(results: LuaValue, query: &str) -> LuaResult<LuaValue> {
+    let width = display_path::width();
+    let LuaValue::Table(ref table) = results else {
+        return Ok(results);
+    };
+    let Some(items) = table.get::<Option<LuaTable>>("items")? else {
+        return Ok(results);
+    };
+    if width == 0 {
+        return Ok(results);
+    }
+
+    for item in items.sequence_values::<LuaTable>() {
+        let item = item?;
+        let Some(relative_path) = item.get::<Option<String>>("relative_path")? else {
+            continue;
+        };
+
+        let elided = display_path::elide(&relative_path, query, width);
+        item.set("display_path", elided.text)?;
+        // 0-based byte ranges with an exclusive end, the same as nvim_buf_add_highlight
+        let highlights = elided
+            .highlights
+            .into_iter()
+            .map(|(start, end)| vec![start, end])
+            .collect::<Vec<_>>();
+        item.set("display_highlights", highlights)?;
+    }
+    Ok(results)
+}
+
+/// Columns the result list has for a path, 0 turns `display_path` off
+pub fn set_path_width(_: &Lua, width: usize) -> LuaResult<bool> {
+    display_path::set_width(width);
+    Ok(true)
+}
+
+/// Number of the latest searches added to debug bundles for replaying them, 0 stops recording
+pub fn set_search_trace(_: &Lua, capacity: usize) -> LuaResult<bool> {
+    replay::set_capacity(capacity);
+    Ok(true)
+}
+
+/// Rows of the list showing the results, `max_results = 0` searches return about what fits
+pub fn set_viewport(_: &Lua, rows: usize) -> LuaResult<bool> {
+    result_count::set_viewport(rows);
+    Ok(true)
+}
+
+/// Matches the most often typed query prefixes in the background while the picker shows the
+/// results of the empty query, so the first keystrokes are answered without matching
+pub fn speculate_search(
+    lua: &Lua,
+    (max_results, max_threads, current_file, picker_id, context): (
+        usize,
+        usize,
+        Option<String>,
+        Option<String>,
+        SearchContext,
+    ),
+) -> LuaResult<bool> {
+    if is_daemon_attached(lua, ())? {
+        return Ok(false);
+    }
+
+    let max_results = result_count::resolve(max_results);
+    let scope = context_root(&context)?;
+    let picker_id = search_picker_id(picker_id.as_deref());
+    std::thread::spawn(move || {
+        // taken before the lock, the first keystroke's search supersedes it while waiting
+        let ticket = SearchTicket::begin(&picker_id);
+        let Ok(file_picker) = FILE_PICKER.read() else {
+            return;
+        };
+        if let Some(picker) = file_picker.as_ref() {
+            picker.speculate(
+                scope.as_deref(),
+                current_file.as_deref(),
+                max_results,
+                max_threads,
+                &ticket,
+            );
+        }
+    });
+    Ok(true)
+}
+
+pub fn get_cached_files(lua: &Lua, options: FileListOptions) -> LuaResult<LuaValue> {
+    let params = json!({
+        "offset": options.offset,
+        "limit": options.limit,
+        "sort_by": options.sort_by.as_str(),
+        "prefix": options.prefix,
+    });
+    if let Some(page) = daemon_call("get_cached_files", params)? {
+        return json_to_lua(lua, &page);
+    }
+
+    let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+    let picker = file_picker
+        .as_ref()
+        .ok_or_else(|| Error::FilePickerMissing)?;
+    picker.list_files(&options).into_lua(lua)
+}
+
+/// Lines of the indexed files containing `pattern`, ready for `setqflist`
+pub fn live_grep(
+    lua: &Lua,
+    (pattern, options, context): (String, GrepOptions, SearchContext),
+) -> LuaResult<LuaValue> {
+    let scope = context_root(&context)?;
+    let params = json!({
+        "pattern": pattern,
+        "before_context": options.before_context,
+        "after_context": options.after_context,
+        "max_matches_per_file": options.max_matches_per_file,
+        "max_matches": options.max_matches,
+        "max_file_size": options.max_file_size,
+        "scope": scope,
+    });
+    if let Some(result) = daemon_call("grep", params)? {
+        return json_to_lua(lua, &result);
+    }
+
+    let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+    let picker = file_picker
+        .as_ref()
+        .ok_or_else(|| Error::FilePickerMissing)?;
+    picker
+        .grep(scope.as_deref(), &pattern, options)
+        .into_lua(lua)
+}
+
+/// Files to jump to from `word`: path matches, files containing it and files edited together
+/// with `current_file`, in one ranked list of quickfix items
+pub fn search_word(
+    lua: &Lua,
+    (word, current_file, max_results, max_threads): (
+        String,
+        Option<String>,
+        Option<usize>,
+        Option<usize>,
+    ),
+) -> LuaResult<LuaValue> {
+    let max_results = max_results.unwrap_or(20);
+    let max_threads = max_threads.unwrap_or(4);
+    let params = json!({
+        "word": word,
+        "current_file": current_file,
+        "max_results": max_results,
+        "max_threads": max_threads,
+    });
+    if let Some(hits) = daemon_call("search_word", params)? {
+        return json_to_lua(lua, &hits);
+    }
+
+    let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+    let picker = file_picker
+        .as_ref()
+        .ok_or_else(|| Error::FilePickerMissing)?;
+    picker
+        .search_word(
+            &word,
+            current_file.as_deref().map(Path::new),
+            max_results,
+            max_threads,
+        )
+        .into_lua(lua)
+}
+
+/// Lines of the indexed files with `pattern` replaced, nothing is written until `apply_replace`
+pub fn prepare_replace(
+    lua: &Lua,
+    (pattern, replacement, globs, ignore_case, context): (
+        String,
+        String,
+        Option<Vec<String>>,
+        Option<bool>,
+        SearchContext,
+    ),
+) -> LuaResult<LuaValue> {
+    let scope = context_root(&context)?;
+    let ignore_case = ignore_case.unwrap_or(false);
+    let params = json!({
+        "pattern": pattern,
+        "replacement": replacement,
+        "globs": globs,
+        "ignore_case": ignore_case,
+        "scope": scope,
+    });
+    if let Some(plan) = daemon_call("prepare_replace", params)? {
+        return json_to_lua(lua, &plan);
+    }
+
+    let glob_filter = globs.as_deref().map(GlobFilter::cached).transpose()?;
+    let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+    let picker = file_picker
+        .as_ref()
+        .ok_or_else(|| Error::FilePickerMissing)?;
+    picker
+        .prepare_replace(
+            scope.as_deref(),
+            &pattern,
+            &replacement,
+            glob_filter.as_deref(),
+            ignore_case,
+        )
+        .into_lua(lua)
+}
+
+/// Writes the prepared hunks with the given ids, all of them without a selection
+pub fn apply_replace(lua: &Lua, selection: Option<Vec<usize>>) -> LuaResult<LuaValue> {
+    let params = json!({ "selection": selection });
+    if let Some(outcome) = daemon_call("apply_replace", params)? {
+        return json_to_lua(lua, &outcome);
+    }
+
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+    picker.apply_replace(selection.as_deref())?.into_lua(lua)
+}
+
+pub fn push_filter(_: &Lua, (query, max_threads): (String, Option<usize>)) -> LuaResult<usize> {
+    let max_threads = max_threads.unwrap_or(4);
+    let params = json!({ "query": query, "max_threads": max_threads });
+    if let Some(result) = daemon_call("push_filter", params)? {
+        return Ok(result.as_u64().unwrap_or_default() as usize);
+    }
+
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    Ok(picker.push_filter(&query, max_threads))
+}
+
+pub fn pop_filter(_: &Lua, _: ()) -> LuaResult<bool> {
+    if let Some(result) = daemon_call("pop_filter", Value::Null)? {
+        return Ok(result.as_bool().unwrap_or_default());
+    }
+
     let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
         return Err(Error::FilePickerMissing)?;
     };
 
-    let results = FilePicker::fuzzy_search(
-        picker.get_files(),
-        &query,
+    Ok(picker.pop_filter())
+}
+
+pub fn set_list_entries(_: &Lua, mut entries: Vec<ListEntry>) -> LuaResult<usize> {
+    if let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? {
+        let cwd = std::env::current_dir()?;
+        list::update_list_frecency(&mut entries, frecency, &cwd);
+    }
+
+    let count = entries.len();
+    *LIST_ENTRIES.write().map_err(|_| Error::AcquireItemLock)? = entries;
+    Ok(count)
+}
+
+pub fn fuzzy_search_list(
+    lua: &Lua,
+    (query, max_results, max_threads): (String, usize, usize),
+) -> LuaResult<LuaValue> {
+    let entries = LIST_ENTRIES.read().map_err(|_| Error::AcquireItemLock)?;
+    let context = ScoringContext {
+        query: &query,
+        current_file: None,
         max_results,
-        max_threads,
-        current_file.as_deref(),
-    );
+        max_typos: (query.len() as u16 / 4).clamp(2, 6),
+        max_threads: max_threads.max(1),
+        weights: Default::default(),
+        category_filter: None,
+        file_filter: None,
+        profile: None,
+        glob_filter: None,
+        current_package: None,
+        ticket: None,
+        narrowed_to: None,
+        co_changed: None,
+        related_by_history: None,
+        session_files: None,
+        time_of_day_scores: None,
+        entry_points: None,
+        candidates: None,
+    };
 
-    results.into_lua(lua)
+    list::match_and_score_list(&entries, &context).into_lua(lua)
 }
 
 pub fn track_access(_: &Lua, file_path: String) -> LuaResult<bool> {
+    if let Some(result) = daemon_call("track_access", json!({ "path": file_path }))? {
+        return Ok(result.as_bool().unwrap_or_default());
+    }
+
+    ensure_frecency_map_capacity()?;
+
     let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
         return Ok(false);
     };
@@ -119,7 +909,7 @@ pub fn track_access(_: &Lua, file_path: String) -> LuaResult<bool> {
         return Err(Error::FilePickerMissing)?;
     };
 
-    let file_path = PathBuf::from(&file_path).canonicalize()?;
+    let file_path = path_utils::canonicalize(&file_path)?;
     frecency.track_access(file_path.as_path())?;
 
     picker.update_single_file_frecency(&file_path, frecency)?;
@@ -127,7 +917,156 @@ pub fn track_access(_: &Lua, file_path: String) -> LuaResult<bool> {
     Ok(true)
 }
 
+/// Records an edit of a buffer as a modification of its file, scored even outside of git
+pub fn track_edit(_: &Lua, (file_path, timestamp): (String, Option<u64>)) -> LuaResult<bool> {
+    let params = json!({ "path": file_path, "timestamp": timestamp });
+    if let Some(result) = daemon_call("track_edit", params)? {
+        return Ok(result.as_bool().unwrap_or_default());
+    }
+
+    let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+        return Ok(false);
+    };
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    let file_path = path_utils::canonicalize(&file_path)?;
+    frecency.track_edit(&file_path, timestamp)?;
+    picker.update_single_file_frecency(&file_path, frecency)?;
+
+    Ok(true)
+}
+
+pub fn get_frecency_details(lua: &Lua, file_path: String) -> LuaResult<LuaValue> {
+    let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+        return Ok(LuaValue::Nil);
+    };
+
+    let file_path = path_utils::canonicalize(&file_path)?;
+    let (modified, git_status) = match *FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)? {
+        Some(ref picker) => picker
+            .get_file_by_path(&file_path)
+            .map_or((None, None), |file| (Some(file.modified), file.git_status)),
+        None => (None, None),
+    };
+
+    frecency
+        .get_frecency_details(&file_path, modified, git_status)?
+        .into_lua(lua)
+}
+
+/// Maps every requested path to its recent access count, paths are returned as they were given
+pub fn get_access_counts(lua: &Lua, paths: Vec<String>) -> LuaResult<LuaTable> {
+    let table = lua.create_table_with_capacity(0, paths.len())?;
+    let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+        return Ok(table);
+    };
+
+    let resolved: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| path_utils::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)))
+        .collect();
+    for (path, count) in paths
+        .into_iter()
+        .zip(frecency.get_access_counts(&resolved)?)
+    {
+        table.set(path, count)?;
+    }
+
+    Ok(table)
+}
+
+pub fn get_related_by_history(lua: &Lua, file_path: String) -> LuaResult<LuaValue> {
+    if let Some(related) = daemon_call("get_related_by_history", json!({ "path": file_path }))? {
+        return json_to_lua(lua, &related);
+    }
+
+    let table = lua.create_table()?;
+    let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+        return Ok(LuaValue::Table(table));
+    };
+
+    let file_path = path_utils::canonicalize(&file_path)?;
+    for (path, count) in frecency.get_co_changes(&file_path)? {
+        let entry = lua.create_table()?;
+        entry.set("path", path.to_string_lossy())?;
+        entry.set("count", count)?;
+        table.push(entry)?;
+    }
+
+    Ok(LuaValue::Table(table))
+}
+
+pub fn set_file_meta(_: &Lua, (path, meta): (String, Option<FileMeta>)) -> LuaResult<bool> {
+    if let Some(result) = daemon_call("set_file_meta", json!({ "path": path, "meta": meta }))? {
+        return Ok(result.as_bool().unwrap_or_default());
+    }
+
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    Ok(picker.set_file_meta(&path, meta))
+}
+
+pub fn set_diagnostics_counts(
+    _: &Lua,
+    counts: HashMap<String, DiagnosticsCount>,
+) -> LuaResult<usize> {
+    if let Some(result) = daemon_call("set_diagnostics_counts", json!({ "counts": counts }))? {
+        return Ok(result.as_u64().unwrap_or_default() as usize);
+    }
+
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    Ok(picker.set_diagnostics_counts(
+        counts
+            .into_iter()
+            .map(|(path, count)| (PathBuf::from(path), count)),
+    ))
+}
+
+pub fn set_jump_positions(_: &Lua, paths: Vec<String>) -> LuaResult<usize> {
+    if let Some(result) = daemon_call("set_jump_positions", json!({ "paths": paths }))? {
+        return Ok(result.as_u64().unwrap_or_default() as usize);
+    }
+
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    Ok(picker.set_jump_positions(paths.into_iter().map(PathBuf::from)))
+}
+
+pub fn configure(_: &Lua, update: ConfigUpdate) -> LuaResult<Vec<&'static str>> {
+    fff_core::config::configure(update).map_err(Into::into)
+}
+
+/// Masks the credentials of preview lines with the configured redaction patterns, returning the
+/// lines and how many of them were changed
+pub fn redact_lines(_: &Lua, mut lines: Vec<String>) -> LuaResult<(Vec<String>, usize)> {
+    let redacted = fff_core::redact::redact_lines(&mut lines)?;
+    Ok((lines, redacted))
+}
+
+/// Whole lines of a previewed file from byte `offset` with their credentials masked, returning
+/// the lines, the offset of the next chunk and whether the file ended
+pub fn read_redacted_lines(
+    _: &Lua,
+    (path, offset, max_bytes): (String, u64, usize),
+) -> LuaResult<(Vec<String>, u64, bool)> {
+    let chunk = fff_core::redact::read_redacted_lines(Path::new(&path), offset, max_bytes)?;
+    Ok((chunk.lines, chunk.next_offset, chunk.eof))
+}
+
 pub fn get_scan_progress(lua: &Lua, _: ()) -> LuaResult<LuaValue> {
+    if let Some(progress) = daemon_call("get_scan_progress", Value::Null)? {
+        return json_to_lua(lua, &progress);
+    }
+
     let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
     let picker = file_picker
         .as_ref()
@@ -137,10 +1076,104 @@ pub fn get_scan_progress(lua: &Lua, _: ()) -> LuaResult<LuaValue> {
     let table = lua.create_table()?;
     table.set("scanned_files_count", progress.scanned_files_count)?;
     table.set("is_scanning", progress.is_scanning)?;
+    table.set("git_status_generation", picker.git_status_generation())?;
     Ok(LuaValue::Table(table))
 }
 
+pub fn check_index_drift(
+    lua: &Lua,
+    (sample_size, threshold_percent): (usize, usize),
+) -> LuaResult<LuaValue> {
+    let params = json!({ "sample_size": sample_size, "threshold_percent": threshold_percent });
+    if let Some(drift) = daemon_call("check_index_drift", params)? {
+        return json_to_lua(lua, &drift);
+    }
+
+    let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
+    let picker = file_picker
+        .as_mut()
+        .ok_or_else(|| Error::FilePickerMissing)?;
+
+    picker
+        .check_index_drift(sample_size, threshold_percent)
+        .into_lua(lua)
+}
+
+pub fn get_index_drift(_: &Lua, _: ()) -> LuaResult<Option<IndexDrift>> {
+    let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+    Ok(file_picker
+        .as_ref()
+        .and_then(|picker| picker.last_index_drift()))
+}
+
+/// Size of the index, the one of the daemon while attached, with the allocator of this process
+pub fn get_metrics(lua: &Lua, _: ()) -> LuaResult<LuaValue> {
+    let metrics = match daemon_call("get_metrics", Value::Null)? {
+        Some(metrics) => json_to_lua(lua, &metrics)?,
+        None => {
+            let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+            let picker = file_picker
+                .as_ref()
+                .ok_or_else(|| Error::FilePickerMissing)?;
+            picker.metrics().into_lua(lua)?
+        }
+    };
+    if let LuaValue::Table(ref table) = metrics {
+        table.set("allocator", allocator::stats().into_lua(lua)?)?;
+    }
+    Ok(metrics)
+}
+
+pub fn diff_index(lua: &Lua, since_generation: u64) -> LuaResult<LuaValue> {
+    if let Some(diff) = daemon_call(
+        "diff_index",
+        json!({ "since_generation": since_generation }),
+    )? {
+        return json_to_lua(lua, &diff);
+    }
+
+    let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+    let picker = file_picker
+        .as_ref()
+        .ok_or_else(|| Error::FilePickerMissing)?;
+    picker.diff_index(since_generation).into_lua(lua)
+}
+
+pub fn get_index_stats(lua: &Lua, _: ()) -> LuaResult<LuaValue> {
+    if let Some(stats) = daemon_call("get_index_stats", Value::Null)? {
+        return json_to_lua(lua, &stats);
+    }
+
+    let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
+    let picker = file_picker
+        .as_ref()
+        .ok_or_else(|| Error::FilePickerMissing)?;
+    picker.index_stats().into_lua(lua)
+}
+
+pub fn poll_events(_: &Lua, _: ()) -> LuaResult<Vec<PickerEvent>> {
+    Ok(events::drain_events())
+}
+
+pub fn subscribe_scan_batches(_: &Lua, _: ()) -> LuaResult<bool> {
+    scan_batches::subscribe();
+    Ok(true)
+}
+
+pub fn unsubscribe_scan_batches(_: &Lua, _: ()) -> LuaResult<bool> {
+    scan_batches::unsubscribe();
+    Ok(true)
+}
+
+pub fn poll_scan_batches(_: &Lua, _: ()) -> LuaResult<Vec<ScanBatch>> {
+    Ok(scan_batches::drain_batches())
+}
+
 pub fn is_scanning(_: &Lua, _: ()) -> LuaResult<bool> {
+    if let Some(progress) = daemon_call("get_scan_progress", Value::Null)? {
+        return Ok(progress["is_scanning"].as_bool().unwrap_or_default());
+    }
+
     let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
     let picker = file_picker
         .as_ref()
@@ -149,10 +1182,33 @@ pub fn is_scanning(_: &Lua, _: ()) -> LuaResult<bool> {
 }
 
 pub fn refresh_git_status(_: &Lua, _: ()) -> LuaResult<usize> {
+    if let Some(updated) = daemon_call("refresh_git_status", Value::Null)? {
+        return Ok(updated.as_u64().unwrap_or_default() as usize);
+    }
+
     FilePicker::refresh_git_status_global().map_err(Into::into)
 }
 
+/// Statuses change behind the watcher's back when git runs outside the workdir (commits from
+/// another worktree, fetches), the editor regaining focus is a good moment to catch up
+pub fn notify_focus_gained(_: &Lua, _: ()) -> LuaResult<bool> {
+    if FILE_PICKER
+        .read()
+        .map_err(|_| Error::AcquireItemLock)?
+        .is_none()
+    {
+        return Ok(false);
+    }
+
+    Ok(FilePicker::refresh_git_status_in_background())
+}
+
 pub fn update_single_file_frecency(_: &Lua, file_path: String) -> LuaResult<bool> {
+    if let Some(result) = daemon_call("update_single_file_frecency", json!({ "path": file_path }))?
+    {
+        return Ok(result.as_bool().unwrap_or_default());
+    }
+
     let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
         return Ok(false);
     };
@@ -164,6 +1220,63 @@ pub fn update_single_file_frecency(_: &Lua, file_path: String) -> LuaResult<bool
     Ok(true)
 }
 
+pub fn migrate_frecency_prefix(
+    _: &Lua,
+    (old_prefix, new_prefix): (String, String),
+) -> LuaResult<usize> {
+    if let Some(result) = daemon_call(
+        "migrate_frecency_prefix",
+        json!({ "old_prefix": old_prefix, "new_prefix": new_prefix }),
+    )? {
+        return Ok(result.as_u64().unwrap_or_default() as usize);
+    }
+
+    let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+        return Ok(0);
+    };
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    // the old location is usually gone and can not be canonicalized anymore
+    let new_prefix = path_utils::canonicalize(&new_prefix).unwrap_or_else(|_| new_prefix.into());
+    Ok(picker.migrate_frecency_prefix(frecency, Path::new(&old_prefix), &new_prefix)?)
+}
+
+/// Drops the frecency history of every file under the root, returns how many files had one
+pub fn forget_frecency_project(_: &Lua, root: String) -> LuaResult<usize> {
+    if let Some(result) = daemon_call("forget_frecency_project", json!({ "root": root }))? {
+        return Ok(result.as_u64().unwrap_or_default() as usize);
+    }
+
+    let Some(ref frecency) = *FRECENCY.read().map_err(|_| Error::AcquireFrecencyLock)? else {
+        return Ok(0);
+    };
+
+    // the project is usually deleted already and can not be canonicalized anymore
+    let root = path_utils::canonicalize(&root).unwrap_or_else(|_| root.into());
+    let forgotten = frecency.forget_project(&root)?;
+    if forgotten > 0 {
+        if let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? {
+            picker.refresh_frecency_scores(frecency)?;
+        }
+    }
+
+    Ok(forgotten)
+}
+
+pub fn watch_extra_path(_: &Lua, path: String) -> LuaResult<bool> {
+    if let Some(result) = daemon_call("watch_extra_path", json!({ "path": path }))? {
+        return Ok(result.as_bool().unwrap_or_default());
+    }
+
+    let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
+        return Err(Error::FilePickerMissing)?;
+    };
+
+    Ok(picker.watch_extra_path(&path)?)
+}
+
 pub fn stop_background_monitor(_: &Lua, _: ()) -> LuaResult<bool> {
     let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)? else {
         return Err(Error::FilePickerMissing)?;
@@ -174,6 +1287,13 @@ pub fn stop_background_monitor(_: &Lua, _: ()) -> LuaResult<bool> {
     Ok(true)
 }
 
+/// Stops the watcher, stores the index snapshot and flushes the frecency database before
+/// Neovim exits, each step waits at most `step_timeout_ms`
+pub fn shutdown(_: &Lua, step_timeout_ms: Option<u64>) -> LuaResult<bool> {
+    let step_timeout = Duration::from_millis(step_timeout_ms.unwrap_or(500));
+    Ok(fff_core::shutdown::shutdown(step_timeout))
+}
+
 pub fn cleanup_file_picker(_: &Lua, _: ()) -> LuaResult<bool> {
     let mut file_picker = FILE_PICKER.write().map_err(|_| Error::AcquireItemLock)?;
     if let Some(picker) = file_picker.take() {
@@ -190,18 +1310,23 @@ pub fn cancel_scan(_: &Lua, _: ()) -> LuaResult<bool> {
     Ok(true)
 }
 
-pub fn wait_for_initial_scan(_: &Lua, timeout_ms: Option<u64>) -> LuaResult<bool> {
+pub fn wait_for_initial_scan(lua: &Lua, timeout_ms: Option<u64>) -> LuaResult<bool> {
     let file_picker = FILE_PICKER.read().map_err(|_| Error::AcquireItemLock)?;
-    let picker = file_picker
-        .as_ref()
-        .ok_or_else(|| Error::FilePickerMissing)?;
+    let picker = file_picker.as_ref();
+    if picker.is_none() && !is_daemon_attached(lua, ())? {
+        return Err(Error::FilePickerMissing.into());
+    }
 
     let timeout_ms = timeout_ms.unwrap_or(500);
     let timeout_duration = Duration::from_millis(timeout_ms);
     let start_time = std::time::Instant::now();
     let mut sleep_duration = Duration::from_millis(1);
 
-    while picker.is_scan_active() {
+    let is_scan_active = || match picker {
+        Some(picker) => Ok(picker.is_scan_active()),
+        None => is_scanning(lua, ()),
+    };
+    while is_scan_active()? {
         if start_time.elapsed() >= timeout_duration {
             ::tracing::warn!("wait_for_initial_scan timed out after {}ms", timeout_ms);
             return Ok(false);
@@ -222,27 +1347,134 @@ pub fn init_tracing(
     _: &Lua,
     (log_file_path, log_level): (String, Option<String>),
 ) -> LuaResult<String> {
-    crate::tracing::init_tracing(&log_file_path, log_level.as_deref())
+    fff_core::tracing::init_tracing(&log_file_path, log_level.as_deref())
         .map_err(|e| LuaError::RuntimeError(format!("Failed to initialize tracing: {}", e)))
 }
 
+pub fn generate_debug_bundle(
+    _: &Lua,
+    (bundle_path, extra_sections, include_paths): (
+        String,
+        Option<BTreeMap<String, String>>,
+        Option<bool>,
+    ),
+) -> LuaResult<String> {
+    fff_core::debug_bundle::generate_debug_bundle(
+        std::path::Path::new(&bundle_path),
+        &extra_sections.unwrap_or_default(),
+        include_paths.unwrap_or(false),
+    )?;
+
+    Ok(bundle_path)
+}
+
 fn create_exports(lua: &Lua) -> LuaResult<LuaTable> {
     let exports = lua.create_table()?;
+    exports.set(
+        "get_api_version",
+        lua.create_function(api::get_api_version)?,
+    )?;
     exports.set("init_db", lua.create_function(init_db)?)?;
     exports.set("destroy_db", lua.create_function(destroy_db)?)?;
+    exports.set("flush_db", lua.create_function(flush_db)?)?;
+    exports.set("shutdown", lua.create_function(shutdown)?)?;
+    exports.set("compact_db", lua.create_function(compact_db)?)?;
     exports.set("init_file_picker", lua.create_function(init_file_picker)?)?;
     exports.set(
         "restart_index_in_path",
         lua.create_function(restart_index_in_path)?,
     )?;
+    exports.set("index_file_list", lua.create_function(index_file_list)?)?;
+    exports.set("index_remote", lua.create_function(index_remote)?)?;
+    exports.set("set_context_root", lua.create_function(set_context_root)?)?;
+    exports.set("speculate_search", lua.create_function(speculate_search)?)?;
     exports.set("scan_files", lua.create_function(scan_files)?)?;
+    exports.set("init_snapshots", lua.create_function(init_snapshots)?)?;
+    exports.set(
+        "notify_focus_gained",
+        lua.create_function(notify_focus_gained)?,
+    )?;
+    exports.set("attach_daemon", lua.create_function(attach_daemon)?)?;
+    exports.set("detach_daemon", lua.create_function(detach_daemon)?)?;
+    exports.set(
+        "is_daemon_attached",
+        lua.create_function(is_daemon_attached)?,
+    )?;
     exports.set(
         "fuzzy_search_files",
         lua.create_function(fuzzy_search_files)?,
     )?;
+    exports.set("get_cached_files", lua.create_function(get_cached_files)?)?;
+    exports.set("live_grep", lua.create_function(live_grep)?)?;
+    exports.set("search_word", lua.create_function(search_word)?)?;
+    exports.set("prepare_replace", lua.create_function(prepare_replace)?)?;
+    exports.set("apply_replace", lua.create_function(apply_replace)?)?;
+    exports.set("push_filter", lua.create_function(push_filter)?)?;
+    exports.set("pop_filter", lua.create_function(pop_filter)?)?;
+    exports.set("set_list_entries", lua.create_function(set_list_entries)?)?;
+    exports.set("fuzzy_search_list", lua.create_function(fuzzy_search_list)?)?;
     exports.set("track_access", lua.create_function(track_access)?)?;
+    exports.set("track_edit", lua.create_function(track_edit)?)?;
+    exports.set("get_access_counts", lua.create_function(get_access_counts)?)?;
+    exports.set(
+        "get_related_by_history",
+        lua.create_function(get_related_by_history)?,
+    )?;
+    exports.set(
+        "migrate_frecency_prefix",
+        lua.create_function(migrate_frecency_prefix)?,
+    )?;
+    exports.set("register_scorer", lua.create_function(register_scorer)?)?;
+    exports.set("unregister_scorer", lua.create_function(unregister_scorer)?)?;
+    exports.set("list_scorers", lua.create_function(list_scorers)?)?;
+    exports.set(
+        "forget_frecency_project",
+        lua.create_function(forget_frecency_project)?,
+    )?;
+    exports.set("watch_extra_path", lua.create_function(watch_extra_path)?)?;
+    exports.set(
+        "get_frecency_details",
+        lua.create_function(get_frecency_details)?,
+    )?;
+    exports.set("set_file_meta", lua.create_function(set_file_meta)?)?;
+    exports.set(
+        "set_diagnostics_counts",
+        lua.create_function(set_diagnostics_counts)?,
+    )?;
+    exports.set(
+        "set_jump_positions",
+        lua.create_function(set_jump_positions)?,
+    )?;
+    exports.set("configure", lua.create_function(configure)?)?;
+    exports.set("redact_lines", lua.create_function(redact_lines)?)?;
+    exports.set(
+        "read_redacted_lines",
+        lua.create_function(read_redacted_lines)?,
+    )?;
     exports.set("cancel_scan", lua.create_function(cancel_scan)?)?;
     exports.set("get_scan_progress", lua.create_function(get_scan_progress)?)?;
+    exports.set("check_index_drift", lua.create_function(check_index_drift)?)?;
+    exports.set("get_index_drift", lua.create_function(get_index_drift)?)?;
+    exports.set("get_metrics", lua.create_function(get_metrics)?)?;
+    exports.set("poll_events", lua.create_function(poll_events)?)?;
+    exports.set("set_viewport", lua.create_function(set_viewport)?)?;
+    exports.set("set_path_width", lua.create_function(set_path_width)?)?;
+    exports.set("set_search_trace", lua.create_function(set_search_trace)?)?;
+    exports.set(
+        "forget_result_diff",
+        lua.create_function(forget_result_diff)?,
+    )?;
+    exports.set(
+        "subscribe_scan_batches",
+        lua.create_function(subscribe_scan_batches)?,
+    )?;
+    exports.set(
+        "unsubscribe_scan_batches",
+        lua.create_function(unsubscribe_scan_batches)?,
+    )?;
+    exports.set("poll_scan_batches", lua.create_function(poll_scan_batches)?)?;
+    exports.set("get_index_stats", lua.create_function(get_index_stats)?)?;
+    exports.set("diff_index", lua.create_function(diff_index)?)?;
     exports.set(
         "refresh_git_status",
         lua.create_function(refresh_git_status)?,
@@ -260,6 +1492,10 @@ fn create_exports(lua: &Lua) -> LuaResult<LuaTable> {
         "cleanup_file_picker",
         lua.create_function(cleanup_file_picker)?,
     )?;
+    exports.set(
+        "generate_debug_bundle",
+        lua.create_function(generate_debug_bundle)?,
+    )?;
     Ok(exports)
 }
 
@@ -17,4 +17,22 @@ function M.format_file_size(size)
   end
 end
 
+--- Url of an archive entry indexed as `archive.zip::path/inside`, opened without extracting the
+--- archive by the zip.vim and tar.vim plugins shipped with neovim
+--- @param path string Indexed path
+--- @return string|nil Url of the entry, nil for regular files
+function M.archive_entry_url(path)
+  local archive, entry = path:match('^(.-)::(.+)$')
+  if not archive then return nil end
+
+  if archive:match('%.tar$') then return 'tarfile:' .. archive .. '::' .. entry end
+  return 'zipfile://' .. archive .. '::' .. entry
+end
+
+--- Url netrw opens a file of a remote host with, absolute paths get the double slash scp needs
+--- @param host string Host the file is on
+--- @param path string Path of the file on the host
+--- @return string Url like `scp://host//srv/app/main.rs`
+function M.remote_url(host, path) return 'scp://' .. host .. '/' .. path end
+
 return M
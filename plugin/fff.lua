@@ -1 +1,14 @@
+-- defined before the native library is loaded so a missing build can still be downloaded
+vim.api.nvim_create_user_command('FFFDownload', function(opts)
+  local path, err = require('fff.download').download_prebuilt(opts.args ~= '' and opts.args or nil)
+  if path then
+    vim.notify('FFF library installed to ' .. path .. ', restart neovim to load it', vim.log.levels.INFO)
+  else
+    vim.notify('FFF library download failed: ' .. err, vim.log.levels.ERROR)
+  end
+end, {
+  nargs = '?',
+  desc = 'Download the prebuilt FFF library of a release tag, the latest one by default',
+})
+
 require('fff.main')
@@ -0,0 +1,530 @@
+use fff_core::{file_picker::FilePicker, types::ScanOptions, FILE_PICKER};
+use std::alloc::{GlobalAlloc, Layout};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// the allocator of the library, which isn't linked into the benchmark to count allocations
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+use mimalloc::MiMalloc as Allocator;
+#[cfg(not(any(feature = "mimalloc", feature = "jemalloc")))]
+use std::alloc::System as Allocator;
+#[cfg(feature = "jemalloc")]
+use tikv_jemallocator::Jemalloc as Allocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// The allocator of the library counting every allocation and the bytes requested, reallocations count
+/// as one allocation of the new size. Frees are not tracked, the counts show allocation churn.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        unsafe { Allocator.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        unsafe { Allocator.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation(new_size);
+        unsafe { Allocator.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { Allocator.dealloc(ptr, layout) }
+    }
+}
+
+fn count_allocation(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed);
+}
+
+/// Allocations and allocated bytes since `start`
+#[derive(Clone, Copy)]
+struct AllocationCount {
+    allocations: usize,
+    bytes: usize,
+}
+
+impl AllocationCount {
+    fn start() -> Self {
+        Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    fn elapsed(self) -> Self {
+        let now = Self::start();
+        Self {
+            allocations: now.allocations - self.allocations,
+            bytes: now.bytes - self.bytes,
+        }
+    }
+}
+
+const DIR_WORDS: &[&str] = &[
+    "src",
+    "lib",
+    "core",
+    "utils",
+    "models",
+    "services",
+    "api",
+    "components",
+    "handlers",
+    "config",
+    "tests",
+    "internal",
+    "pkg",
+    "views",
+    "controllers",
+    "helpers",
+    "legacy",
+    "shared",
+    "plugins",
+    "scripts",
+];
+
+const FILE_WORDS: &[&str] = &[
+    "user",
+    "account",
+    "session",
+    "picker",
+    "search",
+    "index",
+    "main",
+    "server",
+    "client",
+    "parser",
+    "router",
+    "store",
+    "cache",
+    "event",
+    "logger",
+    "config",
+    "token",
+    "query",
+    "mod",
+    "handler",
+    "middleware",
+    "schema",
+    "worker",
+    "queue",
+];
+
+const EXTENSIONS: &[&str] = &[
+    "rs", "lua", "ts", "tsx", "js", "py", "go", "md", "toml", "json",
+];
+
+struct BenchConfig {
+    files: usize,
+    depth: usize,
+    fanout: usize,
+    seed: u64,
+    iterations: usize,
+    max_results: usize,
+    threads: usize,
+    corpus_dir: Option<PathBuf>,
+    keep_corpus: bool,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            files: 50_000,
+            depth: 6,
+            fanout: 6,
+            seed: 42,
+            iterations: 30,
+            max_results: 100,
+            threads: 4,
+            corpus_dir: None,
+            keep_corpus: false,
+        }
+    }
+}
+
+fn print_usage() {
+    println!("Usage: bench_search [options]");
+    println!();
+    println!("  --files <n>        number of files in the synthetic tree (default 50000)");
+    println!("  --depth <n>        maximum directory depth (default 6)");
+    println!("  --fanout <n>       subdirectories per directory (default 6)");
+    println!("  --seed <n>         seed of the corpus and query generator (default 42)");
+    println!("  --iterations <n>   runs of every query (default 30)");
+    println!("  --max-results <n>  max results per search (default 100)");
+    println!("  --threads <n>      max threads per search (default 4)");
+    println!("  --corpus <dir>     where to generate the corpus (default: temp dir)");
+    println!("  --keep             do not remove the generated corpus");
+}
+
+fn parse_args() -> Result<BenchConfig, Box<dyn std::error::Error>> {
+    let mut config = BenchConfig::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--files" => config.files = value()?.parse()?,
+            "--depth" => config.depth = value()?.parse()?,
+            "--fanout" => config.fanout = value()?.parse()?,
+            "--seed" => config.seed = value()?.parse()?,
+            "--iterations" => config.iterations = value()?.parse()?,
+            "--max-results" => config.max_results = value()?.parse()?,
+            "--threads" => config.threads = value()?.parse()?,
+            "--corpus" => config.corpus_dir = Some(PathBuf::from(value()?)),
+            "--keep" => config.keep_corpus = true,
+            "--help" | "-h" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            other => return Err(format!("Unknown argument: {}", other).into()),
+        }
+    }
+
+    config.files = config.files.max(1);
+    config.fanout = config.fanout.max(1);
+    Ok(config)
+}
+
+/// Small xorshift generator, the corpus has to be identical for the same seed on every machine
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound.max(1) as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+fn generate_corpus(
+    root: &Path,
+    config: &BenchConfig,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut rng = Rng::new(config.seed);
+    let mut dirs = vec![PathBuf::new()];
+    let mut relative_paths = Vec::with_capacity(config.files);
+
+    // breadth first so that shallow directories are filled before deep ones
+    let mut index = 0;
+    while index < dirs.len() && dirs.len() < config.files / 4 + 1 {
+        let parent = dirs[index].clone();
+        index += 1;
+        if parent.components().count() >= config.depth {
+            continue;
+        }
+
+        for child in 0..config.fanout {
+            dirs.push(parent.join(format!("{}_{}", rng.pick(DIR_WORDS), child)));
+        }
+    }
+
+    for file_index in 0..config.files {
+        let dir = &dirs[rng.below(dirs.len())];
+        let file_name = format!(
+            "{}_{}{}.{}",
+            rng.pick(FILE_WORDS),
+            rng.pick(FILE_WORDS),
+            file_index,
+            rng.pick(EXTENSIONS)
+        );
+
+        let relative = dir.join(file_name);
+        let full_path = root.join(&relative);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&full_path, b"")?;
+        relative_paths.push(relative.to_string_lossy().into_owned());
+    }
+
+    Ok(relative_paths)
+}
+
+/// Scripted queries covering the most common ways people type into the picker
+fn build_workloads(relative_paths: &[String], seed: u64) -> Vec<(&'static str, Vec<String>)> {
+    let mut rng = Rng::new(seed ^ 0xBE7C);
+    let mut sample = |count: usize| -> Vec<&String> {
+        (0..count)
+            .map(|_| &relative_paths[rng.below(relative_paths.len())])
+            .collect()
+    };
+
+    let file_names = sample(10)
+        .into_iter()
+        .filter_map(|path| Path::new(path).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+
+    let incremental_typing = file_names
+        .iter()
+        .take(3)
+        .flat_map(|name| (1..=name.len().min(12)).map(move |len| name[..len].to_string()))
+        .collect();
+
+    let typos = file_names
+        .iter()
+        .filter(|name| name.len() > 4)
+        .map(|name| {
+            let mut chars = name.chars().collect::<Vec<_>>();
+            chars.swap(1, 2);
+            chars.remove(chars.len() / 2);
+            chars.into_iter().collect()
+        })
+        .collect();
+
+    let path_queries = sample(10)
+        .into_iter()
+        .filter_map(|path| {
+            let path = Path::new(path);
+            let parent = path.parent()?.file_name()?.to_string_lossy();
+            let stem = path.file_stem()?.to_string_lossy();
+            Some(format!(
+                "{}{}{}",
+                &parent[..parent.len().min(4)],
+                std::path::MAIN_SEPARATOR,
+                &stem[..stem.len().min(6)]
+            ))
+        })
+        .collect();
+
+    vec![
+        ("empty (frecency only)", vec![String::new()]),
+        ("short", vec!["ma".into(), "us".into(), "rs".into()]),
+        ("file names", file_names),
+        ("incremental typing", incremental_typing),
+        ("typos", typos),
+        ("path queries", path_queries),
+        (
+            "no matches",
+            vec!["zzqxj_nothing".into(), "qqqqwwwwzzzz".into()],
+        ),
+    ]
+}
+
+fn get_memory_usage() -> usize {
+    #[cfg(target_os = "linux")]
+    {
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        status
+            .lines()
+            .find(|line| line.starts_with("VmRSS:"))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<usize>().ok())
+            .map_or(0, |kb| kb * 1024)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let pid = std::process::id();
+        std::process::Command::new("ps")
+            .args(["-o", "rss=", "-p", &pid.to_string()])
+            .output()
+            .ok()
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .and_then(|rss| rss.trim().parse::<usize>().ok())
+            .map_or(0, |kb| kb * 1024)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        0
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    format!("{:.2} {}", size, UNITS[unit_index])
+}
+
+fn percentile(sorted: &[Duration], percentile: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+
+    let rank = (sorted.len() * percentile).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+/// Allocations are reported per run, i.e. per query of the search workloads
+fn print_latencies(
+    name: &str,
+    mut samples: Vec<Duration>,
+    memory_growth: usize,
+    allocations: AllocationCount,
+) {
+    samples.sort_unstable();
+    let runs = samples.len().max(1);
+    println!(
+        "{:<24} {:>6} {:>12} {:>12} {:>12} {:>12} {:>10} {:>12}",
+        name,
+        samples.len(),
+        format!("{:.2?}", percentile(&samples, 50)),
+        format!("{:.2?}", percentile(&samples, 95)),
+        format!("{:.2?}", percentile(&samples, 99)),
+        format_bytes(memory_growth),
+        allocations.allocations / runs,
+        format_bytes(allocations.bytes / runs),
+    );
+}
+
+fn wait_for_scan() {
+    loop {
+        if let Ok(file_picker_guard) = FILE_PICKER.read() {
+            if let Some(ref picker) = *file_picker_guard {
+                if !picker.is_scan_active() && !picker.get_files().is_empty() {
+                    break;
+                }
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = parse_args()?;
+    let corpus_dir = config.corpus_dir.clone().unwrap_or_else(|| {
+        env::temp_dir().join(format!("fff_bench_{}_{}", config.seed, config.files))
+    });
+
+    println!("FFF.nvim search benchmark");
+    println!("=========================");
+    println!(
+        "files: {}, depth: {}, fanout: {}, seed: {}, iterations: {}",
+        config.files, config.depth, config.fanout, config.seed, config.iterations
+    );
+    println!("corpus: {}", corpus_dir.display());
+
+    let _ = fs::remove_dir_all(&corpus_dir);
+    let generation_start = Instant::now();
+    let relative_paths = generate_corpus(&corpus_dir, &config)?;
+    println!(
+        "generated {} files in {:?}",
+        relative_paths.len(),
+        generation_start.elapsed()
+    );
+    println!();
+
+    let base_path = corpus_dir.to_string_lossy().into_owned();
+    let scan_allocations = AllocationCount::start();
+    let scan_start = Instant::now();
+    {
+        let mut file_picker_guard = FILE_PICKER.write().unwrap();
+        *file_picker_guard = Some(FilePicker::new(base_path, ScanOptions::default())?);
+    }
+    wait_for_scan();
+    let initial_scan = scan_start.elapsed();
+    let scan_allocations = scan_allocations.elapsed();
+
+    if let Some(ref mut picker) = *FILE_PICKER.write().unwrap() {
+        picker.stop_background_monitor();
+    }
+
+    println!(
+        "{:<24} {:>6} {:>12} {:>12} {:>12} {:>12} {:>10} {:>12}",
+        "workload", "runs", "p50", "p95", "p99", "rss growth", "allocs/run", "bytes/run"
+    );
+    println!("{:-<108}", "");
+    print_latencies("initial scan", vec![initial_scan], 0, scan_allocations);
+
+    let memory_before = get_memory_usage();
+    let rescan_allocations = AllocationCount::start();
+    let rescans = (0..config.iterations.clamp(1, 5))
+        .map(|_| {
+            let start = Instant::now();
+            if let Some(ref mut picker) = *FILE_PICKER.write().unwrap() {
+                let _ = picker.trigger_rescan();
+            }
+            start.elapsed()
+        })
+        .collect();
+    print_latencies(
+        "rescan",
+        rescans,
+        get_memory_usage().saturating_sub(memory_before),
+        rescan_allocations.elapsed(),
+    );
+
+    let file_picker_guard = FILE_PICKER.read().unwrap();
+    let picker = file_picker_guard.as_ref().unwrap();
+    let files = picker.get_files();
+
+    for (name, queries) in build_workloads(&relative_paths, config.seed) {
+        let memory_before = get_memory_usage();
+        let mut samples = Vec::with_capacity(queries.len() * config.iterations);
+        let mut allocations = AllocationCount {
+            allocations: 0,
+            bytes: 0,
+        };
+
+        for _ in 0..config.iterations {
+            for query in &queries {
+                let query_allocations = AllocationCount::start();
+                let start = Instant::now();
+                let result = FilePicker::fuzzy_search(
+                    files,
+                    query,
+                    config.max_results,
+                    config.threads,
+                    None,
+                );
+                samples.push(start.elapsed());
+                // the result is part of the search, dropping it isn't
+                let query_allocations = query_allocations.elapsed();
+                allocations.allocations += query_allocations.allocations;
+                allocations.bytes += query_allocations.bytes;
+                std::hint::black_box(result);
+            }
+        }
+
+        print_latencies(
+            name,
+            samples,
+            get_memory_usage().saturating_sub(memory_before),
+            allocations,
+        );
+    }
+    drop(file_picker_guard);
+
+    FILE_PICKER.write().unwrap().take();
+    if !config.keep_corpus {
+        let _ = fs::remove_dir_all(&corpus_dir);
+    }
+
+    Ok(())
+}
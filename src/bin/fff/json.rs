@@ -0,0 +1,279 @@
+use fff_core::glob_filter::GlobFilter;
+use fff_core::grep::GrepOptions;
+use fff_core::memory::IndexMetrics;
+use fff_core::replace::ReplaceOutcome;
+use fff_core::supersede::SearchTicket;
+use fff_core::types::{FileItem, FileListOptions, IndexDrift, ScoringWeights, SearchResult};
+use fff_core::{file_picker::FilePicker, git::format_git_status, FILE_PICKER};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Runs a query against the global index and serializes the results with their scores
+#[allow(clippy::too_many_arguments)]
+pub fn search(
+    query: &str,
+    max_results: usize,
+    max_threads: usize,
+    current_file: Option<&str>,
+    ticket: Option<&SearchTicket>,
+    group_by_directory: bool,
+    scope: Option<&Path>,
+    glob_filter: Option<&GlobFilter>,
+) -> Value {
+    let file_picker_guard = FILE_PICKER.read().unwrap();
+    let Some(ref picker) = *file_picker_guard else {
+        return Value::Null;
+    };
+
+    let result = FilePicker::fuzzy_search_weighted(
+        picker.scoped_files(scope),
+        query,
+        max_results,
+        max_threads,
+        current_file,
+        ScoringWeights::default(),
+        None,
+        ticket,
+        picker.narrowed_paths(),
+        picker.index_stats().entry_points(),
+        Some(picker.prefix_candidates()),
+        glob_filter,
+        None,
+    );
+    let result = SearchResult {
+        display_prefix: picker.display_prefix(),
+        ..result
+    };
+
+    let (layouts, columns) = result.layout();
+    let items = result
+        .items
+        .iter()
+        .zip(result.scores.iter())
+        .zip(layouts)
+        .map(|((file, score), layout)| {
+            let mut item = file_json(file);
+            item["relative_path"] = json!(result.display_path(&file.relative_path));
+            item["layout"] = json!({
+                "filename": layout.filename,
+                "directory": layout.directory,
+                "icon_class": layout.icon_class,
+                "git_badge": layout.git_badge,
+                "score_bucket": layout.score_bucket,
+                "frecency_bucket": layout.frecency_bucket,
+            });
+            item["score"] = json!({
+                "total": score.total,
+                "base_score": score.base_score,
+                "filename_bonus": score.filename_bonus,
+                "special_filename_bonus": score.special_filename_bonus,
+                "frecency_boost": score.frecency_boost,
+                "distance_penalty": score.distance_penalty,
+                "depth_penalty": score.depth_penalty,
+                "path_component_bonus": score.path_component_bonus,
+                "word_order_bonus": score.word_order_bonus,
+                "abbreviation_bonus": score.abbreviation_bonus,
+                "typo_bonus": score.typo_bonus,
+                "co_change_bonus": score.co_change_bonus,
+                "session_bonus": score.session_bonus,
+                "time_of_day_boost": score.time_of_day_boost,
+                "match_type": score.match_type,
+            });
+            item
+        })
+        .collect::<Vec<_>>();
+    let groups = group_by_directory.then(|| {
+        result
+            .group_by_directory()
+            .into_iter()
+            .map(|group| json!({ "directory": group.directory, "items": group.items }))
+            .collect::<Vec<_>>()
+    });
+
+    json!({
+        "query": query,
+        "items": items,
+        "total_matched": result.total_matched,
+        "total_files": result.total_files,
+        "superseded": result.superseded,
+        "approximate": result.approximate,
+        "groups": groups,
+        "columns": {
+            "filename_width": columns.filename_width,
+            "directory_width": columns.directory_width,
+        },
+    })
+}
+
+/// Serializes one page of the indexed files
+pub fn list_files(options: &FileListOptions) -> Value {
+    let file_picker_guard = FILE_PICKER.read().unwrap();
+    let Some(ref picker) = *file_picker_guard else {
+        return Value::Null;
+    };
+
+    let page = picker.list_files(options);
+    json!({
+        "items": page.items.into_iter().map(file_json).collect::<Vec<_>>(),
+        "total": page.total,
+    })
+}
+
+/// Greps the indexed files under `scope`, matches keep the quickfix item field names
+pub fn grep(pattern: &str, options: GrepOptions, scope: Option<&Path>) -> Value {
+    let file_picker_guard = FILE_PICKER.read().unwrap();
+    let Some(ref picker) = *file_picker_guard else {
+        return Value::Null;
+    };
+
+    let result = picker.grep(scope, pattern, options);
+    let matches: Vec<Value> = result
+        .matches
+        .into_iter()
+        .map(|m| {
+            json!({
+                "filename": m.path,
+                "relative_path": m.relative_path,
+                "lnum": m.lnum,
+                "col": m.col,
+                "end_col": m.end_col,
+                "text": m.text,
+                "before": m.before,
+                "after": m.after,
+                "encoding": m.encoding.as_str(),
+            })
+        })
+        .collect();
+    json!({
+        "matches": matches,
+        "matched_files": result.matched_files,
+        "searched_files": result.searched_files,
+        "truncated": result.truncated,
+        "skipped_files": result.skipped_files,
+    })
+}
+
+/// Prepares replacing `pattern` in the indexed files under `scope`, hunks are applied by id
+pub fn prepare_replace(
+    pattern: &str,
+    replacement: &str,
+    scope: Option<&Path>,
+    glob_filter: Option<&GlobFilter>,
+    ignore_case: bool,
+) -> Value {
+    let file_picker_guard = FILE_PICKER.read().unwrap();
+    let Some(ref picker) = *file_picker_guard else {
+        return Value::Null;
+    };
+
+    let plan = picker.prepare_replace(scope, pattern, replacement, glob_filter, ignore_case);
+    let files: Vec<Value> = plan
+        .files
+        .iter()
+        .map(|file| {
+            let hunks: Vec<Value> = file
+                .hunks
+                .iter()
+                .map(|hunk| {
+                    json!({
+                        "id": hunk.id,
+                        "lnum": hunk.lnum,
+                        "old_text": hunk.old_text,
+                        "new_text": hunk.new_text,
+                    })
+                })
+                .collect();
+            json!({
+                "filename": file.path,
+                "relative_path": file.relative_path,
+                "encoding": file.encoding.as_str(),
+                "hunks": hunks,
+            })
+        })
+        .collect();
+    json!({
+        "pattern": plan.pattern,
+        "replacement": plan.replacement,
+        "hunk_count": plan.hunk_count(),
+        "files": files,
+    })
+}
+
+/// Files to jump to from the word under the cursor, in the shape of quickfix items
+pub fn search_word(
+    word: &str,
+    current_file: Option<&Path>,
+    max_results: usize,
+    max_threads: usize,
+) -> Value {
+    let file_picker_guard = FILE_PICKER.read().unwrap();
+    let Some(ref picker) = *file_picker_guard else {
+        return Value::Null;
+    };
+
+    let hits: Vec<Value> = picker
+        .search_word(word, current_file, max_results, max_threads)
+        .into_iter()
+        .map(|hit| {
+            json!({
+                "filename": hit.path,
+                "relative_path": hit.relative_path,
+                "score": hit.score,
+                "name_match": hit.name_match,
+                "content_matches": hit.content_matches,
+                "lnum": hit.lnum.max(1),
+                "col": hit.col.max(1),
+                "text": hit.text,
+                "co_changed": hit.co_changed,
+            })
+        })
+        .collect();
+    Value::Array(hits)
+}
+
+pub fn replace_outcome(outcome: ReplaceOutcome) -> Value {
+    json!({
+        "written": outcome.written,
+        "applied_hunks": outcome.applied_hunks,
+        "conflicts": outcome.conflicts,
+        "failed": outcome.failed,
+        "error": outcome.error,
+    })
+}
+
+pub fn index_drift(drift: IndexDrift) -> Value {
+    json!({
+        "sampled": drift.sampled,
+        "missing": drift.missing,
+        "stale": drift.stale,
+        "percent": drift.percent(),
+        "rescan_triggered": drift.rescan_triggered,
+    })
+}
+
+pub fn index_metrics(metrics: IndexMetrics) -> Value {
+    json!({
+        "files": metrics.files,
+        "index_bytes": metrics.index_bytes,
+        "meta_bytes": metrics.meta_bytes,
+        "budget_bytes": metrics.budget_bytes,
+        "over_budget": metrics.is_over_budget(),
+        "dropped_meta": metrics.dropped_meta,
+    })
+}
+
+fn file_json(file: &FileItem) -> Value {
+    json!({
+        "path": file.path,
+        "relative_path": file.relative_path,
+        "name": file.file_name,
+        "size": file.size,
+        "modified": file.modified,
+        "git_status": format_git_status(file.git_status),
+        "category": file.category.as_deref(),
+        "sensitivity": file.sensitivity.map(|sensitivity| sensitivity.as_str()),
+        "package": file.package.as_deref(),
+        "root": file.root.as_deref(),
+        "total_frecency_score": file.total_frecency_score,
+    })
+}
@@ -0,0 +1,273 @@
+mod json;
+mod server;
+
+use fff_core::{
+    file_picker::FilePicker, frecency::FrecencyTracker, glob_filter::GlobFilter, path_utils,
+    types::ScanOptions, FILE_PICKER, FRECENCY,
+};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct CliArgs {
+    base_path: PathBuf,
+    json_query: Option<String>,
+    globs: Vec<String>,
+    track: Option<PathBuf>,
+    db_path: Option<PathBuf>,
+    max_results: usize,
+    max_threads: usize,
+    current_file: Option<String>,
+    scan_timeout: Duration,
+    rpc_stdio: bool,
+    rpc_socket: Option<PathBuf>,
+    idle_exit: Option<Duration>,
+}
+
+fn print_usage() {
+    println!("Usage: fff [options] [path]");
+    println!();
+    println!("Indexes <path> (default: current directory) and reads queries from stdin, printing");
+    println!("the best matching files after every line. An empty line ends the output of a query.");
+    println!();
+    println!("  --json <query>        run a single query and print the results as json");
+    println!(
+        "  -g, --glob <glob>     with --json, keep (or with ! hide) matching files like rg -g"
+    );
+    println!(
+        "  --track <file>        record an access of <file> in the frecency database and exit"
+    );
+    println!(
+        "  --db <dir>            frecency database directory (default: the one used by neovim)"
+    );
+    println!("  --no-frecency         do not open the frecency database");
+    println!("  --max-results <n>     number of results per query (default 20)");
+    println!("  --threads <n>         max threads per query (default 4)");
+    println!("  --current-file <f>    relative path of the file to deprioritize");
+    println!("  --scan-timeout <ms>   max time to wait for the initial scan (default 30000)");
+    println!("  --rpc                 serve line delimited JSON-RPC 2.0 on stdin/stdout");
+    println!("  --listen <socket>     serve line delimited JSON-RPC 2.0 on a unix socket");
+    println!("  --idle-exit <secs>    with --listen, exit after <secs> without connected clients");
+}
+
+/// Same location as `vim.fn.stdpath('cache') .. '/fff_nvim'` so the terminal and the editor
+/// share the access history.
+fn default_db_path() -> Option<PathBuf> {
+    let app_name = env::var("NVIM_APPNAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "nvim".to_string());
+
+    let cache_dir = if cfg!(target_os = "windows") {
+        env::var_os("LOCALAPPDATA")
+            .map(|dir| PathBuf::from(dir).join("Temp"))?
+            .join(app_name)
+    } else {
+        env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?
+            .join(app_name)
+    };
+
+    Some(cache_dir.join("fff_nvim"))
+}
+
+fn parse_args() -> Result<CliArgs, Box<dyn std::error::Error>> {
+    let mut base_path = None;
+    let mut json_query = None;
+    let mut globs = Vec::new();
+    let mut track = None;
+    let mut db_path = default_db_path();
+    let mut max_results = 20;
+    let mut max_threads = 4;
+    let mut current_file = None;
+    let mut scan_timeout = Duration::from_secs(30);
+    let mut rpc_stdio = false;
+    let mut rpc_socket = None;
+    let mut idle_exit = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--json" => json_query = Some(value()?),
+            "--glob" | "-g" => globs.push(value()?),
+            "--track" => track = Some(PathBuf::from(value()?)),
+            "--db" => db_path = Some(PathBuf::from(value()?)),
+            "--no-frecency" => db_path = None,
+            "--max-results" => max_results = value()?.parse()?,
+            "--threads" => max_threads = value()?.parse()?,
+            "--current-file" => current_file = Some(value()?),
+            "--scan-timeout" => scan_timeout = Duration::from_millis(value()?.parse()?),
+            "--rpc" => rpc_stdio = true,
+            "--listen" => rpc_socket = Some(PathBuf::from(value()?)),
+            "--idle-exit" => idle_exit = Some(Duration::from_secs(value()?.parse()?)),
+            "--help" | "-h" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            other if other.starts_with("--") => {
+                return Err(format!("Unknown argument: {}", other).into())
+            }
+            path => base_path = Some(PathBuf::from(path)),
+        }
+    }
+
+    let base_path = match base_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    Ok(CliArgs {
+        base_path: path_utils::canonicalize(&base_path)?,
+        json_query,
+        globs,
+        track,
+        db_path,
+        max_results,
+        max_threads,
+        current_file,
+        scan_timeout,
+        rpc_stdio,
+        rpc_socket,
+        idle_exit,
+    })
+}
+
+fn init_frecency(db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let db_path = db_path
+        .to_str()
+        .ok_or("Frecency database path is not utf-8")?;
+    // another writer next to the plugin, so LMDB's locking is kept on, the tracker serializes
+    // its write transactions with the ones of the plugin
+    *FRECENCY.write().map_err(|_| "Failed to lock frecency")? =
+        Some(FrecencyTracker::new(db_path, false)?);
+    Ok(())
+}
+
+fn wait_for_scan(timeout: Duration) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Ok(file_picker_guard) = FILE_PICKER.read() {
+            if file_picker_guard
+                .as_ref()
+                .is_some_and(|picker| !picker.is_scan_active())
+            {
+                return true;
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    false
+}
+
+fn serve_stdin(args: &CliArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let query = line?;
+        {
+            let file_picker_guard = FILE_PICKER.read().unwrap();
+            if let Some(ref picker) = *file_picker_guard {
+                let result = FilePicker::fuzzy_search(
+                    picker.get_files(),
+                    query.trim(),
+                    args.max_results,
+                    args.max_threads,
+                    args.current_file.as_deref(),
+                );
+
+                for file in &result.items {
+                    writeln!(stdout, "{}", file.relative_path)?;
+                }
+            }
+        }
+
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = parse_args()?;
+
+    if let Some(ref db_path) = args.db_path {
+        if let Err(e) = init_frecency(db_path) {
+            eprintln!("fff: frecency disabled, failed to open database: {}", e);
+        }
+    }
+
+    if let Some(ref file) = args.track {
+        let file = path_utils::canonicalize(file)?;
+        let frecency = FRECENCY.read().map_err(|_| "Failed to lock frecency")?;
+        let tracker = frecency
+            .as_ref()
+            .ok_or("Frecency database is not available")?;
+        tracker.track_access(&file)?;
+        tracker.flush()?;
+        return Ok(());
+    }
+
+    {
+        let mut file_picker = FILE_PICKER
+            .write()
+            .map_err(|_| "Failed to lock file picker")?;
+        *file_picker = Some(FilePicker::new(
+            args.base_path.to_string_lossy().into_owned(),
+            ScanOptions::default(),
+        )?);
+    }
+
+    if !wait_for_scan(args.scan_timeout) {
+        eprintln!(
+            "fff: initial scan did not finish in {:?}, results may be incomplete",
+            args.scan_timeout
+        );
+    }
+
+    match args.json_query {
+        Some(ref query) => {
+            if let Some(ref mut picker) = *FILE_PICKER.write().unwrap() {
+                picker.stop_background_monitor();
+            }
+
+            let glob_filter = if args.globs.is_empty() {
+                None
+            } else {
+                Some(GlobFilter::new(&args.globs)?)
+            };
+            let output = json::search(
+                query,
+                args.max_results,
+                args.max_threads,
+                args.current_file.as_deref(),
+                None,
+                false,
+                None,
+                glob_filter.as_ref(),
+            );
+            let mut stdout = io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &output)?;
+            writeln!(stdout)?;
+        }
+        None if args.rpc_stdio => server::serve_stdio()?,
+        #[cfg(unix)]
+        None if args.rpc_socket.is_some() => {
+            server::serve_unix_socket(args.rpc_socket.as_deref().unwrap(), args.idle_exit)?
+        }
+        None => serve_stdin(&args)?,
+    }
+
+    if let Some(mut picker) = FILE_PICKER.write().unwrap().take() {
+        picker.stop_background_monitor();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,772 @@
+//! Line delimited JSON-RPC 2.0 server exposing the index, so editors and scripts can query the
+//! same warm index without loading the shared library themselves.
+//!
+//! Every request is a single line `{"jsonrpc":"2.0","id":1,"method":"search","params":{...}}`
+//! and gets exactly one response line back.
+
+use crate::json;
+use fff_core::glob_filter::GlobFilter;
+use fff_core::grep::GrepOptions;
+use fff_core::result_count;
+use fff_core::shutdown;
+use fff_core::supersede::SearchTicket;
+use fff_core::types::{DiagnosticsCount, FileListOptions, FileListSort, FileMeta};
+use fff_core::{file_picker::FilePicker, path_utils, FILE_PICKER, FRECENCY};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// The daemon exits once no client is left, nothing waits on it
+const SHUTDOWN_STEP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+#[cfg(unix)]
+const ACCEPT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn internal(error: impl std::fmt::Display) -> Self {
+        Self::new(INTERNAL_ERROR, error.to_string())
+    }
+}
+
+enum Control {
+    Continue,
+    /// `shutdown` ends the connection of that client, the daemon stops once no client is left
+    Detach,
+    /// `stop_daemon` stops the daemon for every attached client
+    Shutdown,
+}
+
+pub fn serve_stdio() -> io::Result<()> {
+    let stdin = io::stdin();
+    let served = serve_connection(stdin.lock(), io::stdout().lock()).map(|_| ());
+    shutdown_index();
+    served
+}
+
+/// Frecency accesses are written behind in the background, the last ones have to land and
+/// the watcher has to stop before exiting
+fn shutdown_index() {
+    if !shutdown::shutdown(SHUTDOWN_STEP_TIMEOUT) {
+        eprintln!("fff: shutdown timed out, see the log for the step");
+    }
+}
+
+/// Serves every connection on its own thread. A client's `shutdown` only detaches it and stops
+/// the daemon when it was the last one, `stop_daemon` stops it regardless. With `idle_exit` the
+/// process also exits once no client has been connected for that long, which lets editors share
+/// one daemon per project without having to agree on who stops it. Fails when another daemon
+/// serves the socket already, a lock file next to it tells whether the socket left behind
+/// belongs to a live one.
+#[cfg(unix)]
+pub fn serve_unix_socket(
+    socket_path: &std::path::Path,
+    idle_exit: Option<std::time::Duration>,
+) -> io::Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    let mut lock_path = socket_path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    // held until the process exits
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    let already_served = || {
+        io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("another daemon serves {}", socket_path.display()),
+        )
+    };
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(std::fs::TryLockError::WouldBlock) => return Err(already_served()),
+        Err(std::fs::TryLockError::Error(e)) => return Err(e),
+    }
+    // a daemon of a release without the lock file
+    if UnixStream::connect(socket_path).is_ok() {
+        return Err(already_served());
+    }
+
+    let _ = std::fs::remove_file(socket_path);
+    let listener = UnixListener::bind(socket_path)?;
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let connections = Arc::new(AtomicUsize::new(0));
+    eprintln!("fff: listening on {}", socket_path.display());
+
+    if let Some(idle_exit) = idle_exit {
+        let connections = Arc::clone(&connections);
+        let socket_path = socket_path.to_path_buf();
+        std::thread::spawn(move || {
+            let mut idle_since = std::time::Instant::now();
+            loop {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                if connections.load(Ordering::Relaxed) > 0 {
+                    idle_since = std::time::Instant::now();
+                } else if idle_since.elapsed() >= idle_exit {
+                    eprintln!("fff: no clients for {:?}, exiting", idle_exit);
+                    let _ = std::fs::remove_file(&socket_path);
+                    shutdown_index();
+                    std::process::exit(0);
+                }
+            }
+        });
+    }
+
+    // polled, so a client connecting while the last one detaches is accepted before the daemon
+    // decides to stop
+    listener.set_nonblocking(true)?;
+    let last_detached = Arc::new(AtomicBool::new(false));
+    loop {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+                if last_detached.swap(false, Ordering::Relaxed)
+                    && connections.load(Ordering::Relaxed) == 0
+                {
+                    break;
+                }
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        stream.set_nonblocking(false)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let shutdown = Arc::clone(&shutdown);
+        let connections = Arc::clone(&connections);
+        let last_detached = Arc::clone(&last_detached);
+
+        connections.fetch_add(1, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            let control = serve_connection(reader, stream);
+            let remaining = connections.fetch_sub(1, Ordering::Relaxed) - 1;
+            match control {
+                Ok(Control::Shutdown) => shutdown.store(true, Ordering::Relaxed),
+                Ok(Control::Detach) if remaining == 0 => {
+                    last_detached.store(true, Ordering::Relaxed)
+                }
+                _ => {}
+            }
+        });
+    }
+
+    let _ = std::fs::remove_file(socket_path);
+    shutdown_index();
+    Ok(())
+}
+
+fn serve_connection(reader: impl BufRead, mut writer: impl Write) -> io::Result<Control> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (response, control) = handle_request(&line);
+        serde_json::to_writer(&mut writer, &response)?;
+        writeln!(writer)?;
+        writer.flush()?;
+
+        if !matches!(control, Control::Continue) {
+            return Ok(control);
+        }
+    }
+
+    Ok(Control::Continue)
+}
+
+fn handle_request(line: &str) -> (Value, Control) {
+    let request = match serde_json::from_str::<Value>(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return (
+                error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+                Control::Continue,
+            )
+        }
+    };
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return (
+            error_response(id, RpcError::new(INVALID_REQUEST, "missing method")),
+            Control::Continue,
+        );
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let control = match method {
+        "shutdown" => Control::Detach,
+        "stop_daemon" => Control::Shutdown,
+        _ => Control::Continue,
+    };
+
+    let response = match dispatch(method, &params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_response(id, error),
+    };
+
+    (response, control)
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// rg style `globs` of the request, compiled once per distinct list
+fn glob_filter_param(params: &Value) -> Result<Option<Arc<GlobFilter>>, RpcError> {
+    match params.get("globs") {
+        None | Some(Value::Null) => Ok(None),
+        Some(globs) => {
+            let globs: Vec<String> = serde_json::from_value(globs.clone())
+                .map_err(|_| RpcError::new(INVALID_PARAMS, "globs must be a list of strings"))?;
+            let filter = GlobFilter::cached(&globs)
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+            Ok(Some(filter))
+        }
+    }
+}
+
+fn dispatch(method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "search" => {
+            let query = params
+                .get("query")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "query is required"))?;
+            let max_results = params.get("max_results").and_then(Value::as_u64);
+            let max_threads = params.get("max_threads").and_then(Value::as_u64);
+            let current_file = params.get("current_file").and_then(Value::as_str);
+            let group_by_directory = params
+                .get("group_by_directory")
+                .and_then(Value::as_bool)
+                .unwrap_or_default();
+            let ticket = params
+                .get("picker_id")
+                .and_then(Value::as_str)
+                .map(SearchTicket::begin);
+            let scope = params.get("scope").and_then(Value::as_str).map(Path::new);
+            let glob_filter = glob_filter_param(params)?;
+
+            Ok(json::search(
+                query,
+                result_count::resolve(max_results.unwrap_or(50) as usize),
+                max_threads.unwrap_or(4) as usize,
+                current_file,
+                ticket.as_ref(),
+                group_by_directory,
+                scope,
+                glob_filter.as_deref(),
+            ))
+        }
+        "grep" => {
+            let pattern = params
+                .get("pattern")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "pattern is required"))?;
+            let defaults = GrepOptions::default();
+            let option = |name: &str, default: usize| {
+                params
+                    .get(name)
+                    .and_then(Value::as_u64)
+                    .map_or(default, |value| value as usize)
+            };
+            let options = GrepOptions {
+                before_context: option("before_context", defaults.before_context),
+                after_context: option("after_context", defaults.after_context),
+                max_matches_per_file: option("max_matches_per_file", defaults.max_matches_per_file),
+                max_matches: option("max_matches", defaults.max_matches),
+                max_file_size: params
+                    .get("max_file_size")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(defaults.max_file_size),
+                skip_sensitive: defaults.skip_sensitive,
+            };
+            let scope = params.get("scope").and_then(Value::as_str).map(Path::new);
+
+            Ok(json::grep(pattern, options, scope))
+        }
+        "search_word" => {
+            let word = params
+                .get("word")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "word is required"))?;
+            let current_file = params
+                .get("current_file")
+                .and_then(Value::as_str)
+                .map(Path::new);
+            let max_results = params.get("max_results").and_then(Value::as_u64);
+            let max_threads = params.get("max_threads").and_then(Value::as_u64);
+
+            Ok(json::search_word(
+                word,
+                current_file,
+                max_results.unwrap_or(20) as usize,
+                max_threads.unwrap_or(4) as usize,
+            ))
+        }
+        "prepare_replace" => {
+            let pattern = params
+                .get("pattern")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "pattern is required"))?;
+            let replacement = params
+                .get("replacement")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "replacement is required"))?;
+            let scope = params.get("scope").and_then(Value::as_str).map(Path::new);
+            let glob_filter = glob_filter_param(params)?;
+            let ignore_case = params.get("ignore_case").and_then(Value::as_bool);
+
+            Ok(json::prepare_replace(
+                pattern,
+                replacement,
+                scope,
+                glob_filter.as_deref(),
+                ignore_case.unwrap_or(false),
+            ))
+        }
+        "apply_replace" => {
+            let selection: Option<Vec<usize>> = match params.get("selection") {
+                None | Some(Value::Null) => None,
+                Some(ids) => Some(serde_json::from_value(ids.clone()).map_err(|_| {
+                    RpcError::new(INVALID_PARAMS, "selection must be a list of hunk ids")
+                })?),
+            };
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+            let outcome = picker
+                .apply_replace(selection.as_deref())
+                .map_err(RpcError::internal)?;
+
+            Ok(json::replace_outcome(outcome))
+        }
+        "get_cached_files" => {
+            let sort_by = match params.get("sort_by").and_then(Value::as_str) {
+                Some(sort_by) => FileListSort::parse(sort_by).ok_or_else(|| {
+                    RpcError::new(
+                        INVALID_PARAMS,
+                        "sort_by must be path, frecency, modified, size or git_status",
+                    )
+                })?,
+                None => FileListSort::default(),
+            };
+            let options = FileListOptions {
+                offset: params
+                    .get("offset")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_default() as usize,
+                limit: params
+                    .get("limit")
+                    .and_then(Value::as_u64)
+                    .map(|limit| limit as usize),
+                sort_by,
+                prefix: params
+                    .get("prefix")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            };
+
+            Ok(json::list_files(&options))
+        }
+        "push_filter" => {
+            let query = params
+                .get("query")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "query is required"))?;
+            let max_threads = params.get("max_threads").and_then(Value::as_u64);
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            Ok(Value::from(
+                picker.push_filter(query, max_threads.unwrap_or(4) as usize),
+            ))
+        }
+        "pop_filter" => {
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            Ok(Value::Bool(picker.pop_filter()))
+        }
+        "track_access" => {
+            let path = params
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "path is required"))?;
+            let path = path_utils::canonicalize(path).map_err(RpcError::internal)?;
+
+            let frecency = FRECENCY
+                .read()
+                .map_err(|_| RpcError::internal("frecency lock"))?;
+            let Some(ref frecency) = *frecency else {
+                return Ok(Value::Bool(false));
+            };
+            frecency.track_access(&path).map_err(RpcError::internal)?;
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            if let Some(ref mut picker) = *file_picker {
+                picker
+                    .update_single_file_frecency(&path, frecency)
+                    .map_err(RpcError::internal)?;
+            }
+
+            Ok(Value::Bool(true))
+        }
+        "track_edit" => {
+            let path = params
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "path is required"))?;
+            let path = path_utils::canonicalize(path).map_err(RpcError::internal)?;
+            let timestamp = params.get("timestamp").and_then(Value::as_u64);
+
+            let frecency = FRECENCY
+                .read()
+                .map_err(|_| RpcError::internal("frecency lock"))?;
+            let Some(ref frecency) = *frecency else {
+                return Ok(Value::Bool(false));
+            };
+            frecency
+                .track_edit(&path, timestamp)
+                .map_err(RpcError::internal)?;
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            if let Some(ref mut picker) = *file_picker {
+                picker
+                    .update_single_file_frecency(&path, frecency)
+                    .map_err(RpcError::internal)?;
+            }
+
+            Ok(Value::Bool(true))
+        }
+        "update_single_file_frecency" => {
+            let path = params
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "path is required"))?;
+
+            let frecency = FRECENCY
+                .read()
+                .map_err(|_| RpcError::internal("frecency lock"))?;
+            let Some(ref frecency) = *frecency else {
+                return Ok(Value::Bool(false));
+            };
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+            picker
+                .update_single_file_frecency(path, frecency)
+                .map_err(RpcError::internal)?;
+
+            Ok(Value::Bool(true))
+        }
+        "set_file_meta" => {
+            let path = params
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "path is required"))?;
+            let meta: Option<FileMeta> = match params.get("meta") {
+                None | Some(Value::Null) => None,
+                Some(meta) => Some(serde_json::from_value(meta.clone()).map_err(|_| {
+                    RpcError::new(INVALID_PARAMS, "meta must be a table of values")
+                })?),
+            };
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            Ok(Value::Bool(picker.set_file_meta(path, meta)))
+        }
+        "set_diagnostics_counts" => {
+            let counts: HashMap<PathBuf, DiagnosticsCount> = serde_json::from_value(
+                params.get("counts").cloned().unwrap_or_default(),
+            )
+            .map_err(|_| {
+                RpcError::new(
+                    INVALID_PARAMS,
+                    "counts must map paths to errors and warnings",
+                )
+            })?;
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            Ok(Value::from(picker.set_diagnostics_counts(counts)))
+        }
+        "set_jump_positions" => {
+            let paths: Vec<PathBuf> =
+                serde_json::from_value(params.get("paths").cloned().unwrap_or_default())
+                    .map_err(|_| RpcError::new(INVALID_PARAMS, "paths must be a list of paths"))?;
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            Ok(Value::from(picker.set_jump_positions(paths)))
+        }
+        "migrate_frecency_prefix" => {
+            let prefix = |name: &str| {
+                params
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .map(PathBuf::from)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("{} is required", name)))
+            };
+            let old_prefix = prefix("old_prefix")?;
+            let new_prefix = prefix("new_prefix")?;
+            let new_prefix = path_utils::canonicalize(&new_prefix).unwrap_or(new_prefix);
+
+            let frecency = FRECENCY
+                .read()
+                .map_err(|_| RpcError::internal("frecency lock"))?;
+            let Some(ref frecency) = *frecency else {
+                return Ok(Value::from(0));
+            };
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            picker
+                .migrate_frecency_prefix(frecency, &old_prefix, &new_prefix)
+                .map(Value::from)
+                .map_err(RpcError::internal)
+        }
+        "forget_frecency_project" => {
+            let root = params
+                .get("root")
+                .and_then(Value::as_str)
+                .map(PathBuf::from)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "root is required"))?;
+            let root = path_utils::canonicalize(&root).unwrap_or(root);
+
+            let frecency = FRECENCY
+                .read()
+                .map_err(|_| RpcError::internal("frecency lock"))?;
+            let Some(ref frecency) = *frecency else {
+                return Ok(Value::from(0));
+            };
+            let forgotten = frecency.forget_project(&root).map_err(RpcError::internal)?;
+            if forgotten > 0 {
+                let mut file_picker = FILE_PICKER
+                    .write()
+                    .map_err(|_| RpcError::internal("file picker lock"))?;
+                if let Some(ref mut picker) = *file_picker {
+                    picker
+                        .refresh_frecency_scores(frecency)
+                        .map_err(RpcError::internal)?;
+                }
+            }
+
+            Ok(Value::from(forgotten))
+        }
+        "get_related_by_history" => {
+            let path = params
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "path is required"))?;
+            let path = path_utils::canonicalize(path).map_err(RpcError::internal)?;
+
+            let frecency = FRECENCY
+                .read()
+                .map_err(|_| RpcError::internal("frecency lock"))?;
+            let Some(ref frecency) = *frecency else {
+                return Ok(json!([]));
+            };
+            let related = frecency.get_co_changes(&path).map_err(RpcError::internal)?;
+
+            Ok(related
+                .into_iter()
+                .map(|(path, count)| json!({ "path": path.to_string_lossy(), "count": count }))
+                .collect())
+        }
+        "watch_extra_path" => {
+            let path = params
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "path is required"))?;
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            picker
+                .watch_extra_path(path)
+                .map(Value::Bool)
+                .map_err(RpcError::internal)
+        }
+        "scan_files" => {
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+            picker.trigger_rescan().map_err(RpcError::internal)?;
+
+            Ok(Value::Bool(true))
+        }
+        "get_index_stats" => {
+            let file_picker = FILE_PICKER
+                .read()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_ref()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+            let stats = picker.index_stats();
+
+            Ok(json!({
+                "files": stats.files,
+                "extensions": stats
+                    .extensions
+                    .iter()
+                    .map(|(extension, count)| json!({ "extension": extension, "count": count }))
+                    .collect::<Vec<_>>(),
+                "languages": stats.languages,
+            }))
+        }
+        "diff_index" => {
+            let since_generation = params
+                .get("since_generation")
+                .and_then(Value::as_u64)
+                .unwrap_or_default();
+
+            let file_picker = FILE_PICKER
+                .read()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_ref()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+            let diff = picker.diff_index(since_generation);
+            let paths = |paths: Vec<PathBuf>| -> Vec<String> {
+                paths
+                    .into_iter()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect()
+            };
+
+            Ok(json!({
+                "generation": diff.generation,
+                "added": paths(diff.added),
+                "removed": paths(diff.removed),
+                "changed": paths(diff.changed),
+                "reset": diff.reset,
+            }))
+        }
+        "check_index_drift" => {
+            let sample_size = params.get("sample_size").and_then(Value::as_u64);
+            let threshold_percent = params.get("threshold_percent").and_then(Value::as_u64);
+
+            let mut file_picker = FILE_PICKER
+                .write()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_mut()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+            let drift = picker.check_index_drift(
+                sample_size.unwrap_or(100) as usize,
+                threshold_percent.unwrap_or(10) as usize,
+            );
+
+            Ok(json::index_drift(drift))
+        }
+        "get_metrics" => {
+            let file_picker = FILE_PICKER
+                .read()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_ref()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+
+            Ok(json::index_metrics(picker.metrics()))
+        }
+        "get_scan_progress" => {
+            let file_picker = FILE_PICKER
+                .read()
+                .map_err(|_| RpcError::internal("file picker lock"))?;
+            let picker = file_picker
+                .as_ref()
+                .ok_or_else(|| RpcError::internal("file picker is not initialized"))?;
+            let progress = picker.get_scan_progress();
+
+            Ok(json!({
+                "scanned_files_count": progress.scanned_files_count,
+                "is_scanning": progress.is_scanning,
+                "git_status_generation": picker.git_status_generation(),
+            }))
+        }
+        "refresh_git_status" => FilePicker::refresh_git_status_global()
+            .map(Value::from)
+            .map_err(RpcError::internal),
+        "shutdown" | "stop_daemon" => Ok(Value::Null),
+        other => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("unknown method: {}", other),
+        )),
+    }
+}
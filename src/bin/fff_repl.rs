@@ -0,0 +1,443 @@
+use fff_core::{
+    evaluation,
+    file_picker::FilePicker,
+    frecency::FrecencyTracker,
+    path_utils, replay,
+    types::{ScanOptions, Score, ScoringWeights, SearchResult},
+    FILE_PICKER, FRECENCY,
+};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct ReplState {
+    weights: ScoringWeights,
+    max_results: usize,
+    max_threads: usize,
+    current_file: Option<String>,
+    /// Print every score component instead of the non zero ones
+    verbose: bool,
+}
+
+fn print_usage() {
+    println!("Usage: fff_repl [options] [path]");
+    println!();
+    println!("Indexes <path> (default: current directory) and ranks the typed queries, printing");
+    println!("the score breakdown and the timing of every search. Type :help for the commands.");
+    println!();
+    println!("  --db <dir>            frecency database to score with (default: none)");
+    println!("  --max-results <n>     number of results per query (default 10)");
+    println!("  --threads <n>         max threads per query (default 4)");
+    println!("  --replay <trace>      rerun the searches of a trace or debug bundle and exit");
+    println!("  --eval <corpus>       rank the (query, expected) json lines of a corpus and exit");
+    println!("  --json                print the --eval report as json");
+}
+
+fn print_commands() {
+    println!(":set <component> <n>   percentage weight of a score component, 100 is the default");
+    println!(":toggle <component>    switch a component between 0 and 100, or normalize");
+    println!(":weights               print the current weights");
+    println!(":current [file]        relative path of the current file, none without one");
+    println!(":limit <n>             number of printed results");
+    println!(":verbose               print the zero score components too");
+    println!(":rescan                scan the directory again");
+    println!(":eval <corpus>         MRR and top-k hit rates of a corpus with the current weights");
+    println!(":quit                  exit, so does ctrl-d");
+    println!("anything else is searched, an empty line searches the empty query");
+}
+
+/// Weights reachable by name, the same names as the `scoring` table of the plugin config
+fn weight_mut<'a>(weights: &'a mut ScoringWeights, name: &str) -> Option<&'a mut i32> {
+    Some(match name {
+        "frecency" => &mut weights.frecency,
+        "distance" => &mut weights.distance,
+        "depth" => &mut weights.depth,
+        "filename" => &mut weights.filename,
+        "path_component" => &mut weights.path_component,
+        "diagnostics" => &mut weights.diagnostics,
+        "word_order" => &mut weights.word_order,
+        "session" => &mut weights.session,
+        "time_of_day" => &mut weights.time_of_day,
+        _ => return None,
+    })
+}
+
+fn print_weights(state: &ReplState) {
+    let weights = &state.weights;
+    println!(
+        "frecency {} distance {} depth {} filename {} path_component {} diagnostics {}",
+        weights.frecency,
+        weights.distance,
+        weights.depth,
+        weights.filename,
+        weights.path_component,
+        weights.diagnostics
+    );
+    println!(
+        "word_order {} session {} time_of_day {} normalize {} package_scope {:?}",
+        weights.word_order,
+        weights.session,
+        weights.time_of_day,
+        weights.normalize_scores,
+        weights.package_scope
+    );
+    println!(
+        "limit {} current file {}",
+        state.max_results,
+        state.current_file.as_deref().unwrap_or("none")
+    );
+}
+
+fn score_components(score: &Score) -> [(&'static str, i32); 16] {
+    [
+        ("base", score.base_score),
+        ("filename", score.filename_bonus),
+        ("special", score.special_filename_bonus),
+        ("frecency", score.frecency_boost),
+        ("distance", score.distance_penalty),
+        ("depth", score.depth_penalty),
+        ("path_component", score.path_component_bonus),
+        ("diagnostics", score.diagnostics_bonus),
+        ("package", score.package_bonus),
+        ("word_order", score.word_order_bonus),
+        ("abbreviation", score.abbreviation_bonus),
+        ("typo", score.typo_bonus),
+        ("co_change", score.co_change_bonus),
+        ("session", score.session_bonus),
+        ("time_of_day", score.time_of_day_boost),
+        ("total", score.total),
+    ]
+}
+
+fn print_results(result: &SearchResult, elapsed: Duration, verbose: bool) {
+    for (rank, (file, score)) in result.items.iter().zip(&result.scores).enumerate() {
+        println!(
+            "{:>3}. {:>6}  {}  [{}]",
+            rank + 1,
+            score.total,
+            file.relative_path,
+            score.match_type
+        );
+
+        let breakdown = score_components(score)
+            .into_iter()
+            .filter(|(name, value)| *name != "total" && (verbose || *value != 0))
+            .map(|(name, value)| format!("{} {}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let normalized = score
+            .normalized
+            .map(|normalized| format!(" normalized {}", normalized))
+            .unwrap_or_default();
+        println!(
+            "       {}{} | frecency access {} modification {}",
+            breakdown, normalized, file.access_frecency_score, file.modification_frecency_score
+        );
+    }
+
+    println!(
+        "{} of {} matched out of {} files in {:.2?}{}",
+        result.items.len(),
+        result.total_matched,
+        result.total_files,
+        elapsed,
+        if result.approximate {
+            ", approximate"
+        } else {
+            ""
+        }
+    );
+}
+
+fn search(state: &ReplState, query: &str) {
+    let Ok(file_picker_guard) = FILE_PICKER.read() else {
+        eprintln!("Failed to lock the file picker");
+        return;
+    };
+    let Some(ref picker) = *file_picker_guard else {
+        eprintln!("File picker not initialized");
+        return;
+    };
+
+    let start = Instant::now();
+    let result = FilePicker::fuzzy_search_weighted(
+        picker.get_files(),
+        query,
+        state.max_results,
+        state.max_threads,
+        state.current_file.as_deref(),
+        state.weights,
+        None,
+        None,
+        picker.narrowed_paths(),
+        picker.index_stats().entry_points(),
+        Some(picker.prefix_candidates()),
+        None,
+        None,
+    );
+    let elapsed = start.elapsed();
+
+    print_results(&result, elapsed, state.verbose);
+}
+
+fn rescan() {
+    let start = Instant::now();
+    if let Ok(mut file_picker_guard) = FILE_PICKER.write() {
+        if let Some(ref mut picker) = *file_picker_guard {
+            if let Err(e) = picker.trigger_rescan() {
+                eprintln!("Rescan failed: {:?}", e);
+                return;
+            }
+        }
+    }
+    wait_for_scan();
+    println!("Rescanned in {:.2?}", start.elapsed());
+}
+
+/// Applies a `:command`, false once the repl should exit
+fn run_command(state: &mut ReplState, command: &str) -> bool {
+    let mut words = command.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let args = words.collect::<Vec<_>>();
+
+    match (name, args.as_slice()) {
+        ("quit" | "q", _) => return false,
+        ("help" | "h", _) => print_commands(),
+        ("weights", _) => print_weights(state),
+        ("verbose", _) => {
+            state.verbose = !state.verbose;
+            println!("verbose {}", state.verbose);
+        }
+        ("rescan", _) => rescan(),
+        ("eval", [corpus]) => {
+            if let Err(e) = print_evaluation(state, Path::new(corpus), false) {
+                eprintln!("Evaluation failed: {}", e);
+            }
+        }
+        ("limit", [limit]) => match limit.parse() {
+            Ok(limit) => state.max_results = limit,
+            Err(_) => eprintln!("Not a number: {}", limit),
+        },
+        ("current", []) => state.current_file = None,
+        ("current", [file]) => state.current_file = Some(file.to_string()),
+        ("toggle", ["normalize"]) => {
+            state.weights.normalize_scores = !state.weights.normalize_scores;
+            println!("normalize {}", state.weights.normalize_scores);
+        }
+        ("toggle", [component]) => match weight_mut(&mut state.weights, component) {
+            Some(weight) => {
+                *weight = if *weight == 0 { 100 } else { 0 };
+                println!("{} {}", component, weight);
+            }
+            None => eprintln!("Unknown component: {}", component),
+        },
+        ("set", [component, value]) => match (
+            weight_mut(&mut state.weights, component),
+            value.parse::<i32>(),
+        ) {
+            (Some(weight), Ok(value)) => *weight = value,
+            (None, _) => eprintln!("Unknown component: {}", component),
+            (_, Err(_)) => eprintln!("Not a number: {}", value),
+        },
+        _ => eprintln!("Unknown command :{}, type :help for the commands", command),
+    }
+
+    true
+}
+
+/// Reruns the traced searches and prints the ones ranking differently than recorded
+fn print_replay(trace_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let replayed = replay::replay(trace_path)?;
+    let mut reproduced = 0;
+
+    for search in &replayed {
+        let Some(rank) = search.first_difference() else {
+            reproduced += 1;
+            println!("same      {:?}", search.search.query);
+            continue;
+        };
+
+        println!(
+            "differs   {:?} from rank {}{}",
+            search.search.query,
+            rank + 1,
+            if search.same_generation {
+                ""
+            } else {
+                " (the index changed since the search)"
+            }
+        );
+        let rows = search.search.results.len().max(search.results.len());
+        for row in rank..rows {
+            println!(
+                "  {:>3}. {:<50} {}",
+                row + 1,
+                search.search.results.get(row).map_or("-", String::as_str),
+                search.results.get(row).map_or("-", String::as_str)
+            );
+        }
+    }
+
+    println!(
+        "{} of {} searches rank the same as recorded",
+        reproduced,
+        replayed.len()
+    );
+    Ok(())
+}
+
+/// Ranks the cases of the corpus against the index with the weights of the repl
+fn print_evaluation(
+    state: &ReplState,
+    corpus_path: &Path,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let cases = evaluation::read_corpus(corpus_path)?;
+    let file_picker_guard = FILE_PICKER
+        .read()
+        .map_err(|_| "Failed to lock the file picker")?;
+    let picker = file_picker_guard
+        .as_ref()
+        .ok_or("File picker not initialized")?;
+
+    let start = Instant::now();
+    let report = evaluation::evaluate(picker, &cases, state.weights, 100, None);
+    if json {
+        println!("{}", report.to_json()?);
+    } else {
+        print!("{}", report);
+        println!("Evaluated in {:.2?}", start.elapsed());
+    }
+    Ok(())
+}
+
+fn wait_for_scan() {
+    loop {
+        if let Ok(file_picker_guard) = FILE_PICKER.read() {
+            if file_picker_guard
+                .as_ref()
+                .is_some_and(|picker| !picker.is_scan_active())
+            {
+                break;
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut base_path = None;
+    let mut db_path = None;
+    let mut replay_path = None;
+    let mut eval_path = None;
+    let mut json = false;
+    let mut state = ReplState {
+        weights: ScoringWeights::default(),
+        max_results: 10,
+        max_threads: 4,
+        current_file: None,
+        verbose: false,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--db" => db_path = Some(PathBuf::from(value()?)),
+            "--max-results" => state.max_results = value()?.parse()?,
+            "--threads" => state.max_threads = value()?.parse()?,
+            "--replay" => replay_path = Some(PathBuf::from(value()?)),
+            "--eval" => eval_path = Some(PathBuf::from(value()?)),
+            "--json" => json = true,
+            "--help" | "-h" => {
+                print_usage();
+                return Ok(());
+            }
+            other if other.starts_with("--") => {
+                return Err(format!("Unknown argument: {}", other).into())
+            }
+            path => base_path = Some(PathBuf::from(path)),
+        }
+    }
+
+    if let Some(replay_path) = replay_path {
+        return print_replay(&replay_path);
+    }
+
+    if let Some(db_path) = db_path {
+        let db_path = db_path
+            .to_str()
+            .ok_or("Frecency database path is not utf-8")?;
+        // another writer next to the plugin, so LMDB's locking is kept on, the tracker serializes
+        // its write transactions with the ones of the plugin
+        *FRECENCY.write().map_err(|_| "Failed to lock frecency")? =
+            Some(FrecencyTracker::new(db_path, false)?);
+    }
+
+    let base_path = match base_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+    let base_path = path_utils::canonicalize(&base_path)?;
+
+    let scan_start = Instant::now();
+    *FILE_PICKER
+        .write()
+        .map_err(|_| "Failed to lock the file picker")? = Some(FilePicker::new(
+        base_path.to_string_lossy().into_owned(),
+        ScanOptions::default(),
+    )?);
+    wait_for_scan();
+    if let Some(ref mut picker) = *FILE_PICKER.write().map_err(|_| "Failed to lock")? {
+        // a stable index, the ranking shouldn't change between two runs of the same query
+        picker.stop_background_monitor();
+        if eval_path.is_none() {
+            println!(
+                "Indexed {} files of {} in {:.2?}, type :help for the commands",
+                picker.get_files().len(),
+                base_path.display(),
+                scan_start.elapsed()
+            );
+        }
+    }
+
+    if let Some(eval_path) = eval_path {
+        let evaluated = print_evaluation(&state, &eval_path, json);
+        FILE_PICKER
+            .write()
+            .ok()
+            .and_then(|mut picker| picker.take());
+        return evaluated;
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("fff> ");
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+        let line = line?;
+        let line = line.trim();
+
+        match line.strip_prefix(':') {
+            Some(command) => {
+                if !run_command(&mut state, command) {
+                    break;
+                }
+            }
+            None => search(&state, line),
+        }
+    }
+
+    FILE_PICKER
+        .write()
+        .ok()
+        .and_then(|mut picker| picker.take());
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use fff_nvim::{file_picker::FilePicker, FILE_PICKER};
+use fff_nvim::{file_picker::FilePicker, types::ScanOptions, FILE_PICKER};
 use std::env;
 use std::thread;
 use std::time::Duration;
@@ -169,7 +169,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let mut file_picker_guard = FILE_PICKER.write().unwrap();
         if file_picker_guard.is_none() {
             println!("Initializing FilePicker...");
-            *file_picker_guard = Some(FilePicker::new(base_path.clone())?);
+            *file_picker_guard = Some(FilePicker::new(base_path.clone(), ScanOptions::default())?);
         }
     }
 
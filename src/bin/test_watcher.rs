@@ -2,7 +2,9 @@
 #![allow(dead_code)]
 #![allow(clippy::enum_variant_names)]
 
-use fff_nvim::{file_picker::FilePicker, git::format_git_status, FILE_PICKER, FRECENCY};
+use fff_nvim::{
+    file_picker::FilePicker, git::format_git_status, types::ScanOptions, FILE_PICKER, FRECENCY,
+};
 use std::env;
 use std::io::{self, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -56,7 +58,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             eprintln!("❌ FilePicker already initialized");
             std::process::exit(1);
         }
-        *file_picker = Some(FilePicker::new(base_path.clone())?);
+        *file_picker = Some(FilePicker::new(base_path.clone(), ScanOptions::default())?);
     }
 
     // Get initial file count from global state
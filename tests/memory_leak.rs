@@ -0,0 +1,117 @@
+//! Resident memory of repeated searches, run with `cargo test --release --features leak-check`.
+//! Searches allocate and free a lot on every keystroke, a leak shows up as memory growing with
+//! the number of searches long after the allocator reached its steady state.
+#![cfg(any(target_os = "linux", target_os = "macos"))]
+
+use fff_nvim::{file_picker::FilePicker, types::ScanOptions, FILE_PICKER};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Searches run before the baseline is taken, every thread count and result count is seen once
+const WARMUP_SEARCHES: usize = 1_000;
+const MEASURED_SEARCHES: usize = 10_000;
+/// Resident memory is counted in pages, a few of them over the whole run are noise
+const MAX_GROWTH_PER_SEARCH: u64 = 64;
+const FIXTURE_DIRS: usize = 50;
+const FIXTURE_FILES_PER_DIR: usize = 60;
+
+const QUERIES: &[&str] = &[
+    "rs", "mod", "lib", "main", "test", "src", "lua", "cargo", "toml", "init", "config", "util",
+    "file", "picker", "fuzzy", "search", "git", "fn", "struct", "impl", "pub", "use", "let",
+    "match", "async", "Result", "Error", "Vec", "String", "Option",
+];
+
+fn resident_memory() -> u64 {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        let rss_kb = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))
+            .and_then(|value| value.split_whitespace().next())
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .expect("VmRSS in /proc/self/status");
+        rss_kb * 1024
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("ps")
+            .args(["-o", "rss=", "-p", &std::process::id().to_string()])
+            .output()
+            .unwrap();
+        let rss_kb: u64 = String::from_utf8(output.stdout)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        rss_kb * 1024
+    }
+}
+
+fn write_fixture(dir: &Path) {
+    for d in 0..FIXTURE_DIRS {
+        let sub_dir = dir.join(format!("src/module_{}", d));
+        std::fs::create_dir_all(&sub_dir).unwrap();
+        for f in 0..FIXTURE_FILES_PER_DIR {
+            let name = format!("{}_{}.rs", QUERIES[(d + f) % QUERIES.len()], f);
+            std::fs::write(sub_dir.join(name), "").unwrap();
+        }
+    }
+}
+
+fn run_searches(count: usize) {
+    let file_picker = FILE_PICKER.read().unwrap();
+    let picker = file_picker.as_ref().unwrap();
+    for i in 0..count {
+        let result = FilePicker::fuzzy_search(
+            picker.get_files(),
+            QUERIES[i % QUERIES.len()],
+            50 + i % 100,
+            1 + i % 8,
+            None,
+        );
+        assert!(result.total_files > 0);
+    }
+}
+
+#[test]
+fn test_searches_do_not_grow_memory() {
+    let dir = std::env::temp_dir().join("fff_test_leak_check");
+    let _ = std::fs::remove_dir_all(&dir);
+    write_fixture(&dir);
+
+    let picker = FilePicker::new(dir.to_string_lossy().into_owned(), ScanOptions::default());
+    *FILE_PICKER.write().unwrap() = Some(picker.unwrap());
+
+    let start = Instant::now();
+    loop {
+        let scanned = FILE_PICKER.read().unwrap().as_ref().is_some_and(|picker| {
+            !picker.is_scan_active()
+                && picker.get_files().len() == FIXTURE_DIRS * FIXTURE_FILES_PER_DIR
+        });
+        if scanned {
+            break;
+        }
+        assert!(start.elapsed() < Duration::from_secs(30), "scan timed out");
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    run_searches(WARMUP_SEARCHES);
+    let baseline = resident_memory();
+    run_searches(MEASURED_SEARCHES);
+    let growth = resident_memory().saturating_sub(baseline);
+
+    *FILE_PICKER.write().unwrap() = None;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let growth_per_search = growth / MEASURED_SEARCHES as u64;
+    assert!(
+        growth_per_search <= MAX_GROWTH_PER_SEARCH,
+        "memory grew by {} bytes over {} searches, {} bytes per search",
+        growth,
+        MEASURED_SEARCHES,
+        growth_per_search
+    );
+}